@@ -0,0 +1,21 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use mpc_in_rust::circuit::circuit_parser::Circuit;
+use mpc_in_rust::testing::synthetic_circuit;
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+
+    for gates in [10_000, 100_000, 500_000] {
+        let text = synthetic_circuit(64, gates, 0.3, 0).to_bristol();
+        group.throughput(Throughput::Elements(gates as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(gates), &text, |b, text| {
+            b.iter(|| Circuit::parse(black_box(text)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
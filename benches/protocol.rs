@@ -0,0 +1,69 @@
+use std::fs;
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use mpc_in_rust::circuit::circuit_parser::Circuit;
+use mpc_in_rust::party::party_gmw::new_party_pair;
+use mpc_in_rust::testing::{layered_circuit, synthetic_circuit};
+
+/// Runs both parties on their own thread, either with one round per AND gate or batched by layer.
+fn run(circuit: &Circuit, batched: bool) -> Vec<bool> {
+    let (mut p0, mut p1) = new_party_pair(circuit.clone()).unwrap();
+    let input0 = vec![true; circuit.header().niv[0]];
+    let input1 = vec![false; circuit.header().niv[1]];
+
+    let h0 = thread::spawn(move || {
+        if batched {
+            p0.execute_batched(&input0).unwrap()
+        } else {
+            p0.execute(&input0).unwrap()
+        }
+    });
+    let h1 = thread::spawn(move || {
+        if batched {
+            p1.execute_batched(&input1).unwrap()
+        } else {
+            p1.execute(&input1).unwrap()
+        }
+    });
+    h1.join().unwrap();
+    h0.join().unwrap()
+}
+
+fn bench_protocol(c: &mut Criterion) {
+    let adder = fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+    let adder = Circuit::parse(&adder).unwrap();
+    let and_heavy = synthetic_circuit(64, 5_000, 0.9, 0);
+
+    let mut group = c.benchmark_group("protocol");
+    group.sample_size(10);
+    group.bench_function("adder", |b| b.iter(|| run(&adder, false)));
+    group.bench_function("adder_batched", |b| b.iter(|| run(&adder, true)));
+    group.bench_function("and_heavy_5k", |b| b.iter(|| run(&and_heavy, false)));
+    group.bench_function("and_heavy_5k_batched", |b| b.iter(|| run(&and_heavy, true)));
+    group.finish();
+}
+
+/// Online phase of circuits of the same width and growing AND depth, i.e. number of rounds.
+fn bench_and_depth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("and_depth");
+    group.sample_size(10);
+    for and_depth in [1, 16, 64, 256] {
+        let circuit = layered_circuit(64, and_depth, 0);
+        group.bench_with_input(
+            BenchmarkId::new("naive", and_depth),
+            &circuit,
+            |b, circuit| b.iter(|| run(circuit, false)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("batched", and_depth),
+            &circuit,
+            |b, circuit| b.iter(|| run(circuit, true)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_protocol, bench_and_depth);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::rngs::StdRng;
+
+use mpc_in_rust::mul_triple::{MTProvider, SeededMTP};
+
+const COUNT: usize = 1_000_000;
+
+/// Generates the triples of a large circuit at once, one at a time or in bulk with the given
+/// number of threads.
+fn bench_triples(c: &mut Criterion) {
+    let mut group = c.benchmark_group("triples");
+    group.sample_size(10);
+    group.bench_function("serial_1m", |b| {
+        b.iter(|| {
+            let mut mtp: SeededMTP<StdRng> = SeededMTP::new([7; 32]);
+            (0..COUNT).map(|_| mtp.get_triple()).collect::<Vec<_>>()
+        })
+    });
+    for threads in [1, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_function(format!("bulk_1m_{}_threads", threads), |b| {
+            b.iter(|| {
+                let mut mtp: SeededMTP<StdRng> = SeededMTP::new([7; 32]);
+                pool.install(|| mtp.get_triples(COUNT))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_triples);
+criterion_main!(benches);
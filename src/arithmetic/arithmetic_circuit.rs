@@ -0,0 +1,188 @@
+use crate::circuit::circuit_error::CircuitError;
+
+/// The operation of an arithmetic gate, on the numbers of the given wires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticGateType {
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    /// Multiplies a wire with a public constant, which needs no communication.
+    MulConst(usize, u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArithmeticGate {
+    pub gate_type: ArithmeticGateType,
+    pub output: usize,
+}
+
+/// A circuit over the integers modulo 2^`bits`. Like a bristol circuit, the inputs of party 0
+/// come first, followed by the inputs of party 1, and the outputs are the last wires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArithmeticCircuit {
+    bits: u32,
+    inputs: [usize; 2],
+    outputs: usize,
+    wires_amount: usize,
+    gates: Vec<ArithmeticGate>,
+}
+
+impl ArithmeticCircuit {
+    /// Creates a circuit computing modulo 2^`bits`, with `inputs[i]` input wires of party i.
+    /// Checks that the gates can be evaluated in order, like
+    /// [`crate::circuit::circuit_parser::Circuit::validate`] does for boolean circuits.
+    pub fn new(
+        bits: u32,
+        inputs: [usize; 2],
+        outputs: usize,
+        wires_amount: usize,
+        gates: Vec<ArithmeticGate>,
+    ) -> Result<Self, CircuitError> {
+        if !(1..=64).contains(&bits) {
+            return Err(CircuitError::UnsupportedShape(format!(
+                "it computes modulo 2^{}, but only 1 to 64 bits are supported",
+                bits
+            )));
+        }
+        let inputs_sum = inputs[0] + inputs[1];
+        if inputs_sum + outputs > wires_amount {
+            return Err(CircuitError::ParsingError(format!(
+                "the circuit declaring more input and output wires than its {} wires",
+                wires_amount
+            )));
+        }
+
+        let mut set = vec![false; wires_amount];
+        set[..inputs_sum].fill(true);
+        for (gate_index, gate) in gates.iter().enumerate() {
+            let operands = match gate.gate_type {
+                ArithmeticGateType::Add(a, b)
+                | ArithmeticGateType::Sub(a, b)
+                | ArithmeticGateType::Mul(a, b) => vec![a, b],
+                ArithmeticGateType::MulConst(a, _) => vec![a],
+            };
+            for wire in operands {
+                match set.get(wire) {
+                    None => return Err(CircuitError::WireOutOfRange(gate_index, wire)),
+                    Some(false) => return Err(CircuitError::WireUsedBeforeSet(gate_index, wire)),
+                    Some(true) => {}
+                }
+            }
+            match set.get_mut(gate.output) {
+                None => return Err(CircuitError::WireOutOfRange(gate_index, gate.output)),
+                Some(true) => return Err(CircuitError::WireAssignedTwice(gate_index, gate.output)),
+                Some(assigned) => *assigned = true,
+            }
+        }
+        if let Some(wire) = (wires_amount - outputs..wires_amount).find(|&w| !set[w]) {
+            return Err(CircuitError::WireNotSetError(wire));
+        }
+
+        Ok(ArithmeticCircuit {
+            bits,
+            inputs,
+            outputs,
+            wires_amount,
+            gates,
+        })
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Number of input wires of party 0 and of party 1.
+    pub fn inputs(&self) -> [usize; 2] {
+        self.inputs
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    pub fn wires_amount(&self) -> usize {
+        self.wires_amount
+    }
+
+    pub fn gates(&self) -> &[ArithmeticGate] {
+        &self.gates
+    }
+
+    /// Number of arithmetic triples needed to evaluate the circuit, one per MUL gate.
+    pub fn required_triples(&self) -> usize {
+        self.gates
+            .iter()
+            .filter(|g| matches!(g.gate_type, ArithmeticGateType::Mul(_, _)))
+            .count()
+    }
+
+    /// Reduces a number modulo 2^`bits`.
+    pub fn reduce(&self, value: u64) -> u64 {
+        match self.bits {
+            64 => value,
+            bits => value & ((1 << bits) - 1),
+        }
+    }
+
+    /// Evaluates the circuit on the inputs in the clear, without any protocol, e.g. to check the
+    /// result of a protocol run.
+    pub fn evaluate_plain(&self, first_in: &[u64], second_in: &[u64]) -> Vec<u64> {
+        // computing modulo 2^64 and reducing at the end gives the same result as reducing after
+        // every gate, because 2^bits divides 2^64
+        let mut wires = vec![0u64; self.wires_amount];
+        wires[..first_in.len()].copy_from_slice(first_in);
+        wires[first_in.len()..first_in.len() + second_in.len()].copy_from_slice(second_in);
+        for gate in &self.gates {
+            wires[gate.output] = match gate.gate_type {
+                ArithmeticGateType::Add(a, b) => wires[a].wrapping_add(wires[b]),
+                ArithmeticGateType::Sub(a, b) => wires[a].wrapping_sub(wires[b]),
+                ArithmeticGateType::Mul(a, b) => wires[a].wrapping_mul(wires[b]),
+                ArithmeticGateType::MulConst(a, k) => wires[a].wrapping_mul(k),
+            };
+        }
+        wires[self.wires_amount - self.outputs..]
+            .iter()
+            .map(|&v| self.reduce(v))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArithmeticCircuit, ArithmeticGate, ArithmeticGateType};
+    use crate::circuit::circuit_error::CircuitError;
+
+    fn gate(gate_type: ArithmeticGateType, output: usize) -> ArithmeticGate {
+        ArithmeticGate { gate_type, output }
+    }
+
+    #[test]
+    fn test_arithmetic_circuit() {
+        // (x * y + x) * 3 modulo 2^8
+        let c = ArithmeticCircuit::new(
+            8,
+            [1, 1],
+            1,
+            5,
+            vec![
+                gate(ArithmeticGateType::Mul(0, 1), 2),
+                gate(ArithmeticGateType::Add(2, 0), 3),
+                gate(ArithmeticGateType::MulConst(3, 3), 4),
+            ],
+        )
+        .unwrap();
+        assert_eq!(c.required_triples(), 1);
+        assert_eq!(c.evaluate_plain(&[5], &[7]), vec![120]);
+        assert_eq!(c.evaluate_plain(&[20], &[30]), vec![(620 * 3) % 256]);
+
+        let reads_unset = vec![gate(ArithmeticGateType::Sub(0, 3), 2)];
+        assert!(matches!(
+            ArithmeticCircuit::new(8, [1, 1], 1, 4, reads_unset),
+            Err(CircuitError::WireUsedBeforeSet(0, 3))
+        ));
+        assert!(matches!(
+            ArithmeticCircuit::new(65, [1, 1], 0, 2, Vec::new()),
+            Err(CircuitError::UnsupportedShape(_))
+        ));
+    }
+}
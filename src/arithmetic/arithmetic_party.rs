@@ -0,0 +1,258 @@
+use crate::arithmetic::arithmetic_circuit::{ArithmeticCircuit, ArithmeticGateType};
+use crate::arithmetic::arithmetic_triple::ArithmeticMTProvider;
+use crate::party::errors::{PartyError, Phase};
+use crate::party::handshake::{self, PROTOCOL_VERSION};
+use crate::party::party_gmw::{in_phase, Messages};
+use crate::party::transport::{ChannelTransport, Envelope, Transport};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use zeroize::Zeroize;
+
+/// A party evaluating an [`ArithmeticCircuit`] on additively shared numbers, the arithmetic
+/// counterpart of [`crate::party::party_gmw::Party`]. It talks to the other party over the same
+/// transports and messages.
+pub struct ArithmeticParty<T: ArithmeticMTProvider, C: Transport = ChannelTransport> {
+    circuit: Arc<ArithmeticCircuit>,
+    transport: C,
+    pub is_p1: bool,
+    mtp: T,
+    // this party's shares of the wires, modulo 2^64
+    wires: Vec<u64>,
+    // randomness for masking the input shares
+    share_rng: StdRng,
+    // whether the protocol versions were checked with the other party
+    greeted: bool,
+    // number of the next message, see `Envelope`
+    sequence: usize,
+}
+
+impl<T: ArithmeticMTProvider, C: Transport> ArithmeticParty<T, C> {
+    pub fn new(
+        circuit: impl Into<Arc<ArithmeticCircuit>>,
+        transport: C,
+        is_p1: bool,
+        mtp: T,
+    ) -> Self {
+        let circuit = circuit.into();
+        ArithmeticParty {
+            wires: vec![0; circuit.wires_amount()],
+            circuit,
+            transport,
+            is_p1,
+            mtp,
+            share_rng: StdRng::from_entropy(),
+            greeted: false,
+            sequence: 0,
+        }
+    }
+
+    /// Sends the message of the current round, which is finished by [`ArithmeticParty::recv`].
+    fn send(&self, message: Messages, phase: Phase) -> Result<(), PartyError> {
+        self.transport
+            .send(Envelope::new(self.sequence, message))
+            .map_err(|e| in_phase(e, phase))
+    }
+
+    /// Receives the other party's message of the current round and moves on to the next round.
+    fn recv(&mut self, phase: Phase) -> Result<Messages, PartyError> {
+        let envelope = self.transport.recv().map_err(|e| in_phase(e, phase))?;
+        let message = envelope.open(self.sequence, phase)?;
+        self.sequence += 1;
+        Ok(message)
+    }
+
+    /// Evaluates the circuit with this party's input numbers and returns the outputs, reduced
+    /// modulo 2^k. Both parties have to call this at the same time.
+    pub fn execute(&mut self, input: &[u64]) -> Result<Vec<u64>, PartyError> {
+        let [width_p0, width_p1] = self.circuit.inputs();
+        let own_width = if self.is_p1 { width_p1 } else { width_p0 };
+        if input.len() != own_width {
+            return Err(PartyError::WrongInputLength(own_width, input.len()));
+        }
+        if !self.greeted {
+            handshake::exchange_versions(&self.transport, PROTOCOL_VERSION)?;
+            self.greeted = true;
+        }
+
+        self.share_inputs(input)?;
+        let mut round = 0;
+        for gate_index in 0..self.circuit.gates().len() {
+            let gate = self.circuit.gates()[gate_index];
+            self.wires[gate.output] = match gate.gate_type {
+                ArithmeticGateType::Add(a, b) => self.wires[a].wrapping_add(self.wires[b]),
+                ArithmeticGateType::Sub(a, b) => self.wires[a].wrapping_sub(self.wires[b]),
+                ArithmeticGateType::MulConst(a, k) => self.wires[a].wrapping_mul(k),
+                ArithmeticGateType::Mul(a, b) => {
+                    round += 1;
+                    let phase = Phase::Mul { round, gate_index };
+                    self.mul(self.wires[a], self.wires[b], phase)?
+                }
+            };
+        }
+        let outputs = self.reconstruct_outputs();
+        self.wires.zeroize();
+        outputs
+    }
+
+    /// Shares the inputs: each party keeps x - r of its inputs x and sends the random masks r to
+    /// the other party as its shares.
+    fn share_inputs(&mut self, input: &[u64]) -> Result<(), PartyError> {
+        let phase = Phase::InputSharing;
+        let masks: Vec<u64> = (0..input.len()).map(|_| self.share_rng.gen()).collect();
+        self.send(
+            Messages::ArithmeticShares {
+                shares: masks.clone(),
+            },
+            phase,
+        )?;
+        let Messages::ArithmeticShares { shares } = self.recv(phase)? else {
+            return Err(PartyError::UnexpectedMessage { phase });
+        };
+        let [width_p0, width_p1] = self.circuit.inputs();
+        let their_width = if self.is_p1 { width_p0 } else { width_p1 };
+        if shares.len() != their_width {
+            return Err(PartyError::UnexpectedMessage { phase });
+        }
+
+        let own: Vec<u64> = input
+            .iter()
+            .zip(&masks)
+            .map(|(&x, &r)| x.wrapping_sub(r))
+            .collect();
+        // the inputs of party 0 come first
+        let (first, second) = if self.is_p1 {
+            (shares, own)
+        } else {
+            (own, shares)
+        };
+        self.wires[..width_p0].copy_from_slice(&first);
+        self.wires[width_p0..width_p0 + width_p1].copy_from_slice(&second);
+        Ok(())
+    }
+
+    /// Multiplies the shared numbers x and y with a Beaver triple. Both parties open d = x - a and
+    /// e = y - b, which reveals nothing about x and y as a and b are random. Then
+    /// x * y = c + d * b + e * a + d * e, where only one party adds the public d * e.
+    fn mul(&mut self, x: u64, y: u64, phase: Phase) -> Result<u64, PartyError> {
+        let triple = self.mtp.get_triple();
+        let (d, e) = (x.wrapping_sub(triple.a), y.wrapping_sub(triple.b));
+        self.send(Messages::ArithmeticMul { d, e }, phase)?;
+        let Messages::ArithmeticMul {
+            d: their_d,
+            e: their_e,
+        } = self.recv(phase)?
+        else {
+            return Err(PartyError::UnexpectedMessage { phase });
+        };
+        let (d, e) = (d.wrapping_add(their_d), e.wrapping_add(their_e));
+
+        let z = triple
+            .c
+            .wrapping_add(d.wrapping_mul(triple.b))
+            .wrapping_add(e.wrapping_mul(triple.a));
+        Ok(if self.is_p1 {
+            z.wrapping_add(d.wrapping_mul(e))
+        } else {
+            z
+        })
+    }
+
+    /// Exchanges the shares of the output wires and adds them up.
+    fn reconstruct_outputs(&mut self) -> Result<Vec<u64>, PartyError> {
+        let phase = Phase::OutputExchange;
+        let start = self.circuit.wires_amount() - self.circuit.outputs();
+        let shares = self.wires[start..].to_vec();
+        self.send(Messages::ArithmeticResult(shares.clone()), phase)?;
+        let Messages::ArithmeticResult(theirs) = self.recv(phase)? else {
+            return Err(PartyError::UnexpectedMessage { phase });
+        };
+        if theirs.len() != shares.len() {
+            return Err(PartyError::UnexpectedMessage { phase });
+        }
+        Ok(shares
+            .iter()
+            .zip(&theirs)
+            .map(|(&a, &b)| self.circuit.reduce(a.wrapping_add(b)))
+            .collect())
+    }
+}
+
+impl<T: ArithmeticMTProvider, C: Transport> Drop for ArithmeticParty<T, C> {
+    fn drop(&mut self) {
+        self.wires.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArithmeticParty;
+    use crate::arithmetic::arithmetic_circuit::{
+        ArithmeticCircuit, ArithmeticGate, ArithmeticGateType,
+    };
+    use crate::arithmetic::arithmetic_triple::SeededArithmeticMTP;
+    use crate::party::transport::channel_pair;
+    use rand::rngs::StdRng;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn run(circuit: ArithmeticCircuit, x: Vec<u64>, y: Vec<u64>) -> (Vec<u64>, Vec<u64>) {
+        let circuit = Arc::new(circuit);
+        let (t0, t1) = channel_pair();
+        let mtp0: SeededArithmeticMTP<StdRng> = SeededArithmeticMTP::new([5; 32], false);
+        let mtp1: SeededArithmeticMTP<StdRng> = SeededArithmeticMTP::new([5; 32], true);
+        let mut p0 = ArithmeticParty::new(Arc::clone(&circuit), t0, false, mtp0);
+        let mut p1 = ArithmeticParty::new(circuit, t1, true, mtp1);
+        let h0 = thread::spawn(move || p0.execute(&x).unwrap());
+        let h1 = thread::spawn(move || p1.execute(&y).unwrap());
+        (h0.join().unwrap(), h1.join().unwrap())
+    }
+
+    #[test]
+    fn test_shared_multiplication() {
+        let mul = ArithmeticCircuit::new(
+            64,
+            [1, 1],
+            1,
+            3,
+            vec![ArithmeticGate {
+                gate_type: ArithmeticGateType::Mul(0, 1),
+                output: 2,
+            }],
+        )
+        .unwrap();
+        for (x, y) in [(6, 7), (0, 12345), (u64::MAX, 3), (1 << 40, 1 << 30)] {
+            let expected = vec![u64::wrapping_mul(x, y)];
+            assert_eq!(
+                run(mul.clone(), vec![x], vec![y]),
+                (expected.clone(), expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_arithmetic_circuit_in_protocol() {
+        // x0 * y0 + x1 * y1 - x0 * 5 modulo 2^16, a dot product with a correction
+        let gate = |gate_type, output| ArithmeticGate { gate_type, output };
+        let c = ArithmeticCircuit::new(
+            16,
+            [2, 2],
+            1,
+            9,
+            vec![
+                gate(ArithmeticGateType::Mul(0, 2), 4),
+                gate(ArithmeticGateType::Mul(1, 3), 5),
+                gate(ArithmeticGateType::Add(4, 5), 6),
+                gate(ArithmeticGateType::MulConst(0, 5), 7),
+                gate(ArithmeticGateType::Sub(6, 7), 8),
+            ],
+        )
+        .unwrap();
+        let (x, y) = (vec![300, 2], vec![400, 60000]);
+        let expected = c.evaluate_plain(&x, &y);
+        assert_eq!(expected, vec![(300 * 400 + 2 * 60000 - 1500) % 65536]);
+        let (out0, out1) = run(c, x, y);
+        assert_eq!(out0, expected);
+        assert_eq!(out1, expected);
+    }
+}
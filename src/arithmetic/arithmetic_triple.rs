@@ -0,0 +1,74 @@
+use rand::{Rng, SeedableRng};
+use zeroize::Zeroize;
+
+/// An arithmetic Beaver triple: random numbers a, b and c = a * b (mod 2^k), additively shared
+/// between the parties. Like [`crate::mul_triple::MulTriple`], this struct holds the shares of
+/// one party, so [a]_0 + [a]_1 = a.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Zeroize)]
+pub struct ArithmeticMulTriple {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+/// The counterpart of [`crate::mul_triple::MTProvider`] for arithmetic triples.
+pub trait ArithmeticMTProvider {
+    fn get_triple(&mut self) -> ArithmeticMulTriple;
+}
+
+/// An ArithmeticMTProvider which derives the triples from a seed both parties share. Other than
+/// for XOR shares, both parties cannot simply take the same shares: they would add up to 2a, 2b
+/// and 2c, and 2c = 4ab does not hold. Instead both parties sample the same true triple and the
+/// same share of party 0 from the seed, and party 1 takes the difference.
+///
+/// INSECURE: both parties know the true triples. This is a trusted dealer run by both parties.
+pub struct SeededArithmeticMTP<T: SeedableRng + Rng> {
+    rng: T,
+    is_p1: bool,
+}
+
+impl<T: SeedableRng + Rng> SeededArithmeticMTP<T> {
+    pub fn new(seed: T::Seed, is_p1: bool) -> Self {
+        SeededArithmeticMTP {
+            rng: T::from_seed(seed),
+            is_p1,
+        }
+    }
+}
+
+impl<T: SeedableRng + Rng> ArithmeticMTProvider for SeededArithmeticMTP<T> {
+    fn get_triple(&mut self) -> ArithmeticMulTriple {
+        let (a, b): (u64, u64) = (self.rng.gen(), self.rng.gen());
+        let share0 = ArithmeticMulTriple {
+            a: self.rng.gen(),
+            b: self.rng.gen(),
+            c: self.rng.gen(),
+        };
+        if !self.is_p1 {
+            return share0;
+        }
+        ArithmeticMulTriple {
+            a: a.wrapping_sub(share0.a),
+            b: b.wrapping_sub(share0.b),
+            c: a.wrapping_mul(b).wrapping_sub(share0.c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArithmeticMTProvider, SeededArithmeticMTP};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_seeded_triples_multiply() {
+        let mut mtp0: SeededArithmeticMTP<StdRng> = SeededArithmeticMTP::new([9; 32], false);
+        let mut mtp1: SeededArithmeticMTP<StdRng> = SeededArithmeticMTP::new([9; 32], true);
+        for _ in 0..100 {
+            let (t0, t1) = (mtp0.get_triple(), mtp1.get_triple());
+            let a = t0.a.wrapping_add(t1.a);
+            let b = t0.b.wrapping_add(t1.b);
+            assert_eq!(t0.c.wrapping_add(t1.c), a.wrapping_mul(b));
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Evaluation of arithmetic circuits, whose wires carry numbers instead of bits. The numbers are
+//! shared additively modulo 2^k: party 0 holds [x]_0 and party 1 holds [x]_1 with
+//! [x]_0 + [x]_1 = x (mod 2^k). Additions are local, multiplications use arithmetic Beaver
+//! triples, just like AND gates use multiplication triples in the boolean GMW protocol.
+pub mod arithmetic_circuit;
+pub mod arithmetic_party;
+pub mod arithmetic_triple;
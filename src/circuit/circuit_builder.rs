@@ -0,0 +1,333 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::{Circuit, Gate, GateType, Header, WireId};
+
+/// A bit of a function synthesized from a truth table, which may not need a wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bit {
+    Constant(bool),
+    Wire(WireId),
+}
+
+/// Builds a circuit gate by gate, without caring for the wire numbers of a bristol file. Values
+/// are lists of wires, least significant bit first. The wires are numbered in the order they
+/// are created and only renumbered by [`CircuitBuilder::build`], when the outputs are moved to
+/// the last wires.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBuilder {
+    gates: Vec<Gate>,
+    wires: usize,
+    inputs: Vec<usize>,
+    outputs: Vec<Vec<WireId>>,
+    // the wires holding the constants 0 and 1, once a truth table needed them
+    constants: [Option<WireId>; 2],
+}
+
+impl CircuitBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an input value of `width` bits and returns its wires. The first input belongs to
+    /// party 0, the others to party 1.
+    ///
+    /// Panics if a gate was added before, as the inputs are the first wires of a circuit.
+    pub fn input(&mut self, width: usize) -> Vec<WireId> {
+        assert!(
+            self.gates.is_empty(),
+            "inputs have to be added before gates"
+        );
+        let wires = (self.wires..self.wires + width)
+            .map(WireId::unchecked)
+            .collect();
+        self.wires += width;
+        self.inputs.push(width);
+        wires
+    }
+
+    /// Adds a gate and returns its output wire.
+    ///
+    /// Panics if the gate reads a wire which was not created by this builder.
+    pub fn gate(&mut self, gate_type: GateType) -> WireId {
+        let wires = self.wires;
+        gate_type.map_wires(|w| {
+            assert!(w.index() < wires, "wire {} does not exist yet", w);
+            w
+        });
+        let output = WireId::unchecked(self.wires);
+        self.wires += 1;
+        self.gates.push(Gate { gate_type, output });
+        output
+    }
+
+    pub fn xor(&mut self, a: WireId, b: WireId) -> WireId {
+        self.gate(GateType::XOR(a, b))
+    }
+
+    pub fn and(&mut self, a: WireId, b: WireId) -> WireId {
+        self.gate(GateType::AND(a, b))
+    }
+
+    pub fn inv(&mut self, a: WireId) -> WireId {
+        self.gate(GateType::INV(a))
+    }
+
+    /// a | b, built as (a ^ b) ^ (a & b).
+    pub fn or(&mut self, a: WireId, b: WireId) -> WireId {
+        let x = self.xor(a, b);
+        let y = self.and(a, b);
+        self.xor(x, y)
+    }
+
+    /// Synthesizes the function given by a truth table over the `inputs` wires and returns the
+    /// wires of its output bits. Entry `x * width + j` of the table is output bit j for the input
+    /// x, where bit i of x is the value of `inputs[i]`. So the table has 2^n entries per output
+    /// bit for n inputs.
+    ///
+    /// Every output bit is built by Shannon expansion, f = x ? f1 : f0 on the last input x, which
+    /// costs an AND gate per distinct pair of cofactors. This is far from optimal, but good
+    /// enough for s-boxes and other small lookups.
+    pub fn from_truth_table(
+        &mut self,
+        inputs: &[WireId],
+        table: &[bool],
+    ) -> Result<Vec<WireId>, CircuitError> {
+        let length_error = || CircuitError::TruthTableLength {
+            inputs: inputs.len(),
+            entries: table.len(),
+        };
+        let rows = u32::try_from(inputs.len())
+            .ok()
+            .and_then(|n| 1usize.checked_shl(n))
+            .ok_or_else(length_error)?;
+        if table.is_empty() || !table.len().is_multiple_of(rows) {
+            return Err(length_error());
+        }
+        // the constants are derived from a wire, so a function without inputs needs some wire
+        if inputs.is_empty() && self.wires == 0 {
+            return Err(length_error());
+        }
+
+        let width = table.len() / rows;
+        let mut outputs = Vec::with_capacity(width);
+        for j in 0..width {
+            let column: Vec<bool> = table.iter().skip(j).step_by(width).copied().collect();
+            let bit = self.shannon(inputs, &column);
+            outputs.push(self.materialize(bit));
+        }
+        Ok(outputs)
+    }
+
+    /// Builds the function with the truth table `column` over `inputs`.
+    fn shannon(&mut self, inputs: &[WireId], column: &[bool]) -> Bit {
+        let Some((&x, rest)) = inputs.split_last() else {
+            return Bit::Constant(column[0]);
+        };
+        let (low, high) = column.split_at(column.len() / 2);
+        let f0 = self.shannon(rest, low);
+        let f1 = self.shannon(rest, high);
+        match (f0, f1) {
+            _ if f0 == f1 => f0,
+            (Bit::Constant(false), Bit::Constant(true)) => Bit::Wire(x),
+            (Bit::Constant(true), Bit::Constant(false)) => Bit::Wire(self.inv(x)),
+            (Bit::Constant(false), Bit::Wire(f1)) => Bit::Wire(self.and(x, f1)),
+            _ => {
+                // x ? f1 : f0 = f0 ^ (x & (f0 ^ f1))
+                let f0 = self.materialize(f0);
+                let f1 = self.materialize(f1);
+                let differs = self.xor(f0, f1);
+                let chosen = self.and(x, differs);
+                Bit::Wire(self.xor(f0, chosen))
+            }
+        }
+    }
+
+    /// Returns the wire of a bit, creating the wire of a constant on its first use.
+    fn materialize(&mut self, bit: Bit) -> WireId {
+        let value = match bit {
+            Bit::Wire(wire) => return wire,
+            Bit::Constant(value) => value,
+        };
+        if let Some(wire) = self.constants[usize::from(value)] {
+            return wire;
+        }
+        // 0 = w ^ w for any wire w, and 1 = !0
+        let zero = match self.constants[0] {
+            Some(zero) => zero,
+            None => self.xor(WireId::unchecked(0), WireId::unchecked(0)),
+        };
+        self.constants[0] = Some(zero);
+        if value {
+            self.constants[1] = Some(self.inv(zero));
+        }
+        self.constants[usize::from(value)].unwrap()
+    }
+
+    /// Adds an output value. The outputs are in the order they are added.
+    pub fn output(&mut self, value: Vec<WireId>) {
+        self.outputs.push(value);
+    }
+
+    /// Number of output values added so far.
+    pub fn outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Moves the outputs to the last wires and returns the circuit.
+    pub fn build(mut self) -> Circuit {
+        // An output bit needs a wire of its own at the end. A bit which is an input, or which is
+        // also another output bit, is copied there with two INV gates.
+        let inputs_sum: usize = self.inputs.iter().sum();
+        let mut claimed = vec![false; self.wires];
+        let mut output_wires = Vec::new();
+        for value in self.outputs.clone() {
+            for mut wire in value {
+                if wire.index() < inputs_sum || claimed[wire.index()] {
+                    let inverted = self.inv(wire);
+                    wire = self.inv(inverted);
+                    claimed.resize(self.wires, false);
+                }
+                claimed[wire.index()] = true;
+                output_wires.push(wire);
+            }
+        }
+
+        // renumber: the inputs stay, the other gates follow in order, the outputs come last
+        let mut renumbered: Vec<WireId> = (0..self.wires).map(WireId::unchecked).collect();
+        let mut next = inputs_sum;
+        for gate in self.gates.iter().filter(|g| !claimed[g.output.index()]) {
+            renumbered[gate.output.index()] = WireId::unchecked(next);
+            next += 1;
+        }
+        for wire in &output_wires {
+            renumbered[wire.index()] = WireId::unchecked(next);
+            next += 1;
+        }
+        let gates: Vec<Gate> = self
+            .gates
+            .iter()
+            .map(|gate| Gate {
+                gate_type: gate.gate_type.map_wires(|w| renumbered[w.index()]),
+                output: renumbered[gate.output.index()],
+            })
+            .collect();
+
+        Circuit::new(
+            Header {
+                gates_amount: gates.len(),
+                wires_amount: self.wires,
+                niv: self.inputs,
+                nov: self.outputs.iter().map(|value| value.len()).collect(),
+            },
+            gates,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitBuilder;
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_parser::{GateType, WireId};
+
+    #[test]
+    fn test_builder() {
+        let mut builder = CircuitBuilder::new();
+        let a = builder.input(2);
+        let b = builder.input(1);
+        let x = builder.xor(a[0], b[0]);
+        let y = builder.or(a[1], x);
+        // an input as output is copied to a wire of its own
+        builder.output(vec![y, a[0]]);
+        let c = builder.build();
+        c.validate().unwrap();
+        assert_eq!(c.header.niv, vec![2, 1]);
+        assert_eq!(c.header.nov, vec![2]);
+        assert_eq!(c.header.wires_amount, 9);
+
+        for input in 0..8u8 {
+            let bits: Vec<bool> = (0..3).map(|i| input >> i & 1 == 1).collect();
+            let expected = vec![bits[1] | (bits[0] ^ bits[2]), bits[0]];
+            assert_eq!(c.evaluate_plain(&bits).unwrap(), expected);
+        }
+    }
+
+    /// Builds a circuit with one input of `n` bits and the truth table as output, and checks it
+    /// against the table for every input.
+    fn check_truth_table(n: usize, table: &[bool]) {
+        let mut builder = CircuitBuilder::new();
+        let inputs = builder.input(n);
+        let outputs = builder.from_truth_table(&inputs, table).unwrap();
+        let width = outputs.len();
+        builder.output(outputs);
+        let c = builder.build();
+        c.validate().unwrap();
+        for x in 0..1usize << n {
+            let bits: Vec<bool> = (0..n).map(|i| x >> i & 1 == 1).collect();
+            let expected = &table[x * width..(x + 1) * width];
+            assert_eq!(c.evaluate_plain(&bits).unwrap(), expected, "input {}", x);
+        }
+    }
+
+    #[test]
+    fn test_truth_table_majority() {
+        let table: Vec<bool> = (0..8u32).map(|x| x.count_ones() >= 2).collect();
+        check_truth_table(3, &table);
+    }
+
+    #[test]
+    fn test_truth_table_nibble_swap() {
+        let mut table = Vec::new();
+        for x in 0..256u32 {
+            let swapped = (x >> 4) | ((x & 0xf) << 4);
+            table.extend((0..8).map(|j| swapped >> j & 1 == 1));
+        }
+        check_truth_table(8, &table);
+    }
+
+    #[test]
+    fn test_truth_table_constants() {
+        // constant outputs, an inverted input and an output which only depends on one input
+        let mut table = Vec::new();
+        for x in 0..4 {
+            table.extend([false, true, x & 1 == 0, x & 2 == 2]);
+        }
+        check_truth_table(2, &table);
+    }
+
+    #[test]
+    fn test_truth_table_length() {
+        let mut builder = CircuitBuilder::new();
+        let inputs = builder.input(3);
+        for entries in [0, 7, 12] {
+            let e = builder
+                .from_truth_table(&inputs, &vec![false; entries])
+                .unwrap_err();
+            assert!(matches!(
+                e,
+                CircuitError::TruthTableLength { inputs: 3, entries: n } if n == entries
+            ));
+        }
+        assert_eq!(
+            builder
+                .from_truth_table(&inputs, &[true; 7])
+                .unwrap_err()
+                .to_string(),
+            "A truth table of 3 input(s) needs 2^3 entries per output bit, but it has 7 entries"
+        );
+        assert_eq!(
+            builder
+                .from_truth_table(&inputs, &[true; 16])
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "wire 3 does not exist yet")]
+    fn test_builder_unknown_wire() {
+        let mut builder = CircuitBuilder::new();
+        builder.input(2);
+        builder.gate(GateType::AND(WireId::unchecked(0), WireId::unchecked(3)));
+    }
+}
@@ -0,0 +1,328 @@
+use crate::circuit::circuit_parser::{Circuit, Gate, GateType, WireId};
+use crate::circuit::circuit_stats::CircuitStats;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// Number of differences the `Display` of a [`CircuitDiff`] lists at most.
+pub const DEFAULT_DIFF_LIMIT: usize = 20;
+
+/// Number of random inputs [`Circuit::diff`] evaluates both circuits on to compare what they
+/// compute.
+pub const DIFF_EQUIV_SAMPLES: usize = 100;
+
+/// A header field which differs between two circuits, with the value of the first circuit and
+/// the value of the second one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderChange {
+    Gates(usize, usize),
+    Wires(usize, usize),
+    InputValues(Vec<usize>, Vec<usize>),
+    OutputValues(Vec<usize>, Vec<usize>),
+}
+
+/// The differences between two circuits, see [`Circuit::diff`]. The gates are aligned by their
+/// output wire and listed in the order of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CircuitDiff {
+    pub header: Vec<HeaderChange>,
+    /// Gates of the first circuit whose output wire no gate of the second circuit writes.
+    pub removed: Vec<Gate>,
+    /// Gates of the second circuit whose output wire no gate of the first circuit writes.
+    pub added: Vec<Gate>,
+    /// Gates writing the same output wire, but with another type or other inputs. The gate of the
+    /// first circuit comes first.
+    pub changed: Vec<(Gate, Gate)>,
+    /// How the size, depth and function changed, which is what matters after an optimization
+    /// that renumbers the wires and thereby changes almost every gate.
+    pub summary: DiffSummary,
+}
+
+/// The size and depth of both circuits, the first one coming first, and whether they compute
+/// the same function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffSummary {
+    pub stats: [CircuitStats; 2],
+    pub depths: [usize; 2],
+    pub wires: [usize; 2],
+    /// Whether the circuits agree on [`DIFF_EQUIV_SAMPLES`] random inputs. A `true` is no proof,
+    /// but a `false` is. `None` if they have different input or output widths, or if one of them
+    /// cannot be evaluated, e.g. because it reads a wire no gate writes.
+    pub equivalent: Option<bool>,
+}
+
+fn delta([first, second]: [usize; 2]) -> isize {
+    second as isize - first as isize
+}
+
+impl DiffSummary {
+    /// How many AND gates the second circuit has more than the first, negative if it has fewer.
+    pub fn and_delta(&self) -> isize {
+        delta(self.stats.map(|s| s.and))
+    }
+
+    pub fn xor_delta(&self) -> isize {
+        delta(self.stats.map(|s| s.xor))
+    }
+
+    pub fn inv_delta(&self) -> isize {
+        delta(self.stats.map(|s| s.inv))
+    }
+
+    pub fn depth_delta(&self) -> isize {
+        delta(self.depths)
+    }
+
+    pub fn wire_delta(&self) -> isize {
+        delta(self.wires)
+    }
+}
+
+impl CircuitDiff {
+    /// True if the circuits are the same, up to the order of their gates.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of differences, every header field and gate counting once.
+    pub fn len(&self) -> usize {
+        self.header.len() + self.removed.len() + self.added.len() + self.changed.len()
+    }
+
+    /// Lists at most `limit` differences when displayed.
+    pub fn listing(&self, limit: usize) -> DiffListing<'_> {
+        DiffListing { diff: self, limit }
+    }
+}
+
+impl Circuit {
+    /// Compares this circuit with `other`, e.g. to see what a converter or optimizer changed.
+    pub fn diff(&self, other: &Circuit) -> CircuitDiff {
+        let (ours, theirs) = (&self.header, &other.header);
+        let mut header = Vec::new();
+        if ours.gates_amount != theirs.gates_amount {
+            header.push(HeaderChange::Gates(ours.gates_amount, theirs.gates_amount));
+        }
+        if ours.wires_amount != theirs.wires_amount {
+            header.push(HeaderChange::Wires(ours.wires_amount, theirs.wires_amount));
+        }
+        if ours.niv != theirs.niv {
+            header.push(HeaderChange::InputValues(
+                ours.niv.clone(),
+                theirs.niv.clone(),
+            ));
+        }
+        if ours.nov != theirs.nov {
+            header.push(HeaderChange::OutputValues(
+                ours.nov.clone(),
+                theirs.nov.clone(),
+            ));
+        }
+
+        let by_output = |c: &Circuit| -> BTreeMap<WireId, GateType> {
+            c.gates
+                .iter()
+                .map(|g| (g.output, g.gate_type.clone()))
+                .collect()
+        };
+        let (ours, mut theirs) = (by_output(self), by_output(other));
+        let mut diff = CircuitDiff {
+            header,
+            summary: DiffSummary {
+                stats: [self.stats(), other.stats()],
+                depths: [self.depth(), other.depth()],
+                wires: [self.header.wires_amount, other.header.wires_amount],
+                equivalent: self.equivalent(other, DIFF_EQUIV_SAMPLES).ok(),
+            },
+            ..CircuitDiff::default()
+        };
+        for (output, gate_type) in ours {
+            match theirs.remove(&output) {
+                None => diff.removed.push(Gate { gate_type, output }),
+                Some(other_type) if other_type != gate_type => diff.changed.push((
+                    Gate { gate_type, output },
+                    Gate {
+                        gate_type: other_type,
+                        output,
+                    },
+                )),
+                Some(_) => {}
+            }
+        }
+        diff.added = theirs
+            .into_iter()
+            .map(|(output, gate_type)| Gate { gate_type, output })
+            .collect();
+        diff
+    }
+}
+
+/// Displays a [`CircuitDiff`] like a unified diff, with lines of the first circuit starting with
+/// `-` and lines of the second one with `+`. Only the first `limit` differences are listed.
+pub struct DiffListing<'a> {
+    diff: &'a CircuitDiff,
+    limit: usize,
+}
+
+fn gate_line(gate: &Gate) -> String {
+    format!("wire {}: {}", gate.output, gate.gate_type)
+}
+
+impl Display for DiffListing<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let widths = |values: &[usize]| {
+            let widths: Vec<String> = values.iter().map(|w| w.to_string()).collect();
+            widths.join(", ")
+        };
+        let header = self.diff.header.iter().map(|change| match change {
+            HeaderChange::Gates(a, b) => {
+                (Some(format!("gates: {}", a)), Some(format!("gates: {}", b)))
+            }
+            HeaderChange::Wires(a, b) => {
+                (Some(format!("wires: {}", a)), Some(format!("wires: {}", b)))
+            }
+            HeaderChange::InputValues(a, b) => (
+                Some(format!("input bits: {}", widths(a))),
+                Some(format!("input bits: {}", widths(b))),
+            ),
+            HeaderChange::OutputValues(a, b) => (
+                Some(format!("output bits: {}", widths(a))),
+                Some(format!("output bits: {}", widths(b))),
+            ),
+        });
+        let removed = self.diff.removed.iter().map(|g| (Some(gate_line(g)), None));
+        let added = self.diff.added.iter().map(|g| (None, Some(gate_line(g))));
+        let changed = self.diff.changed.iter();
+        let changed = changed.map(|(a, b)| (Some(gate_line(a)), Some(gate_line(b))));
+
+        let entries = header.chain(removed).chain(added).chain(changed);
+        for (ours, theirs) in entries.take(self.limit) {
+            if let Some(line) = ours {
+                writeln!(f, "-{}", line)?;
+            }
+            if let Some(line) = theirs {
+                writeln!(f, "+{}", line)?;
+            }
+        }
+        if self.diff.len() > self.limit {
+            writeln!(
+                f,
+                "... and {} more difference(s)",
+                self.diff.len() - self.limit
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DiffSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let line = |f: &mut Formatter<'_>, name: &str, [a, b]: [usize; 2]| {
+            writeln!(f, "{}: {} -> {} ({:+})", name, a, b, delta([a, b]))
+        };
+        line(f, "AND gates", self.stats.map(|s| s.and))?;
+        line(f, "XOR gates", self.stats.map(|s| s.xor))?;
+        line(f, "INV gates", self.stats.map(|s| s.inv))?;
+        line(f, "depth", self.depths)?;
+        line(f, "wires", self.wires)?;
+        match self.equivalent {
+            Some(true) => writeln!(
+                f,
+                "equivalent: yes, on {} random inputs",
+                DIFF_EQUIV_SAMPLES
+            ),
+            Some(false) => writeln!(f, "equivalent: no"),
+            None => writeln!(f, "equivalent: unknown, the circuits cannot be compared"),
+        }
+    }
+}
+
+impl Display for CircuitDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.listing(DEFAULT_DIFF_LIMIT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderChange;
+    use crate::circuit::circuit_parser::{Circuit, Gate, GateType, WireId};
+
+    #[test]
+    fn test_diff_mutated_adder() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+        assert!(adder.diff(&adder).is_empty());
+
+        let mut mutated = adder.clone();
+        let changed = mutated.gates[10].clone();
+        let removed = mutated.gates.remove(20);
+        mutated.gates[10].gate_type = GateType::AND(WireId::unchecked(0), WireId::unchecked(64));
+        mutated.gates.push(Gate {
+            gate_type: GateType::INV(WireId::unchecked(3)),
+            output: WireId::unchecked(mutated.header.wires_amount),
+        });
+        mutated.header.wires_amount += 1;
+
+        let diff = adder.diff(&mutated);
+        assert_eq!(diff.header, vec![HeaderChange::Wires(504, 505)]);
+        assert_eq!(diff.removed, vec![removed.clone()]);
+        assert_eq!(diff.added, vec![mutated.gates.last().unwrap().clone()]);
+        assert_eq!(
+            diff.changed,
+            vec![(changed.clone(), mutated.gates[10].clone())]
+        );
+        assert_eq!(diff.len(), 4);
+        assert_eq!(diff.summary.and_delta(), 1);
+        assert_eq!(diff.summary.wire_delta(), 1);
+        // the removed gate leaves a wire unset
+        assert_eq!(diff.summary.equivalent, None);
+
+        let listing = diff.to_string();
+        assert!(listing.starts_with("-wires: 504\n+wires: 505\n"));
+        assert!(listing.contains(&format!(
+            "-wire {}: {}\n",
+            removed.output, removed.gate_type
+        )));
+        assert!(listing.contains("+wire 504: INV 3\n"));
+        assert!(listing.contains(&format!(
+            "-wire {0}: {1}\n+wire {0}: AND 0 64\n",
+            changed.output, changed.gate_type
+        )));
+    }
+
+    #[test]
+    fn test_diff_listing_is_capped() {
+        let a = Circuit::parse("2 5\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n2 1 0 2 4 XOR\n").unwrap();
+        let b = Circuit::parse("2 6\n2 1 1\n1 2\n\n2 1 0 1 3 XOR\n1 1 0 5 INV\n").unwrap();
+        let diff = a.diff(&b);
+        assert_eq!(diff.len(), 6);
+        assert_eq!(
+            diff.listing(3).to_string(),
+            "-wires: 5\n+wires: 6\n-output bits: 1\n+output bits: 2\n-wire 2: AND 0 1\n\
+             ... and 3 more difference(s)\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_optimized() {
+        // the first two AND gates are dead, only the XOR gate computes the output
+        let c = Circuit::parse("3 5\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n2 1 2 1 3 AND\n2 1 0 1 4 XOR\n")
+            .unwrap();
+        let summary = c.diff(&c.optimize()).summary;
+        assert_eq!(summary.and_delta(), -2);
+        assert_eq!(summary.xor_delta(), 0);
+        assert_eq!(summary.depth_delta(), -1);
+        assert_eq!(summary.wire_delta(), -2);
+        assert_eq!(summary.equivalent, Some(true));
+        assert!(summary.to_string().starts_with("AND gates: 2 -> 0 (-2)\n"));
+
+        let adder = Circuit::parse(&std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap());
+        let adder = adder.unwrap();
+        let summary = adder.diff(&adder.optimize()).summary;
+        assert!(summary.and_delta() <= 0);
+        assert_eq!(summary.equivalent, Some(true));
+
+        let one_bit = Circuit::parse("1 3\n1 1\n1 1\n\n1 1 0 2 INV\n").unwrap();
+        assert_eq!(c.diff(&one_bit).summary.equivalent, None);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::{Circuit, GateType};
+use std::fmt::Write;
+
+/// What [`Circuit::to_dot_with`] draws of a circuit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotOptions {
+    /// Draw at most this many gates, the first ones in gate order.
+    pub max_gates: Option<usize>,
+    /// Only draw the gates these wires depend on, see [`Circuit::extract_cone`]. All outputs are
+    /// drawn if it is empty.
+    pub cone: Vec<usize>,
+}
+
+/// Quotes a DOT identifier or label.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Circuit {
+    /// Draws the circuit as a Graphviz digraph, e.g. for `dot -Tsvg`. Input wires are boxes,
+    /// gates are ellipses labelled with their type and index, and output wires are filled
+    /// circles. An edge leads from the node assigning a wire to every node reading it.
+    pub fn to_dot(&self) -> String {
+        self.draw(&DotOptions::default())
+    }
+
+    /// Draws the circuit like [`Circuit::to_dot`], restricted by `options`. The wire and gate
+    /// numbers of a cone are those of the extracted circuit.
+    ///
+    /// Fails like [`Circuit::extract_cone`] if a wire of the cone is not assigned by a gate or
+    /// given twice.
+    pub fn to_dot_with(&self, options: &DotOptions) -> Result<String, CircuitError> {
+        if options.cone.is_empty() {
+            return Ok(self.draw(options));
+        }
+        let (cone, _) = self.extract_cone(&options.cone)?;
+        Ok(cone.draw(options))
+    }
+
+    /// Draws the whole circuit, with at most the maximum number of gates of `options`.
+    fn draw(&self, options: &DotOptions) -> String {
+        let drawn = options
+            .max_gates
+            .unwrap_or(usize::MAX)
+            .min(self.gates.len());
+        let inputs = self.get_niv_sum();
+        let outputs = self.get_output_wires();
+        // the node assigning each wire, if it is drawn
+        let mut source: Vec<Option<String>> = vec![None; self.header.wires_amount];
+
+        let mut out = String::from("digraph circuit {\n    rankdir=LR;\n");
+        for (wire, node) in source.iter_mut().enumerate().take(inputs) {
+            let id = quote(&format!("i{}", wire));
+            let label = quote(&format!("input wire {}", wire));
+            writeln!(out, "    {} [label={}, shape=box];", id, label).unwrap();
+            *node = Some(id);
+        }
+
+        for (index, gate) in self.gates[..drawn].iter().enumerate() {
+            let id = quote(&format!("g{}", index));
+            let (name, operands) = match gate.gate_type {
+                GateType::XOR(a, b) => ("XOR", vec![a, b]),
+                GateType::AND(a, b) => ("AND", vec![a, b]),
+                GateType::INV(a) => ("INV", vec![a]),
+            };
+            let label = quote(&format!("{} #{}", name, index));
+            writeln!(out, "    {} [label={}];", id, label).unwrap();
+            for wire in operands {
+                if let Some(from) = &source[wire.index()] {
+                    writeln!(out, "    {} -> {};", from, id).unwrap();
+                }
+            }
+            source[gate.output.index()] = Some(id);
+        }
+
+        for (wire, from) in source.iter().enumerate().skip(outputs) {
+            let Some(from) = from else { continue };
+            let id = quote(&format!("o{}", wire));
+            let label = quote(&format!("output wire {}", wire));
+            writeln!(
+                out,
+                "    {} [label={}, shape=circle, style=filled, fillcolor=lightblue];",
+                id, label
+            )
+            .unwrap();
+            writeln!(out, "    {} -> {};", from, id).unwrap();
+        }
+
+        if drawn < self.gates.len() {
+            let label = quote(&format!("... {} more gate(s)", self.gates.len() - drawn));
+            writeln!(out, "    \"truncated\" [label={}, shape=plaintext];", label).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DotOptions;
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_parser::Circuit;
+
+    /// Checks that the braces and brackets match and every quote is closed.
+    fn assert_balanced(dot: &str) {
+        let mut depth = 0i32;
+        let mut quoted = false;
+        let mut escaped = false;
+        for c in dot.chars() {
+            match (quoted, c) {
+                (true, _) if escaped => escaped = false,
+                (true, '\\') => escaped = true,
+                (_, '"') => quoted = !quoted,
+                (false, '{' | '[') => depth += 1,
+                (false, '}' | ']') => depth -= 1,
+                _ => {}
+            }
+            assert!(depth >= 0);
+        }
+        assert_eq!(depth, 0);
+        assert!(!quoted);
+    }
+
+    #[test]
+    fn test_dot_single_and() {
+        let contents = std::fs::read_to_string("test_circuits/1_And.txt").unwrap();
+        let dot = Circuit::parse(&contents).unwrap().to_dot();
+        assert_balanced(&dot);
+        assert!(dot.starts_with("digraph circuit {\n"));
+        assert!(dot.contains("\"i0\" [label=\"input wire 0\", shape=box];"));
+        assert!(dot.contains("\"i1\" [label=\"input wire 1\", shape=box];"));
+        assert!(dot.contains("\"g0\" [label=\"AND #0\"];"));
+        assert!(dot.contains("\"o2\" [label=\"output wire 2\", shape=circle"));
+        for edge in [
+            "\"i0\" -> \"g0\";",
+            "\"i1\" -> \"g0\";",
+            "\"g0\" -> \"o2\";",
+        ] {
+            assert!(dot.contains(edge), "missing edge {}", edge);
+        }
+        assert_eq!(dot.matches("->").count(), 3);
+    }
+
+    #[test]
+    fn test_dot_options() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+
+        let dot = adder
+            .to_dot_with(&DotOptions {
+                max_gates: Some(10),
+                ..DotOptions::default()
+            })
+            .unwrap();
+        assert_balanced(&dot);
+        assert!(dot.contains("\"g9\""));
+        assert!(!dot.contains("\"g10\""));
+        assert!(dot.contains("... 366 more gate(s)"));
+
+        // the lowest output bit only depends on two inputs
+        let low_bit = adder.header.wires_amount - 64;
+        let dot = adder
+            .to_dot_with(&DotOptions {
+                cone: vec![low_bit],
+                ..DotOptions::default()
+            })
+            .unwrap();
+        assert_balanced(&dot);
+        assert_eq!(dot.matches("shape=box").count(), 2);
+        assert_eq!(dot.matches("shape=circle").count(), 1);
+
+        // a wire past the end of the circuit has no cone to draw
+        let result = adder.to_dot_with(&DotOptions {
+            cone: vec![adder.header.wires_amount],
+            ..DotOptions::default()
+        });
+        assert!(matches!(result, Err(CircuitError::NotAssignedByGate(_))));
+    }
+}
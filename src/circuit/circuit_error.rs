@@ -1,5 +1,7 @@
 pub use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum CircuitError {
@@ -9,10 +11,63 @@ pub enum CircuitError {
     ParsingNivError(usize, usize),
     EmptyLineMissingError,
     NotAGateError(String),
-    WrongGateAmount(usize, usize),
+    TooManyGates(usize, usize),
+    TooFewGates(usize, usize),
+    WrongGateAmount(usize),
+    WrongInputLength(usize, usize),
+    WireNotSetError(usize),
+    InvalidWiring(usize, usize),
+    InputWidthMismatch(usize, usize),
+    OutputWidthMismatch(usize, usize),
+    UnsupportedShape(String),
+    WireOutOfRange(usize, usize),
+    WireIndexOutOfRange {
+        line: usize,
+        wire: usize,
+        wires_amount: usize,
+    },
+    WireUsedBeforeSet(usize, usize),
+    WireAssignedTwice(usize, usize),
+    WireCountTooSmall {
+        declared: usize,
+        needed: usize,
+    },
+    TruthTableLength {
+        inputs: usize,
+        entries: usize,
+    },
+    LimitExceeded {
+        what: &'static str,
+        limit: usize,
+        requested: usize,
+    },
+    ExpressionError {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Decompression {
+        path: PathBuf,
+        source: io::Error,
+    },
+    NotAssignedByGate(usize),
+    WireGivenTwice(usize),
 }
 
-impl Error for CircuitError {}
+impl Error for CircuitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CircuitError::Io { source, .. } | CircuitError::Decompression { source, .. } => {
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+}
 
 impl Display for CircuitError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -48,13 +103,146 @@ impl Display for CircuitError {
             CircuitError::NotAGateError(g) => {
                 write!(f, "{} is not a valid gate.", g)
             }
-            CircuitError::WrongGateAmount(expected, actual) => {
+            CircuitError::TooManyGates(expected, line) => {
+                write!(
+                    f,
+                    "Too many Gates. Expected: {}, but line {} contains another gate",
+                    expected, line
+                )
+            }
+            CircuitError::TooFewGates(expected, actual) => {
+                write!(
+                    f,
+                    "Too few Gates. Expected: {}, actually: {}",
+                    expected, actual
+                )
+            }
+            CircuitError::WrongGateAmount(expected) => {
+                write!(
+                    f,
+                    "The header declares {} gate(s), but no gates follow it",
+                    expected
+                )
+            }
+            CircuitError::WrongInputLength(expected, actual) => {
                 write!(
                     f,
-                    "Wrong amount of Gates. Expected: {}, actually: {}",
+                    "Wrong amount of input bits. Expected: {}, actually: {}",
                     expected, actual
                 )
             }
+            CircuitError::WireNotSetError(wire) => {
+                write!(f, "Wire {} has not been set yet", wire)
+            }
+            CircuitError::InvalidWiring(output, input) => {
+                write!(
+                    f,
+                    "Cannot connect output bit {} to input bit {}",
+                    output, input
+                )
+            }
+            CircuitError::InputWidthMismatch(left, right) => {
+                write!(
+                    f,
+                    "The circuits have different input widths: {} and {} bits",
+                    left, right
+                )
+            }
+            CircuitError::OutputWidthMismatch(left, right) => {
+                write!(
+                    f,
+                    "The circuits have different output widths: {} and {} bits",
+                    left, right
+                )
+            }
+            CircuitError::UnsupportedShape(reason) => {
+                write!(f, "The circuit is not supported, because {}", reason)
+            }
+            CircuitError::WireOutOfRange(gate, wire) => {
+                write!(
+                    f,
+                    "Gate {} refers to wire {}, which does not exist",
+                    gate, wire
+                )
+            }
+            CircuitError::WireIndexOutOfRange {
+                line,
+                wire,
+                wires_amount,
+            } => {
+                write!(
+                    f,
+                    "The gate in line {} refers to wire {}, but the header declares {} wire(s)",
+                    line, wire, wires_amount
+                )
+            }
+            CircuitError::WireUsedBeforeSet(gate, wire) => {
+                write!(
+                    f,
+                    "Gate {} reads wire {}, which is not set by an earlier gate",
+                    gate, wire
+                )
+            }
+            CircuitError::WireAssignedTwice(gate, wire) => {
+                write!(
+                    f,
+                    "Gate {} assigns wire {}, which is already set",
+                    gate, wire
+                )
+            }
+            CircuitError::WireCountTooSmall { declared, needed } => {
+                write!(
+                    f,
+                    "The header declares {} wires, but the gates use {} wires",
+                    declared, needed
+                )
+            }
+            CircuitError::TruthTableLength { inputs, entries } => {
+                write!(
+                    f,
+                    "A truth table of {} input(s) needs 2^{} entries per output bit, but it has {} entries",
+                    inputs, inputs, entries
+                )
+            }
+            CircuitError::LimitExceeded {
+                what,
+                limit,
+                requested,
+            } => {
+                write!(
+                    f,
+                    "The circuit declares {} {}, but at most {} are allowed",
+                    requested, what, limit
+                )
+            }
+            CircuitError::ExpressionError {
+                line,
+                column,
+                message,
+            } => {
+                write!(
+                    f,
+                    "Parsing failed at line {}, column {}: {}",
+                    line, column, message
+                )
+            }
+            CircuitError::Io { path, source } => {
+                write!(f, "Could not read {}: {}", path.display(), source)
+            }
+            CircuitError::Decompression { path, source } => {
+                write!(
+                    f,
+                    "Could not decompress the gzip file {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            CircuitError::NotAssignedByGate(wire) => {
+                write!(f, "Wire {} is not assigned by a gate", wire)
+            }
+            CircuitError::WireGivenTwice(wire) => {
+                write!(f, "Wire {} is given twice", wire)
+            }
         }
     }
 }
@@ -0,0 +1,307 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::{Circuit, GateType};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Circuits with at most this many input bits can be checked with [`EquivCheck::Exhaustive`].
+pub const EXHAUSTIVE_EQUIV_BITS: usize = 20;
+
+/// How [`Circuit::equivalent_to`] chooses the inputs both circuits are evaluated on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquivCheck {
+    /// All inputs, for circuits with at most [`EXHAUSTIVE_EQUIV_BITS`] input bits.
+    Exhaustive,
+    /// `samples` random inputs, drawn from an RNG seeded with `seed`.
+    Random { samples: usize, seed: u64 },
+}
+
+/// The result of [`Circuit::equivalent_to`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    /// The circuits agree on all checked inputs.
+    Equivalent,
+    /// An input on which the outputs of the circuits differ.
+    Counterexample(Vec<bool>),
+}
+
+impl Equivalence {
+    pub fn is_equivalent(&self) -> bool {
+        matches!(self, Equivalence::Equivalent)
+    }
+}
+
+impl Circuit {
+    /// Evaluates the circuit in the clear, without any secret sharing. The input contains the bits
+    /// of all input values in header order, the output the bits of all output values.
+    /// This is useful as a reference to check the results of the GMW protocol against.
+    pub fn evaluate_plain(&self, input: &[bool]) -> Result<Vec<bool>, CircuitError> {
+        let input_wires = self.get_niv_sum();
+        if input.len() != input_wires {
+            return Err(CircuitError::WrongInputLength(input_wires, input.len()));
+        }
+
+        let mut wires: Vec<Option<bool>> = vec![None; self.header.wires_amount];
+        for (wire, &bit) in wires.iter_mut().zip(input) {
+            *wire = Some(bit);
+        }
+
+        let get =
+            |wires: &[Option<bool>], w: usize| wires[w].ok_or(CircuitError::WireNotSetError(w));
+
+        for gate in &self.gates {
+            let value = match gate.gate_type {
+                GateType::XOR(a, b) => get(&wires, a.index())? ^ get(&wires, b.index())?,
+                GateType::AND(a, b) => get(&wires, a.index())? & get(&wires, b.index())?,
+                GateType::INV(a) => !get(&wires, a.index())?,
+            };
+            wires[gate.output.index()] = Some(value);
+        }
+
+        (self.get_output_wires()..self.header.wires_amount)
+            .map(|w| get(&wires, w))
+            .collect()
+    }
+
+    /// Checks whether this circuit computes the same function as `other`, by evaluating both on
+    /// `trials` random inputs. A `true` result is no proof, but a `false` one is. Fails if the
+    /// circuits have different input or output widths.
+    pub fn equivalent(&self, other: &Circuit, trials: usize) -> Result<bool, CircuitError> {
+        Ok(self.find_difference(other, trials)?.is_none())
+    }
+
+    /// Like [`Circuit::equivalent`], but returns the first random input on which the outputs of
+    /// both circuits differ.
+    pub fn find_difference(
+        &self,
+        other: &Circuit,
+        trials: usize,
+    ) -> Result<Option<Vec<bool>>, CircuitError> {
+        let strategy = EquivCheck::Random {
+            samples: trials,
+            seed: rand::thread_rng().gen(),
+        };
+        match self.equivalent_to(other, strategy)? {
+            Equivalence::Equivalent => Ok(None),
+            Equivalence::Counterexample(input) => Ok(Some(input)),
+        }
+    }
+
+    /// Checks whether this circuit computes the same function as `other` on the inputs chosen by
+    /// `strategy`, and returns the first input on which they differ. Only the exhaustive check
+    /// proves equivalence. Fails if the circuits have different input or output widths, or if
+    /// they are too wide for the exhaustive check.
+    pub fn equivalent_to(
+        &self,
+        other: &Circuit,
+        strategy: EquivCheck,
+    ) -> Result<Equivalence, CircuitError> {
+        if self.get_niv_sum() != other.get_niv_sum() {
+            return Err(CircuitError::InputWidthMismatch(
+                self.get_niv_sum(),
+                other.get_niv_sum(),
+            ));
+        }
+        if self.get_nov_sum() != other.get_nov_sum() {
+            return Err(CircuitError::OutputWidthMismatch(
+                self.get_nov_sum(),
+                other.get_nov_sum(),
+            ));
+        }
+
+        let differs = |input: &[bool]| -> Result<bool, CircuitError> {
+            Ok(self.evaluate_plain(input)? != other.evaluate_plain(input)?)
+        };
+        match find_input(self.get_niv_sum(), strategy, differs)?.0 {
+            Some(input) => Ok(Equivalence::Counterexample(input)),
+            None => Ok(Equivalence::Equivalent),
+        }
+    }
+}
+
+/// Runs `differs` on the inputs of `width` bits chosen by `strategy`, in order, until it returns
+/// true for one of them. Returns that input, if any, and the number of inputs checked. The
+/// exhaustive check counts the inputs up from all zeros, with the first input bit the lowest.
+pub(crate) fn find_input(
+    width: usize,
+    strategy: EquivCheck,
+    mut differs: impl FnMut(&[bool]) -> Result<bool, CircuitError>,
+) -> Result<(Option<Vec<bool>>, u64), CircuitError> {
+    let mut checked = 0;
+    match strategy {
+        EquivCheck::Exhaustive => {
+            if width > EXHAUSTIVE_EQUIV_BITS {
+                return Err(CircuitError::UnsupportedShape(format!(
+                    "its {} input bits are too many to check all inputs (at most {})",
+                    width, EXHAUSTIVE_EQUIV_BITS
+                )));
+            }
+            for v in 0..1u32 << width {
+                let input: Vec<bool> = (0..width).map(|i| (v >> i) & 1 == 1).collect();
+                checked += 1;
+                if differs(&input)? {
+                    return Ok((Some(input), checked));
+                }
+            }
+        }
+        EquivCheck::Random { samples, seed } => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for _ in 0..samples {
+                let input: Vec<bool> = (0..width).map(|_| rng.gen()).collect();
+                checked += 1;
+                if differs(&input)? {
+                    return Ok((Some(input), checked));
+                }
+            }
+        }
+    }
+    Ok((None, checked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EquivCheck, Equivalence};
+    use crate::circuit::circuit_parser::{Circuit, Gate, GateType, WireId};
+    use crate::input::to_bits;
+
+    fn from_bits(bits: &[bool]) -> u64 {
+        bits.iter()
+            .enumerate()
+            .fold(0, |acc, (i, &b)| acc | (u64::from(b) << i))
+    }
+
+    #[test]
+    fn test_evaluate_plain_sub() {
+        let contents = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        let input = [to_bits(10, 64).unwrap(), to_bits(3, 64).unwrap()].concat();
+        assert_eq!(from_bits(&c.evaluate_plain(&input).unwrap()), 7);
+    }
+
+    #[test]
+    fn test_evaluate_plain_wrong_input_length() {
+        let contents = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        assert!(c.evaluate_plain(&to_bits(10, 64).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_equivalent_to_itself() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        assert!(c.equivalent(&c.clone(), 20).unwrap());
+    }
+
+    #[test]
+    fn test_not_equivalent_to_modified_copy() {
+        let and = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let mut xor = and.clone();
+        xor.gates[0].gate_type = GateType::XOR(WireId::unchecked(0), WireId::unchecked(1));
+
+        // the circuits differ on 3 of the 4 inputs, so 64 trials find one of them
+        assert!(!and.equivalent(&xor, 64).unwrap());
+        let input = and.find_difference(&xor, 64).unwrap().unwrap();
+        assert_ne!(
+            and.evaluate_plain(&input).unwrap(),
+            xor.evaluate_plain(&input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_equivalent_mismatched_widths() {
+        let and = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let adder = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&adder).unwrap();
+
+        assert!(and.equivalent(&adder, 1).is_err());
+    }
+
+    #[test]
+    fn test_equivalent_to_dead_gates_eliminated() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+
+        // an AND gate whose output is never read, assigned to a new wire before the outputs
+        let mut dead = adder.clone();
+        let dead_wire = dead.get_output_wires();
+        for gate in &mut dead.gates {
+            let shift = |w: WireId| match w.index() >= dead_wire {
+                true => WireId::unchecked(w.index() + 1),
+                false => w,
+            };
+            gate.gate_type = gate.gate_type.map_wires(shift);
+            gate.output = shift(gate.output);
+        }
+        dead.gates.insert(
+            0,
+            Gate {
+                gate_type: GateType::AND(WireId::unchecked(0), WireId::unchecked(64)),
+                output: WireId::unchecked(dead_wire),
+            },
+        );
+        dead.header.gates_amount += 1;
+        dead.header.wires_amount += 1;
+
+        let eliminated = dead.eliminate_dead_gates();
+        assert_eq!(eliminated.gates.len(), adder.gates.len());
+        let strategy = EquivCheck::Random {
+            samples: 200,
+            seed: 3,
+        };
+        assert!(dead
+            .equivalent_to(&adder, strategy)
+            .unwrap()
+            .is_equivalent());
+        let adder_eliminated = adder.eliminate_dead_gates();
+        assert!(adder_eliminated
+            .equivalent_to(&adder, strategy)
+            .unwrap()
+            .is_equivalent());
+        assert!(eliminated
+            .equivalent_to(&dead, strategy)
+            .unwrap()
+            .is_equivalent());
+    }
+
+    #[test]
+    fn test_equivalent_to_counterexample() {
+        let adder = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let sub = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let adder = Circuit::parse(&adder).unwrap();
+        let sub = Circuit::parse(&sub).unwrap();
+
+        let strategy = EquivCheck::Random {
+            samples: 10,
+            seed: 3,
+        };
+        let Equivalence::Counterexample(input) = adder.equivalent_to(&sub, strategy).unwrap()
+        else {
+            panic!("an adder is no subtractor");
+        };
+        assert_ne!(
+            adder.evaluate_plain(&input).unwrap(),
+            sub.evaluate_plain(&input).unwrap()
+        );
+
+        // too wide to check all inputs
+        assert!(adder.equivalent_to(&sub, EquivCheck::Exhaustive).is_err());
+    }
+
+    #[test]
+    fn test_equivalent_to_exhaustive() {
+        let and = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let mut xor = and.clone();
+        xor.gates[0].gate_type = GateType::XOR(WireId::unchecked(0), WireId::unchecked(1));
+
+        let result = and.equivalent_to(&and.clone(), EquivCheck::Exhaustive);
+        assert_eq!(result.unwrap(), Equivalence::Equivalent);
+        // AND and XOR first differ on the inputs 1 and 0
+        let result = and.equivalent_to(&xor, EquivCheck::Exhaustive);
+        assert_eq!(
+            result.unwrap(),
+            Equivalence::Counterexample(vec![true, false])
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! A small language for writing boolean circuits as expressions instead of bristol files, e.g.
+//!
+//! ```text
+//! let t = a & b   # a temporary value
+//! out = t ^ !c[0]
+//! ```
+//!
+//! Every statement `name = expr` defines an output value, `let name = expr` a value which is
+//! only used by later statements. The operators work bitwise on values of the same width, `a[i]`
+//! selects bit `i` of `a`. `&` binds stronger than `^`, which binds stronger than `|`.
+//! Statements are separated by new lines or `;`, and `#` starts a comment.
+
+use crate::circuit::circuit_builder::CircuitBuilder;
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::{Circuit, WireId};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Number(usize),
+    Let,
+    Assign,
+    And,
+    Xor,
+    Or,
+    Not,
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    End,
+}
+
+/// A token with its line and column, both counted from 1.
+type Positioned = (Token, usize, usize);
+
+fn error(line: usize, column: usize, message: impl Into<String>) -> CircuitError {
+    CircuitError::ExpressionError {
+        line,
+        column,
+        message: message.into(),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Positioned>, CircuitError> {
+    let mut tokens = Vec::new();
+    for (l, line) in source.lines().enumerate() {
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let column = line[..i].chars().count() + 1;
+            let token = match c {
+                '#' => break,
+                ';' => Token::End,
+                '=' => Token::Assign,
+                '&' => Token::And,
+                '^' => Token::Xor,
+                '|' => Token::Or,
+                '!' => Token::Not,
+                '(' => Token::Open,
+                ')' => Token::Close,
+                '[' => Token::OpenBracket,
+                ']' => Token::CloseBracket,
+                c if c.is_whitespace() => continue,
+                c if c.is_ascii_alphanumeric() || c == '_' => {
+                    let mut end = i + c.len_utf8();
+                    while let Some(&(j, d)) = chars.peek() {
+                        if !(d.is_ascii_alphanumeric() || d == '_') {
+                            break;
+                        }
+                        end = j + d.len_utf8();
+                        chars.next();
+                    }
+                    let word = &line[i..end];
+                    if c.is_ascii_digit() {
+                        let number = word
+                            .parse()
+                            .map_err(|_| error(l + 1, column, format!("{} is no number", word)))?;
+                        Token::Number(number)
+                    } else if word == "let" {
+                        Token::Let
+                    } else {
+                        Token::Name(word.to_string())
+                    }
+                }
+                c => return Err(error(l + 1, column, format!("unexpected character {}", c))),
+            };
+            tokens.push((token, l + 1, column));
+        }
+        tokens.push((Token::End, l + 1, line.chars().count() + 1));
+    }
+    Ok(tokens)
+}
+
+/// Compiles the statements into gates of a [`CircuitBuilder`].
+struct Compiler {
+    tokens: Vec<Positioned>,
+    next: usize,
+    builder: CircuitBuilder,
+    values: HashMap<String, Vec<WireId>>,
+}
+
+impl Compiler {
+    fn peek(&self) -> &Positioned {
+        &self.tokens[self.next.min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) -> Positioned {
+        let token = self.peek().clone();
+        self.next += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), CircuitError> {
+        let (token, line, column) = self.advance();
+        if token != expected {
+            return Err(error(line, column, format!("expected {}", what)));
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), CircuitError> {
+        let is_output = match self.peek().0 {
+            Token::End => {
+                self.advance();
+                return Ok(());
+            }
+            Token::Let => {
+                self.advance();
+                false
+            }
+            _ => true,
+        };
+        let (token, line, column) = self.advance();
+        let Token::Name(name) = token else {
+            return Err(error(line, column, "expected the name of a value"));
+        };
+        if self.values.contains_key(&name) {
+            return Err(error(line, column, format!("{} is already defined", name)));
+        }
+        self.expect(Token::Assign, "=")?;
+        let value = self.or()?;
+        self.expect(Token::End, "the end of the statement")?;
+        if is_output {
+            self.builder.output(value.clone());
+        }
+        self.values.insert(name, value);
+        Ok(())
+    }
+
+    /// Parses the operands of a left-associative operator with `operand` and combines them.
+    fn binary(
+        &mut self,
+        operator: Token,
+        operand: fn(&mut Self) -> Result<Vec<WireId>, CircuitError>,
+        combine: fn(&mut Self, WireId, WireId) -> WireId,
+    ) -> Result<Vec<WireId>, CircuitError> {
+        let mut left = operand(self)?;
+        while self.peek().0 == operator {
+            let (_, line, column) = self.advance();
+            let right = operand(self)?;
+            if left.len() != right.len() {
+                return Err(error(
+                    line,
+                    column,
+                    format!(
+                        "the operands have {} and {} bit(s)",
+                        left.len(),
+                        right.len()
+                    ),
+                ));
+            }
+            left = left
+                .into_iter()
+                .zip(right)
+                .map(|(a, b)| combine(self, a, b))
+                .collect();
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Vec<WireId>, CircuitError> {
+        self.binary(Token::Or, Self::xor, |c, a, b| c.builder.or(a, b))
+    }
+
+    fn xor(&mut self) -> Result<Vec<WireId>, CircuitError> {
+        self.binary(Token::Xor, Self::and, |c, a, b| c.builder.xor(a, b))
+    }
+
+    fn and(&mut self) -> Result<Vec<WireId>, CircuitError> {
+        self.binary(Token::And, Self::unary, |c, a, b| c.builder.and(a, b))
+    }
+
+    fn unary(&mut self) -> Result<Vec<WireId>, CircuitError> {
+        let (token, line, column) = self.advance();
+        match token {
+            Token::Not => {
+                let value = self.unary()?;
+                Ok(value.into_iter().map(|a| self.builder.inv(a)).collect())
+            }
+            Token::Open => {
+                let value = self.or()?;
+                self.expect(Token::Close, ")")?;
+                Ok(value)
+            }
+            Token::Name(name) => {
+                let value = self
+                    .values
+                    .get(&name)
+                    .ok_or_else(|| error(line, column, format!("{} is not defined", name)))?
+                    .clone();
+                if self.peek().0 != Token::OpenBracket {
+                    return Ok(value);
+                }
+                self.advance();
+                let (token, line, column) = self.advance();
+                let Token::Number(bit) = token else {
+                    return Err(error(line, column, "expected a bit index"));
+                };
+                let wire = *value.get(bit).ok_or_else(|| {
+                    error(
+                        line,
+                        column,
+                        format!("{} has no bit {}, only {} bit(s)", name, bit, value.len()),
+                    )
+                })?;
+                self.expect(Token::CloseBracket, "]")?;
+                Ok(vec![wire])
+            }
+            _ => Err(error(line, column, "expected a value")),
+        }
+    }
+}
+
+impl Circuit {
+    /// Compiles a program of the expression language (see [`crate::circuit::circuit_expr`]) into
+    /// a circuit. `inputs` names the input values and their widths, in the order of the header,
+    /// so the first one is the input of party 0. The output values are in the order of their
+    /// statements.
+    pub fn from_expr(source: &str, inputs: &[(&str, usize)]) -> Result<Circuit, CircuitError> {
+        let mut compiler = Compiler {
+            tokens: tokenize(source)?,
+            next: 0,
+            builder: CircuitBuilder::new(),
+            values: HashMap::new(),
+        };
+        for &(name, width) in inputs {
+            let wires = compiler.builder.input(width);
+            if compiler.values.insert(name.to_string(), wires).is_some() {
+                return Err(CircuitError::ParsingError(format!(
+                    "the input {} being declared twice",
+                    name
+                )));
+            }
+        }
+        while compiler.next < compiler.tokens.len() {
+            compiler.statement()?;
+        }
+        if compiler.builder.outputs() == 0 {
+            return Err(CircuitError::ParsingError(
+                "the expression having no output".to_string(),
+            ));
+        }
+        Ok(compiler.builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::party::local::run_local;
+
+    #[test]
+    fn test_single_bit_expression() {
+        let c = Circuit::from_expr("out = (a & b) ^ !c", &[("a", 1), ("b", 1), ("c", 1)]).unwrap();
+        c.validate().unwrap();
+        assert_eq!(c.stats().and, 1);
+        assert_eq!(c.stats().gates(), 3);
+        assert_eq!(c.header.niv, vec![1, 1, 1]);
+        assert_eq!(c.header.nov, vec![1]);
+        for v in 0..8u64 {
+            let (a, b, c_) = (v & 1 == 1, v & 2 == 2, v & 4 == 4);
+            assert_eq!(run_local(&c, &[a], &[b, c_]).unwrap(), vec![(a & b) ^ !c_]);
+        }
+    }
+
+    #[test]
+    fn test_multi_bit_program() {
+        let program = "\
+            # bitwise or, and a carry-free sum of the low bits
+            let t = x | y
+            or = t
+            low = x[0] ^ y[0]; high = x[1] & y[1]
+            copy = x
+        ";
+        let c = Circuit::from_expr(program, &[("x", 2), ("y", 2)]).unwrap();
+        c.validate().unwrap();
+        assert_eq!(c.header.nov, vec![2, 1, 1, 2]);
+        // two ORs of three gates, one XOR, one AND and two copies of two INV gates
+        assert_eq!(c.gates.len(), 12);
+        assert_eq!(c.required_triples(), 3);
+        for x in 0..4 {
+            for y in 0..4 {
+                let mut expected = to_bits(x | y, 2).unwrap();
+                expected.push((x ^ y) & 1 == 1);
+                expected.push((x & y) & 2 == 2);
+                expected.extend(to_bits(x, 2).unwrap());
+                assert_eq!(
+                    run_local(&c, &to_bits(x, 2).unwrap(), &to_bits(y, 2).unwrap()).unwrap(),
+                    expected
+                );
+                let mut input = to_bits(x, 2).unwrap();
+                input.extend(to_bits(y, 2).unwrap());
+                assert_eq!(c.evaluate_plain(&input).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        let position = |source: &str| match Circuit::from_expr(source, &[("a", 2), ("b", 1)]) {
+            Err(CircuitError::ExpressionError { line, column, .. }) => (line, column),
+            other => panic!("expected an expression error, got {:?}", other),
+        };
+        assert_eq!(position("out = a & b"), (1, 9));
+        assert_eq!(position("out = a[0] &"), (1, 13));
+        assert_eq!(position("out = b\nx = (a[1] ^ c)"), (2, 13));
+        assert_eq!(position("out = a[2]"), (1, 9));
+        assert_eq!(position("out = a $ b"), (1, 9));
+        assert_eq!(position("a = b"), (1, 1));
+        assert_eq!(
+            Circuit::from_expr("out = a[0] &", &[("a", 2)])
+                .unwrap_err()
+                .to_string(),
+            "Parsing failed at line 1, column 13: expected a value"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Standard circuits of any width, built with a [`CircuitBuilder`] instead of read from a file.
+//! Party 0 enters the first operand a and party 1 the second operand b, both of the same width
+//! and least significant bit first.
+
+use crate::circuit::circuit_builder::CircuitBuilder;
+use crate::circuit::circuit_parser::{Circuit, WireId};
+use clap::ValueEnum;
+
+/// A circuit which can be generated for any width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Builtin {
+    /// a + b, modulo 2^width
+    Adder,
+    /// a - b, modulo 2^width
+    #[value(alias = "sub")]
+    Subtractor,
+    /// a < b as one bit, for unsigned a and b
+    #[value(alias = "lt")]
+    LessThan,
+    /// a == b as one bit
+    #[value(alias = "eq")]
+    Equal,
+    /// a & b, bitwise
+    And,
+    /// a ^ b, bitwise
+    Xor,
+    /// b if the select bit is set, else a. Party 1 enters the select bit after b.
+    Mux,
+}
+
+impl Builtin {
+    /// Generates the circuit for operands of `width` bits.
+    ///
+    /// Panics if the width is 0.
+    pub fn circuit(self, width: usize) -> Circuit {
+        match self {
+            Builtin::Adder => adder(width),
+            Builtin::Subtractor => subtractor(width),
+            Builtin::LessThan => less_than(width),
+            Builtin::Equal => equal(width),
+            Builtin::And => bitwise_and(width),
+            Builtin::Xor => bitwise_xor(width),
+            Builtin::Mux => mux(width),
+        }
+    }
+}
+
+/// Adds the operands a and b of `width` bits to a new builder.
+fn operands(width: usize) -> (CircuitBuilder, Vec<WireId>, Vec<WireId>) {
+    assert!(width > 0, "a circuit needs operands of at least one bit");
+    let mut builder = CircuitBuilder::new();
+    let a = builder.input(width);
+    let b = builder.input(width);
+    (builder, a, b)
+}
+
+/// The majority of three bits with a single AND gate: maj(x, y, z) = z ^ ((x ^ z) & (y ^ z)).
+fn majority(builder: &mut CircuitBuilder, x: WireId, y: WireId, z: WireId) -> WireId {
+    let xz = builder.xor(x, z);
+    let yz = builder.xor(y, z);
+    let both = builder.and(xz, yz);
+    builder.xor(z, both)
+}
+
+/// The bits of a - b and the final borrow, which is set if a < b.
+fn subtract(builder: &mut CircuitBuilder, a: &[WireId], b: &[WireId]) -> (Vec<WireId>, WireId) {
+    // the first bit has no borrow in, it borrows if a is 0 and b is 1
+    let mut difference = vec![builder.xor(a[0], b[0])];
+    let not_a = builder.inv(a[0]);
+    let mut borrow = builder.and(not_a, b[0]);
+    for i in 1..a.len() {
+        let half = builder.xor(a[i], b[i]);
+        difference.push(builder.xor(half, borrow));
+        // a bit borrows if at least two of !a, b and the borrow in are set
+        let not_a = builder.inv(a[i]);
+        borrow = majority(builder, not_a, b[i], borrow);
+    }
+    (difference, borrow)
+}
+
+/// A ripple-carry adder, with one AND gate per bit but the last.
+pub fn adder(width: usize) -> Circuit {
+    let (mut builder, a, b) = operands(width);
+    let mut sum = vec![builder.xor(a[0], b[0])];
+    let mut carry = builder.and(a[0], b[0]);
+    for i in 1..width {
+        let half = builder.xor(a[i], b[i]);
+        sum.push(builder.xor(half, carry));
+        // the carry of the top bit is dropped, so it is not computed
+        if i + 1 < width {
+            carry = majority(&mut builder, a[i], b[i], carry);
+        }
+    }
+    builder.output(sum);
+    builder.build()
+}
+
+/// A ripple-borrow subtractor.
+pub fn subtractor(width: usize) -> Circuit {
+    let (mut builder, a, b) = operands(width);
+    let (difference, _) = subtract(&mut builder, &a, &b);
+    builder.output(difference);
+    builder.build()
+}
+
+/// Compares unsigned operands. a < b exactly if a - b borrows at the top bit.
+pub fn less_than(width: usize) -> Circuit {
+    let (mut builder, a, b) = operands(width);
+    let (_, borrow) = subtract(&mut builder, &a, &b);
+    builder.output(vec![borrow]);
+    builder.build()
+}
+
+/// Checks the operands for equality, by ANDing the negated XOR of every bit pair.
+pub fn equal(width: usize) -> Circuit {
+    let (mut builder, a, b) = operands(width);
+    let mut equal = None;
+    for (&a, &b) in a.iter().zip(&b) {
+        let differs = builder.xor(a, b);
+        let same = builder.inv(differs);
+        equal = Some(match equal {
+            None => same,
+            Some(equal) => builder.and(equal, same),
+        });
+    }
+    builder.output(vec![equal.expect("the operands have at least one bit")]);
+    builder.build()
+}
+
+pub fn bitwise_and(width: usize) -> Circuit {
+    let (mut builder, a, b) = operands(width);
+    let value = a.iter().zip(&b).map(|(&a, &b)| builder.and(a, b)).collect();
+    builder.output(value);
+    builder.build()
+}
+
+pub fn bitwise_xor(width: usize) -> Circuit {
+    let (mut builder, a, b) = operands(width);
+    let value = a.iter().zip(&b).map(|(&a, &b)| builder.xor(a, b)).collect();
+    builder.output(value);
+    builder.build()
+}
+
+/// Selects b if the select bit is set and a otherwise, as a ^ (s & (a ^ b)).
+pub fn mux(width: usize) -> Circuit {
+    let (mut builder, a, b) = operands(width);
+    let select = builder.input(1)[0];
+    let value = a
+        .iter()
+        .zip(&b)
+        .map(|(&a, &b)| {
+            let differs = builder.xor(a, b);
+            let chosen = builder.and(select, differs);
+            builder.xor(a, chosen)
+        })
+        .collect();
+    builder.output(value);
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Builtin;
+    use crate::input::{encode_input, parse_number};
+    use crate::party::local::run_local;
+    use clap::ValueEnum;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn bits(value: u128, width: usize) -> Vec<bool> {
+        encode_input(&parse_number(&value.to_string()).unwrap(), width, false).unwrap()
+    }
+
+    fn mask(width: usize) -> u128 {
+        (1 << width) - 1
+    }
+
+    /// The native result of the builtin and its width, for the operands and the select bit.
+    fn expected(builtin: Builtin, width: usize, a: u128, b: u128, select: bool) -> (u128, usize) {
+        match builtin {
+            Builtin::Adder => (a.wrapping_add(b) & mask(width), width),
+            Builtin::Subtractor => (a.wrapping_sub(b) & mask(width), width),
+            Builtin::LessThan => (u128::from(a < b), 1),
+            Builtin::Equal => (u128::from(a == b), 1),
+            Builtin::And => (a & b, width),
+            Builtin::Xor => (a ^ b, width),
+            Builtin::Mux => (if select { b } else { a }, width),
+        }
+    }
+
+    #[test]
+    fn test_builtins_match_native_operations() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for &builtin in Builtin::value_variants() {
+            for width in [1, 8, 64, 65] {
+                let c = builtin.circuit(width);
+                c.validate().unwrap();
+                // the edge cases, then random operands
+                let mut cases = vec![(0, 0), (mask(width), 0), (0, mask(width)), (1, 1)];
+                cases.extend((0..8).map(|_| (rng.gen(), rng.gen())));
+                for (a, b) in cases {
+                    let (a, b) = (a & mask(width), b & mask(width));
+                    let select = rng.gen();
+                    let mut second = bits(b, width);
+                    if builtin == Builtin::Mux {
+                        second.push(select);
+                    }
+                    let (value, output_width) = expected(builtin, width, a, b, select);
+                    assert_eq!(
+                        run_local(&c, &bits(a, width), &second).unwrap(),
+                        bits(value, output_width),
+                        "{:?} of width {} on {} and {}",
+                        builtin,
+                        width,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_adder_cost() {
+        let c = Builtin::Adder.circuit(64);
+        assert_eq!(c.required_triples(), 63);
+        assert_eq!(c.header.niv, vec![64, 64]);
+        assert_eq!(c.header.nov, vec![64]);
+        assert_eq!(Builtin::from_str("lt", false), Ok(Builtin::LessThan));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::circuit::circuit_parser::{Circuit, GateType};
+
+/// A set of gates which can be evaluated together once all previous layers are done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    /// Indices of the XOR and INV gates of the layer, in topological order. They may depend on
+    /// each other, but only on AND gates of previous layers.
+    pub local: Vec<usize>,
+    /// Indices of the AND gates of the layer. They only depend on previous layers and the local
+    /// gates of this layer, so they can be evaluated in a single communication round.
+    pub and: Vec<usize>,
+}
+
+impl Circuit {
+    /// Partitions the gates into layers by multiplicative depth. Layer `d` contains the AND gates
+    /// with `d` AND gates on their longest path from an input, and the local gates they depend
+    /// on.
+    ///
+    /// The layers are computed on the first call and cached.
+    pub fn layers(&self) -> &[Layer] {
+        self.layers.get_or_init(|| self.compute_layers())
+    }
+
+    fn compute_layers(&self) -> Vec<Layer> {
+        let mut depth: Vec<usize> = vec![0; self.header.wires_amount];
+        let mut layers: Vec<Layer> = Vec::new();
+
+        for (i, gate) in self.gates.iter().enumerate() {
+            let (d, is_and) = match gate.gate_type {
+                GateType::INV(a) => (depth[a.index()], false),
+                GateType::XOR(a, b) => (depth[a.index()].max(depth[b.index()]), false),
+                GateType::AND(a, b) => (depth[a.index()].max(depth[b.index()]), true),
+            };
+
+            if layers.len() <= d {
+                layers.resize(
+                    d + 1,
+                    Layer {
+                        local: Vec::new(),
+                        and: Vec::new(),
+                    },
+                );
+            }
+
+            if is_and {
+                layers[d].and.push(i);
+                depth[gate.output.index()] = d + 1;
+            } else {
+                layers[d].local.push(i);
+                depth[gate.output.index()] = d;
+            }
+        }
+
+        layers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Layer;
+    use crate::circuit::circuit_parser::Circuit;
+
+    #[test]
+    fn test_diamond_layers() {
+        // c = a & b and e = !(a ^ b) both only depend on the inputs, f = c & e joins them again.
+        // g = f ^ c is local to the last layer, which has no AND gates.
+        let circuit = "\
+            5 7\n\
+            2 1 1\n\
+            1 1\n\
+            \n\
+            2 1 0 1 2 AND\n\
+            2 1 0 1 3 XOR\n\
+            1 1 3 4 INV\n\
+            2 1 2 4 5 AND\n\
+            2 1 5 2 6 XOR\n";
+        let c = Circuit::parse(circuit).unwrap();
+
+        let expected = [
+            Layer {
+                local: vec![1, 2],
+                and: vec![0],
+            },
+            Layer {
+                local: vec![],
+                and: vec![3],
+            },
+            Layer {
+                local: vec![4],
+                and: vec![],
+            },
+        ];
+        assert_eq!(c.layers(), expected);
+        // the second call returns the cached layers
+        assert!(std::ptr::eq(c.layers(), c.layers()));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::circuit::circuit_parser::Circuit;
+use std::ops::Range;
+
+/// One of the two parties of the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PartyId {
+    P0,
+    P1,
+}
+
+impl PartyId {
+    /// The party's number, 0 or 1.
+    pub fn index(self) -> usize {
+        match self {
+            PartyId::P0 => 0,
+            PartyId::P1 => 1,
+        }
+    }
+}
+
+impl Circuit {
+    /// Returns which party supplies each input value declared in the header, and the input wires
+    /// holding its bits, in header order. Party 0 supplies the first input value, party 1 all
+    /// remaining ones. The bits of a party's input are the bits of its values in this order.
+    pub fn input_layout(&self) -> Vec<(PartyId, Range<usize>)> {
+        let mut start = 0;
+        self.header
+            .niv
+            .iter()
+            .enumerate()
+            .map(|(value, &width)| {
+                let party = if value == 0 { PartyId::P0 } else { PartyId::P1 };
+                start += width;
+                (party, start - width..start)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PartyId;
+    use crate::circuit::circuit_parser::Circuit;
+
+    #[test]
+    fn test_input_layout() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+        assert_eq!(
+            adder.input_layout(),
+            vec![(PartyId::P0, 0..64), (PartyId::P1, 64..128)]
+        );
+
+        let three = Circuit::parse("1 6\n3 2 1 2\n1 1\n\n2 1 0 4 5 AND\n").unwrap();
+        assert_eq!(
+            three.input_layout(),
+            vec![
+                (PartyId::P0, 0..2),
+                (PartyId::P1, 2..3),
+                (PartyId::P1, 3..5)
+            ]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Names of the input and output values of a circuit. A bristol file only declares the widths
+//! of its values, the names come from a JSON sidecar file next to it, e.g. `adder.meta.json`
+//! for `adder.txt`:
+//!
+//! ```text
+//! {
+//!     "inputs": [{"name": "a", "width": 64}, {"name": "b", "width": 64}],
+//!     "outputs": [{"name": "sum", "width": 64}]
+//! }
+//! ```
+
+use crate::circuit::circuit_parser::Circuit;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum MetaError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Syntax(serde_json::Error),
+    DuplicateName(String),
+    ValueCount {
+        kind: &'static str,
+        named: usize,
+        declared: usize,
+    },
+    WidthMismatch {
+        name: String,
+        named: usize,
+        declared: usize,
+    },
+}
+
+impl Error for MetaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MetaError::Io { source, .. } => Some(source),
+            MetaError::Syntax(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Display for MetaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetaError::Io { path, source } => {
+                write!(
+                    f,
+                    "Could not read the metadata {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            MetaError::Syntax(e) => write!(f, "The metadata is malformed: {}", e),
+            MetaError::DuplicateName(name) => {
+                write!(f, "The metadata names two values {}", name)
+            }
+            MetaError::ValueCount {
+                kind,
+                named,
+                declared,
+            } => {
+                write!(
+                    f,
+                    "The metadata names {} {} value(s), but the circuit declares {}",
+                    named, kind, declared
+                )
+            }
+            MetaError::WidthMismatch {
+                name,
+                named,
+                declared,
+            } => {
+                write!(
+                    f,
+                    "The metadata gives {} {} bit(s), but the circuit declares {}",
+                    name, named, declared
+                )
+            }
+        }
+    }
+}
+
+/// The name and width of an input or output value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValueMeta {
+    pub name: String,
+    pub width: usize,
+}
+
+/// The names of the input and output values of a circuit, in header order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitMeta {
+    pub inputs: Vec<ValueMeta>,
+    pub outputs: Vec<ValueMeta>,
+}
+
+impl CircuitMeta {
+    /// Parses the JSON contents of a sidecar file. The inputs and the outputs each need distinct
+    /// names.
+    pub fn parse(json: &str) -> Result<Self, MetaError> {
+        let meta: CircuitMeta = serde_json::from_str(json).map_err(MetaError::Syntax)?;
+        for values in [&meta.inputs, &meta.outputs] {
+            for (i, value) in values.iter().enumerate() {
+                if values[..i].iter().any(|v| v.name == value.name) {
+                    return Err(MetaError::DuplicateName(value.name.clone()));
+                }
+            }
+        }
+        Ok(meta)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, MetaError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|source| MetaError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&json)
+    }
+
+    /// The sidecar file of a circuit file, which has the extension `meta.json` instead of the
+    /// circuit's.
+    pub fn sidecar_path(circuit: impl AsRef<Path>) -> PathBuf {
+        circuit.as_ref().with_extension("meta.json")
+    }
+
+    /// Reads the sidecar file of the circuit file at `path`, if there is one. A circuit read from
+    /// stdin (`-`) has none.
+    pub fn for_circuit(path: impl AsRef<Path>) -> Result<Option<Self>, MetaError> {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            return Ok(None);
+        }
+        let sidecar = Self::sidecar_path(path);
+        if !sidecar.is_file() {
+            return Ok(None);
+        }
+        Self::from_file(sidecar).map(Some)
+    }
+
+    /// The index of the input value with the given name.
+    pub fn input_index(&self, name: &str) -> Option<usize> {
+        self.inputs.iter().position(|v| v.name == name)
+    }
+
+    pub fn output_names(&self) -> Vec<String> {
+        self.outputs.iter().map(|v| v.name.clone()).collect()
+    }
+
+    /// Checks that the values have the widths the header declares.
+    fn check(&self, niv: &[usize], nov: &[usize]) -> Result<(), MetaError> {
+        for (kind, values, widths) in [("input", &self.inputs, niv), ("output", &self.outputs, nov)]
+        {
+            if values.len() != widths.len() {
+                return Err(MetaError::ValueCount {
+                    kind,
+                    named: values.len(),
+                    declared: widths.len(),
+                });
+            }
+            if let Some((value, &width)) = values.iter().zip(widths).find(|(v, &w)| v.width != w) {
+                return Err(MetaError::WidthMismatch {
+                    name: value.name.clone(),
+                    named: value.width,
+                    declared: width,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Circuit {
+    /// Names the input and output values of the circuit. Fails if the metadata does not describe
+    /// the values of the header, in which case the circuit keeps its previous metadata.
+    pub fn attach_meta(&mut self, meta: CircuitMeta) -> Result<(), MetaError> {
+        meta.check(&self.header.niv, &self.header.nov)?;
+        self.meta = Some(meta);
+        Ok(())
+    }
+
+    /// The names of the values, if metadata was attached with [`Circuit::attach_meta`].
+    pub fn meta(&self) -> Option<&CircuitMeta> {
+        self.meta.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitMeta, MetaError};
+    use crate::circuit::circuit_parser::Circuit;
+
+    fn adder() -> Circuit {
+        Circuit::from_file("test_circuits/64_Adder.txt").unwrap()
+    }
+
+    #[test]
+    fn test_matching_meta() {
+        let json = r#"{
+            "inputs": [{"name": "salary_a", "width": 64}, {"name": "salary_b", "width": 64}],
+            "outputs": [{"name": "total", "width": 64}]
+        }"#;
+        let meta = CircuitMeta::parse(json).unwrap();
+        assert_eq!(meta.input_index("salary_b"), Some(1));
+        assert_eq!(meta.input_index("salary_c"), None);
+        assert_eq!(meta.output_names(), vec!["total"]);
+
+        let mut c = adder();
+        assert!(c.meta().is_none());
+        c.attach_meta(meta.clone()).unwrap();
+        assert_eq!(c.meta(), Some(&meta));
+        assert_eq!(
+            CircuitMeta::sidecar_path("circuits/adder.txt"),
+            std::path::Path::new("circuits/adder.meta.json")
+        );
+    }
+
+    #[test]
+    fn test_mismatched_meta() {
+        let mut c = adder();
+        let narrow = r#"{
+            "inputs": [{"name": "a", "width": 64}, {"name": "b", "width": 32}],
+            "outputs": [{"name": "sum", "width": 64}]
+        }"#;
+        let e = c
+            .attach_meta(CircuitMeta::parse(narrow).unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            e,
+            MetaError::WidthMismatch { ref name, named: 32, declared: 64 } if name == "b"
+        ));
+        assert_eq!(
+            e.to_string(),
+            "The metadata gives b 32 bit(s), but the circuit declares 64"
+        );
+        assert!(c.meta().is_none());
+
+        let missing = r#"{"inputs": [{"name": "a", "width": 64}], "outputs": []}"#;
+        let e = c
+            .attach_meta(CircuitMeta::parse(missing).unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            e,
+            MetaError::ValueCount {
+                kind: "input",
+                named: 1,
+                declared: 2
+            }
+        ));
+
+        let twice =
+            r#"{"inputs": [{"name": "a", "width": 1}, {"name": "a", "width": 1}], "outputs": []}"#;
+        assert!(matches!(
+            CircuitMeta::parse(twice),
+            Err(MetaError::DuplicateName(name)) if name == "a"
+        ));
+        assert!(matches!(
+            CircuitMeta::parse(r#"{"inputs": []}"#),
+            Err(MetaError::Syntax(_))
+        ));
+    }
+}
@@ -3,22 +3,71 @@
 // way to represent it.
 // A rust enum is similar to a tagged union in C/C++.
 
-use std::usize;
-
 use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_layers::Layer;
+use crate::circuit::circuit_meta::CircuitMeta;
+use crate::circuit::circuit_wires::WireIndex;
+use flate2::bufread::GzDecoder;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GateType {
     // Enum variants can have fields themselves.
-    XOR(usize, usize),
-    AND(usize, usize),
-    INV(usize),
+    XOR(WireId, WireId),
+    AND(WireId, WireId),
+    INV(WireId),
+}
+
+// Display is written like the gate in a bristol file, with the gate name first.
+impl Display for GateType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GateType::XOR(a, b) => write!(f, "XOR {} {}", a, b),
+            GateType::AND(a, b) => write!(f, "AND {} {}", a, b),
+            GateType::INV(a) => write!(f, "INV {}", a),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Gate {
     pub gate_type: GateType,
-    pub output: usize,
+    pub output: WireId,
+}
+
+/// The index of a wire of a gate. A wire index read from a file may be anything up to
+/// `usize::MAX`, so it only becomes a `WireId` after checking it against the wire count, and the
+/// wires of a parsed gate can be used to index and count wires without overflowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WireId(usize);
+
+impl WireId {
+    /// Checks that `index` is one of `wires_amount` wires.
+    pub fn new(index: usize, wires_amount: usize) -> Option<Self> {
+        (index < wires_amount).then_some(WireId(index))
+    }
+
+    /// A wire the crate creates itself, e.g. while building or transforming a circuit, whose wire
+    /// count is only known once it is done. The circuit is validated as a whole before it is
+    /// evaluated.
+    pub(crate) const fn unchecked(index: usize) -> Self {
+        WireId(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl Display for WireId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 // We can 'derive' some traits like Debug and Clone on types via a derive attribute. This is a
@@ -34,75 +83,332 @@ pub struct Header {
 
 #[derive(Debug, Clone)]
 pub struct Circuit {
-    // a circuit consists of a header and the gates of a circuit
-    pub header: Header,
-    pub gates: Vec<Gate>,
+    // a circuit consists of a header and the gates of a circuit. Both are read-only from outside
+    // the module, so that the cached values below always belong to them.
+    pub(super) header: Header,
+    pub(super) gates: Vec<Gate>,
+    // computed on demand by `Circuit::layers`
+    pub(super) layers: OnceLock<Vec<Layer>>,
+    // computed on demand by `Circuit::wire_index`, with the gate and wire making it invalid
+    pub(super) wire_index: OnceLock<Result<WireIndex, (usize, usize)>>,
+    // set once `Circuit::validate_with` passed, so that parties do not validate it again
+    pub(super) validated: OnceLock<()>,
+    // the names of the values, see `Circuit::attach_meta`
+    pub(super) meta: Option<CircuitMeta>,
+}
+
+/// Default of [`ParseOptions::max_wires`]. A party holds a share of every wire, so this keeps a
+/// party's wire buffer below a few hundred megabytes.
+pub const DEFAULT_MAX_WIRES: usize = 1 << 28;
+/// Default of [`ParseOptions::max_gates`].
+pub const DEFAULT_MAX_GATES: usize = 1 << 28;
+/// Default of [`ParseOptions::max_input_width`].
+pub const DEFAULT_MAX_INPUT_WIDTH: usize = 1 << 24;
+
+/// Gate mnemonics of other bristol dialects, which are accepted by default.
+pub const DEFAULT_ALIASES: [(&str, &str); 2] = [("EOR", "XOR"), ("NOT", "INV")];
+
+/// Options of the parser: limits on the size of a circuit, which are checked before anything is
+/// allocated for it, and the accepted aliases of the gate mnemonics. Without the limits, a
+/// corrupted or malicious file could declare 10^12 wires and make the parties allocate terabytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Maximum number of wires declared by the header.
+    pub max_wires: usize,
+    /// Maximum number of gates declared by the header.
+    pub max_gates: usize,
+    /// Maximum number of input bits of both parties together.
+    pub max_input_width: usize,
+    /// Other mnemonics of the gates, mapped to the canonical `XOR`, `AND`, `INV` or `MAND`.
+    pub aliases: HashMap<String, String>,
 }
 
-fn get_expected_line_length_header(lines: Vec<&str>, l: usize) -> Result<usize, CircuitError> {
-    match lines[l].get(0..1) {
-        Some(value) => match value.parse::<usize>() {
-            Ok(count) => Ok(count),
-            Err(_) => {
-                let m = format!("Something went wrong whilst parsing line {}", l);
-                Err(CircuitError::ParsingError(m))
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_wires: DEFAULT_MAX_WIRES,
+            max_gates: DEFAULT_MAX_GATES,
+            max_input_width: DEFAULT_MAX_INPUT_WIDTH,
+            aliases: DEFAULT_ALIASES
+                .iter()
+                .map(|&(alias, name)| (alias.to_string(), name.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// The [`ParseOptions::default`] options, built once and shared by all parses which do not
+    /// pass their own options.
+    pub(crate) fn shared_default() -> &'static ParseOptions {
+        static DEFAULT: OnceLock<ParseOptions> = OnceLock::new();
+        DEFAULT.get_or_init(ParseOptions::default)
+    }
+
+    /// The canonical mnemonic of a gate name, which is the name itself unless it is an alias.
+    pub fn canonical_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map_or(name, String::as_str)
+    }
+
+    /// Checks the numbers declared by a header against the limits.
+    pub fn check(&self, header: &Header) -> Result<(), CircuitError> {
+        let input_width = header
+            .niv
+            .iter()
+            .fold(0usize, |sum, &width| sum.saturating_add(width));
+        for (what, limit, requested) in [
+            ("wires", self.max_wires, header.wires_amount),
+            ("gates", self.max_gates, header.gates_amount),
+            ("input bits", self.max_input_width, input_width),
+        ] {
+            if requested > limit {
+                return Err(CircuitError::LimitExceeded {
+                    what,
+                    limit,
+                    requested,
+                });
             }
-        },
-        None => {
-            let m = format!("Something went wrong whilst parsing line {}", l);
-            Err(CircuitError::ParsingError(m))
         }
+        Ok(())
     }
 }
 
+/// Parses a wire index or count, either decimal or hex with a `0x` prefix.
+fn parse_index(token: &str) -> Result<usize, CircuitError> {
+    let parsed = match token.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => token.parse::<usize>(),
+    };
+    parsed.map_err(|_| CircuitError::ParsingError(format!("{} not being a valid number", token)))
+}
+
+/// The marker at the start of every line of the metadata block a Bristol Fashion file may start
+/// with, e.g. `% version 1.2`.
+pub const METADATA_MARKER: char = '%';
+
+/// Whether the line belongs to the metadata block of a Bristol Fashion file, which precedes the
+/// header.
+fn is_metadata_line(line: &str) -> bool {
+    line.trim_start().starts_with(METADATA_MARKER)
+}
+
+/// Parses the amount of values declared at the start of the niv or nov line, which is line
+/// `line_number` of the file, counted from 1.
+fn get_expected_line_length_header(line: &str, line_number: usize) -> Result<usize, CircuitError> {
+    let token = line.split_whitespace().next().unwrap_or("");
+    parse_index(token).map_err(|_| {
+        CircuitError::ParsingError(format!(
+            "the value count \"{}\" in line {} not being a valid number",
+            token, line_number
+        ))
+    })
+}
+
 impl Circuit {
+    /// Creates a circuit from its header and gates.
+    pub fn new(header: Header, gates: Vec<Gate>) -> Self {
+        Circuit {
+            header,
+            gates,
+            layers: OnceLock::new(),
+            wire_index: OnceLock::new(),
+            validated: OnceLock::new(),
+            meta: None,
+        }
+    }
+
+    /// The header, with the number of gates and wires and the widths of the values.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The gates, in the order they are evaluated.
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    /// Takes the circuit apart into its header and gates, e.g. to change them and create a new
+    /// circuit from them with [`Circuit::new`].
+    pub fn into_parts(self) -> (Header, Vec<Gate>) {
+        (self.header, self.gates)
+    }
+
     pub fn get_output_wires(&self) -> usize {
         self.header.wires_amount - self.get_nov_sum()
     }
 
-    fn get_nov_sum(&self) -> usize {
+    pub(crate) fn get_nov_sum(&self) -> usize {
         self.header.nov.iter().sum()
     }
 
-    /// Parses the bristol file contents into a circuit
+    /// The wire with the given index, if the circuit has it.
+    pub fn wire_id(&self, index: usize) -> Option<WireId> {
+        WireId::new(index, self.header.wires_amount)
+    }
+
+    pub(crate) fn get_niv_sum(&self) -> usize {
+        self.header.niv.iter().sum()
+    }
+
+    /// Number of multiplication triples needed to evaluate the circuit, one per AND gate.
+    pub fn required_triples(&self) -> usize {
+        self.gates
+            .iter()
+            .filter(|g| matches!(g.gate_type, GateType::AND(_, _)))
+            .count()
+    }
+
+    /// Parses the bristol file contents into a circuit. Besides `XOR`, `AND`, `INV` and `MAND`,
+    /// the gates may use the [`DEFAULT_ALIASES`].
     pub fn parse(circuit: &str) -> Result<Self, CircuitError> {
-        // This method parses the circuit string representation into the Circuit type
-        // Split the input string into lines
-        let lines: Vec<&str> = circuit.lines().collect();
+        Self::parse_with(circuit, ParseOptions::shared_default())
+    }
 
-        if lines.len() < 5 {
-            return Err(CircuitError::ParsingError(
-                "the Circuit being too small".to_string(),
-            ));
+    /// Parses the bristol file contents like [`Circuit::parse`], with the given limits and
+    /// aliases.
+    pub fn parse_with(circuit: &str, options: &ParseOptions) -> Result<Self, CircuitError> {
+        Self::parse_lines(circuit.lines().map(Ok), options)
+    }
+
+    /// Reads a bristol file and parses it into a circuit. The file is read line by line through a
+    /// buffered reader, so its contents are never held in memory as a whole. Gzip compressed
+    /// files are recognized by their first bytes and decompressed while reading.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CircuitError> {
+        Self::from_file_with(path, ParseOptions::shared_default())
+    }
+
+    /// Reads a bristol file like [`Circuit::from_file`], with the given limits and aliases.
+    pub fn from_file_with(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<Self, CircuitError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| CircuitError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_reader(BufReader::new(file), path, options)
+    }
+
+    /// Parses a bristol file from `reader`, decompressing it if it starts with the gzip magic
+    /// bytes. `path` is only used in error messages.
+    fn from_reader(
+        mut reader: impl BufRead,
+        path: &Path,
+        options: &ParseOptions,
+    ) -> Result<Self, CircuitError> {
+        let io_error = |source| CircuitError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        // a buffer holds at least the two magic bytes, unless the file is shorter
+        let is_gzip = reader
+            .fill_buf()
+            .map_err(io_error)?
+            .starts_with(&[0x1f, 0x8b]);
+        if is_gzip {
+            let decompression_error = |source| CircuitError::Decompression {
+                path: path.to_path_buf(),
+                source,
+            };
+            let lines = BufReader::new(GzDecoder::new(reader)).lines();
+            Self::parse_lines(lines.map(|l| l.map_err(decompression_error)), options)
+        } else {
+            Self::parse_lines(reader.lines().map(|l| l.map_err(io_error)), options)
+        }
+    }
+
+    /// Reads a circuit like [`Circuit::from_file`], but reads it from `stdin` if the path is `-`.
+    /// Taking stdin as a parameter allows passing an in-memory reader instead.
+    pub fn from_path_or_stdin(
+        path: impl AsRef<Path>,
+        stdin: impl Read,
+    ) -> Result<Self, CircuitError> {
+        Self::from_path_or_stdin_with(path, stdin, ParseOptions::shared_default())
+    }
+
+    /// Reads a circuit like [`Circuit::from_path_or_stdin`], with the given limits and aliases.
+    pub fn from_path_or_stdin_with(
+        path: impl AsRef<Path>,
+        stdin: impl Read,
+        options: &ParseOptions,
+    ) -> Result<Self, CircuitError> {
+        let path = path.as_ref();
+        if path != Path::new("-") {
+            return Self::from_file_with(path, options);
         }
+        Self::from_reader(BufReader::new(stdin), &PathBuf::from("<stdin>"), options)
+    }
 
-        // =========== Parse the header ==========
-        let header_info: Vec<usize> = lines[0]
-            .split_whitespace()
-            .map(|s| s.parse().unwrap())
-            .collect();
+    /// Parses the lines of a bristol file into a circuit. The header is checked against the
+    /// limits before any gate is read.
+    fn parse_lines<S: AsRef<str>>(
+        mut input: impl Iterator<Item = Result<S, CircuitError>>,
+        options: &ParseOptions,
+    ) -> Result<Self, CircuitError> {
+        let too_small = || CircuitError::ParsingError("the Circuit being too small".to_string());
 
-        if header_info.len() != 2 {
+        // Bristol Fashion files may start with a metadata block, e.g. the name and version of the
+        // circuit. Only lines with the marker belong to it, any other line is taken for the header.
+        let mut metadata_lines = 0;
+        let first = loop {
+            let line = input.next().ok_or_else(too_small)??;
+            if !is_metadata_line(line.as_ref()) {
+                break line;
+            }
+            metadata_lines += 1;
+        };
+        if !first
+            .as_ref()
+            .trim_start()
+            .starts_with(|c: char| c.is_ascii_digit())
+        {
+            return Err(CircuitError::ParsingError(format!(
+                "line {} neither starting with the gate and wire amounts nor with the metadata \
+                 marker {}",
+                metadata_lines + 1,
+                METADATA_MARKER
+            )));
+        }
+
+        // the header consists of the first four lines, the gates follow. A circuit without gates
+        // may end right after the nov line.
+        let mut header_lines: Vec<S> = Vec::with_capacity(4);
+        header_lines.push(first);
+        for l in 1..4 {
+            match input.next() {
+                Some(line) => header_lines.push(line?),
+                None if l == 3 => break,
+                None => return Err(too_small()),
+            }
+        }
+        let lines: Vec<&str> = header_lines.iter().map(|l| l.as_ref()).collect();
+
+        // =========== Parse the header ==========
+        // the number of tokens is checked before the tokens themselves, so a line with the wrong
+        // number of tokens is reported as such even if some of them are no numbers
+        let header_tokens: Vec<&str> = lines[0].split_whitespace().collect();
+        if header_tokens.len() != 2 {
             return Err(CircuitError::ParsingHeaderInformationError(
                 2,
-                header_info.len(),
+                header_tokens.len(),
             ));
         }
+        let header_info: Vec<usize> = header_tokens
+            .into_iter()
+            .map(parse_index)
+            .collect::<Result<_, _>>()?;
 
         // Parsing niv line
 
-        let inputs_count = match get_expected_line_length_header(lines.clone(), 1) {
-            Ok(v) => v,
-            Err(e) => {
-                return Err(e);
-            }
-        };
+        let inputs_count = get_expected_line_length_header(lines[1], metadata_lines + 2)?;
 
         let niv: Vec<usize> = lines[1]
             .split_whitespace()
             .skip(1)
-            .map(|s| s.parse().unwrap())
-            .collect();
+            .map(parse_index)
+            .collect::<Result<_, _>>()?;
 
         if inputs_count != niv.len() {
             return Err(CircuitError::ParsingNivError(inputs_count, niv.len()));
@@ -110,82 +416,162 @@ impl Circuit {
 
         // Parsing Nov line
 
-        let outputs_count = match get_expected_line_length_header(lines.clone(), 2) {
-            Ok(v) => v,
-            Err(e) => {
-                return Err(e);
-            }
-        };
+        let outputs_count = get_expected_line_length_header(lines[2], metadata_lines + 3)?;
 
         let nov: Vec<usize> = lines[2]
             .split_whitespace()
             .skip(1)
-            .map(|s| s.parse().unwrap())
-            .collect();
+            .map(parse_index)
+            .collect::<Result<_, _>>()?;
 
         if outputs_count != nov.len() {
             return Err(CircuitError::ParsingNovError(outputs_count, nov.len()));
         }
 
-        let header: Header = Header {
+        let mut header: Header = Header {
             gates_amount: header_info[0],
             wires_amount: header_info[1],
             niv,
             nov,
         };
+        options.check(&header)?;
 
-        if !lines[3].is_empty() {
+        // the separating line may contain whitespace, like the empty lines between gates
+        if lines.get(3).is_some_and(|l| !l.trim().is_empty()) {
             return Err(CircuitError::EmptyLineMissingError);
         }
 
         // ============= parse the gates ============
 
         let mut gates: Vec<Gate> = Vec::new();
+        let mut gate_lines = 0;
 
-        for line in lines[4..].iter() {
+        // the gates start in the fifth line after the metadata
+        for (line_number, line) in (5 + metadata_lines..).zip(input) {
+            let line = line?;
+            let line = line.as_ref();
+            if line.trim().is_empty() {
+                continue;
+            }
+            // every line is one gate, even if it is a MAND which is split into several AND gates
+            if gate_lines == header.gates_amount {
+                return Err(CircuitError::TooManyGates(header.gates_amount, line_number));
+            }
+            gate_lines += 1;
+            // A gate line consists of the input and output amounts, the input wires, the output
+            // wires and the gate type. The positions follow from the amounts, not from fixed
+            // columns, and the tokens can be separated by any run of spaces and tabs.
             let gate_info: Vec<&str> = line.split_whitespace().collect();
+            if gate_info.len() < 2 {
+                return Err(CircuitError::ParsingError(format!(
+                    "the gate in line {} missing its input and output amounts",
+                    line_number
+                )));
+            }
+            let input_amount: usize = parse_index(gate_info[0])?;
+            let output_amount: usize = parse_index(gate_info[1])?;
+            let expected_tokens = input_amount.saturating_add(output_amount).saturating_add(3);
+            if gate_info.len() != expected_tokens {
+                return Err(CircuitError::ParsingError(format!(
+                    "the gate in line {} having {} token(s) instead of {}",
+                    line_number,
+                    gate_info.len(),
+                    expected_tokens
+                )));
+            }
+            let inputs = &gate_info[2..2 + input_amount];
+            let outputs = &gate_info[2 + input_amount..2 + input_amount + output_amount];
+            let gate_name = options.canonical_name(gate_info[2 + input_amount + output_amount]);
+            // every wire is checked against the header right away, so that no later stage indexes
+            // the wires out of bounds
+            let wire = |token: &str| {
+                let wire = parse_index(token)?;
+                WireId::new(wire, header.wires_amount).ok_or(CircuitError::WireIndexOutOfRange {
+                    line: line_number,
+                    wire,
+                    wires_amount: header.wires_amount,
+                })
+            };
 
-            let input_amount: usize = gate_info[0].parse().unwrap();
-            let output_amount: usize = gate_info[1].parse().unwrap();
-
-            let gate_type: GateType = match gate_info[input_amount + output_amount + 2] {
-                "XOR" => {
-                    GateType::XOR(gate_info[2].parse().unwrap(), gate_info[3].parse().unwrap())
+            if gate_name == "MAND" {
+                // A MAND gate (Bristol Fashion) computes several ANDs at once. The first half of
+                // the inputs are the left operands, the second half the right ones.
+                if input_amount != 2 * output_amount {
+                    return Err(CircuitError::ParsingError(
+                        "Something went wrong whilst parsing a MAND gate".to_string(),
+                    ));
                 }
-                "AND" => {
-                    GateType::AND(gate_info[2].parse().unwrap(), gate_info[3].parse().unwrap())
+                for k in 0..output_amount {
+                    gates.push(Gate {
+                        gate_type: GateType::AND(
+                            wire(inputs[k])?,
+                            wire(inputs[output_amount + k])?,
+                        ),
+                        output: wire(outputs[k])?,
+                    });
                 }
-                "INV" => GateType::INV(gate_info[2].parse().unwrap()),
-                _ => {
-                    return Err(CircuitError::NotAGateError(
-                        gate_info[input_amount + output_amount + 2].to_string(),
-                    ))
-                }
-            };
+                continue;
+            }
 
-            let output_index: usize;
-            if input_amount == 2 {
-                output_index = 4;
-            } else if input_amount == 1 {
-                output_index = 3;
-            } else {
-                return Err(CircuitError::ParsingError(
-                    "Something went wrong whilst parsing a gate".to_string(),
-                ));
+            let arity = match gate_name {
+                "XOR" | "AND" => 2,
+                "INV" => 1,
+                _ => return Err(CircuitError::NotAGateError(gate_name.to_string())),
+            };
+            if input_amount != arity || output_amount != 1 {
+                return Err(CircuitError::ParsingError(format!(
+                    "the {} gate in line {} having {} input(s) and {} output(s)",
+                    gate_name, line_number, input_amount, output_amount
+                )));
             }
+            let gate_type: GateType = match gate_name {
+                "XOR" => GateType::XOR(wire(inputs[0])?, wire(inputs[1])?),
+                "AND" => GateType::AND(wire(inputs[0])?, wire(inputs[1])?),
+                _ => GateType::INV(wire(inputs[0])?),
+            };
 
             gates.push(Gate {
                 gate_type,
-                output: gate_info[output_index].parse().unwrap(),
+                output: wire(outputs[0])?,
             })
         }
-        if gates.len() != header.gates_amount {
-            return Err(CircuitError::WrongGateAmount(
-                header.gates_amount,
-                gates.len(),
-            ));
+        if gate_lines == 0 && header.gates_amount > 0 {
+            return Err(CircuitError::WrongGateAmount(header.gates_amount));
+        }
+        if gate_lines < header.gates_amount {
+            return Err(CircuitError::TooFewGates(header.gates_amount, gate_lines));
         }
-        Ok(Circuit { header, gates })
+        // the header counted the lines, the circuit counts the AND gates of a MAND line separately
+        header.gates_amount = gates.len();
+        Ok(Circuit::new(header, gates))
+    }
+
+    /// Writes the circuit in the bristol format, such that `Circuit::parse` yields the same
+    /// circuit again.
+    pub fn to_bristol(&self) -> String {
+        let mut out = String::new();
+        let values = |v: &[usize]| {
+            let widths: Vec<String> = v.iter().map(|w| w.to_string()).collect();
+            format!("{} {}\n", v.len(), widths.join(" "))
+        };
+
+        out.push_str(&format!(
+            "{} {}\n",
+            self.header.gates_amount, self.header.wires_amount
+        ));
+        out.push_str(&values(&self.header.niv));
+        out.push_str(&values(&self.header.nov));
+        out.push('\n');
+
+        for gate in &self.gates {
+            let line = match gate.gate_type {
+                GateType::XOR(a, b) => format!("2 1 {} {} {} XOR\n", a, b, gate.output),
+                GateType::AND(a, b) => format!("2 1 {} {} {} AND\n", a, b, gate.output),
+                GateType::INV(a) => format!("1 1 {} {} INV\n", a, gate.output),
+            };
+            out.push_str(&line);
+        }
+        out
     }
 }
 
@@ -194,9 +580,10 @@ impl Circuit {
 #[cfg(test)]
 mod tests {
 
-    use crate::circuit::circuit_parser::{Gate, GateType};
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_parser::{Gate, GateType, WireId};
 
-    use super::Circuit;
+    use super::{Circuit, ParseOptions};
     // Functions marked with `#[test]` are automatically run when you execute `cargo test`.
     #[test]
     fn test_and() {
@@ -205,17 +592,24 @@ mod tests {
             2 1 1\n\
             1 1\n\
             \n\
-            2 1 0 1 9 AND\n";
+            2 1 0 1 2 AND\n";
 
         let c = Circuit::parse(circuit).unwrap();
 
         let g: Gate = Gate {
-            gate_type: GateType::AND(0, 1),
-            output: 9,
+            gate_type: GateType::AND(WireId::unchecked(0), WireId::unchecked(1)),
+            output: WireId::unchecked(2),
         };
         assert_eq!(c.gates, vec![g]);
     }
 
+    #[test]
+    fn test_single_output_wire() {
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        assert_eq!(c.get_nov_sum(), 1);
+        assert_eq!(c.get_output_wires(), 2);
+    }
+
     #[test]
     fn test_xor() {
         let circuit = "\
@@ -223,13 +617,13 @@ mod tests {
             2 1 1\n\
             1 1\n\
             \n\
-            2 1 0 1 9 XOR\n";
+            2 1 0 1 2 XOR\n";
 
         let c = Circuit::parse(circuit).unwrap();
 
         let g: Gate = Gate {
-            gate_type: GateType::XOR(0, 1),
-            output: 9,
+            gate_type: GateType::XOR(WireId::unchecked(0), WireId::unchecked(1)),
+            output: WireId::unchecked(2),
         };
         assert_eq!(c.gates, vec![g]);
     }
@@ -240,14 +634,427 @@ mod tests {
             1 1\n\
             1 1\n\
             \n\
-            1 1 0 9 INV\n";
+            1 1 0 1 INV\n";
 
         let c = Circuit::parse(circuit).unwrap();
 
         let g: Gate = Gate {
-            gate_type: GateType::INV(0),
-            output: 9,
+            gate_type: GateType::INV(WireId::unchecked(0)),
+            output: WireId::unchecked(1),
         };
         assert_eq!(c.gates, vec![g]);
     }
+
+    #[test]
+    fn test_malformed_first_line() {
+        let rest = "2 1 1\n1 1\n\n2 1 0 1 2 AND\n";
+        let parse = |first: &str| Circuit::parse(&format!("{}\n{}", first, rest));
+        for (first, tokens) in [("32", 1), ("1 3 5", 3), ("1 x y", 3), ("3\t", 1)] {
+            assert!(
+                matches!(
+                    parse(first),
+                    Err(CircuitError::ParsingHeaderInformationError(2, n)) if n == tokens
+                ),
+                "first line {:?}",
+                first
+            );
+        }
+        for first in ["32 x", "1 -3", "1 3.0"] {
+            assert!(matches!(parse(first), Err(CircuitError::ParsingError(_))));
+        }
+        // an empty input has no first line, and a line without tokens is no header line
+        assert!(matches!(
+            Circuit::parse(""),
+            Err(CircuitError::ParsingError(_))
+        ));
+        assert_eq!(
+            parse("  ").unwrap_err().to_string(),
+            "Parsing failed, due to line 1 neither starting with the gate and wire amounts nor \
+             with the metadata marker %"
+        );
+    }
+
+    #[test]
+    fn test_wire_index_out_of_range() {
+        let e = Circuit::parse("1 10\n2 1 1\n1 1\n\n2 1 0 1 999 AND\n").unwrap_err();
+        assert!(matches!(
+            e,
+            CircuitError::WireIndexOutOfRange {
+                line: 5,
+                wire: 999,
+                wires_amount: 10
+            }
+        ));
+        assert_eq!(
+            e.to_string(),
+            "The gate in line 5 refers to wire 999, but the header declares 10 wire(s)"
+        );
+        // inputs are checked as well, also those of a MAND gate
+        for gate in ["1 1 10 9 INV", "4 2 0 1 2 10 8 9 MAND"] {
+            let circuit = format!("1 10\n2 1 1\n1 2\n\n{}\n", gate);
+            assert!(matches!(
+                Circuit::parse(&circuit),
+                Err(CircuitError::WireIndexOutOfRange { wire: 10, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_gate_aliases() {
+        let canonical = "3 5\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n1 1 2 3 INV\n2 1 3 0 4 AND\n";
+        let aliased = "3 5\n2 1 1\n1 1\n\n2 1 0 1 2 EOR\n1 1 2 3 NOT\n2 1 3 0 4 AND\n";
+        let expected = vec![
+            GateType::XOR(WireId::unchecked(0), WireId::unchecked(1)),
+            GateType::INV(WireId::unchecked(2)),
+            GateType::AND(WireId::unchecked(3), WireId::unchecked(0)),
+        ];
+        for circuit in [canonical, aliased] {
+            let c = Circuit::parse(circuit).unwrap();
+            let types: Vec<GateType> = c.gates.iter().map(|g| g.gate_type.clone()).collect();
+            assert_eq!(types, expected);
+        }
+
+        // a dialect of its own, which also replaces the defaults
+        let mut options = ParseOptions::default();
+        options.aliases.clear();
+        options.aliases.insert("X".to_string(), "XOR".to_string());
+        options.aliases.insert("N".to_string(), "INV".to_string());
+        options.aliases.insert("A".to_string(), "AND".to_string());
+        let custom = "3 5\n2 1 1\n1 1\n\n2 1 0 1 2 X\n1 1 2 3 N\n2 1 3 0 4 A\n";
+        let c = Circuit::parse_with(custom, &options).unwrap();
+        assert_eq!(c.gates, Circuit::parse(canonical).unwrap().gates);
+        assert!(matches!(
+            Circuit::parse_with(aliased, &options),
+            Err(CircuitError::NotAGateError(name)) if name == "EOR"
+        ));
+    }
+
+    #[test]
+    fn test_mand() {
+        let circuit = "\
+            1 6\n\
+            2 2 2\n\
+            1 2\n\
+            \n\
+            4 2 0 1 2 3 4 5 MAND\n";
+
+        let c = Circuit::parse(circuit).unwrap();
+
+        let g1: Gate = Gate {
+            gate_type: GateType::AND(WireId::unchecked(0), WireId::unchecked(2)),
+            output: WireId::unchecked(4),
+        };
+        let g2: Gate = Gate {
+            gate_type: GateType::AND(WireId::unchecked(1), WireId::unchecked(3)),
+            output: WireId::unchecked(5),
+        };
+        assert_eq!(c.gates, vec![g1, g2]);
+        assert_eq!(c.header.gates_amount, 2);
+
+        // the split gates are written as separate AND lines
+        let c2 = Circuit::parse(&c.to_bristol()).unwrap();
+        assert_eq!(c2.header, c.header);
+        assert_eq!(c2.gates, c.gates);
+    }
+
+    #[test]
+    fn test_to_bristol_round_trip() {
+        let contents = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        let c2 = Circuit::parse(&c.to_bristol()).unwrap();
+        assert_eq!(c2.header, c.header);
+        assert_eq!(c2.gates, c.gates);
+    }
+
+    #[test]
+    fn test_hex_indices() {
+        let circuit = "\
+            2 0x83\n\
+            2 64 0x40\n\
+            1 1\n\
+            \n\
+            2 1 0x0 64 0x80 AND\n\
+            1 1 0x80 130 INV\n";
+
+        let c = Circuit::parse(circuit).unwrap();
+
+        assert_eq!(c.header.wires_amount, 131);
+        assert_eq!(c.header.niv, vec![64, 64]);
+        let g1: Gate = Gate {
+            gate_type: GateType::AND(WireId::unchecked(0), WireId::unchecked(64)),
+            output: WireId::unchecked(128),
+        };
+        let g2: Gate = Gate {
+            gate_type: GateType::INV(WireId::unchecked(128)),
+            output: WireId::unchecked(130),
+        };
+        assert_eq!(c.gates, vec![g1, g2]);
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let circuit = "\
+            1 3\n\
+            2 1 1\n\
+            1 1\n\
+            \n\
+            2 1 0 0xg 2 AND\n";
+
+        assert!(Circuit::parse(circuit).is_err());
+    }
+
+    #[test]
+    fn test_many_values() {
+        // 12 input values of one bit each and 10 output values, the last ten inputs negated
+        let mut circuit =
+            String::from("10 22\n12 1 1 1 1 1 1 1 1 1 1 1 1\n10 1 1 1 1 1 1 1 1 1 1\n\n");
+        for i in 0..10 {
+            circuit.push_str(&format!("1 1 {} {} INV\n", i + 2, i + 12));
+        }
+
+        let c = Circuit::parse(&circuit).unwrap();
+        assert_eq!(c.header.niv, vec![1; 12]);
+        assert_eq!(c.header.nov, vec![1; 10]);
+        assert_eq!(c.gates.len(), 10);
+    }
+
+    #[test]
+    fn test_malformed_value_count() {
+        let circuit = "\
+            1 3\n\
+            x2 1 1\n\
+            1 1\n\
+            \n\
+            2 1 0 1 2 AND\n";
+
+        let e = Circuit::parse(circuit).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Parsing failed, due to the value count \"x2\" in line 2 not being a valid number"
+        );
+
+        // the lines of the metadata block are counted as well
+        let e = Circuit::parse("% name and\n1 3\n2 1 1\nx1 1\n\n2 1 0 1 2 AND\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Parsing failed, due to the value count \"x1\" in line 4 not being a valid number"
+        );
+    }
+
+    #[test]
+    fn test_from_file() {
+        let c = Circuit::from_file("test_circuits/64_Sub.txt").unwrap();
+        let contents = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let expected = Circuit::parse(&contents).unwrap();
+        assert_eq!(c.header, expected.header);
+        assert_eq!(c.gates, expected.gates);
+    }
+
+    #[test]
+    fn test_from_file_io_errors() {
+        // a missing file fails when opening it, a directory when reading from it
+        for path in ["test_circuits/missing.txt", "test_circuits"] {
+            let e = Circuit::from_file(path).unwrap_err();
+            assert!(matches!(e, CircuitError::Io { .. }));
+            assert!(e.to_string().contains(path));
+        }
+    }
+
+    #[test]
+    fn test_from_stdin() {
+        let contents = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let c = Circuit::from_path_or_stdin("-", contents.as_bytes()).unwrap();
+        let expected = Circuit::parse(&contents).unwrap();
+        assert_eq!(c.header, expected.header);
+        assert_eq!(c.gates, expected.gates);
+
+        // stdin is only read for "-"
+        let c = Circuit::from_path_or_stdin("test_circuits/64_Sub.txt", std::io::empty()).unwrap();
+        assert_eq!(c.gates, expected.gates);
+
+        let e = Circuit::from_path_or_stdin("-", &[0xff, 0xfe][..]).unwrap_err();
+        assert!(e.to_string().contains("<stdin>"));
+    }
+
+    #[test]
+    fn test_from_gzip_file() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let path = std::env::temp_dir().join(format!("mpc_adder_{}.txt.gz", std::process::id()));
+        std::fs::write(&path, &compressed).unwrap();
+        let c = Circuit::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let expected = Circuit::parse(&contents).unwrap();
+        assert_eq!(c.header, expected.header);
+        assert_eq!(c.gates, expected.gates);
+
+        // a truncated archive fails to decompress, rather than to parse
+        let truncated = &compressed[..compressed.len() / 2];
+        let e = Circuit::from_path_or_stdin("-", truncated).unwrap_err();
+        assert!(matches!(e, CircuitError::Decompression { .. }));
+    }
+
+    #[test]
+    fn test_gate_amount() {
+        let header = "2 4\n2 1 1\n1 1\n\n";
+        let gates = "2 1 0 1 2 XOR\n2 1 0 2 3 AND\n";
+
+        // trailing blank lines are ignored
+        let c = Circuit::parse(&format!("{}{}\n  \n", header, gates)).unwrap();
+        assert_eq!(c.gates.len(), 2);
+
+        let e = Circuit::parse(&format!("{}{}1 1 3 3 INV\n", header, gates)).unwrap_err();
+        assert!(matches!(e, CircuitError::TooManyGates(2, 7)));
+
+        let e = Circuit::parse(&format!("{}2 1 0 1 3 XOR\n", header)).unwrap_err();
+        assert!(matches!(e, CircuitError::TooFewGates(2, 1)));
+
+        // a header declaring gates, but none following it
+        let e = Circuit::parse(header).unwrap_err();
+        assert!(matches!(e, CircuitError::WrongGateAmount(2)));
+        let e = Circuit::parse("2 4\n2 1 1\n1 1\n").unwrap_err();
+        assert!(matches!(e, CircuitError::WrongGateAmount(2)));
+    }
+
+    #[test]
+    fn test_zero_gates() {
+        // the outputs are the input wires themselves, with or without the empty line
+        for contents in ["0 2\n2 1 1\n1 2\n\n", "0 2\n2 1 1\n1 2\n"] {
+            let c = Circuit::parse(contents).unwrap();
+            assert!(c.gates.is_empty());
+            assert_eq!(c.header.nov, vec![2]);
+        }
+        let e = Circuit::parse("0 2\n2 1 1\n").unwrap_err();
+        assert!(matches!(e, CircuitError::ParsingError(_)));
+    }
+
+    #[test]
+    fn test_whitespace_between_tokens() {
+        let canonical = "3 6\n2 2 1\n1 1\n\n2 1 0 1 3 AND\n1 1 2 4 INV\n2 1 3 4 5 XOR\n";
+        let expected = Circuit::parse(canonical).unwrap();
+
+        let spaced =
+            "3   6\n2  2   1 \n  1 1\n   \n2 1  0 1   3 AND\n 1 1 2 4  INV  \n2 1 3 4 5    XOR\n";
+        let tabbed =
+            "3\t6\n2\t2\t1\n1\t1\n\t\n2\t1\t0\t1\t3\tAND\n1\t1\t2\t4\tINV\n2 \t1\t 3\t\t4 5\tXOR\n";
+        let mand = "2 6\n2 2 1\n1 2\n\n4\t2  0 1\t1 2\t3 \t4 MAND\n1 1 2 5 INV\n";
+        for contents in [spaced, tabbed, &canonical.replace('\n', "\r\n")] {
+            let c = Circuit::parse(contents).unwrap();
+            assert_eq!(c.header, expected.header);
+            assert_eq!(c.gates, expected.gates);
+        }
+        assert_eq!(Circuit::parse(mand).unwrap().gates.len(), 3);
+    }
+
+    #[test]
+    fn test_bristol_fashion_metadata() {
+        let circuit = "2 6\n2 2 1\n1 2\n\n4 2 0 1 1 2 3 4 MAND\n1 1 2 5 INV\n";
+        let expected = Circuit::parse(circuit).unwrap();
+        let metadata = "% circuit: and-not\n%\n  % version 1.2\n";
+        let c = Circuit::parse(&format!("{}{}", metadata, circuit)).unwrap();
+        assert_eq!(c.header, expected.header);
+        assert_eq!(c.gates, expected.gates);
+
+        // the line numbers of the gates count the metadata lines, too
+        let e = Circuit::parse(&format!("{}2 4\n2 1 1\n1 1\n\n2 1 0 1 AND\n", metadata));
+        assert_eq!(
+            e.unwrap_err().to_string(),
+            "Parsing failed, due to the gate in line 8 having 5 token(s) instead of 6"
+        );
+        let e = Circuit::parse(metadata).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Parsing failed, due to the Circuit being too small"
+        );
+
+        // lines without the marker are not skipped, even if they look like metadata
+        for unmarked in ["circuit: and-not\n", "% circuit: and-not\n\n"] {
+            let e = Circuit::parse(&format!("{}{}", unmarked, circuit)).unwrap_err();
+            assert!(matches!(e, CircuitError::ParsingError(_)), "{}", e);
+            assert!(e.to_string().contains("metadata marker"));
+        }
+    }
+
+    #[test]
+    fn test_malformed_gate_lines() {
+        let header = "1 4\n2 1 1\n1 1\n\n";
+        for (gate, message) in [
+            (
+                "2 1 0 1 AND",
+                "the gate in line 5 having 5 token(s) instead of 6",
+            ),
+            (
+                "2 1 0 1 2 3 AND",
+                "the gate in line 5 having 7 token(s) instead of 6",
+            ),
+            (
+                "2",
+                "the gate in line 5 missing its input and output amounts",
+            ),
+            (
+                "1 1 0 3 AND",
+                "the AND gate in line 5 having 1 input(s) and 1 output(s)",
+            ),
+            (
+                "2 1 0 1 3 INV",
+                "the INV gate in line 5 having 2 input(s) and 1 output(s)",
+            ),
+        ] {
+            let e = Circuit::parse(&format!("{}{}\n", header, gate)).unwrap_err();
+            assert_eq!(e.to_string(), format!("Parsing failed, due to {}", message));
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        // an absurd header fails before anything is allocated for it
+        let huge_wires = "1 1000000000000\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n";
+        let e = Circuit::parse(huge_wires).unwrap_err();
+        assert!(matches!(
+            e,
+            CircuitError::LimitExceeded {
+                what: "wires",
+                requested: 1_000_000_000_000,
+                ..
+            }
+        ));
+        let huge_gates = "1000000000000 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n";
+        let e = Circuit::parse(huge_gates).unwrap_err();
+        assert!(matches!(
+            e,
+            CircuitError::LimitExceeded { what: "gates", .. }
+        ));
+        let huge_inputs = format!("1 3\n2 {} {}\n1 1\n\n2 1 0 1 2 AND\n", usize::MAX, 2);
+        let e = Circuit::parse(&huge_inputs).unwrap_err();
+        assert!(matches!(
+            e,
+            CircuitError::LimitExceeded {
+                what: "input bits",
+                requested: usize::MAX,
+                ..
+            }
+        ));
+
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let options = ParseOptions {
+            max_wires: 100,
+            ..ParseOptions::default()
+        };
+        let e = Circuit::parse_with(&contents, &options).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "The circuit declares 504 wires, but at most 100 are allowed"
+        );
+        let adder = Circuit::parse(&contents).unwrap();
+        assert!(adder.validate_with(&options).is_err());
+        assert!(adder.validate().is_ok());
+    }
 }
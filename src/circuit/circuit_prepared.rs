@@ -0,0 +1,94 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::Circuit;
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+/// A circuit which passed [`Circuit::validate`], with its layers computed. Cloning it is cheap, so
+/// both parties, and the parties of several runs, share the same circuit instead of copying,
+/// validating and layering it each time.
+#[derive(Debug, Clone)]
+pub struct PreparedCircuit {
+    circuit: Arc<Circuit>,
+    output_wires: Range<usize>,
+}
+
+impl Circuit {
+    /// Validates the circuit and computes its layers once, for all parties which evaluate it.
+    pub fn prepare(self) -> Result<PreparedCircuit, CircuitError> {
+        self.validate()?;
+        self.layers();
+        Ok(PreparedCircuit {
+            output_wires: self.get_output_wires()..self.header.wires_amount,
+            circuit: Arc::new(self),
+        })
+    }
+}
+
+impl PreparedCircuit {
+    /// The wires holding the output bits, in output order.
+    pub fn output_wires(&self) -> Range<usize> {
+        self.output_wires.clone()
+    }
+}
+
+impl Deref for PreparedCircuit {
+    type Target = Circuit;
+
+    fn deref(&self) -> &Circuit {
+        &self.circuit
+    }
+}
+
+impl From<PreparedCircuit> for Arc<Circuit> {
+    fn from(prepared: PreparedCircuit) -> Self {
+        prepared.circuit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::mul_triple::SeededMTP;
+    use crate::party::party_gmw::Party;
+    use crate::party::transport::channel_pair;
+    use rand::rngs::StdRng;
+    use std::thread;
+
+    #[test]
+    fn test_prepare_once_for_several_runs() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let prepared = Circuit::parse(&contents).unwrap().prepare().unwrap();
+        assert_eq!(prepared.output_wires(), 440..504);
+
+        for (a, b) in [(3u64, 4u64), (100, 23)] {
+            let (t0, t1) = channel_pair();
+            let mut p0 = Party::new(
+                prepared.clone(),
+                t0,
+                false,
+                SeededMTP::<StdRng>::new([1; 32]),
+            )
+            .unwrap();
+            let mut p1 = Party::new(
+                prepared.clone(),
+                t1,
+                true,
+                SeededMTP::<StdRng>::new([1; 32]),
+            )
+            .unwrap();
+
+            let input = to_bits(b, 64).unwrap();
+            let h1 = thread::spawn(move || p1.execute(&input).unwrap());
+            let output = p0.execute(&to_bits(a, 64).unwrap()).unwrap();
+            assert_eq!(output, h1.join().unwrap());
+            assert_eq!(output, to_bits(a + b, 64).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_prepare_rejects_invalid_circuit() {
+        let c = Circuit::parse("1 4\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        assert!(c.prepare().is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::circuit::circuit_parser::{Circuit, GateType};
+
+/// Number of gates of each type in a circuit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub and: usize,
+    pub xor: usize,
+    pub inv: usize,
+}
+
+impl CircuitStats {
+    pub fn gates(&self) -> usize {
+        self.and + self.xor + self.inv
+    }
+}
+
+impl Circuit {
+    /// Counts the gates of each type.
+    pub fn stats(&self) -> CircuitStats {
+        let mut stats = CircuitStats::default();
+        for gate in &self.gates {
+            match gate.gate_type {
+                GateType::AND(_, _) => stats.and += 1,
+                GateType::XOR(_, _) => stats.xor += 1,
+                GateType::INV(_) => stats.inv += 1,
+            }
+        }
+        stats
+    }
+
+    /// Number of gates on the longest path from an input wire to any other wire.
+    pub fn depth(&self) -> usize {
+        self.longest_path(|_| true)
+    }
+
+    /// Number of AND gates on the path from an input wire with the most of them, i.e. the number
+    /// of communication rounds needed when all AND gates of a layer are evaluated together.
+    pub fn and_depth(&self) -> usize {
+        self.longest_path(|gate_type| matches!(gate_type, GateType::AND(_, _)))
+    }
+
+    /// The indices of the AND gates at each multiplicative depth: level `d` holds the AND gates
+    /// with `d` AND gates on their longest path from an input. All gates of a level can be
+    /// evaluated in the same communication round, like the AND gates of [`Circuit::layers`].
+    pub fn and_layers(&self) -> Vec<Vec<usize>> {
+        self.layers()
+            .iter()
+            // only the last layer can lack AND gates, when local gates follow the last AND gate
+            .filter(|layer| !layer.and.is_empty())
+            .map(|layer| layer.and.clone())
+            .collect()
+    }
+
+    /// Length of the longest path, on which only gates for which `counts` is true are counted.
+    fn longest_path(&self, counts: impl Fn(&GateType) -> bool) -> usize {
+        self.wire_depths(counts).into_iter().max().unwrap_or(0)
+    }
+
+    /// Length of the longest path to every wire, on which only gates for which `counts` is true
+    /// are counted.
+    fn wire_depths(&self, counts: impl Fn(&GateType) -> bool) -> Vec<usize> {
+        let mut depth = vec![0; self.header.wires_amount];
+        for gate in &self.gates {
+            depth[gate.output.index()] = match gate.gate_type {
+                GateType::XOR(a, b) | GateType::AND(a, b) => depth[a.index()].max(depth[b.index()]),
+                GateType::INV(a) => depth[a.index()],
+            } + usize::from(counts(&gate.gate_type));
+        }
+        depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitStats;
+    use crate::circuit::circuit_parser::Circuit;
+
+    #[test]
+    fn test_stats_and_depth() {
+        // out = !((a ^ b) & b)
+        let c = Circuit::parse("3 5\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n2 1 2 1 3 AND\n1 1 3 4 INV\n")
+            .unwrap();
+        assert_eq!(
+            c.stats(),
+            CircuitStats {
+                and: 1,
+                xor: 1,
+                inv: 1
+            }
+        );
+        assert_eq!(c.stats().gates(), 3);
+        assert_eq!(c.depth(), 3);
+        assert_eq!(c.and_depth(), 1);
+    }
+
+    #[test]
+    fn test_and_layers() {
+        // c = a & b and d = a & !b only depend on the inputs, e = c & d on both of them, and
+        // f = e ^ (a & b) is a second AND of the first level after the last gate of the second
+        let c = Circuit::parse(
+            "6 8\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n1 1 1 3 INV\n2 1 0 3 4 AND\n\
+             2 1 2 4 5 AND\n2 1 0 1 6 AND\n2 1 5 6 7 XOR\n",
+        )
+        .unwrap();
+        assert_eq!(c.and_layers(), vec![vec![0, 2, 4], vec![3]]);
+        // the last layer of the batched evaluation only holds the final XOR gate
+        let batched: Vec<Vec<usize>> = c.layers()[..2].iter().map(|l| l.and.clone()).collect();
+        assert_eq!(c.and_layers(), batched);
+
+        let xor_only = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        assert!(xor_only.and_layers().is_empty());
+    }
+
+    #[test]
+    fn test_stats_adder() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let stats = c.stats();
+        assert_eq!(stats.gates(), c.gates.len());
+        assert_eq!(stats.and, c.required_triples());
+        // the carry ripples through one AND gate per bit
+        assert_eq!(c.and_depth(), 63);
+    }
+}
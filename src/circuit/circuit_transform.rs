@@ -0,0 +1,721 @@
+use crate::circuit::circuit_builder::CircuitBuilder;
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::{Circuit, Gate, GateType, Header, WireId};
+use std::collections::HashMap;
+
+/// The value of a wire while propagating constants: a constant, or a wire of the reduced circuit
+/// which is negated if the flag is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Propagated {
+    Constant(bool),
+    Wire(WireId, bool),
+}
+
+impl GateType {
+    /// Returns the gate type with every wire replaced by `f(wire)`.
+    pub fn map_wires(&self, mut f: impl FnMut(WireId) -> WireId) -> GateType {
+        match *self {
+            GateType::XOR(a, b) => GateType::XOR(f(a), f(b)),
+            GateType::AND(a, b) => GateType::AND(f(a), f(b)),
+            GateType::INV(a) => GateType::INV(f(a)),
+        }
+    }
+}
+
+impl Circuit {
+    /// Composes this circuit with `other` by feeding outputs of this circuit into inputs of
+    /// `other`. Every `(output, input)` pair in `wiring` connects output bit `output` of this
+    /// circuit to input bit `input` of `other` (both counted from 0 over all values).
+    ///
+    /// The inputs of the composed circuit are the inputs of this circuit, followed by the inputs
+    /// of `other` which are not connected. Input values of `other` shrink by the connected bits,
+    /// and are dropped if all their bits are connected. The outputs of the composed circuit are
+    /// the outputs of `other`.
+    pub fn concat(
+        &self,
+        other: &Circuit,
+        wiring: &[(usize, usize)],
+    ) -> Result<Circuit, CircuitError> {
+        let self_inputs = self.get_niv_sum();
+        let self_outputs = self.get_nov_sum();
+        let other_inputs = other.get_niv_sum();
+
+        // the output bit of self connected to each input bit of other, if any
+        let mut connected: Vec<Option<usize>> = vec![None; other_inputs];
+        for &(output, input) in wiring {
+            if output >= self_outputs || input >= other_inputs || connected[input].is_some() {
+                return Err(CircuitError::InvalidWiring(output, input));
+            }
+            connected[input] = Some(output);
+        }
+
+        // remaining input values of other, with the connected bits removed
+        let mut niv = self.header.niv.clone();
+        let mut bit = 0;
+        for &width in &other.header.niv {
+            let free = connected[bit..bit + width]
+                .iter()
+                .filter(|c| c.is_none())
+                .count();
+            if free > 0 {
+                niv.push(free);
+            }
+            bit += width;
+        }
+        let free_inputs = niv.iter().sum::<usize>() - self_inputs;
+
+        // Wire layout: inputs of self, free inputs of other, remaining wires of self, remaining
+        // wires of other. The outputs of other thereby stay the last wires.
+        let self_offset = free_inputs;
+        let other_offset = self.header.wires_amount + free_inputs - other_inputs;
+
+        let map_self = |w: WireId| {
+            if w.index() < self_inputs {
+                w
+            } else {
+                WireId::unchecked(w.index() + self_offset)
+            }
+        };
+
+        let mut other_input_map = Vec::with_capacity(other_inputs);
+        let mut next_free = self_inputs;
+        for c in &connected {
+            match c {
+                Some(output) => other_input_map.push(map_self(WireId::unchecked(
+                    self.get_output_wires() + output,
+                ))),
+                None => {
+                    other_input_map.push(WireId::unchecked(next_free));
+                    next_free += 1;
+                }
+            }
+        }
+        let map_other = |w: WireId| {
+            if w.index() < other_inputs {
+                other_input_map[w.index()]
+            } else {
+                WireId::unchecked(w.index() + other_offset)
+            }
+        };
+
+        let mut gates = Vec::with_capacity(self.gates.len() + other.gates.len());
+        for gate in &self.gates {
+            gates.push(Gate {
+                gate_type: gate.gate_type.map_wires(map_self),
+                output: map_self(gate.output),
+            });
+        }
+        for gate in &other.gates {
+            gates.push(Gate {
+                gate_type: gate.gate_type.map_wires(map_other),
+                output: map_other(gate.output),
+            });
+        }
+
+        Ok(Circuit::new(
+            Header {
+                gates_amount: gates.len(),
+                wires_amount: other.header.wires_amount + other_offset,
+                niv,
+                nov: other.header.nov.clone(),
+            },
+            gates,
+        ))
+    }
+
+    /// Returns the indices of the gates whose output neither is an output of the circuit nor is
+    /// read by a gate which is not dead itself, in gate order. Dead AND gates waste a triple and a
+    /// communication round each.
+    pub fn dead_gates(&self) -> Vec<usize> {
+        let mut live = vec![false; self.header.wires_amount];
+        live[self.get_output_wires()..].fill(true);
+
+        // walking backwards, every gate is seen after all gates reading its output
+        let mut dead = Vec::new();
+        for (index, gate) in self.gates.iter().enumerate().rev() {
+            if !live[gate.output.index()] {
+                dead.push(index);
+                continue;
+            }
+            match gate.gate_type {
+                GateType::XOR(a, b) | GateType::AND(a, b) => {
+                    live[a.index()] = true;
+                    live[b.index()] = true;
+                }
+                GateType::INV(a) => live[a.index()] = true,
+            }
+        }
+        dead.reverse();
+        dead
+    }
+
+    /// Removes the gates whose output neither is an output of the circuit nor is read by a
+    /// remaining gate, see [`Circuit::dead_gates`]. Wire indices are kept, so the removed outputs
+    /// become unused wires.
+    pub fn eliminate_dead_gates(&self) -> Circuit {
+        let mut dead = self.dead_gates().into_iter().peekable();
+        let kept: Vec<Gate> = self
+            .gates
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| dead.next_if_eq(&index).is_none())
+            .map(|(_, gate)| gate.clone())
+            .collect();
+
+        Circuit::new(
+            Header {
+                gates_amount: kept.len(),
+                ..self.header.clone()
+            },
+            kept,
+        )
+    }
+
+    /// Strips the dead gates and renumbers the remaining wires densely, which computes the same
+    /// outputs with at most as many gates, wires and triples.
+    pub fn optimize(&self) -> Circuit {
+        self.eliminate_dead_gates().canonicalize()
+    }
+
+    /// Renumbers the wires densely, e.g. after [`Circuit::eliminate_dead_gates`] left unused
+    /// wires behind. The inputs keep their wires, the outputs of the gates are numbered in gate
+    /// order and the outputs of the circuit stay the last wires, in the same order. Wires which
+    /// are neither inputs nor outputs nor assigned by a gate are dropped.
+    pub fn canonicalize(&self) -> Circuit {
+        let inputs = self.get_niv_sum();
+        let output_wires = self.get_output_wires();
+
+        let mut renumbered: Vec<usize> = (0..self.header.wires_amount).collect();
+        let mut next = inputs;
+        for gate in self
+            .gates
+            .iter()
+            .filter(|gate| gate.output.index() < output_wires)
+        {
+            renumbered[gate.output.index()] = next;
+            next += 1;
+        }
+        let wires_amount = next + self.get_nov_sum();
+        for (i, wire) in (output_wires..self.header.wires_amount).enumerate() {
+            renumbered[wire] = next + i;
+        }
+
+        let map = |w: WireId| WireId::unchecked(renumbered[w.index()]);
+        let gates: Vec<Gate> = self
+            .gates
+            .iter()
+            .map(|gate| Gate {
+                gate_type: gate.gate_type.map_wires(map),
+                output: map(gate.output),
+            })
+            .collect();
+
+        Circuit::new(
+            Header {
+                wires_amount,
+                ..self.header.clone()
+            },
+            gates,
+        )
+    }
+
+    /// Partially evaluates the circuit for the wires with known values, e.g. public inputs, and
+    /// returns the reduced circuit together with its output wires which became constants. An AND
+    /// gate with a known operand becomes a constant or a copy of the other operand, so it no
+    /// longer needs a triple. The reduced circuit takes the same inputs, the known input bits are
+    /// ignored, and computes the same outputs for every assignment of the other inputs.
+    ///
+    /// Panics if the circuit has no input wires, as the constants are derived from one.
+    pub fn propagate_constants(
+        &self,
+        known: &HashMap<usize, bool>,
+    ) -> (Circuit, HashMap<usize, bool>) {
+        let known_or = |wire: WireId, value: Propagated| {
+            known
+                .get(&wire.index())
+                .map_or(value, |&b| Propagated::Constant(b))
+        };
+        let mut builder = CircuitBuilder::new();
+        let mut values = vec![Propagated::Constant(false); self.header.wires_amount];
+        for &width in &self.header.niv {
+            // the builder numbers the inputs like this circuit
+            for wire in builder.input(width) {
+                values[wire.index()] = known_or(wire, Propagated::Wire(wire, false));
+            }
+        }
+
+        // the wires holding the negation of a wire, and the constant 0, once they are needed
+        let mut negated: HashMap<WireId, WireId> = HashMap::new();
+        let mut zero = None;
+        let mut materialize = |builder: &mut CircuitBuilder, value: Propagated| match value {
+            Propagated::Wire(w, false) => w,
+            Propagated::Wire(w, true) => *negated.entry(w).or_insert_with(|| builder.inv(w)),
+            Propagated::Constant(c) => {
+                assert!(
+                    self.get_niv_sum() > 0,
+                    "a circuit without inputs has no wire to derive constants from"
+                );
+                let first = WireId::unchecked(0);
+                let zero = *zero.get_or_insert_with(|| builder.xor(first, first));
+                match c {
+                    false => zero,
+                    true => *negated.entry(zero).or_insert_with(|| builder.inv(zero)),
+                }
+            }
+        };
+
+        for gate in &self.gates {
+            let value = match gate.gate_type {
+                GateType::INV(a) => match values[a.index()] {
+                    Propagated::Constant(c) => Propagated::Constant(!c),
+                    Propagated::Wire(w, n) => Propagated::Wire(w, !n),
+                },
+                GateType::XOR(a, b) => match (values[a.index()], values[b.index()]) {
+                    (Propagated::Constant(x), Propagated::Constant(y)) => {
+                        Propagated::Constant(x ^ y)
+                    }
+                    (Propagated::Constant(c), Propagated::Wire(w, n))
+                    | (Propagated::Wire(w, n), Propagated::Constant(c)) => {
+                        Propagated::Wire(w, n ^ c)
+                    }
+                    (Propagated::Wire(x, m), Propagated::Wire(y, n)) if x == y => {
+                        Propagated::Constant(m ^ n)
+                    }
+                    // the negations cancel out or move behind the gate
+                    (Propagated::Wire(x, m), Propagated::Wire(y, n)) => {
+                        Propagated::Wire(builder.xor(x, y), m ^ n)
+                    }
+                },
+                GateType::AND(a, b) => match (values[a.index()], values[b.index()]) {
+                    (Propagated::Constant(false), _) | (_, Propagated::Constant(false)) => {
+                        Propagated::Constant(false)
+                    }
+                    (Propagated::Constant(true), v) | (v, Propagated::Constant(true)) => v,
+                    (Propagated::Wire(x, m), Propagated::Wire(y, n)) if x == y => match m == n {
+                        true => Propagated::Wire(x, m),
+                        false => Propagated::Constant(false),
+                    },
+                    (x, y) => {
+                        let x = materialize(&mut builder, x);
+                        let y = materialize(&mut builder, y);
+                        Propagated::Wire(builder.and(x, y), false)
+                    }
+                },
+            };
+            values[gate.output.index()] = known_or(gate.output, value);
+        }
+
+        // the output bits which became constants, counted from 0
+        let mut constant_outputs = Vec::new();
+        let mut bit = 0;
+        for &width in &self.header.nov {
+            let mut output = Vec::with_capacity(width);
+            for _ in 0..width {
+                let value = values[self.get_output_wires() + bit];
+                if let Propagated::Constant(c) = value {
+                    constant_outputs.push((bit, c));
+                }
+                output.push(materialize(&mut builder, value));
+                bit += 1;
+            }
+            builder.output(output);
+        }
+
+        // folding leaves gates behind whose outputs are no longer read
+        let reduced = builder.build().optimize();
+        let output_wires = reduced.get_output_wires();
+        let constants = constant_outputs
+            .into_iter()
+            .map(|(bit, c)| (output_wires + bit, c))
+            .collect();
+        (reduced, constants)
+    }
+
+    /// Extracts the gates the given wires depend on into a circuit of its own, e.g. to debug a
+    /// wrong output bit in isolation. The outputs of the extracted circuit are the given wires, in
+    /// the given order. Its inputs are the input wires of this circuit the given wires depend on,
+    /// grouped into values like in this circuit, and the returned vector maps every input of the
+    /// extracted circuit to the wire of this circuit it corresponds to.
+    ///
+    /// Fails with [`CircuitError::NotAssignedByGate`] if a given wire does not exist or is not
+    /// assigned by a gate, and with [`CircuitError::WireGivenTwice`] if a wire is given twice.
+    pub fn extract_cone(
+        &self,
+        output_wires: &[usize],
+    ) -> Result<(Circuit, Vec<usize>), CircuitError> {
+        let inputs = self.get_niv_sum();
+        let mut extracted = vec![false; self.header.wires_amount];
+        for &wire in output_wires {
+            match extracted.get_mut(wire) {
+                None => return Err(CircuitError::NotAssignedByGate(wire)),
+                Some(true) => return Err(CircuitError::WireGivenTwice(wire)),
+                Some(seen) => *seen = true,
+            }
+        }
+        let mut live = extracted.clone();
+
+        // walking backwards, every gate is seen after all gates reading its output
+        let mut kept: Vec<&Gate> = Vec::new();
+        for gate in self.gates.iter().rev() {
+            if !live[gate.output.index()] {
+                continue;
+            }
+            match gate.gate_type {
+                GateType::XOR(a, b) | GateType::AND(a, b) => {
+                    live[a.index()] = true;
+                    live[b.index()] = true;
+                }
+                GateType::INV(a) => live[a.index()] = true,
+            }
+            kept.push(gate);
+        }
+        kept.reverse();
+
+        // Wire layout: the used inputs, the outputs of the kept gates which are not extracted, and
+        // the extracted wires.
+        let input_map: Vec<usize> = (0..inputs).filter(|&w| live[w]).collect();
+        let mut renumbered: Vec<Option<usize>> = vec![None; self.header.wires_amount];
+        for (new, &old) in input_map.iter().enumerate() {
+            renumbered[old] = Some(new);
+        }
+        let mut next = input_map.len();
+        for gate in kept.iter().filter(|gate| !extracted[gate.output.index()]) {
+            renumbered[gate.output.index()] = Some(next);
+            next += 1;
+        }
+        for &wire in output_wires {
+            if !kept.iter().any(|gate| gate.output.index() == wire) {
+                return Err(CircuitError::NotAssignedByGate(wire));
+            }
+            renumbered[wire] = Some(next);
+            next += 1;
+        }
+
+        let map = |w: WireId| {
+            WireId::unchecked(renumbered[w.index()].expect("every wire of the cone is renumbered"))
+        };
+        let gates: Vec<Gate> = kept
+            .iter()
+            .map(|gate| Gate {
+                gate_type: gate.gate_type.map_wires(map),
+                output: map(gate.output),
+            })
+            .collect();
+
+        // the used inputs of every input value of this circuit
+        let mut niv = Vec::new();
+        let mut start = 0;
+        for &width in &self.header.niv {
+            let used = input_map
+                .iter()
+                .filter(|&&w| (start..start + width).contains(&w))
+                .count();
+            if used > 0 {
+                niv.push(used);
+            }
+            start += width;
+        }
+
+        let circuit = Circuit::new(
+            Header {
+                gates_amount: gates.len(),
+                wires_amount: input_map.len() + kept.len(),
+                niv,
+                nov: vec![output_wires.len()],
+            },
+            gates,
+        );
+        Ok((circuit, input_map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_eval::EquivCheck;
+    use crate::circuit::circuit_parser::{Circuit, Gate, GateType, WireId};
+    use crate::input::to_bits;
+    use crate::party::local::run_local;
+    use crate::testing::{generate_random_circuit, RandomCircuitParams};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    fn from_bits(bits: &[bool]) -> u64 {
+        bits.iter()
+            .enumerate()
+            .fold(0, |acc, (i, &b)| acc | (u64::from(b) << i))
+    }
+
+    #[test]
+    fn test_concat_small() {
+        // (a & b) followed by (x ^ y), with the AND output connected to y
+        let and = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let xor = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+
+        let c = and.concat(&xor, &[(0, 1)]).unwrap();
+        assert_eq!(c.header.niv, vec![1, 1, 1]);
+        assert_eq!(c.header.nov, vec![1]);
+        assert_eq!(c.header.gates_amount, 2);
+        assert_eq!(c.header.wires_amount, 5);
+
+        for v in 0..8u8 {
+            let (a, b, x) = (v & 1 == 1, v & 2 == 2, v & 4 == 4);
+            assert_eq!(c.evaluate_plain(&[a, b, x]).unwrap(), vec![x ^ (a & b)]);
+        }
+    }
+
+    #[test]
+    fn test_concat_add_then_sub() {
+        let adder = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let sub = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let adder = Circuit::parse(&adder).unwrap();
+        let sub = Circuit::parse(&sub).unwrap();
+
+        // (a + b) - c
+        let wiring: Vec<(usize, usize)> = (0..64).map(|i| (i, i)).collect();
+        let c = adder.concat(&sub, &wiring).unwrap();
+        assert_eq!(c.header.niv, vec![64, 64, 64]);
+        assert_eq!(c.header.gates_amount, adder.gates.len() + sub.gates.len());
+
+        let input = [
+            to_bits(100, 64).unwrap(),
+            to_bits(20, 64).unwrap(),
+            to_bits(7, 64).unwrap(),
+        ]
+        .concat();
+        assert_eq!(from_bits(&c.evaluate_plain(&input).unwrap()), 113);
+
+        // c - (a + b)
+        let wiring: Vec<(usize, usize)> = (0..64).map(|i| (i, 64 + i)).collect();
+        let c = adder.concat(&sub, &wiring).unwrap();
+        let input = [
+            to_bits(100, 64).unwrap(),
+            to_bits(20, 64).unwrap(),
+            to_bits(200, 64).unwrap(),
+        ]
+        .concat();
+        assert_eq!(from_bits(&c.evaluate_plain(&input).unwrap()), 80);
+    }
+
+    #[test]
+    fn test_extract_cone() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+        let top_bit = adder.header.wires_amount - 1;
+
+        // the most significant sum bit depends on all input bits
+        let (cone, input_map) = adder.extract_cone(&[top_bit]).unwrap();
+        cone.validate().unwrap();
+        assert_eq!(cone.header.niv, vec![64, 64]);
+        assert_eq!(cone.header.nov, vec![1]);
+        assert_eq!(input_map, (0..128).collect::<Vec<_>>());
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let (a, b) = (rng.gen::<u64>(), rng.gen::<u64>());
+            let input = [to_bits(a, 64).unwrap(), to_bits(b, 64).unwrap()].concat();
+            let cone_input: Vec<bool> = input_map.iter().map(|&w| input[w]).collect();
+            assert_eq!(
+                cone.evaluate_plain(&cone_input).unwrap(),
+                vec![adder.evaluate_plain(&input).unwrap()[63]]
+            );
+        }
+
+        // the lowest sum bit only depends on the lowest bits of both inputs
+        let low_bit = adder.get_output_wires();
+        let (cone, input_map) = adder.extract_cone(&[low_bit]).unwrap();
+        cone.validate().unwrap();
+        assert_eq!(input_map, vec![0, 64]);
+        assert_eq!(cone.header.niv, vec![1, 1]);
+        assert_eq!(cone.gates.len(), 1);
+        assert_eq!(cone.evaluate_plain(&[true, false]).unwrap(), vec![true]);
+
+        // input wires, wires past the end and repeated wires are rejected
+        for (wires, wire) in [
+            (vec![0], 0),
+            (vec![top_bit + 1], top_bit + 1),
+            (vec![usize::MAX], usize::MAX),
+        ] {
+            assert!(matches!(
+                adder.extract_cone(&wires),
+                Err(CircuitError::NotAssignedByGate(w)) if w == wire
+            ));
+        }
+        assert!(matches!(
+            adder.extract_cone(&[low_bit, top_bit, low_bit]),
+            Err(CircuitError::WireGivenTwice(w)) if w == low_bit
+        ));
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        // wires 2 to 4 and 6 to 8 are never used
+        let sparse = Circuit::parse("2 10\n2 1 1\n1 1\n\n2 1 0 1 5 AND\n1 1 5 9 INV\n").unwrap();
+        let dense = sparse.canonicalize();
+        dense.validate().unwrap();
+        assert_eq!(dense.header.wires_amount, 4);
+        assert_eq!(dense.header.niv, sparse.header.niv);
+        assert_eq!(dense.header.nov, sparse.header.nov);
+        assert_eq!(
+            dense.gates,
+            vec![
+                Gate {
+                    gate_type: GateType::AND(WireId::unchecked(0), WireId::unchecked(1)),
+                    output: WireId::unchecked(2)
+                },
+                Gate {
+                    gate_type: GateType::INV(WireId::unchecked(2)),
+                    output: WireId::unchecked(3)
+                },
+            ]
+        );
+        for v in 0..4 {
+            let input = [v & 1 == 1, v & 2 == 2];
+            assert_eq!(
+                dense.evaluate_plain(&input).unwrap(),
+                sparse.evaluate_plain(&input).unwrap()
+            );
+        }
+
+        // the gates of the adder computing the unused low bits of the sum leave holes
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let mut adder = Circuit::parse(&contents).unwrap();
+        adder.header.nov = vec![32];
+        let sparse = adder.eliminate_dead_gates();
+        let dense = sparse.canonicalize();
+        dense.validate().unwrap();
+        assert_eq!(dense.gates.len(), sparse.gates.len());
+        assert_eq!(dense.header.wires_amount, 128 + dense.gates.len());
+        assert!(dense.header.wires_amount < sparse.header.wires_amount);
+        let check = EquivCheck::Random {
+            samples: 200,
+            seed: 3,
+        };
+        assert!(dense.equivalent_to(&sparse, check).unwrap().is_equivalent());
+        assert_eq!(dense.canonicalize().gates, dense.gates);
+    }
+
+    #[test]
+    fn test_dead_gates() {
+        // the AND gate and the XOR reading only it are dead, out = !(a ^ b)
+        let c = Circuit::parse(
+            "5 7\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n2 1 0 1 3 XOR\n2 1 2 0 4 XOR\n1 1 1 5 INV\n1 1 3 6 INV\n",
+        )
+        .unwrap();
+        assert_eq!(c.dead_gates(), vec![0, 2, 3]);
+        assert!(c.eliminate_dead_gates().dead_gates().is_empty());
+
+        let optimized = c.optimize();
+        optimized.validate().unwrap();
+        assert_eq!(optimized.gates.len(), 2);
+        assert_eq!(optimized.required_triples(), 0);
+        assert_eq!(optimized.header.wires_amount, 4);
+        for v in 0..4 {
+            let input = [v & 1 == 1, v & 2 == 2];
+            assert_eq!(
+                optimized.evaluate_plain(&input).unwrap(),
+                c.evaluate_plain(&input).unwrap()
+            );
+        }
+
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        assert!(Circuit::parse(&contents).unwrap().dead_gates().is_empty());
+    }
+
+    #[test]
+    fn test_propagate_public_operand() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+
+        // adding 0 needs no carries
+        let zero: HashMap<usize, bool> = (64..128).map(|w| (w, false)).collect();
+        let (reduced, constants) = adder.propagate_constants(&zero);
+        reduced.validate().unwrap();
+        assert_eq!(reduced.required_triples(), 0);
+        assert!(constants.is_empty());
+        assert_eq!(reduced.header.niv, adder.header.niv);
+        let input = [to_bits(12345, 64).unwrap(), to_bits(999, 64).unwrap()].concat();
+        assert_eq!(from_bits(&reduced.evaluate_plain(&input).unwrap()), 12345);
+
+        // with both operands known, every output bit is a constant
+        let mut all = zero;
+        all.extend((0..64).map(|w| (w, w < 3)));
+        let (reduced, constants) = adder.propagate_constants(&all);
+        assert_eq!(reduced.required_triples(), 0);
+        assert_eq!(constants.len(), 64);
+        let output_wires = reduced.get_output_wires();
+        assert!(constants[&output_wires]);
+        assert!(!constants[&(output_wires + 3)]);
+        let output =
+            run_local(&reduced, &to_bits(0, 64).unwrap(), &to_bits(0, 64).unwrap()).unwrap();
+        assert_eq!(from_bits(&output), 7);
+    }
+
+    #[test]
+    fn test_propagate_constants_matches_plain_evaluation() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let params = RandomCircuitParams {
+            gates: 60,
+            and_ratio: 0.5,
+            input_widths: vec![6, 6],
+            output_widths: vec![4, 4],
+            ..RandomCircuitParams::default()
+        };
+        for _ in 0..50 {
+            let c = generate_random_circuit(&mut rng, &params);
+            // a random part of the inputs and a few gate outputs are known
+            let mut known = HashMap::new();
+            for wire in 0..c.header.wires_amount {
+                if rng.gen_bool(if wire < 12 { 0.4 } else { 0.05 }) {
+                    known.insert(wire, rng.gen::<bool>());
+                }
+            }
+            let (reduced, constants) = c.propagate_constants(&known);
+            reduced.validate().unwrap();
+            assert!(reduced.required_triples() <= c.required_triples());
+
+            for _ in 0..20 {
+                let input: Vec<bool> = (0..12).map(|_| rng.gen()).collect();
+                let mut wires: Vec<Option<bool>> = vec![None; c.header.wires_amount];
+                // the original circuit, with the known wires overriding their gates
+                for (wire, bit) in input.iter().enumerate() {
+                    wires[wire] = Some(known.get(&wire).copied().unwrap_or(*bit));
+                }
+                for gate in &c.gates {
+                    let get = |w: WireId| wires[w.index()].unwrap();
+                    let value = match gate.gate_type {
+                        GateType::XOR(a, b) => get(a) ^ get(b),
+                        GateType::AND(a, b) => get(a) & get(b),
+                        GateType::INV(a) => !get(a),
+                    };
+                    let output = gate.output.index();
+                    wires[output] = Some(known.get(&output).copied().unwrap_or(value));
+                }
+                let expected: Vec<bool> = wires[c.get_output_wires()..]
+                    .iter()
+                    .map(|w| w.unwrap())
+                    .collect();
+
+                let output = reduced.evaluate_plain(&input).unwrap();
+                assert_eq!(output, expected);
+                for (wire, value) in &constants {
+                    assert_eq!(output[wire - reduced.get_output_wires()], *value);
+                }
+            }
+            let input: Vec<bool> = (0..12).map(|_| rng.gen()).collect();
+            assert_eq!(
+                run_local(&reduced, &input[..6], &input[6..]).unwrap(),
+                reduced.evaluate_plain(&input).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_concat_invalid_wiring() {
+        let and = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        assert!(and.concat(&and, &[(1, 0)]).is_err());
+        assert!(and.concat(&and, &[(0, 2)]).is_err());
+        assert!(and.concat(&and, &[(0, 0), (0, 0)]).is_err());
+    }
+}
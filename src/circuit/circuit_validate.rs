@@ -0,0 +1,243 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::{Circuit, GateType, ParseOptions};
+
+impl Circuit {
+    /// Checks that the circuit can be evaluated gate by gate: the header declares enough wires for
+    /// all wire indices, every gate only reads wires which are inputs or outputs of earlier gates,
+    /// no wire is assigned twice, and all output wires are assigned. Parsing does not check this.
+    /// The header has to stay within the default [`ParseOptions`].
+    pub fn validate(&self) -> Result<(), CircuitError> {
+        self.validate_with(ParseOptions::shared_default())
+    }
+
+    /// Validates the circuit like [`Circuit::validate`], with the given limits. They are checked
+    /// first, as the check allocates a flag per wire. A circuit which passed is not validated
+    /// again when parties are created for it, e.g. from a
+    /// [`crate::circuit::circuit_prepared::PreparedCircuit`].
+    pub fn validate_with(&self, options: &ParseOptions) -> Result<(), CircuitError> {
+        options.check(&self.header)?;
+        self.check_wiring()?;
+        let _ = self.validated.set(());
+        Ok(())
+    }
+
+    /// Validates the circuit like [`Circuit::validate`], unless it already passed a validation.
+    /// Parties check this when they are created.
+    pub(crate) fn ensure_valid(&self) -> Result<(), CircuitError> {
+        match self.validated.get() {
+            Some(()) => Ok(()),
+            None => self.validate(),
+        }
+    }
+
+    /// Checks the wiring of the circuit, without checking its size against any limits.
+    fn check_wiring(&self) -> Result<(), CircuitError> {
+        let wires_amount = self.header.wires_amount;
+        let inputs = self.get_niv_sum();
+        // the output wires may be input wires, e.g. in a circuit without gates
+        if inputs.max(self.get_nov_sum()) > wires_amount {
+            return Err(CircuitError::ParsingError(format!(
+                "the circuit declaring more input or output bits than its {} wires",
+                wires_amount
+            )));
+        }
+
+        // an understated wire count would make the wire buffer of the parties too small, every
+        // wire is in range after this check
+        let highest = self
+            .gates
+            .iter()
+            .flat_map(|gate| match gate.gate_type {
+                GateType::XOR(a, b) | GateType::AND(a, b) => [a, b, gate.output],
+                GateType::INV(a) => [a, a, gate.output],
+            })
+            .max();
+        if let Some(wire) = highest.filter(|w| self.wire_id(w.index()).is_none()) {
+            // the gates may come from a circuit with more wires, see `Circuit::new`
+            return Err(CircuitError::WireCountTooSmall {
+                declared: wires_amount,
+                needed: wire.index().saturating_add(1),
+            });
+        }
+
+        let mut set = vec![false; wires_amount];
+        set[..inputs].fill(true);
+
+        for (gate_index, gate) in self.gates.iter().enumerate() {
+            let operands = match gate.gate_type {
+                GateType::XOR(a, b) | GateType::AND(a, b) => vec![a.index(), b.index()],
+                GateType::INV(a) => vec![a.index()],
+            };
+            for wire in operands {
+                if !set[wire] {
+                    return Err(CircuitError::WireUsedBeforeSet(gate_index, wire));
+                }
+            }
+
+            let output = gate.output.index();
+            if set[output] {
+                return Err(CircuitError::WireAssignedTwice(gate_index, output));
+            }
+            set[output] = true;
+        }
+
+        match (self.get_output_wires()..wires_amount).find(|&w| !set[w]) {
+            Some(wire) => Err(CircuitError::WireNotSetError(wire)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_parser::{Circuit, Gate, GateType, Header, ParseOptions, WireId};
+    use crate::party::party_gmw::new_party_pair;
+
+    #[test]
+    fn test_validate() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        assert!(Circuit::parse(&contents).unwrap().validate().is_ok());
+
+        let err = |c: &str| Circuit::parse(c).unwrap().validate().unwrap_err();
+        let header = "2 4\n2 1 1\n1 1\n\n";
+
+        // the parser rejects wires beyond the header, so the header is understated afterwards
+        let mut c = Circuit::parse("2 6\n2 1 1\n1 1\n\n2 1 0 5 2 XOR\n1 1 2 3 INV\n").unwrap();
+        c.header.wires_amount = 4;
+        let e = c.validate().unwrap_err();
+        assert!(matches!(
+            e,
+            CircuitError::WireCountTooSmall {
+                declared: 4,
+                needed: 6
+            }
+        ));
+        let e = err(&format!("{}2 1 0 3 2 XOR\n1 1 2 3 INV\n", header));
+        assert!(matches!(e, CircuitError::WireUsedBeforeSet(0, 3)));
+        let e = err(&format!("{}2 1 0 1 2 XOR\n1 1 2 2 INV\n", header));
+        assert!(matches!(e, CircuitError::WireAssignedTwice(1, 2)));
+        // input wires are assigned as well
+        let e = err(&format!("{}2 1 0 1 2 XOR\n1 1 2 0 INV\n", header));
+        assert!(matches!(e, CircuitError::WireAssignedTwice(1, 0)));
+        let e = err("1 4\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n");
+        assert!(matches!(e, CircuitError::WireNotSetError(3)));
+        let e = err("0 2\n3 1 1 1\n1 1\n");
+        assert!(matches!(e, CircuitError::ParsingError(_)));
+        // a circuit without gates passes its inputs through
+        assert!(Circuit::parse("0 2\n2 1 1\n1 2\n")
+            .unwrap()
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_adversarial_wire_indices() {
+        // indices up to usize::MAX are numbers, but the parser rejects them
+        let max = usize::MAX;
+        for gate in [
+            format!("2 1 0 {} 2 XOR", max),
+            format!("2 1 0 1 {} AND", max),
+            format!("1 1 {:#x} 2 INV", max),
+        ] {
+            assert!(matches!(
+                Circuit::parse(&format!("1 3\n2 1 1\n1 1\n\n{}\n", gate)),
+                Err(CircuitError::WireIndexOutOfRange {
+                    line: 5,
+                    wire: usize::MAX,
+                    wires_amount: 3
+                })
+            ));
+        }
+        // and a circuit built otherwise is rejected instead of overflowing
+        let [zero, one, two, max] = [0, 1, 2, max].map(WireId::unchecked);
+        for gate in [
+            GateType::XOR(zero, max),
+            GateType::AND(zero, one),
+            GateType::INV(max),
+        ] {
+            let output = if gate == GateType::AND(zero, one) {
+                max
+            } else {
+                two
+            };
+            let c = Circuit::new(
+                Header {
+                    gates_amount: 1,
+                    wires_amount: 3,
+                    niv: vec![1, 1],
+                    nov: vec![1],
+                },
+                vec![Gate {
+                    gate_type: gate,
+                    output,
+                }],
+            );
+            assert!(matches!(
+                c.validate(),
+                Err(CircuitError::WireCountTooSmall {
+                    declared: 3,
+                    needed: usize::MAX
+                })
+            ));
+            assert!(new_party_pair(c.clone()).is_err());
+            assert!(c.wire_index().is_err());
+        }
+        // so are operand counts, which would overflow the number of tokens of the gate
+        for gate in [
+            format!("{} 1 0 1 2 XOR", max),
+            format!("2 {} 0 1 2 XOR", max),
+        ] {
+            assert!(matches!(
+                Circuit::parse(&format!("1 3\n2 1 1\n1 1\n\n{}\n", gate)),
+                Err(CircuitError::ParsingError(_))
+            ));
+        }
+        // an index beyond usize is no number at all
+        assert!(matches!(
+            Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 18446744073709551616 2 XOR\n"),
+            Err(CircuitError::ParsingError(_))
+        ));
+
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        assert_eq!(c.wire_id(2), Some(WireId::unchecked(2)));
+        assert_eq!(c.wire_id(3), None);
+        assert_eq!(c.wire_id(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_validate_understated_wire_count() {
+        // the adder uses wires up to 503, but the header only declares 400
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let mut c = Circuit::parse(&contents).unwrap();
+        let needed = c.header.wires_amount;
+        c.header.wires_amount = 400;
+        assert!(matches!(
+            c.validate(),
+            Err(CircuitError::WireCountTooSmall { declared: 400, needed: n }) if n == needed
+        ));
+    }
+
+    #[test]
+    fn test_validate_once() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let limits = ParseOptions {
+            max_wires: 100,
+            ..ParseOptions::default()
+        };
+        assert!(c.validate_with(&limits).is_err());
+        assert!(c.validated.get().is_none());
+        c.validate().unwrap();
+        assert!(c.validated.get().is_some());
+        assert!(c.ensure_valid().is_ok());
+
+        // circuits which were never validated are checked against the default limits
+        let mut huge = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        huge.header.wires_amount = usize::MAX;
+        assert!(matches!(
+            huge.ensure_valid(),
+            Err(CircuitError::LimitExceeded { what: "wires", .. })
+        ));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::{Circuit, GateType, WireId};
+
+/// Which gate drives each wire of a circuit and which gates read it, see [`Circuit::wire_index`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WireIndex {
+    producers: Vec<Option<usize>>,
+    consumers: Vec<Vec<usize>>,
+    // the first output wire
+    outputs: usize,
+}
+
+impl WireIndex {
+    /// The index of the gate assigning wire `w`, or `None` for input wires, wires no gate
+    /// assigns and wires the circuit does not have.
+    pub fn producer(&self, w: usize) -> Option<usize> {
+        self.producers.get(w).copied().flatten()
+    }
+
+    /// The indices of the gates reading wire `w`, in gate order, or `None` if the circuit does
+    /// not have the wire. A gate reading the wire as both operands is listed once.
+    pub fn consumers(&self, w: usize) -> Option<&[usize]> {
+        self.consumers.get(w).map(Vec::as_slice)
+    }
+
+    /// True if wire `w` is one of the trailing output wires.
+    pub fn is_output(&self, w: usize) -> bool {
+        (self.outputs..self.producers.len()).contains(&w)
+    }
+}
+
+impl Circuit {
+    /// Maps every wire to the gate driving it and the gates reading it, in one pass over the
+    /// gates. Fails with [`CircuitError::WireAssignedTwice`] for the second gate driving a wire,
+    /// or with [`CircuitError::WireOutOfRange`] for a wire beyond the header's wire count.
+    ///
+    /// Like [`Circuit::layers`], the index is computed on the first call and cached.
+    pub fn wire_index(&self) -> Result<&WireIndex, CircuitError> {
+        match self.wire_index.get_or_init(|| self.compute_wire_index()) {
+            Ok(index) => Ok(index),
+            &Err((gate_index, wire)) if wire < self.header.wires_amount => {
+                Err(CircuitError::WireAssignedTwice(gate_index, wire))
+            }
+            &Err((gate_index, wire)) => Err(CircuitError::WireOutOfRange(gate_index, wire)),
+        }
+    }
+
+    /// Builds the index, or returns the gate and wire which make the circuit invalid. Cached
+    /// instead of a `CircuitError`, which cannot be cloned.
+    fn compute_wire_index(&self) -> Result<WireIndex, (usize, usize)> {
+        let wires_amount = self.header.wires_amount;
+        let mut producers = vec![None; wires_amount];
+        let mut consumers: Vec<Vec<usize>> = vec![Vec::new(); wires_amount];
+
+        for (gate_index, gate) in self.gates.iter().enumerate() {
+            let operands = match gate.gate_type {
+                GateType::XOR(a, b) | GateType::AND(a, b) => [a, b],
+                GateType::INV(a) => [a, a],
+            };
+            let checked = |w: WireId| {
+                self.wire_id(w.index())
+                    .map(WireId::index)
+                    .ok_or((gate_index, w.index()))
+            };
+            for wire in operands {
+                let readers = &mut consumers[checked(wire)?];
+                if readers.last() != Some(&gate_index) {
+                    readers.push(gate_index);
+                }
+            }
+
+            let producer = &mut producers[checked(gate.output)?];
+            if producer.is_some() {
+                return Err((gate_index, gate.output.index()));
+            }
+            *producer = Some(gate_index);
+        }
+
+        Ok(WireIndex {
+            producers,
+            consumers,
+            outputs: wires_amount.saturating_sub(self.get_nov_sum()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_parser::{Circuit, GateType, WireId};
+
+    #[test]
+    fn test_wire_index() {
+        // wire 0 is read by two gates, the output wires 4 and 5 by none
+        let c = Circuit::parse(
+            "4 6\n2 1 1\n1 2\n\n2 1 0 1 2 XOR\n2 1 0 2 4 AND\n1 1 2 5 INV\n2 1 2 2 3 AND\n",
+        )
+        .unwrap();
+        let index = c.wire_index().unwrap();
+
+        assert_eq!(index.producer(0), None);
+        assert_eq!(index.producer(1), None);
+        assert_eq!(index.producer(2), Some(0));
+        assert_eq!(index.producer(3), Some(3));
+        assert_eq!(index.producer(4), Some(1));
+        assert_eq!(index.producer(5), Some(2));
+        assert_eq!(index.producer(6), None);
+
+        assert_eq!(index.consumers(0), Some(&[0, 1][..]));
+        assert_eq!(index.consumers(1), Some(&[0][..]));
+        // the last AND reads wire 2 as both operands, but is listed once
+        assert_eq!(index.consumers(2), Some(&[1, 2, 3][..]));
+        assert_eq!(index.consumers(4), Some(&[][..]));
+        assert_eq!(index.consumers(5), Some(&[][..]));
+        assert_eq!(index.consumers(6), None);
+
+        assert!(!index.is_output(3));
+        assert!(index.is_output(4) && index.is_output(5));
+        assert!(!index.is_output(6));
+        // the second call returns the cached index
+        assert!(std::ptr::eq(index, c.wire_index().unwrap()));
+    }
+
+    #[test]
+    fn test_wire_index_multiply_driven() {
+        let c = Circuit::parse("2 4\n2 1 1\n1 1\n\n2 1 0 1 3 XOR\n2 1 0 1 3 AND\n").unwrap();
+        assert!(matches!(
+            c.wire_index(),
+            Err(CircuitError::WireAssignedTwice(1, 3))
+        ));
+
+        // the parser rejects the wire already
+        let mut c = Circuit::parse("1 4\n2 1 1\n1 1\n\n2 1 0 1 3 XOR\n").unwrap();
+        c.gates[0].gate_type = GateType::XOR(WireId::unchecked(0), WireId::unchecked(7));
+        assert!(matches!(
+            c.wire_index(),
+            Err(CircuitError::WireOutOfRange(0, 7))
+        ));
+    }
+}
@@ -1,2 +1,16 @@
+pub mod circuit_builder;
+pub mod circuit_diff;
+pub mod circuit_dot;
 pub mod circuit_error;
+pub mod circuit_eval;
+pub mod circuit_expr;
+pub mod circuit_generators;
+pub mod circuit_layers;
+pub mod circuit_layout;
+pub mod circuit_meta;
 pub mod circuit_parser;
+pub mod circuit_prepared;
+pub mod circuit_stats;
+pub mod circuit_transform;
+pub mod circuit_validate;
+pub mod circuit_wires;
@@ -0,0 +1,84 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::party::errors::PartyError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Any error which can occur between reading a circuit and reconstructing its output, so that
+/// both can be handled with `?` in one function.
+#[derive(Debug)]
+pub enum MpcError {
+    Circuit(CircuitError),
+    Party(PartyError),
+}
+
+impl Display for MpcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MpcError::Circuit(e) => write!(f, "Invalid circuit: {}", e),
+            MpcError::Party(e) => write!(f, "Execution failed: {}", e),
+        }
+    }
+}
+
+impl Error for MpcError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MpcError::Circuit(e) => Some(e),
+            MpcError::Party(e) => Some(e),
+        }
+    }
+}
+
+impl From<CircuitError> for MpcError {
+    fn from(value: CircuitError) -> Self {
+        Self::Circuit(value)
+    }
+}
+
+impl From<PartyError> for MpcError {
+    fn from(value: PartyError) -> Self {
+        Self::Party(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MpcError;
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::input::to_bits;
+    use crate::party::errors::PartyError;
+    use crate::run;
+    use std::error::Error;
+
+    #[test]
+    fn test_conversions() {
+        let e = MpcError::from(CircuitError::EmptyLineMissingError);
+        assert!(matches!(e, MpcError::Circuit(_)));
+        assert_eq!(e.to_string(), "Invalid circuit: Expected an empty line");
+        assert!(e.source().is_some());
+
+        let e = MpcError::from(PartyError::WrongInputLength(64, 3));
+        assert!(matches!(e, MpcError::Party(_)));
+        assert_eq!(
+            e.to_string(),
+            "Execution failed: Wrong amount of input bits. Expected: 64, actually: 3"
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let (a, b) = (to_bits(10, 64).unwrap(), to_bits(3, 64).unwrap());
+
+        let output = run("test_circuits/64_Sub.txt", &a, &b).unwrap();
+        assert_eq!(output, to_bits(7, 64).unwrap());
+
+        let e = run("test_circuits/missing.txt", &a, &b).unwrap_err();
+        assert!(matches!(e, MpcError::Circuit(CircuitError::Io { .. })));
+
+        let e = run("test_circuits/64_Sub.txt", &a, &[true]).unwrap_err();
+        assert!(matches!(
+            e,
+            MpcError::Party(PartyError::WrongInputLength(64, 1))
+        ));
+    }
+}
@@ -0,0 +1,415 @@
+use crate::report::to_hex;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use zeroize::Zeroize;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum InputError {
+    Malformed(String),
+    TooWide(InputValue, usize),
+    SignedTooWide(InputValue, usize),
+    Negative(InputValue),
+    NoInput,
+    TooManyAttempts(usize),
+}
+
+impl Error for InputError {}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Malformed(s) => {
+                write!(
+                    f,
+                    "{} is not a valid number (use decimal, 0x.. for hex or 0b.. for binary)",
+                    s
+                )
+            }
+            InputError::TooWide(value, width) => {
+                write!(f, "{} does not fit into {} bit(s)", value, width)
+            }
+            InputError::SignedTooWide(value, width) => {
+                write!(
+                    f,
+                    "{} does not fit into {} bit(s) as a two's complement number",
+                    value, width
+                )
+            }
+            InputError::Negative(value) => {
+                write!(f, "{} is negative, but the inputs are unsigned", value)
+            }
+            InputError::NoInput => write!(f, "No input was provided"),
+            InputError::TooManyAttempts(attempts) => {
+                write!(f, "No valid input after {} attempt(s)", attempts)
+            }
+        }
+    }
+}
+
+/// An input value of any width: the bits of its magnitude, least significant bit first and
+/// without leading zeros, and whether it is negated. Zero is never negative.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Zeroize)]
+pub struct InputValue {
+    negative: bool,
+    magnitude: Vec<bool>,
+}
+
+impl InputValue {
+    fn new(negative: bool, mut magnitude: Vec<bool>) -> Self {
+        while magnitude.last() == Some(&false) {
+            magnitude.pop();
+        }
+        InputValue {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    /// Whether the value is below zero.
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The bits of the absolute value, least significant bit first, without leading zeros.
+    pub fn magnitude(&self) -> &[bool] {
+        &self.magnitude
+    }
+}
+
+impl From<u64> for InputValue {
+    fn from(value: u64) -> Self {
+        InputValue::new(false, (0..64).map(|i| (value >> i) & 1 == 1).collect())
+    }
+}
+
+impl Display for InputValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.negative { "-" } else { "" };
+        // values which fit into a u128 are shown in decimal, like they are usually entered
+        if self.magnitude.len() <= 128 {
+            let value = self
+                .magnitude
+                .iter()
+                .rev()
+                .fold(0u128, |value, &bit| value << 1 | u128::from(bit));
+            write!(f, "{}{}", sign, value)
+        } else {
+            write!(f, "{}{}", sign, to_hex(&self.magnitude))
+        }
+    }
+}
+
+/// An input value passed on the command line, or `-` to read it from stdin instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputArg {
+    Value(InputValue),
+    Stdin,
+}
+
+/// Parses an input argument, see [`parse_number`] for the accepted values.
+pub fn parse_input_arg(s: &str) -> Result<InputArg, InputError> {
+    if s.trim() == "-" {
+        return Ok(InputArg::Stdin);
+    }
+    parse_number(s).map(InputArg::Value)
+}
+
+/// Parses an input value like [`parse_bits`], which may be negated by a leading `-`.
+pub fn parse_number(s: &str) -> Result<InputValue, InputError> {
+    match s.trim().strip_prefix('-') {
+        Some(magnitude) => parse_bits(magnitude).map(|bits| InputValue::new(true, bits)),
+        None => parse_bits(s).map(|bits| InputValue::new(false, bits)),
+    }
+}
+
+/// Parses an unsigned input value of any length into its bits, least significant bit first and
+/// without leading zeros. Decimal is the default, a `0x` prefix denotes hex and a `0b` prefix
+/// binary. Underscores may be used to group digits.
+pub fn parse_bits(s: &str) -> Result<Vec<bool>, InputError> {
+    let trimmed = s.trim();
+    let (digits, radix) = if let Some(hex) = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+    {
+        (hex, 16)
+    } else if let Some(bin) = trimmed
+        .strip_prefix("0b")
+        .or_else(|| trimmed.strip_prefix("0B"))
+    {
+        (bin, 2)
+    } else {
+        (trimmed, 10)
+    };
+    let malformed = || InputError::Malformed(trimmed.to_string());
+
+    let digits: Vec<u32> = digits
+        .chars()
+        .filter(|&c| c != '_')
+        .map(|c| c.to_digit(radix).ok_or_else(malformed))
+        .collect::<Result<_, _>>()?;
+    if digits.is_empty() {
+        return Err(malformed());
+    }
+    // the value is accumulated in 32 bit limbs, least significant limb first
+    let mut limbs: Vec<u32> = Vec::new();
+    for digit in digits {
+        let mut carry = u64::from(digit);
+        for limb in limbs.iter_mut() {
+            let next = u64::from(*limb) * u64::from(radix) + carry;
+            *limb = next as u32;
+            carry = next >> 32;
+        }
+        if carry != 0 {
+            limbs.push(carry as u32);
+        }
+    }
+
+    let mut bits: Vec<bool> = limbs
+        .iter()
+        .flat_map(|&limb| (0..32).map(move |i| (limb >> i) & 1 == 1))
+        .collect();
+    while bits.last() == Some(&false) {
+        bits.pop();
+    }
+    Ok(bits)
+}
+
+/// Parses an input value like [`parse_bits`], which has to fit into a u64, e.g. a seed.
+pub fn parse_value(s: &str) -> Result<u64, InputError> {
+    let bits = parse_bits(s)?;
+    if bits.len() > 64 {
+        return Err(InputError::TooWide(InputValue::new(false, bits), 64));
+    }
+    Ok(bits
+        .iter()
+        .rev()
+        .fold(0, |value, &bit| value << 1 | u64::from(bit)))
+}
+
+/// Packs the value into `width` bits, least significant bit first. Fails instead of dropping the
+/// high bits if the value does not fit. Values of any width are packed by [`encode_input`].
+pub fn to_bits(value: u64, width: usize) -> Result<Vec<bool>, InputError> {
+    encode_input(&InputValue::from(value), width, false)
+}
+
+/// Packs the value into `width` bits, least significant bit first, as a two's complement number
+/// if `signed` is set, e.g. -1 becomes all ones. Fails instead of dropping the high bits if the
+/// value does not fit. Unsigned values must not be negative.
+pub fn encode_input(
+    value: &InputValue,
+    width: usize,
+    signed: bool,
+) -> Result<Vec<bool>, InputError> {
+    let magnitude = value.magnitude();
+    if !signed {
+        if value.is_negative() {
+            return Err(InputError::Negative(value.clone()));
+        }
+        if magnitude.len() > width {
+            return Err(InputError::TooWide(value.clone(), width));
+        }
+    } else {
+        // the top bit is the sign, only the lowest value needs all bits of its width, e.g. -128
+        // for 8 bits
+        let lowest = |m: &[bool]| m.len() == width && m[..width - 1].iter().all(|&bit| !bit);
+        let fits = magnitude.len() < width || (value.is_negative() && lowest(magnitude));
+        if !fits && !magnitude.is_empty() {
+            return Err(InputError::SignedTooWide(value.clone(), width));
+        }
+    }
+
+    let mut bits = magnitude.to_vec();
+    bits.resize(width, false);
+    if value.is_negative() {
+        // the two's complement keeps the bits up to the lowest set one and inverts the others
+        if let Some(lowest_set) = bits.iter().position(|&bit| bit) {
+            bits[lowest_set + 1..]
+                .iter_mut()
+                .for_each(|bit| *bit = !*bit);
+        }
+    }
+    Ok(bits)
+}
+
+/// Reads an input value of the given width from the lines returned by `next_line`, which returns
+/// `None` once there is no more input. A malformed value is reported to `on_error` and the next
+/// line is read, up to `attempts` times in total. Negative values are only accepted if `signed`
+/// is set, see [`encode_input`].
+pub fn read_value(
+    mut next_line: impl FnMut() -> Option<String>,
+    width: usize,
+    signed: bool,
+    attempts: usize,
+    mut on_error: impl FnMut(&InputError),
+) -> Result<InputValue, InputError> {
+    for _ in 0..attempts {
+        let line = next_line().ok_or(InputError::NoInput)?;
+        let value = parse_number(&line);
+        match value.and_then(|v| encode_input(&v, width, signed).map(|_| v)) {
+            Ok(value) => return Ok(value),
+            Err(e) => on_error(&e),
+        }
+    }
+    Err(InputError::TooManyAttempts(attempts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        encode_input, parse_bits, parse_input_arg, parse_number, parse_value, read_value, to_bits,
+        InputArg, InputError, InputValue,
+    };
+
+    fn number(s: &str) -> InputValue {
+        parse_number(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("42"), Ok(42));
+        assert_eq!(parse_value(" 0x2a\n"), Ok(42));
+        assert_eq!(parse_value("0b101010"), Ok(42));
+        assert_eq!(parse_value("1_000"), Ok(1000));
+        assert_eq!(parse_value("18446744073709551615"), Ok(u64::MAX));
+        assert!(matches!(
+            parse_value("18446744073709551616"),
+            Err(InputError::TooWide(_, 64))
+        ));
+        assert!(matches!(parse_value("0x"), Err(InputError::Malformed(_))));
+        assert!(matches!(
+            parse_value("forty"),
+            Err(InputError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_bits_of_any_length() {
+        assert_eq!(parse_bits("0"), Ok(vec![]));
+        assert_eq!(parse_bits("0b0110"), Ok(vec![false, true, true]));
+
+        // 2^64 + 1, the 65th bit is the one a u64 would lose
+        let mut expected = vec![false; 65];
+        expected[0] = true;
+        expected[64] = true;
+        assert_eq!(
+            parse_bits("0x1_0000_0000_0000_0001").as_ref(),
+            Ok(&expected)
+        );
+        assert_eq!(parse_bits("18446744073709551617").as_ref(), Ok(&expected));
+        assert_eq!(
+            parse_bits(&format!("0b1{}1", "0".repeat(63))).as_ref(),
+            Ok(&expected)
+        );
+
+        assert_eq!(
+            parse_bits(&format!("0x{}", "f".repeat(64))),
+            Ok(vec![true; 256])
+        );
+        assert_eq!(
+            number("0x1_0000_0000_0000_0001").to_string(),
+            "18446744073709551617"
+        );
+        assert_eq!(
+            number(&format!("-0x1{}", "0".repeat(32))).to_string(),
+            format!("-0x1{}", "0".repeat(32))
+        );
+    }
+
+    #[test]
+    fn test_parse_input_arg() {
+        assert_eq!(parse_input_arg("-"), Ok(InputArg::Stdin));
+        assert_eq!(parse_input_arg("0x2a"), Ok(InputArg::Value(42.into())));
+        assert!(parse_input_arg("--").is_err());
+    }
+
+    #[test]
+    fn test_to_bits() {
+        assert_eq!(to_bits(6, 3), Ok(vec![false, true, true]));
+        assert_eq!(to_bits(0, 0), Ok(vec![]));
+        assert_eq!(to_bits(u64::MAX, 64), Ok(vec![true; 64]));
+        assert_eq!(to_bits(1, 70).unwrap().len(), 70);
+        assert_eq!(to_bits(8, 3), Err(InputError::TooWide(8.into(), 3)));
+        assert_eq!(to_bits(1, 0), Err(InputError::TooWide(1.into(), 0)));
+    }
+
+    #[test]
+    fn test_encode_signed() {
+        assert_eq!(parse_number("-0x80"), parse_number("-128"));
+        assert!(number(" -5\n").is_negative());
+        assert!(!number("-0").is_negative());
+        assert!(parse_number("--5").is_err());
+
+        assert_eq!(encode_input(&number("-1"), 8, true), Ok(vec![true; 8]));
+        assert_eq!(
+            encode_input(&number("-128"), 8, true),
+            encode_input(&number("0x80"), 8, false)
+        );
+        assert_eq!(
+            encode_input(&number("127"), 8, true),
+            encode_input(&number("127"), 8, false)
+        );
+        assert_eq!(
+            encode_input(&number("-5"), 64, true),
+            to_bits(-5i64 as u64, 64)
+        );
+        assert_eq!(
+            encode_input(&number("-9223372036854775808"), 64, true),
+            to_bits(1 << 63, 64)
+        );
+        assert_eq!(encode_input(&number("-1"), 70, true), Ok(vec![true; 70]));
+        assert_eq!(encode_input(&number("0"), 0, true), Ok(vec![]));
+        assert_eq!(encode_input(&number("-0"), 0, false), Ok(vec![]));
+
+        // -2^64 needs 65 bits, and differs from 0 only in the top one
+        let mut lowest = vec![false; 65];
+        lowest[64] = true;
+        assert_eq!(
+            encode_input(&number("-0x1_0000_0000_0000_0000"), 65, true),
+            Ok(lowest)
+        );
+
+        assert_eq!(
+            encode_input(&number("128"), 8, true),
+            Err(InputError::SignedTooWide(128.into(), 8))
+        );
+        assert_eq!(
+            encode_input(&number("-129"), 8, true),
+            Err(InputError::SignedTooWide(number("-129"), 8))
+        );
+        assert_eq!(
+            encode_input(&number("-1"), 8, false),
+            Err(InputError::Negative(number("-1")))
+        );
+        assert_eq!(encode_input(&number("255"), 8, false), Ok(vec![true; 8]));
+    }
+
+    #[test]
+    fn test_read_value_reprompts() {
+        let mut lines = vec!["abc", "300", "0xff"].into_iter().map(String::from);
+        let mut errors = Vec::new();
+
+        let value = read_value(|| lines.next(), 8, false, 3, |e| errors.push(e.to_string()));
+        assert_eq!(value, Ok(255.into()));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1], "300 does not fit into 8 bit(s)");
+        assert_eq!(
+            InputError::SignedTooWide(number("-129"), 8).to_string(),
+            "-129 does not fit into 8 bit(s) as a two's complement number"
+        );
+    }
+
+    #[test]
+    fn test_read_value_gives_up() {
+        let mut lines = vec!["a", "b", "c", "1"].into_iter().map(String::from);
+        assert_eq!(
+            read_value(|| lines.next(), 8, false, 3, |_| {}),
+            Err(InputError::TooManyAttempts(3))
+        );
+
+        let mut lines = std::iter::empty();
+        assert_eq!(
+            read_value(|| lines.next(), 8, false, 3, |_| {}),
+            Err(InputError::NoInput)
+        );
+    }
+}
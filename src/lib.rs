@@ -0,0 +1,22 @@
+pub mod arithmetic;
+pub mod circuit;
+pub mod error;
+pub mod input;
+pub mod mul_triple;
+pub mod party;
+pub mod report;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod verify;
+
+use crate::circuit::circuit_parser::Circuit;
+use crate::error::MpcError;
+use std::path::Path;
+
+/// Reads the circuit at `path` and evaluates it with the GMW protocol, with `p0` as the input of
+/// party 0 and `p1` as the input of party 1. Both parties run on the current thread, see
+/// [`party::local::run_local`].
+pub fn run(path: impl AsRef<Path>, p0: &[bool], p1: &[bool]) -> Result<Vec<bool>, MpcError> {
+    let circuit = Circuit::from_file(path)?;
+    Ok(party::local::run_local(&circuit, p0, p1)?)
+}
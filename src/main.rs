@@ -1,14 +1,46 @@
-use clap::Parser;
-use std::fs;
-use std::path::PathBuf;
-use std::thread;
+use clap::builder::RangedU64ValueParser;
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, IsTerminal, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use zeroize::{Zeroize, Zeroizing};
 
-use crate::circuit::circuit_parser::Circuit;
-use crate::party::party_gmw::new_party_pair;
+use mpc_in_rust::circuit::circuit_diff::DEFAULT_DIFF_LIMIT;
+use mpc_in_rust::circuit::circuit_dot::DotOptions;
+use mpc_in_rust::circuit::circuit_generators::Builtin;
+use mpc_in_rust::circuit::circuit_layout::PartyId;
+use mpc_in_rust::circuit::circuit_meta::{CircuitMeta, ValueMeta};
+use mpc_in_rust::circuit::circuit_parser::{Circuit, ParseOptions, DEFAULT_MAX_WIRES};
+use mpc_in_rust::input::{
+    encode_input, parse_input_arg, parse_number, parse_value, read_value, InputArg, InputError,
+    InputValue,
+};
+use mpc_in_rust::mul_triple::{FileMTP, MTProvider, SeededMTP};
+#[cfg(feature = "count-allocations")]
+use mpc_in_rust::party::allocations::CountingAllocator;
+use mpc_in_rust::party::builder::PartyBuilder;
+use mpc_in_rust::party::errors::PartyError;
+use mpc_in_rust::party::party_gmw::Party;
+use mpc_in_rust::party::profile::Profile;
+use mpc_in_rust::party::tcp::{
+    TcpConfig, TcpTransport, DEFAULT_COMPRESS_THRESHOLD, DEFAULT_MAX_FRAME_LEN,
+};
+use mpc_in_rust::party::threads::run_in_threads;
+use mpc_in_rust::party::transport::{
+    channel_pair_with_capacity, Transport, DEFAULT_CHANNEL_CAPACITY,
+};
+use mpc_in_rust::report::{format_result, write_results, Format, RunMetadata, RunReport};
+use mpc_in_rust::verify::{verify, Function, Reference};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 
-pub mod circuit;
-pub mod mul_triple;
-pub mod party;
+// counts the allocations of each party for --profile
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 /// For argument parsing, my favorite crate is clap https://docs.rs/clap/latest/clap/
 /// Especially its derive feature makes declarative argument parsing really easy.
@@ -16,79 +48,822 @@ pub mod party;
 /// and add the necessary fields.
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    /// Path to file, which contains circuit in bristol fashion
+    #[command(subcommand)]
+    command: Option<Command>,
+    // without a subcommand, the circuit is run like with the run subcommand
+    #[command(flatten)]
+    run: RunArgs,
+    /// Refuse circuit files declaring more wires than this. Every party allocates memory for each
+    /// wire, so this protects against corrupted or malicious files.
+    #[arg(long, global = true, default_value_t = DEFAULT_MAX_WIRES)]
+    max_wires: usize,
+}
+
+#[derive(ClapArgs, Debug)]
+struct RunArgs {
+    /// Path to file, which contains circuit in bristol fashion, or - to read it from stdin. Can be
+    /// repeated to run several circuits one after another over the same connection, the n-th
+    /// inputs belong to the n-th circuit.
+    #[arg(short, long, required_unless_present_any = ["expr", "builtin"])]
+    path: Vec<PathBuf>,
+    /// Generate a standard circuit instead of reading it from a file, as NAME:WIDTH, e.g.
+    /// adder:64. NAME is one of adder, sub, lt, eq, and, xor and mux. Can be repeated like
+    /// --path.
+    #[arg(long, value_name = "NAME:WIDTH", value_parser = parse_builtin, conflicts_with_all = ["path", "expr"])]
+    builtin: Vec<(Builtin, usize)>,
+    /// Compile the circuit from an expression instead of reading it from a file, e.g.
+    /// "out = (a & b) ^ !c". Its inputs are declared with --expr-input.
+    #[arg(long, conflicts_with = "path")]
+    expr: Option<String>,
+    /// An input value of the expression as NAME:WIDTH. The first one is the input of party 0, the
+    /// others are those of party 1. Can be repeated.
+    #[arg(long, value_name = "NAME:WIDTH", value_parser = parse_expr_input, requires = "expr")]
+    expr_input: Vec<(String, usize)>,
+    /// Input for party 0 (decimal, 0x.. for hex or 0b.. for binary), or - to read it from stdin
+    #[arg(short, long, value_parser = parse_input_arg, allow_negative_numbers = true, required_unless_present_any = ["prompt_input", "public", "named_in", "connect"], conflicts_with = "connect")]
+    first_in: Vec<InputArg>,
+    /// Input for party 1 (decimal, 0x.. for hex or 0b.. for binary), or - to read it from stdin
+    #[arg(short, long, value_parser = parse_input_arg, allow_negative_numbers = true, required_unless_present_any = ["prompt_input", "public", "named_in", "listen"], conflicts_with = "listen")]
+    second_in: Vec<InputArg>,
+    /// An input value by the name the metadata file of the circuit gives it, as NAME=VALUE,
+    /// instead of the inputs of both parties. Every input value needs one. Can be repeated.
+    #[arg(long = "in", value_name = "NAME=VALUE", value_parser = parse_named_input, allow_negative_numbers = true, conflicts_with_all = ["first_in", "second_in", "prompt_input"])]
+    named_in: Vec<(String, InputValue)>,
+    /// Read inputs which are not passed as arguments from the terminal without echoing them,
+    /// so they do not end up in the shell history. Reads lines from stdin if it is not a terminal.
+    #[arg(long)]
+    prompt_input: bool,
+    /// Format of the printed result
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    output_format: Format,
+    /// Write the result to this file, which is created or truncated, in the --output-format
+    #[arg(long, visible_alias = "out")]
+    output_file: Option<PathBuf>,
+    /// Make an input value public: INDEX=VALUE gives the input value with the index in the header
+    /// (counted from 0) to both parties instead of secret-sharing it, so its party does not supply
+    /// it. Can be repeated.
+    #[arg(long, value_name = "INDEX=VALUE", value_parser = parse_public)]
+    public: Vec<(usize, InputValue)>,
+    /// Treat the inputs and outputs as two's complement numbers of their width, so negative inputs
+    /// can be passed and outputs with the top bit set are printed as negative numbers
+    #[arg(long)]
+    signed: bool,
+    /// Do not print anything to stdout on success
     #[arg(short, long)]
-    path: PathBuf,
-    /// Input for party 0
+    quiet: bool,
+    /// Seed for generating the multiplication triples, for reproducible runs. A random seed is
+    /// used if none is passed.
+    #[arg(long, value_parser = parse_value)]
+    seed: Option<u64>,
+    /// Number of messages buffered between the parties in each direction, at least 1
+    #[arg(long, default_value_t = DEFAULT_CHANNEL_CAPACITY, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    channel_capacity: usize,
+    /// Only play party 0, which waits for party 1 to connect at this address, e.g. 0.0.0.0:7000.
+    /// The triples come from a seed both parties agree on.
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["connect", "named_in", "seed"])]
+    listen: Option<String>,
+    /// Only play party 1, which connects to party 0 at this address
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["named_in", "seed"])]
+    connect: Option<String>,
+    /// How long a single attempt to connect to the other party may take, in milliseconds
+    #[arg(long, default_value_t = 5000)]
+    connect_timeout_ms: u64,
+    /// How often to try connecting again while the other party does not listen yet
+    #[arg(long, default_value_t = 10)]
+    connect_retries: u32,
+    /// Give up if sending or receiving a message takes longer than this many milliseconds
+    #[arg(long)]
+    io_timeout_ms: Option<u64>,
+    /// Refuse messages of the other party which are larger than this many bytes
+    #[arg(long, default_value_t = DEFAULT_MAX_FRAME_LEN)]
+    max_frame_len: usize,
+    /// Compress large messages to the other party, if it compresses as well
+    #[arg(long)]
+    compress: bool,
+    /// Only compress messages of at least this many bytes
+    #[arg(long, default_value_t = DEFAULT_COMPRESS_THRESHOLD, requires = "compress")]
+    compress_threshold: usize,
+    /// Print where each party spent its time to stderr after the run. Allocations are only
+    /// counted if the program is built with the count-allocations feature.
+    #[arg(long)]
+    profile: bool,
+}
+
+impl RunArgs {
+    /// Whether this process enters the inputs of the party, which it does for both parties
+    /// unless it only plays one of them over the network.
+    fn enters(&self, party: usize) -> bool {
+        match party {
+            0 => self.connect.is_none(),
+            _ => self.listen.is_none(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Executes the circuit with the GMW protocol (the default without a subcommand)
+    Run(Box<RunArgs>),
+    /// Prints the size and depth of a circuit, without running it
+    Info(InfoArgs),
+    /// Checks that a circuit computes the expected function of its two inputs, without running
+    /// the protocol
+    Verify(VerifyArgs),
+    /// Generates multiplication triples ahead of time and writes the shares of each party to a
+    /// file of its own
+    GenTriples(GenTriplesArgs),
+    /// Compares the size, depth and function of two circuits and lists their differences, and
+    /// fails if there are any
+    Diff(DiffArgs),
+    /// Draws a circuit as a Graphviz digraph
+    Dot(DotArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+struct InfoArgs {
+    /// Path to file, which contains circuit in bristol fashion, or - to read it from stdin
+    #[arg(required_unless_present = "builtin")]
+    path: Option<PathBuf>,
+    /// Generate a standard circuit instead of reading it from a file, as NAME:WIDTH
+    #[arg(long, value_name = "NAME:WIDTH", value_parser = parse_builtin, conflicts_with = "path")]
+    builtin: Option<(Builtin, usize)>,
+}
+
+#[derive(ClapArgs, Debug)]
+struct VerifyArgs {
+    /// Path to file, which contains circuit in bristol fashion
+    #[arg(short, long, required_unless_present = "builtin")]
+    path: Option<PathBuf>,
+    /// Generate a standard circuit instead of reading it from a file, as NAME:WIDTH
+    #[arg(long, value_name = "NAME:WIDTH", value_parser = parse_builtin, conflicts_with = "path")]
+    builtin: Option<(Builtin, usize)>,
+    /// The expected function: add, sub, mul, and, xor, or custom to compare with --reference
+    #[arg(short, long, value_parser = parse_expected)]
+    function: Expected,
+    /// The circuit to compare with for --function custom
     #[arg(short, long)]
-    first_in: u64,
-    /// Input for party 1
+    reference: Option<PathBuf>,
+    /// Number of random input pairs checked, if the inputs are too wide to check all of them
+    #[arg(long, default_value_t = 10_000)]
+    samples: u64,
+}
+
+#[derive(ClapArgs, Debug)]
+struct DiffArgs {
+    /// Path to the first circuit, or - to read it from stdin
+    first: PathBuf,
+    /// Path to the second circuit
+    #[arg(required_unless_present = "builtin")]
+    second: Option<PathBuf>,
+    /// Compare with a generated standard circuit instead of a second file, as NAME:WIDTH
+    #[arg(long, value_name = "NAME:WIDTH", value_parser = parse_builtin, conflicts_with = "second")]
+    builtin: Option<(Builtin, usize)>,
+    /// Number of differences listed at most
+    #[arg(long, default_value_t = DEFAULT_DIFF_LIMIT)]
+    limit: usize,
+}
+
+#[derive(ClapArgs, Debug)]
+struct DotArgs {
+    /// Path to file, which contains circuit in bristol fashion, or - to read it from stdin
+    #[arg(required_unless_present = "builtin")]
+    path: Option<PathBuf>,
+    /// Generate a standard circuit instead of reading it from a file, as NAME:WIDTH
+    #[arg(long, value_name = "NAME:WIDTH", value_parser = parse_builtin, conflicts_with = "path")]
+    builtin: Option<(Builtin, usize)>,
+    /// Write the digraph to this file instead of stdout
+    #[arg(long)]
+    output_file: Option<PathBuf>,
+    /// Draw at most this many gates
+    #[arg(long)]
+    max_gates: Option<usize>,
+    /// Only draw the gates this wire depends on. Can be repeated.
+    #[arg(long)]
+    wire: Vec<usize>,
+}
+
+#[derive(ClapArgs, Debug)]
+struct GenTriplesArgs {
+    /// Number of triples to generate
     #[arg(short, long)]
-    second_in: u64,
+    count: usize,
+    /// File for the triple shares of party 0
+    #[arg(long)]
+    first_out: PathBuf,
+    /// File for the triple shares of party 1
+    #[arg(long)]
+    second_out: PathBuf,
+    /// Seed for generating the triples, for reproducible files. A random seed is used if none is
+    /// passed.
+    #[arg(long, value_parser = parse_value)]
+    seed: Option<u64>,
 }
 
-fn main() {
-    // The main function should first parse the passed arguments (I recommend to use a crate like
-    // clap), and then evaluate the passed circuit. Note that you will likely need to run each
-    // Party in its own thread (see https://doc.rust-lang.org/std/thread/index.html).
-    let args = Args::parse();
-    let filepath = args.path;
-    let file_contents: String = match fs::read_to_string(filepath) {
-        Ok(contents) => contents,
-        Err(e) => {
-            // print error message and exit from the program
-            eprintln!("An error has occurred whilst accessing the file: {}!", e);
+#[derive(Debug, Clone, Copy)]
+enum Expected {
+    Function(Function),
+    Custom,
+}
+
+fn parse_expected(s: &str) -> Result<Expected, String> {
+    if s == "custom" {
+        return Ok(Expected::Custom);
+    }
+    Function::from_str(s, true).map(Expected::Function)
+}
+
+fn parse_public(s: &str) -> Result<(usize, InputValue), String> {
+    let (index, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("{} is not of the form INDEX=VALUE", s))?;
+    let index = index
+        .parse()
+        .map_err(|_| format!("{} is not a value index", index))?;
+    Ok((index, parse_number(value).map_err(|e| e.to_string())?))
+}
+
+fn parse_named_input(s: &str) -> Result<(String, InputValue), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("{} is not of the form NAME=VALUE", s))?;
+    Ok((
+        name.to_string(),
+        parse_number(value).map_err(|e| e.to_string())?,
+    ))
+}
+
+fn parse_expr_input(s: &str) -> Result<(String, usize), String> {
+    let (name, width) = s
+        .split_once(':')
+        .ok_or_else(|| format!("{} is not of the form NAME:WIDTH", s))?;
+    let width = width
+        .parse()
+        .map_err(|_| format!("{} is not a width", width))?;
+    Ok((name.to_string(), width))
+}
+
+fn parse_builtin(s: &str) -> Result<(Builtin, usize), String> {
+    let (name, width) = s
+        .split_once(':')
+        .ok_or_else(|| format!("{} is not of the form NAME:WIDTH", s))?;
+    let builtin = Builtin::from_str(name, true)?;
+    match width.parse() {
+        Ok(width) if width > 0 => Ok((builtin, width)),
+        _ => Err(format!("{} is not a positive width", width)),
+    }
+}
+
+/// Prints the error and exits.
+fn exit_with(e: impl std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    std::process::exit(1);
+}
+
+/// Reads a circuit within the limits or exits with the error.
+fn read_circuit(path: &Path, limits: &ParseOptions) -> Circuit {
+    // circuits which cannot be evaluated gate by gate would make the parties panic
+    Circuit::from_path_or_stdin_with(path, io::stdin().lock(), limits)
+        .and_then(|c| c.validate_with(limits).map(|_| c))
+        .unwrap_or_else(|e| exit_with(e))
+}
+
+/// Generates the given standard circuit, or reads the circuit from the path like
+/// [`read_circuit`] otherwise, for the subcommands taking either `--builtin` or a path.
+fn builtin_or_read(
+    builtin: Option<(Builtin, usize)>,
+    path: Option<&Path>,
+    read: impl FnOnce(&Path) -> Circuit,
+) -> Circuit {
+    match (builtin, path) {
+        (Some((builtin, width)), _) => builtin.circuit(width),
+        (None, Some(path)) => read(path),
+        (None, None) => unreachable!("clap requires a path without a builtin circuit"),
+    }
+}
+
+/// Reads a circuit like [`read_circuit`] and names its values by its metadata file, if it has
+/// one, see [`CircuitMeta::for_circuit`].
+fn read_circuit_with_meta(path: &Path, limits: &ParseOptions) -> Circuit {
+    let mut circuit = read_circuit(path, limits);
+    let attached = CircuitMeta::for_circuit(path).and_then(|meta| match meta {
+        Some(meta) => circuit.attach_meta(meta),
+        None => Ok(()),
+    });
+    attached.unwrap_or_else(|e| exit_with(e));
+    circuit
+}
+
+/// Runs the verify subcommand and exits with an error code if the check fails.
+fn run_verify(args: VerifyArgs, limits: &ParseOptions) {
+    let circuit = builtin_or_read(args.builtin, args.path.as_deref(), |path| {
+        read_circuit(path, limits)
+    });
+    let reference_circuit;
+    let reference = match (args.function, &args.reference) {
+        (Expected::Function(f), _) => Reference::Function(f),
+        (Expected::Custom, Some(path)) => {
+            reference_circuit = read_circuit(path, limits);
+            Reference::Circuit(&reference_circuit)
+        }
+        (Expected::Custom, None) => {
+            eprintln!("--function custom needs a --reference circuit");
             std::process::exit(1);
         }
     };
 
-    let c: Circuit = match Circuit::parse(&file_contents) {
-        Ok(content) => content,
+    let result = match verify(&circuit, &reference, args.samples, &mut rand::thread_rng()) {
+        Ok(result) => result,
         Err(e) => {
             eprintln!("{}", e);
             std::process::exit(1);
         }
     };
+    let how = if result.exhaustive { "all" } else { "random" };
+    match result.counterexample {
+        None => println!("PASS: checked {} {} input pair(s)", result.checked, how),
+        Some(c) => {
+            println!(
+                "FAIL: for the inputs {} and {}, expected {}, but the circuit computes {} (found after {} {} input pair(s))",
+                c.a, c.b, c.expected, c.actual, result.checked, how
+            );
+            std::process::exit(1);
+        }
+    }
+}
 
-    let (mut p0, mut p1) = new_party_pair(c);
+/// Runs the info subcommand.
+fn run_info(args: InfoArgs, limits: &ParseOptions) {
+    let circuit = builtin_or_read(args.builtin, args.path.as_deref(), |path| {
+        read_circuit_with_meta(path, limits)
+    });
+    let stats = circuit.stats();
+    // the widths, followed by the names of the values if the metadata gives them
+    let widths = |values: &[usize], names: Option<&[ValueMeta]>| {
+        let widths: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(i, w)| match names {
+                Some(names) => format!("{} ({})", w, names[i].name),
+                None => w.to_string(),
+            })
+            .collect();
+        widths.join(", ")
+    };
+    let meta = circuit.meta();
+    println!(
+        "Gates: {} (AND: {}, XOR: {}, INV: {})",
+        stats.gates(),
+        stats.and,
+        stats.xor,
+        stats.inv
+    );
+    println!("Wires: {}", circuit.header().wires_amount);
+    println!(
+        "Input bits: {}",
+        widths(&circuit.header().niv, meta.map(|m| &m.inputs[..]))
+    );
+    println!(
+        "Output bits: {}",
+        widths(&circuit.header().nov, meta.map(|m| &m.outputs[..]))
+    );
+    println!("Depth: {}", circuit.depth());
+    println!("AND depth: {}", circuit.and_depth());
+    println!("Required triples: {}", circuit.required_triples());
+}
 
-    let first: u64 = args.first_in;
-    let second: u64 = args.second_in;
+/// Runs the diff subcommand and exits with an error code if the circuits differ.
+fn run_diff(args: DiffArgs, limits: &ParseOptions) {
+    let first = read_circuit(&args.first, limits);
+    let second = builtin_or_read(args.builtin, args.second.as_deref(), |path| {
+        read_circuit(path, limits)
+    });
+    let diff = first.diff(&second);
+    if !diff.is_empty() {
+        print!("{}{}", diff.summary, diff.listing(args.limit));
+        std::process::exit(1);
+    }
+}
 
-    let mut input_p0 = [false; 64];
-    let mut input_p1 = [false; 64];
+/// Runs the dot subcommand.
+fn run_dot(args: DotArgs, limits: &ParseOptions) {
+    let circuit = builtin_or_read(args.builtin, args.path.as_deref(), |path| {
+        read_circuit(path, limits)
+    });
+    let dot = circuit
+        .to_dot_with(&DotOptions {
+            max_gates: args.max_gates,
+            cone: args.wire,
+        })
+        .unwrap_or_else(|e| exit_with(e));
+    match &args.output_file {
+        Some(path) => {
+            if let Err(e) = fs::write(path, dot) {
+                exit_with(format!("Could not write the digraph: {}", e));
+            }
+        }
+        None => print!("{}", dot),
+    }
+}
 
-    for i in 0..64 {
-        input_p0[i] = (first >> i) & 1 == 1;
-        input_p1[i] = (second >> i) & 1 == 1;
+/// Runs the gen-triples subcommand.
+fn run_gen_triples(args: GenTriplesArgs) {
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    if let Err(e) = FileMTP::generate(&args.first_out, &args.second_out, args.count, &mut rng) {
+        exit_with(format!("Could not write the triples: {}", e));
     }
+}
 
-    let p0 = thread::spawn(move || p0.execute(&input_p0).unwrap());
-    let p1 = thread::spawn(move || p1.execute(&input_p1).unwrap());
+/// How often a malformed interactive input is asked for again before giving up.
+const PROMPT_ATTEMPTS: usize = 3;
 
-    let sol_p0 = match p0.join() {
-        Ok(result) => result,
-        Err(e) => {
-            eprintln!("Error occurred while joining p0 thread: {:?}", e);
-            std::process::exit(1);
+/// Reads the input of a party interactively. On a terminal the input is not echoed, otherwise
+/// (e.g. when piping the input) one line is read from stdin per attempt.
+fn prompt_input(party: usize, width: usize, signed: bool) -> Result<InputValue, InputError> {
+    let prompt = format!("Input for party {} ({} bit): ", party, width);
+    let stdin = io::stdin();
+    let is_terminal = stdin.is_terminal();
+
+    let next_line = || {
+        if is_terminal {
+            rpassword::prompt_password(&prompt).ok()
+        } else {
+            let mut line = String::new();
+            match stdin.lock().read_line(&mut line) {
+                Ok(0) | Err(_) => None,
+                Ok(_) => Some(line),
+            }
         }
     };
 
-    let sol_p1 = match p1.join() {
-        Ok(result) => result,
-        Err(e) => {
-            eprintln!("Error occurred while joining p1 thread: {:?}", e);
+    read_value(next_line, width, signed, PROMPT_ATTEMPTS, |e| {
+        eprintln!("{}, please try again.", e)
+    })
+}
+
+fn main() {
+    // The main function should first parse the passed arguments (I recommend to use a crate like
+    // clap), and then evaluate the passed circuit. Note that you will likely need to run each
+    // Party in its own thread (see https://doc.rust-lang.org/std/thread/index.html).
+    let args = Args::parse();
+    let limits = ParseOptions {
+        max_wires: args.max_wires,
+        ..ParseOptions::default()
+    };
+    match args.command {
+        Some(Command::Run(run_args)) => run(*run_args, &limits),
+        Some(Command::Info(info_args)) => run_info(info_args, &limits),
+        Some(Command::Verify(verify_args)) => run_verify(verify_args, &limits),
+        Some(Command::GenTriples(gen_args)) => run_gen_triples(gen_args),
+        Some(Command::Diff(diff_args)) => run_diff(diff_args, &limits),
+        Some(Command::Dot(dot_args)) => run_dot(dot_args, &limits),
+        None => run(args.run, &limits),
+    }
+}
+
+/// A circuit to run, with the inputs of both parties.
+struct Job {
+    circuit: Arc<Circuit>,
+    public: Vec<(usize, Vec<bool>)>,
+    inputs: [Zeroizing<Vec<bool>>; 2],
+}
+
+/// Reads the circuit at `path` and the inputs of both parties for it, which are prompted for if
+/// they are not passed.
+fn prepare_job(
+    args: &RunArgs,
+    c: Circuit,
+    first_in: Option<InputArg>,
+    second_in: Option<InputArg>,
+) -> Job {
+    if let Some(&(index, _)) = args
+        .public
+        .iter()
+        .find(|&&(i, _)| i >= c.header().niv.len())
+    {
+        exit_with(PartyError::InvalidPublicInput(index));
+    }
+    let public = args
+        .public
+        .iter()
+        .map(|&(index, ref value)| {
+            let bits =
+                encode_input(value, c.header().niv[index], false).unwrap_or_else(|e| exit_with(e));
+            (index, bits)
+        })
+        .collect();
+
+    // the bits each party enters, see Circuit::input_layout, without the public values
+    let is_public = |value: usize| args.public.iter().any(|&(index, _)| index == value);
+    let mut widths = [0, 0];
+    for (value, (party, wires)) in c.input_layout().into_iter().enumerate() {
+        if !is_public(value) {
+            widths[party.index()] += wires.len();
+        }
+    }
+    let [width_p0, width_p1] = widths;
+    let input_or_prompt = |input: Option<InputArg>, party: usize, width: usize| match input {
+        Some(InputArg::Value(value)) => value,
+        // a party whose inputs are all public, or which is played by another process, has
+        // nothing to enter
+        None if width == 0 || !args.enters(party) => InputValue::default(),
+        Some(InputArg::Stdin) | None => match prompt_input(party, width, args.signed) {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    if !args.named_in.is_empty() {
+        let inputs = named_inputs(&c, &args.named_in, is_public, args.signed)
+            .unwrap_or_else(|e| exit_with(e));
+        return Job {
+            circuit: Arc::new(c),
+            public,
+            inputs: inputs.map(Zeroizing::new),
+        };
+    }
+    let mut first = input_or_prompt(first_in, 0, width_p0);
+    let mut second = input_or_prompt(second_in, 1, width_p1);
+
+    // the inputs are cleared from memory once they are dropped
+    let encode = |value, width| encode_input(value, width, args.signed);
+    let input_p0 = Zeroizing::new(encode(&first, width_p0).unwrap_or_else(|e| exit_with(e)));
+    let input_p1 = Zeroizing::new(encode(&second, width_p1).unwrap_or_else(|e| exit_with(e)));
+    first.zeroize();
+    second.zeroize();
+
+    Job {
+        circuit: Arc::new(c),
+        public,
+        inputs: [input_p0, input_p1],
+    }
+}
+
+/// Encodes the input values given by their names in the metadata of the circuit, see
+/// [`RunArgs::named_in`], and returns the input bits of both parties.
+fn named_inputs(
+    c: &Circuit,
+    named: &[(String, InputValue)],
+    is_public: impl Fn(usize) -> bool,
+    signed: bool,
+) -> Result<[Vec<bool>; 2], String> {
+    let meta = c
+        .meta()
+        .ok_or("--in needs a metadata file naming the inputs of the circuit")?;
+    for (i, (name, _)) in named.iter().enumerate() {
+        if meta.input_index(name).is_none() {
+            return Err(format!("The circuit has no input named {}", name));
+        }
+        if named[..i].iter().any(|(n, _)| n == name) {
+            return Err(format!("The input {} is given twice", name));
+        }
+    }
+
+    let mut inputs = [Vec::new(), Vec::new()];
+    for (value, (party, wires)) in c.input_layout().into_iter().enumerate() {
+        if is_public(value) {
+            continue;
+        }
+        let name = &meta.inputs[value].name;
+        let (_, number) = named
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| format!("No value was given for the input {}", name))?;
+        let bits = encode_input(number, wires.len(), signed)
+            .map_err(|e| format!("The input {}: {}", name, e))?;
+        inputs[party.index()].extend(bits);
+    }
+    Ok(inputs)
+}
+
+/// The circuit, the public inputs and the party's input of a job, as handed to a party.
+type PartyJob = (Arc<Circuit>, Vec<(usize, Vec<bool>)>, Zeroizing<Vec<bool>>);
+
+/// Runs the jobs one after another over the same connection and returns the report of every
+/// job. The party starts out with the circuit of the first job.
+fn run_jobs<T: MTProvider, C: Transport>(
+    party: &mut Party<T, C>,
+    jobs: Vec<PartyJob>,
+) -> Result<Vec<RunReport>, PartyError> {
+    let mut reports = Vec::with_capacity(jobs.len());
+    for (i, (circuit, public, input)) in jobs.into_iter().enumerate() {
+        let (rounds, triples) = (party.rounds(), party.triples_consumed());
+        let and_gates = circuit.stats().and;
+        let names = circuit.meta().map(CircuitMeta::output_names);
+        if i > 0 {
+            party.next_circuit(circuit)?;
+        }
+        for (index, bits) in public {
+            party.set_public_input(index, bits)?;
+        }
+        let outputs = party.execute_structured_catching(&input)?;
+        let metadata = RunMetadata {
+            and_gates,
+            rounds: party.rounds() - rounds,
+            triples_consumed: party.triples_consumed() - triples,
+        };
+        reports.push(RunReport {
+            outputs,
+            names,
+            metadata: Some(metadata),
+        });
+    }
+    Ok(reports)
+}
+
+/// Runs the run subcommand, which is also the default without a subcommand.
+fn run(args: RunArgs, limits: &ParseOptions) {
+    let circuit_from_stdin = args.path.iter().any(|path| path.as_os_str() == "-");
+    let reads_input = |input: Option<&InputArg>| !matches!(input, Some(InputArg::Value(_)));
+    let circuits = if args.expr.is_some() {
+        1
+    } else if !args.builtin.is_empty() {
+        args.builtin.len()
+    } else {
+        args.path.len()
+    };
+    let reads_any_input = (0..circuits).any(|i| {
+        (args.enters(0) && reads_input(args.first_in.get(i)))
+            || (args.enters(1) && reads_input(args.second_in.get(i)))
+    });
+    if circuit_from_stdin && reads_any_input {
+        eprintln!("The inputs cannot be read from stdin, if the circuit is read from it");
+        std::process::exit(1);
+    }
+    if circuit_from_stdin && args.path.len() > 1 {
+        eprintln!("Only a single circuit can be read from stdin");
+        std::process::exit(1);
+    }
+    if !args.named_in.is_empty() && circuits > 1 {
+        eprintln!("Inputs can only be given by name for a single circuit");
+        std::process::exit(1);
+    }
+    if args.first_in.len() > circuits || args.second_in.len() > circuits {
+        eprintln!("There are more inputs than circuits, pass one --path per pair of inputs");
+        std::process::exit(1);
+    }
+
+    let circuits: Vec<Circuit> = match &args.expr {
+        Some(expr) => {
+            let inputs: Vec<(&str, usize)> = args
+                .expr_input
+                .iter()
+                .map(|(name, width)| (name.as_str(), *width))
+                .collect();
+            let circuit = Circuit::from_expr(expr, &inputs).unwrap_or_else(|e| exit_with(e));
+            vec![circuit]
+        }
+        None if !args.builtin.is_empty() => args
+            .builtin
+            .iter()
+            .map(|&(builtin, width)| builtin.circuit(width))
+            .collect(),
+        None => args
+            .path
+            .iter()
+            .map(|path| read_circuit_with_meta(path, limits))
+            .collect(),
+    };
+    // the n-th inputs belong to the n-th circuit
+    let jobs: Vec<Job> = circuits
+        .into_iter()
+        .enumerate()
+        .map(|(i, circuit)| {
+            let first_in = args.first_in.get(i).cloned();
+            prepare_job(&args, circuit, first_in, args.second_in.get(i).cloned())
+        })
+        .collect();
+
+    let reports = if args.listen.is_some() || args.connect.is_some() {
+        run_networked(&args, jobs)
+    } else {
+        run_locally(&args, jobs)
+    };
+
+    // one result per circuit, in the order of the paths
+    let results: Vec<String> = reports
+        .into_iter()
+        .map(|report| format_result(&report, args.output_format, args.signed))
+        .collect();
+
+    if let Some(path) = &args.output_file {
+        let written = File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write_results(&mut writer, &results)?;
+            writer.flush()
+        });
+        if let Err(e) = written {
+            eprintln!("An error has occurred whilst writing the result: {}!", e);
             std::process::exit(1);
         }
+    }
+
+    if !args.quiet {
+        for result in results {
+            match args.output_format {
+                Format::Text => println!("The result of the calculation is {}", result),
+                Format::Json => println!("{}", result),
+            }
+        }
+    }
+}
+
+/// Runs both parties of the jobs in threads of this process.
+fn run_locally(args: &RunArgs, jobs: Vec<Job>) -> Vec<RunReport> {
+    let mut seed = [0; 32];
+    match args.seed {
+        Some(value) => seed[..8].copy_from_slice(&value.to_le_bytes()),
+        None => rand::thread_rng().fill_bytes(&mut seed),
+    }
+    // the parties keep their connection and triple provider over all circuits
+    let (t0, t1) = channel_pair_with_capacity(args.channel_capacity);
+    let party = |channel, role| {
+        let mut builder = PartyBuilder::new()
+            .circuit(Arc::clone(&jobs[0].circuit))
+            .channel(channel)
+            .role(role)
+            .mt_provider(SeededMTP::<StdRng>::new(seed));
+        if let Some(timeout) = args.io_timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout));
+        }
+        builder
+            .profiling(args.profile)
+            .build()
+            .unwrap_or_else(|e| exit_with(e))
     };
+    let (mut p0, mut p1) = (party(t0, PartyId::P0), party(t1, PartyId::P1));
+    let mut jobs_p0 = Vec::with_capacity(jobs.len());
+    let mut jobs_p1 = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        let [input_p0, input_p1] = job.inputs;
+        jobs_p0.push((Arc::clone(&job.circuit), job.public.clone(), input_p0));
+        jobs_p1.push((job.circuit, job.public, input_p1));
+    }
 
+    let solutions = run_in_threads(
+        move || Ok((run_jobs(&mut p0, jobs_p0)?, p0.profile())),
+        move || Ok((run_jobs(&mut p1, jobs_p1)?, p1.profile())),
+    );
+    let ((sol_p0, profile_p0), (sol_p1, profile_p1)) = solutions.unwrap_or_else(|e| exit_with(e));
     assert_eq!(sol_p0, sol_p1);
+    print_profile(0, profile_p0);
+    print_profile(1, profile_p1);
+    sol_p0
+}
 
-    let mut solution: i64 = 0;
-    for (i, v) in sol_p0.iter().enumerate().take(64) {
-        solution += if *v { 1 } else { 0 } << i;
-    }
+/// Plays a single party of the jobs, which talks to the other party over TCP, see
+/// [`RunArgs::listen`] and [`RunArgs::connect`].
+fn run_networked(args: &RunArgs, jobs: Vec<Job>) -> Vec<RunReport> {
+    let config = TcpConfig {
+        connect_timeout: Duration::from_millis(args.connect_timeout_ms),
+        io_timeout: args.io_timeout_ms.map(Duration::from_millis),
+        max_frame_len: args.max_frame_len,
+        retries: args.connect_retries,
+        compress: args.compress,
+        compress_threshold: args.compress_threshold,
+    };
+    let transport = match (&args.listen, &args.connect) {
+        (Some(addr), _) => {
+            TcpListener::bind(addr).and_then(|listener| TcpTransport::accept(&listener, &config))
+        }
+        (None, Some(addr)) => TcpTransport::connect(addr.as_str(), &config),
+        (None, None) => unreachable!("a networked run listens or connects"),
+    };
+    let transport = transport
+        .unwrap_or_else(|e| exit_with(format!("Could not connect to the other party: {}", e)));
+
+    let is_p1 = args.connect.is_some();
+    let jobs: Vec<PartyJob> = jobs
+        .into_iter()
+        .map(|job| {
+            let [input_p0, input_p1] = job.inputs;
+            let input = if is_p1 { input_p1 } else { input_p0 };
+            (job.circuit, job.public, input)
+        })
+        .collect();
+    let mut party = PartyBuilder::new()
+        .circuit(Arc::clone(&jobs[0].0))
+        .channel(transport)
+        .role(if is_p1 { PartyId::P1 } else { PartyId::P0 })
+        .agreed_seed()
+        .profiling(args.profile)
+        .build()
+        .unwrap_or_else(|e| exit_with(e));
+    let reports = run_jobs(&mut party, jobs).unwrap_or_else(|e| exit_with(e));
+    party.transport().close().unwrap_or_else(|e| exit_with(e));
+    print_profile(usize::from(is_p1), party.profile());
+    reports
+}
 
-    println!("The result of the calculation is {}", solution)
+/// Prints the profile of a party to stderr, if it was profiled, see [`RunArgs::profile`].
+fn print_profile(party: usize, profile: Option<Profile>) {
+    if let Some(profile) = profile {
+        eprint!("Profile of party {}:\n{}", party, profile);
+    }
 }
@@ -1,9 +1,23 @@
+use crate::party::errors::{PartyError, Phase};
+use crate::party::party_gmw::{in_phase, Messages};
+use crate::party::transport::{Envelope, Transport};
 use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::{sync_channel, Receiver};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use zeroize::Zeroize;
 
 /// A MulTriple (short for multiplication triple) is used to efficiently perform a multiplication
 /// of secret values in the online phase of the GMW protocol. A MulTriple comprises the random values
 /// a,b,c in {0,1} s.t. c = a & b. These random values are secret-shared between the parties, so e.g.
 /// Party 0 has [a]_0, [b]_0, and [c]_0 with [a]_0 ^ [a]_1 = a (likewise for b and c).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Zeroize)]
 pub struct MulTriple {
     // secret-shared parts of multiplication triple. So a is [a]_i for Party i
     pub a: bool,
@@ -11,6 +25,36 @@ pub struct MulTriple {
     pub c: bool,
 }
 
+impl MulTriple {
+    /// Writes the triple as one line of the format [`FileMTP`] reads: the bits a, b and c as 0 or
+    /// 1, separated by spaces.
+    pub fn write_line(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{} {} {}",
+            u8::from(self.a),
+            u8::from(self.b),
+            u8::from(self.c)
+        )
+    }
+
+    /// Parses a line written by [`MulTriple::write_line`].
+    fn parse_line(line: &str) -> Option<Self> {
+        let bits: Vec<bool> = line
+            .split_whitespace()
+            .map(|t| match t {
+                "0" => Some(false),
+                "1" => Some(true),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        match bits[..] {
+            [a, b, c] => Some(MulTriple { a, b, c }),
+            _ => None,
+        }
+    }
+}
+
 /// The MTProvider trait abstracts over different implementations of generating MulTriples. A trivial
 /// implementation always returns a = 0, b = 0, c = 0, as 0 ^ 0 = (0 ^ 0) & (0 ^ 0).
 /// A slightly more realistic implementation could sample triples based on a shared seed used for
@@ -20,8 +64,31 @@ pub struct MulTriple {
 /// fulfills the multiplication triple property (but is still insecure!).
 pub trait MTProvider {
     fn get_triple(&mut self) -> MulTriple;
+
+    /// Like [`MTProvider::get_triple`], but fails instead of panicking if the provider has no
+    /// triple left, e.g. because a file of triples is used up or cannot be read. Parties take
+    /// their triples with this. Providers which can run out override it.
+    fn try_get_triple(&mut self) -> io::Result<MulTriple> {
+        Ok(self.get_triple())
+    }
+
+    /// Returns the next `count` triples at once, e.g. for preprocessing the triples of a whole
+    /// circuit. Providers which can generate triples in bulk more efficiently override this.
+    fn get_triples(&mut self, count: usize) -> Vec<MulTriple> {
+        (0..count).map(|_| self.get_triple()).collect()
+    }
+
+    /// Like [`MTProvider::get_triples`], but fails like [`MTProvider::try_get_triple`] instead of
+    /// panicking. Parties preprocess the triples of a batched execution with this. Providers
+    /// which override `get_triples` and cannot run out override this as well.
+    fn try_get_triples(&mut self, count: usize) -> io::Result<Vec<MulTriple>> {
+        (0..count).map(|_| self.try_get_triple()).collect()
+    }
 }
 
+/// Number of triples [`SeededMTP::get_triples`] generates from one RNG stream.
+const CHUNK_SIZE: usize = 1 << 14;
+
 pub struct SeededMTP<T: SeedableRng + Rng> {
     rng: T,
 }
@@ -34,12 +101,699 @@ impl<T: SeedableRng + Rng> SeededMTP<T> {
     }
 }
 
-impl<T: SeedableRng + Rng> MTProvider for SeededMTP<T> {
+fn sample_triple(rng: &mut impl Rng) -> MulTriple {
+    let a = rng.gen();
+    let b = rng.gen();
+    let c = rng.gen();
+
+    MulTriple { a, b, c }
+}
+
+impl<T> MTProvider for SeededMTP<T>
+where
+    T: SeedableRng + Rng + Send,
+    T::Seed: Send,
+{
+    fn get_triple(&mut self) -> MulTriple {
+        sample_triple(&mut self.rng)
+    }
+
+    /// Generates the triples in parallel. The count is split into chunks of [`CHUNK_SIZE`]
+    /// triples, and every chunk gets an RNG stream of its own, seeded from the provider's RNG.
+    /// The triples therefore only depend on the seed, not on the number of threads. They differ
+    /// from those of repeated `get_triple` calls though, so both parties have to request their
+    /// triples the same way.
+    fn get_triples(&mut self, count: usize) -> Vec<MulTriple> {
+        let seeds: Vec<T::Seed> = (0..count.div_ceil(CHUNK_SIZE))
+            .map(|_| {
+                let mut seed = T::Seed::default();
+                self.rng.fill_bytes(seed.as_mut());
+                seed
+            })
+            .collect();
+
+        let chunks: Vec<Vec<MulTriple>> = seeds
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, seed)| {
+                let mut rng = T::from_seed(seed);
+                let len = CHUNK_SIZE.min(count - i * CHUNK_SIZE);
+                (0..len).map(|_| sample_triple(&mut rng)).collect()
+            })
+            .collect();
+        chunks.concat()
+    }
+
+    fn try_get_triples(&mut self, count: usize) -> io::Result<Vec<MulTriple>> {
+        Ok(self.get_triples(count))
+    }
+}
+
+/// An MTProvider which wraps another provider and records every triple it hands out, in the
+/// order they were requested. This is useful in tests to check how many triples an evaluation
+/// consumes and in which order. The returned triples are exactly those of the inner provider.
+pub struct RecordingMTP<T: MTProvider> {
+    inner: T,
+    recorded: Vec<MulTriple>,
+}
+
+impl<T: MTProvider> RecordingMTP<T> {
+    pub fn new(inner: T) -> Self {
+        RecordingMTP {
+            inner,
+            recorded: Vec::new(),
+        }
+    }
+
+    /// The triples returned so far, in the order of the `get_triple` calls.
+    pub fn recorded(&self) -> &[MulTriple] {
+        &self.recorded
+    }
+}
+
+impl<T: MTProvider> MTProvider for RecordingMTP<T> {
+    fn get_triple(&mut self) -> MulTriple {
+        let triple = self.inner.get_triple();
+        self.recorded.push(triple);
+        triple
+    }
+
+    fn try_get_triple(&mut self) -> io::Result<MulTriple> {
+        let triple = self.inner.try_get_triple()?;
+        self.recorded.push(triple);
+        Ok(triple)
+    }
+
+    fn get_triples(&mut self, count: usize) -> Vec<MulTriple> {
+        let triples = self.inner.get_triples(count);
+        self.recorded.extend_from_slice(&triples);
+        triples
+    }
+
+    fn try_get_triples(&mut self, count: usize) -> io::Result<Vec<MulTriple>> {
+        let triples = self.inner.try_get_triples(count)?;
+        self.recorded.extend_from_slice(&triples);
+        Ok(triples)
+    }
+}
+
+/// An MTProvider whose triples are generated by both parties together, by running a protocol
+/// over their transport before the evaluation. Each party samples its shares [a]_i and [b]_i at
+/// random. The product a & b = [a]_0[b]_0 ^ [a]_1[b]_1 ^ [a]_0[b]_1 ^ [a]_1[b]_0 has two cross
+/// terms which no party can compute alone. Each cross term [a]_i[b]_j is shared with one oblivious
+/// transfer (OT): party i sends the messages (r, r ^ [a]_i), party j chooses with [b]_j and
+/// learns r ^ [a]_i[b]_j, while party i keeps r.
+///
+/// INSECURE: the OT is only simulated. The receiver sends its choice bits in the clear and the
+/// sender answers with the chosen messages, so each party learns the other party's [b]_j. A real
+/// implementation would use an OT extension protocol here, with the same message flow.
+pub struct InteractiveMTP {
+    triples: VecDeque<MulTriple>,
+}
+
+impl InteractiveMTP {
+    /// Generates `count` triples together with the other party, which has to call this with the
+    /// same count at the same time. Both parties exchange exactly two messages for the whole
+    /// batch: the OT choices and the OT answers.
+    pub fn generate<C: Transport>(
+        transport: &C,
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Self, PartyError> {
+        let phase = Phase::Preprocessing;
+        let closed = |e| in_phase(e, phase);
+        let mut random = || -> Vec<bool> { (0..count).map(|_| rng.gen()).collect() };
+        let a = random();
+        let b = random();
+        let r = random();
+
+        // as receiver, choose with our shares of b
+        transport
+            .send(Envelope::new(0, Messages::OtChoices { choices: b.clone() }))
+            .map_err(closed)?;
+        let Messages::OtChoices { choices } = transport.recv().map_err(closed)?.open(0, phase)?
+        else {
+            return Err(PartyError::UnexpectedMessage { phase });
+        };
+        if choices.len() != count {
+            return Err(PartyError::UnexpectedMessage { phase });
+        }
+
+        // as sender, answer with the chosen one of (r, r ^ a)
+        let answers = (0..count).map(|k| r[k] ^ (choices[k] & a[k])).collect();
+        transport
+            .send(Envelope::new(1, Messages::OtAnswers { answers }))
+            .map_err(closed)?;
+        let Messages::OtAnswers { answers } = transport.recv().map_err(closed)?.open(1, phase)?
+        else {
+            return Err(PartyError::UnexpectedMessage { phase });
+        };
+        if answers.len() != count {
+            return Err(PartyError::UnexpectedMessage { phase });
+        }
+
+        // [c]_i = [a]_i[b]_i ^ (share of [a]_i[b]_j) ^ (share of [a]_j[b]_i)
+        let triples = (0..count)
+            .map(|k| MulTriple {
+                a: a[k],
+                b: b[k],
+                c: a[k] & b[k] ^ r[k] ^ answers[k],
+            })
+            .collect();
+        Ok(InteractiveMTP { triples })
+    }
+
+    /// Number of triples which have not been handed out yet.
+    pub fn remaining(&self) -> usize {
+        self.triples.len()
+    }
+}
+
+/// The error of a provider whose triples are all handed out.
+fn used_up(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!("all {} are used up", what),
+    )
+}
+
+impl MTProvider for InteractiveMTP {
+    /// Panics if all generated triples are used up, so generate at least
+    /// `Circuit::required_triples` of them.
+    fn get_triple(&mut self) -> MulTriple {
+        self.try_get_triple().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_get_triple(&mut self) -> io::Result<MulTriple> {
+        self.triples
+            .pop_front()
+            .ok_or_else(|| used_up("interactively generated triples"))
+    }
+}
+
+/// Magic bytes at the start of a file of bit-packed triples, see [`FileMTP::generate`].
+const TRIPLE_FILE_MAGIC: [u8; 4] = *b"MTP\0";
+/// Version of the packed triple file format.
+const TRIPLE_FILE_VERSION: u32 = 1;
+/// Length of the header of a packed triple file: magic, version and the number of triples.
+const TRIPLE_FILE_HEADER_LEN: u64 = 16;
+
+/// Packs up to 8 triples into 3 bytes: one byte of a shares, one of b shares and one of c
+/// shares, the first triple in the lowest bit.
+fn pack_triples(triples: &[MulTriple]) -> [u8; 3] {
+    let mut block = [0; 3];
+    for (i, t) in triples.iter().enumerate() {
+        block[0] |= u8::from(t.a) << i;
+        block[1] |= u8::from(t.b) << i;
+        block[2] |= u8::from(t.c) << i;
+    }
+    block
+}
+
+/// Unpacks the first `len` triples of a block written by [`pack_triples`].
+fn unpack_triples(block: [u8; 3], len: usize) -> impl Iterator<Item = MulTriple> {
+    (0..len).map(move |i| MulTriple {
+        a: (block[0] >> i) & 1 == 1,
+        b: (block[1] >> i) & 1 == 1,
+        c: (block[2] >> i) & 1 == 1,
+    })
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Where a [`FileMTP`] takes its triples from.
+enum TripleSource {
+    /// The triples of a file in the line format, read completely when opening it.
+    Lines(VecDeque<MulTriple>),
+    /// A file of packed triples, read block by block. `block` holds the unpacked triples of the
+    /// current block which have not been handed out yet.
+    Packed {
+        reader: BufReader<File>,
+        block: VecDeque<MulTriple>,
+    },
+}
+
+/// An MTProvider which hands out the triples stored in a file. Two formats are read:
+/// - one triple per line as written by [`MulTriple::write_line`], e.g. by `Party::dump_triples`
+/// - bit-packed triples with a header as written by [`FileMTP::generate`], which are streamed
+///   from the file instead of being read into memory at once
+///
+/// Both parties need files with matching shares.
+pub struct FileMTP {
+    source: TripleSource,
+    remaining: usize,
+}
+
+impl FileMTP {
+    /// Generates `count` triples in a trusted offline phase and writes the shares of party 0 to
+    /// `path0` and those of party 1 to `path1`, in the packed format. Each true triple is sampled
+    /// with c = a & b and split into two random shares.
+    pub fn generate(
+        path0: impl AsRef<Path>,
+        path1: impl AsRef<Path>,
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> io::Result<()> {
+        let mut writers = [
+            BufWriter::new(File::create(path0)?),
+            BufWriter::new(File::create(path1)?),
+        ];
+        for writer in &mut writers {
+            writer.write_all(&TRIPLE_FILE_MAGIC)?;
+            writer.write_all(&TRIPLE_FILE_VERSION.to_le_bytes())?;
+            writer.write_all(&(count as u64).to_le_bytes())?;
+        }
+
+        let mut shares0 = Vec::with_capacity(8);
+        let mut shares1 = Vec::with_capacity(8);
+        for start in (0..count).step_by(8) {
+            shares0.clear();
+            shares1.clear();
+            for _ in start..count.min(start + 8) {
+                let (a, b): (bool, bool) = (rng.gen(), rng.gen());
+                let share0 = sample_triple(rng);
+                shares0.push(share0);
+                shares1.push(MulTriple {
+                    a: a ^ share0.a,
+                    b: b ^ share0.b,
+                    c: (a & b) ^ share0.c,
+                });
+            }
+            writers[0].write_all(&pack_triples(&shares0))?;
+            writers[1].write_all(&pack_triples(&shares1))?;
+        }
+        for writer in &mut writers {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Opens a file of triples in either format. A file of packed triples is checked against its
+    /// header, all other files are read as lines. Fails if a non-empty line is not a triple.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(&path)?);
+        if reader.fill_buf()?.starts_with(&TRIPLE_FILE_MAGIC) {
+            return Self::open_packed(reader);
+        }
+
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        let triples: VecDeque<MulTriple> = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                MulTriple::parse_line(line).ok_or_else(|| {
+                    invalid_data(format!("line {} is not a triple: {}", i + 1, line))
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(FileMTP {
+            remaining: triples.len(),
+            source: TripleSource::Lines(triples),
+        })
+    }
+
+    fn open_packed(mut reader: BufReader<File>) -> io::Result<Self> {
+        let mut header = [0; TRIPLE_FILE_HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != TRIPLE_FILE_VERSION {
+            return Err(invalid_data(format!(
+                "the triples have format version {}, but only version {} is supported",
+                version, TRIPLE_FILE_VERSION
+            )));
+        }
+        let count = u64::from_le_bytes(header[8..].try_into().unwrap());
+
+        // a truncated file would otherwise only be noticed in the middle of a run
+        let expected = TRIPLE_FILE_HEADER_LEN + count.div_ceil(8) * 3;
+        let actual = reader.get_ref().metadata()?.len();
+        if actual != expected {
+            return Err(invalid_data(format!(
+                "the file should hold {} triples in {} bytes, but has {} bytes",
+                count, expected, actual
+            )));
+        }
+        Ok(FileMTP {
+            source: TripleSource::Packed {
+                reader,
+                block: VecDeque::with_capacity(8),
+            },
+            remaining: count as usize,
+        })
+    }
+
+    /// Number of triples which have not been handed out yet.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl MTProvider for FileMTP {
+    /// Panics if all triples of the file are used up, or if reading the file fails.
     fn get_triple(&mut self) -> MulTriple {
-        let a = self.rng.gen();
-        let b = self.rng.gen();
-        let c = self.rng.gen();
+        self.try_get_triple().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_get_triple(&mut self) -> io::Result<MulTriple> {
+        if self.remaining == 0 {
+            return Err(used_up("triples of the file"));
+        }
+        let triple = match &mut self.source {
+            TripleSource::Lines(triples) => triples.pop_front().unwrap(),
+            TripleSource::Packed { reader, block } => {
+                if block.is_empty() {
+                    let mut packed = [0; 3];
+                    reader.read_exact(&mut packed).map_err(|e| {
+                        io::Error::new(e.kind(), format!("could not read the triples: {}", e))
+                    })?;
+                    // the last block may be partially filled
+                    block.extend(unpack_triples(packed, self.remaining.min(8)));
+                }
+                block.pop_front().unwrap()
+            }
+        };
+        self.remaining -= 1;
+        Ok(triple)
+    }
+}
+
+/// An MTProvider which generates the triples of another provider in a background thread, so
+/// that generating the next triples overlaps with evaluating the circuit. The generated triples
+/// wait in a bounded queue. Once it is full, the background thread blocks until the evaluation
+/// takes triples out of it, so at most `capacity` triples are held in memory.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct BackgroundMTP {
+    queue: Receiver<MulTriple>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl BackgroundMTP {
+    /// Starts generating `count` triples of `inner` in a background thread. The thread stops
+    /// after `count` triples, or early once this provider is dropped.
+    pub fn spawn<T: MTProvider + Send + 'static>(
+        mut inner: T,
+        count: usize,
+        capacity: usize,
+    ) -> Self {
+        let (sender, queue) = sync_channel(capacity);
+        thread::spawn(move || {
+            for _ in 0..count {
+                // fails once the provider was dropped, nobody needs the triples anymore
+                if sender.send(inner.get_triple()).is_err() {
+                    break;
+                }
+            }
+        });
+        BackgroundMTP { queue }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MTProvider for BackgroundMTP {
+    /// Blocks until the next triple is generated. Panics if more than `count` triples are taken.
+    fn get_triple(&mut self) -> MulTriple {
+        self.try_get_triple().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_get_triple(&mut self) -> io::Result<MulTriple> {
+        self.queue
+            .recv()
+            .map_err(|_| used_up("triples of the background provider"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BackgroundMTP, FileMTP, InteractiveMTP, MTProvider, MulTriple, RecordingMTP, SeededMTP,
+        CHUNK_SIZE,
+    };
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::party::errors::PartyError;
+    use crate::party::party_gmw::Party;
+    use crate::party::transport::channel_pair;
+    use rand::rngs::StdRng;
+    use rand::thread_rng;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::{channel, Sender};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    /// A provider which takes its time for every triple and counts the generated triples.
+    struct SlowMTP {
+        inner: SeededMTP<StdRng>,
+        delay: Duration,
+        generated: Arc<AtomicUsize>,
+    }
+
+    impl SlowMTP {
+        fn new(delay: Duration) -> (Self, Arc<AtomicUsize>) {
+            let generated = Arc::new(AtomicUsize::new(0));
+            let mtp = SlowMTP {
+                inner: SeededMTP::new([4; 32]),
+                delay,
+                generated: Arc::clone(&generated),
+            };
+            (mtp, generated)
+        }
+    }
+
+    impl MTProvider for SlowMTP {
+        fn get_triple(&mut self) -> MulTriple {
+            thread::sleep(self.delay);
+            self.generated.fetch_add(1, Ordering::SeqCst);
+            self.inner.get_triple()
+        }
+    }
+
+    /// A provider which sends a notification for every triple it generated.
+    struct NotifyingMTP {
+        inner: SeededMTP<StdRng>,
+        generated: Sender<()>,
+    }
+
+    impl MTProvider for NotifyingMTP {
+        fn get_triple(&mut self) -> MulTriple {
+            let triple = self.inner.get_triple();
+            // the test may be over already
+            let _ = self.generated.send(());
+            triple
+        }
+    }
+
+    #[test]
+    fn test_recording_does_not_alter_triples() {
+        let mut plain: SeededMTP<StdRng> = SeededMTP::new([7; 32]);
+        let mut recording = RecordingMTP::new(SeededMTP::<StdRng>::new([7; 32]));
+
+        let expected: Vec<_> = (0..16).map(|_| plain.get_triple()).collect();
+        let returned: Vec<_> = (0..16).map(|_| recording.get_triple()).collect();
+
+        assert_eq!(returned, expected);
+        assert_eq!(recording.recorded(), &expected[..]);
+    }
+
+    #[test]
+    fn test_bulk_triples_independent_of_threads() {
+        let generate = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let mut mtp: SeededMTP<StdRng> = SeededMTP::new([7; 32]);
+            // an uneven count, so that the last chunk is only partially filled
+            pool.install(|| mtp.get_triples(5 * CHUNK_SIZE + 17))
+        };
+
+        let single = generate(1);
+        assert_eq!(single.len(), 5 * CHUNK_SIZE + 17);
+        assert_eq!(generate(8), single);
+        // the chunks use different RNG streams
+        assert_ne!(single[..64], single[CHUNK_SIZE..CHUNK_SIZE + 64]);
+    }
+
+    #[test]
+    fn test_interactive_triples() {
+        let (t0, t1) = channel_pair();
+        let h0 = thread::spawn(move || InteractiveMTP::generate(&t0, 1000, &mut thread_rng()));
+        let h1 = thread::spawn(move || InteractiveMTP::generate(&t1, 1000, &mut thread_rng()));
+        let mut mtp0 = h0.join().unwrap().unwrap();
+        let mut mtp1 = h1.join().unwrap().unwrap();
+
+        let mut products = [0; 2];
+        for _ in 0..1000 {
+            let (t0, t1) = (mtp0.get_triple(), mtp1.get_triple());
+            let (a, b, c) = (t0.a ^ t1.a, t0.b ^ t1.b, t0.c ^ t1.c);
+            assert_eq!(c, a & b);
+            products[usize::from(c)] += 1;
+        }
+        // the triples are random, not all zero like those of two SeededMTPs with the same seed
+        assert!(products[1] > 0);
+        assert_eq!(mtp0.remaining(), 0);
+    }
+
+    #[test]
+    fn test_interactive_triples_in_protocol() {
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+
+        for (x, y) in [(false, true), (true, true)] {
+            let (t0, t1) = channel_pair();
+            let c0 = c.clone();
+            let c1 = c.clone();
+            let h0 = thread::spawn(move || {
+                let mtp = InteractiveMTP::generate(&t0, c0.required_triples(), &mut thread_rng());
+                Party::new(c0, t0, false, mtp.unwrap())
+                    .unwrap()
+                    .execute(&[x])
+            });
+            let h1 = thread::spawn(move || {
+                let mtp = InteractiveMTP::generate(&t1, c1.required_triples(), &mut thread_rng());
+                Party::new(c1, t1, true, mtp.unwrap())
+                    .unwrap()
+                    .execute(&[y])
+            });
+            assert_eq!(h0.join().unwrap().unwrap(), vec![x & y]);
+            assert_eq!(h1.join().unwrap().unwrap(), vec![x & y]);
+        }
+    }
+
+    #[test]
+    fn test_background_triples_in_protocol() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let count = c.required_triples();
+
+        let (slow0, generated0) = SlowMTP::new(Duration::from_micros(100));
+        let (slow1, generated1) = SlowMTP::new(Duration::from_micros(100));
+        let (t0, t1) = channel_pair();
+        let mut p0 =
+            Party::new(c.clone(), t0, false, BackgroundMTP::spawn(slow0, count, 8)).unwrap();
+        let mut p1 = Party::new(c, t1, true, BackgroundMTP::spawn(slow1, count, 8)).unwrap();
+
+        let (x, y) = (to_bits(1234, 64).unwrap(), to_bits(4321, 64).unwrap());
+        let h0 = thread::spawn(move || p0.execute(&x).unwrap());
+        let h1 = thread::spawn(move || p1.execute(&y).unwrap());
+        assert_eq!(h0.join().unwrap(), to_bits(1234 + 4321, 64).unwrap());
+        assert_eq!(h1.join().unwrap(), to_bits(1234 + 4321, 64).unwrap());
+        assert_eq!(generated0.load(Ordering::SeqCst), count);
+        assert_eq!(generated1.load(Ordering::SeqCst), count);
+    }
+
+    #[test]
+    fn test_background_queue_is_bounded() {
+        let (generated, notifications) = channel();
+        let inner = SeededMTP::new([4; 32]);
+        let mut mtp = BackgroundMTP::spawn(NotifyingMTP { inner, generated }, 1000, 4);
+        // the queue is full, and one more triple waits to be put into it
+        for _ in 0..5 {
+            notifications.recv().unwrap();
+        }
+        let idle = Duration::from_millis(50);
+        assert!(notifications.recv_timeout(idle).is_err());
+
+        // taking a triple makes room for exactly one more
+        mtp.get_triple();
+        notifications.recv().unwrap();
+        assert!(notifications.recv_timeout(idle).is_err());
+    }
+
+    #[test]
+    fn test_file_triples() {
+        let dir = std::env::temp_dir();
+        let path0 = dir.join(format!("mpc_packed_p0_{}.mtp", std::process::id()));
+        let path1 = dir.join(format!("mpc_packed_p1_{}.mtp", std::process::id()));
+        FileMTP::generate(&path0, &path1, 1000, &mut thread_rng()).unwrap();
+        // 16 header bytes and 3 bytes for every 8 triples
+        assert_eq!(std::fs::metadata(&path0).unwrap().len(), 16 + 125 * 3);
+
+        let mut mtp0 = FileMTP::open(&path0).unwrap();
+        let mut mtp1 = FileMTP::open(&path1).unwrap();
+        assert_eq!(mtp0.remaining(), 1000);
+        let mut products = [0; 2];
+        for _ in 0..1000 {
+            let (t0, t1) = (mtp0.get_triple(), mtp1.get_triple());
+            let (a, b, c) = (t0.a ^ t1.a, t0.b ^ t1.b, t0.c ^ t1.c);
+            assert_eq!(c, a & b);
+            products[usize::from(c)] += 1;
+        }
+        assert!(products[1] > 0);
+        assert_eq!(mtp1.remaining(), 0);
+
+        std::fs::remove_file(path0).unwrap();
+        std::fs::remove_file(path1).unwrap();
+    }
+
+    #[test]
+    fn test_file_triples_in_protocol() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let dir = std::env::temp_dir();
+        let path0 = dir.join(format!("mpc_adder_p0_{}.mtp", std::process::id()));
+        let path1 = dir.join(format!("mpc_adder_p1_{}.mtp", std::process::id()));
+
+        let run = |count: usize| {
+            FileMTP::generate(&path0, &path1, count, &mut thread_rng()).unwrap();
+            let (t0, t1) = channel_pair();
+            let mut p0 = Party::new(c.clone(), t0, false, FileMTP::open(&path0).unwrap()).unwrap();
+            let mut p1 = Party::new(c.clone(), t1, true, FileMTP::open(&path1).unwrap()).unwrap();
+            let (x, y) = (to_bits(1234, 64).unwrap(), to_bits(4321, 64).unwrap());
+            let h0 = thread::spawn(move || p0.execute_structured_catching(&x));
+            let h1 = thread::spawn(move || p1.execute_structured_catching(&y));
+            (h0.join().unwrap(), h1.join().unwrap())
+        };
+
+        let (out0, out1) = run(c.required_triples());
+        assert_eq!(out0.unwrap(), vec![to_bits(1234 + 4321, 64).unwrap()]);
+        assert_eq!(out1.unwrap(), vec![to_bits(1234 + 4321, 64).unwrap()]);
+
+        let (out0, _) = run(c.required_triples() - 1);
+        match out0 {
+            Err(PartyError::TripleUnavailable(e)) => {
+                assert_eq!(e.to_string(), "all triples of the file are used up")
+            }
+            other => panic!("expected the triples to run out, got {:?}", other),
+        }
+
+        // a batched run takes all triples up front, and fails the same way
+        let count = c.required_triples() - 1;
+        FileMTP::generate(&path0, &path1, count, &mut thread_rng()).unwrap();
+        let (t0, t1) = channel_pair();
+        let mut p0 = Party::new(c.clone(), t0, false, FileMTP::open(&path0).unwrap()).unwrap();
+        let mut p1 = Party::new(c.clone(), t1, true, FileMTP::open(&path1).unwrap()).unwrap();
+        let h1 = thread::spawn(move || p1.execute_batched(&to_bits(4321, 64).unwrap()));
+        for result in [
+            p0.execute_batched(&to_bits(1234, 64).unwrap()),
+            h1.join().unwrap(),
+        ] {
+            assert!(matches!(result, Err(PartyError::TripleUnavailable(_))));
+        }
+
+        std::fs::remove_file(path0).unwrap();
+        std::fs::remove_file(path1).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_triple_file() {
+        let dir = std::env::temp_dir();
+        let path0 = dir.join(format!("mpc_truncated_p0_{}.mtp", std::process::id()));
+        let path1 = dir.join(format!("mpc_truncated_p1_{}.mtp", std::process::id()));
+        FileMTP::generate(&path0, &path1, 20, &mut thread_rng()).unwrap();
+        let bytes = std::fs::read(&path0).unwrap();
+        std::fs::write(&path0, &bytes[..bytes.len() - 1]).unwrap();
+
+        let e = FileMTP::open(&path0).err().unwrap();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        // an empty packed file is fine
+        FileMTP::generate(&path0, &path1, 0, &mut thread_rng()).unwrap();
+        assert_eq!(FileMTP::open(&path1).unwrap().remaining(), 0);
 
-        MulTriple { a, b, c }
+        std::fs::remove_file(path0).unwrap();
+        std::fs::remove_file(path1).unwrap();
     }
 }
@@ -0,0 +1,358 @@
+use crate::circuit::circuit_layout::PartyId;
+use crate::circuit::circuit_parser::Circuit;
+use crate::mul_triple::{MTProvider, SeededMTP};
+use crate::party::coin_toss;
+use crate::party::errors::PartyError;
+use crate::party::observer::ExecutionObserver;
+use crate::party::party_gmw::{OutputMode, Party};
+use crate::party::transport::{ChannelTransport, Transport};
+use rand::rngs::StdRng;
+use rand::RngCore;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Creates the triple provider of a party together with the other party over the channel, see
+/// [`PartyBuilder::agreed_seed`].
+type AgreeOnMTP<T, C> = fn(&C, bool) -> Result<T, PartyError>;
+
+/// Configures a [`Party`] setting by setting, instead of passing everything to [`Party::new`].
+/// The circuit, the channel, the role and the triple provider, or an agreed seed to derive the
+/// triples from, have to be set, everything else defaults to what [`Party::new`] does.
+///
+/// ```
+/// # use mpc_in_rust::circuit::circuit_layout::PartyId;
+/// # use mpc_in_rust::circuit::circuit_parser::Circuit;
+/// # use mpc_in_rust::mul_triple::SeededMTP;
+/// # use mpc_in_rust::party::builder::PartyBuilder;
+/// # use mpc_in_rust::party::transport::channel_pair;
+/// # use rand::rngs::StdRng;
+/// let circuit = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+/// let (channel, _) = channel_pair();
+/// let party = PartyBuilder::new()
+///     .circuit(circuit)
+///     .channel(channel)
+///     .role(PartyId::P0)
+///     .mt_provider(SeededMTP::<StdRng>::new([0; 32]))
+///     .build()
+///     .unwrap();
+/// ```
+pub struct PartyBuilder<T: MTProvider, C: Transport = ChannelTransport> {
+    circuit: Option<Arc<Circuit>>,
+    channel: Option<C>,
+    role: Option<PartyId>,
+    mtp: Option<T>,
+    agreed_mtp: Option<AgreeOnMTP<T, C>>,
+    timeout: Option<Duration>,
+    output_mode: OutputMode,
+    share_seed: Option<[u8; 32]>,
+    observer: Option<Box<dyn ExecutionObserver>>,
+    profiling: bool,
+}
+
+impl<T: MTProvider, C: Transport> Default for PartyBuilder<T, C> {
+    fn default() -> Self {
+        PartyBuilder {
+            circuit: None,
+            channel: None,
+            role: None,
+            mtp: None,
+            agreed_mtp: None,
+            timeout: None,
+            output_mode: OutputMode::default(),
+            share_seed: None,
+            observer: None,
+            profiling: false,
+        }
+    }
+}
+
+impl<T: MTProvider, C: Transport> PartyBuilder<T, C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The circuit the party evaluates.
+    pub fn circuit(mut self, circuit: impl Into<Arc<Circuit>>) -> Self {
+        self.circuit = Some(circuit.into());
+        self
+    }
+
+    /// The connection to the other party.
+    pub fn channel(mut self, channel: C) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Which of the two parties this one plays.
+    pub fn role(mut self, role: PartyId) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// The source of the multiplication triples the party consumes for its AND gates.
+    pub fn mt_provider(mut self, mtp: T) -> Self {
+        self.mtp = Some(mtp);
+        self
+    }
+
+    /// Lets sending and receiving fail if they block for longer than `timeout`. Without one, the
+    /// party waits forever for a stalled party.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// What the executions of the party do with the output, see [`Party::set_output_mode`].
+    pub fn output_mode(mut self, mode: OutputMode) -> Self {
+        self.output_mode = mode;
+        self
+    }
+
+    /// Takes the random masks of the input shares from `rng` instead of the thread local RNG.
+    pub fn rng(mut self, mut rng: impl RngCore) -> Self {
+        let mut seed = [0; 32];
+        rng.fill_bytes(&mut seed);
+        self.share_seed = Some(seed);
+        self
+    }
+
+    /// Lets `observer` watch the party evaluate the circuit, see [`Party::set_observer`].
+    pub fn observer(mut self, observer: impl ExecutionObserver + 'static) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Measures where the time of the executions goes, see [`Party::set_profiling`].
+    pub fn profiling(mut self, profiling: bool) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
+    /// Creates the party. Fails with [`PartyError::MissingSetting`] if a required setting is
+    /// missing, with [`PartyError::TimeoutUnsupported`] if a timeout is set for a channel which
+    /// cannot time out, and like [`Party::new`] if the circuit is not valid.
+    pub fn build(self) -> Result<Party<T, C>, PartyError> {
+        let circuit = self.circuit.ok_or(PartyError::MissingSetting("circuit"))?;
+        let mut channel = self.channel.ok_or(PartyError::MissingSetting("channel"))?;
+        let role = self.role.ok_or(PartyError::MissingSetting("role"))?;
+        if self.mtp.is_none() && self.agreed_mtp.is_none() {
+            return Err(PartyError::MissingSetting("triple provider"));
+        }
+        if let Some(timeout) = self.timeout {
+            channel.set_timeout(timeout)?;
+        }
+
+        let is_p1 = role == PartyId::P1;
+        let (mtp, agreed) = match (self.mtp, self.agreed_mtp) {
+            (Some(mtp), _) => (mtp, false),
+            (None, Some(agree)) => {
+                // an invalid circuit is rejected before talking to the other party at all
+                circuit.ensure_valid().map_err(PartyError::InvalidCircuit)?;
+                (agree(&channel, is_p1)?, true)
+            }
+            (None, None) => unreachable!("the triple provider was checked above"),
+        };
+        let mut party = match self.share_seed {
+            Some(seed) => Party::with_share_seed(circuit, channel, is_p1, mtp, seed)?,
+            None => Party::new(circuit, channel, is_p1, mtp)?,
+        };
+        party.set_output_mode(self.output_mode);
+        if let Some(observer) = self.observer {
+            party.set_observer(observer);
+        }
+        party.set_profiling(self.profiling);
+        if agreed {
+            // the versions were exchanged before agreeing on the seed
+            party.set_versions_checked();
+        }
+        Ok(party)
+    }
+}
+
+impl<C: Transport> PartyBuilder<SeededMTP<StdRng>, C> {
+    /// Derives the triples from a seed agreed on with the other party by
+    /// [`crate::party::coin_toss::agree_on_seed`], unless a triple provider is set.
+    /// [`PartyBuilder::build`] then talks to the other party, which has to be built the same way
+    /// at the same time.
+    pub fn agreed_seed(mut self) -> Self {
+        self.agreed_mtp = Some(coin_toss::agreed_mtp);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PartyBuilder;
+    use crate::circuit::circuit_layout::PartyId;
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::mul_triple::SeededMTP;
+    use crate::party::errors::{PartyError, Phase};
+    use crate::party::party_gmw::{OutputMode, Party};
+    use crate::party::transport::{channel_pair, ChannelTransport, Envelope, Transport};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::thread;
+    use std::time::Duration;
+
+    type Builder = PartyBuilder<SeededMTP<StdRng>, ChannelTransport>;
+
+    fn adder() -> Circuit {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        Circuit::parse(&contents).unwrap()
+    }
+
+    fn message(result: Result<Party<SeededMTP<StdRng>>, PartyError>) -> String {
+        match result {
+            Ok(_) => panic!("the party was built"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_missing_settings() {
+        let (t0, _t1) = channel_pair();
+        let complete = || {
+            Builder::new()
+                .circuit(adder())
+                .role(PartyId::P0)
+                .mt_provider(SeededMTP::new([0; 32]))
+        };
+        assert_eq!(
+            message(complete().build()),
+            "The party cannot be built without a channel"
+        );
+        assert!(matches!(
+            Builder::new().channel(t0).build(),
+            Err(PartyError::MissingSetting("circuit"))
+        ));
+        let (t0, _t1) = channel_pair();
+        assert!(matches!(
+            complete().channel(t0).role(PartyId::P1).build(),
+            Ok(Party { is_p1: true, .. })
+        ));
+        let (t0, _t1) = channel_pair();
+        assert!(matches!(
+            Builder::new().circuit(adder()).channel(t0).build(),
+            Err(PartyError::MissingSetting("role"))
+        ));
+    }
+
+    #[test]
+    fn test_defaults_match_new() {
+        // the same seeds give the same messages, so a built pair has to run just like a new one
+        let run = |built: bool| {
+            let (t0, t1) = channel_pair();
+            let (mut p0, mut p1) = if built {
+                let party = |channel, role| {
+                    Builder::new()
+                        .circuit(adder())
+                        .channel(channel)
+                        .role(role)
+                        .mt_provider(SeededMTP::new([1; 32]))
+                        .rng(StdRng::seed_from_u64(2))
+                        .build()
+                        .unwrap()
+                };
+                (party(t0, PartyId::P0), party(t1, PartyId::P1))
+            } else {
+                let mut seed = [0; 32];
+                rand::RngCore::fill_bytes(&mut StdRng::seed_from_u64(2), &mut seed);
+                let party = |channel, is_p1| {
+                    Party::with_share_seed(adder(), channel, is_p1, SeededMTP::new([1; 32]), seed)
+                        .unwrap()
+                };
+                (party(t0, false), party(t1, true))
+            };
+            let h1 = thread::spawn(move || p1.execute(&to_bits(4, 64).unwrap()).unwrap());
+            let output = p0.execute(&to_bits(3, 64).unwrap()).unwrap();
+            assert_eq!(output, h1.join().unwrap());
+            (output, p0.rounds())
+        };
+        assert_eq!(run(true), run(false));
+        assert_eq!(run(true).0, to_bits(7, 64).unwrap());
+    }
+
+    #[test]
+    fn test_timeout_and_output_mode() {
+        let (t0, t1) = channel_pair();
+        let mut p0 = Builder::new()
+            .circuit(adder())
+            .channel(t0)
+            .role(PartyId::P0)
+            .mt_provider(SeededMTP::new([0; 32]))
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        // the other party never answers
+        let result = p0.execute(&to_bits(1, 64).unwrap());
+        assert!(matches!(
+            result,
+            Err(PartyError::Timeout {
+                phase: Phase::Handshake
+            })
+        ));
+        drop(t1);
+
+        let (t0, t1) = channel_pair();
+        let party = |channel, role| {
+            Builder::new()
+                .circuit(adder())
+                .channel(channel)
+                .role(role)
+                .mt_provider(SeededMTP::new([0; 32]))
+                .output_mode(OutputMode::RevealTo(PartyId::P1))
+                .build()
+                .unwrap()
+        };
+        let mut p0 = party(t0, PartyId::P0);
+        let mut p1 = party(t1, PartyId::P1);
+        let h1 = thread::spawn(move || p1.execute(&to_bits(5, 64).unwrap()).unwrap());
+        assert!(p0.execute(&to_bits(6, 64).unwrap()).unwrap().is_empty());
+        assert_eq!(h1.join().unwrap(), to_bits(11, 64).unwrap());
+
+        // a transport which cannot time out is rejected
+        struct NoTimeout;
+        impl Transport for NoTimeout {
+            fn send(&self, _: Envelope) -> Result<(), PartyError> {
+                Ok(())
+            }
+            fn recv(&self) -> Result<Envelope, PartyError> {
+                Err(PartyError::TransportClosed)
+            }
+        }
+        let result = PartyBuilder::new()
+            .circuit(adder())
+            .channel(NoTimeout)
+            .role(PartyId::P0)
+            .mt_provider(SeededMTP::<StdRng>::new([0; 32]))
+            .timeout(Duration::from_secs(1))
+            .build();
+        assert!(matches!(result, Err(PartyError::TimeoutUnsupported)));
+    }
+
+    #[test]
+    fn test_agreed_seed() {
+        let (t0, t1) = channel_pair();
+        let party = |channel, role| {
+            Builder::new()
+                .circuit(adder())
+                .channel(channel)
+                .role(role)
+                .agreed_seed()
+                .build()
+                .unwrap()
+        };
+        let h1 = thread::spawn(move || {
+            party(t1, PartyId::P1)
+                .execute(&to_bits(5, 64).unwrap())
+                .unwrap()
+        });
+        let mut p0 = party(t0, PartyId::P0);
+        assert_eq!(
+            p0.execute(&to_bits(6, 64).unwrap()).unwrap(),
+            to_bits(11, 64).unwrap()
+        );
+        assert_eq!(h1.join().unwrap(), to_bits(11, 64).unwrap());
+    }
+}
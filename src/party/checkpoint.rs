@@ -0,0 +1,36 @@
+use crate::party::party_gmw::InvConvention;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A snapshot of a party's evaluation state, taken between two gates. Both parties can be rebuilt
+/// from their snapshots via `Party::resume` and continue the evaluation, as long as they took the
+/// snapshot at the same gate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionSnapshot {
+    pub is_p1: bool,
+    /// Index of the next gate to evaluate.
+    pub gate_index: usize,
+    /// This party's shares of all wires. Wires which have not been evaluated yet are `None`.
+    pub wires: Vec<Option<bool>>,
+    /// Number of multiplication triples drawn from the provider so far.
+    pub triples_consumed: usize,
+    /// Number of messages sent to the other party so far.
+    pub sequence: usize,
+    /// Missing in snapshots taken before the convention was configurable.
+    #[serde(default)]
+    pub inv_convention: InvConvention,
+}
+
+impl ExecutionSnapshot {
+    /// Writes the snapshot as JSON to the given path.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec(self)?)
+    }
+
+    /// Reads a snapshot previously written by [`ExecutionSnapshot::save`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
@@ -0,0 +1,174 @@
+//! Agreeing on a common random seed by coin tossing, so that parties in different processes can
+//! derive matching multiplication triples from a [`SeededMTP`] without a trusted dealer handing
+//! them the seed.
+
+use crate::circuit::circuit_layout::PartyId;
+use crate::circuit::circuit_parser::Circuit;
+use crate::mul_triple::SeededMTP;
+use crate::party::builder::PartyBuilder;
+use crate::party::errors::{PartyError, Phase};
+use crate::party::handshake::{self, PROTOCOL_VERSION};
+use crate::party::party_gmw::{in_phase, Messages, Party};
+use crate::party::transport::{Envelope, Transport};
+use rand::rngs::StdRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Agrees on a random seed with the other party, which has to call this at the same time.
+///
+/// Each party samples a random contribution and sends a hash of it as a commitment. Only after
+/// receiving the other party's commitment, it opens its own by sending the contribution. Neither
+/// party can choose its contribution depending on the other one, so the seed
+/// `H(contribution of party 0 || contribution of party 1)` is random as long as one of them is
+/// honest. Fails with [`PartyError::InvalidSeedOpening`] if the opened contribution of the other
+/// party does not match its commitment.
+pub fn agree_on_seed<C: Transport>(
+    transport: &C,
+    is_p1: bool,
+    rng: &mut impl RngCore,
+) -> Result<[u8; 32], PartyError> {
+    let phase = Phase::Preprocessing;
+    let closed = |e| in_phase(e, phase);
+
+    let mut contribution = [0; 32];
+    rng.fill_bytes(&mut contribution);
+
+    let commitment = Messages::SeedCommitment {
+        hash: Sha256::digest(contribution).into(),
+    };
+    transport
+        .send(Envelope::new(0, commitment))
+        .map_err(closed)?;
+    let Messages::SeedCommitment { hash } = transport.recv().map_err(closed)?.open(0, phase)?
+    else {
+        return Err(PartyError::UnexpectedMessage { phase });
+    };
+
+    transport
+        .send(Envelope::new(1, Messages::SeedOpening { contribution }))
+        .map_err(closed)?;
+    let Messages::SeedOpening {
+        contribution: others_contribution,
+    } = transport.recv().map_err(closed)?.open(1, phase)?
+    else {
+        return Err(PartyError::UnexpectedMessage { phase });
+    };
+    if <[u8; 32]>::from(Sha256::digest(others_contribution)) != hash {
+        return Err(PartyError::InvalidSeedOpening);
+    }
+
+    let (first, second) = if is_p1 {
+        (others_contribution, contribution)
+    } else {
+        (contribution, others_contribution)
+    };
+    Ok(Sha256::new()
+        .chain_update(first)
+        .chain_update(second)
+        .finalize()
+        .into())
+}
+
+impl<C: Transport> Party<SeededMTP<StdRng>, C> {
+    /// Creates a new party like [`Party::new`], whose triples are derived from a seed agreed on
+    /// with the other party by [`agree_on_seed`]. The other party has to be created the same way
+    /// at the same time.
+    pub fn with_agreed_seed(
+        circuit: impl Into<Arc<Circuit>>,
+        transport: C,
+        is_p1: bool,
+    ) -> Result<Self, PartyError> {
+        PartyBuilder::new()
+            .circuit(circuit)
+            .channel(transport)
+            .role(if is_p1 { PartyId::P1 } else { PartyId::P0 })
+            .agreed_seed()
+            .build()
+    }
+}
+
+/// Checks the protocol versions of both parties and creates a triple provider from a seed agreed
+/// on with the other party, see [`crate::party::builder::PartyBuilder::agreed_seed`].
+pub(crate) fn agreed_mtp<C: Transport>(
+    transport: &C,
+    is_p1: bool,
+) -> Result<SeededMTP<StdRng>, PartyError> {
+    // the versions are checked first, a party of another version may toss coins differently
+    handshake::exchange_versions(transport, PROTOCOL_VERSION)?;
+    let seed = agree_on_seed(transport, is_p1, &mut rand::thread_rng())?;
+    Ok(SeededMTP::new(seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::agree_on_seed;
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::party::errors::PartyError;
+    use crate::party::party_gmw::{Messages, Party};
+    use crate::party::transport::{channel_pair, Envelope, Transport};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use sha2::{Digest, Sha256};
+    use std::thread;
+
+    #[test]
+    fn test_both_parties_agree() {
+        let (t0, t1) = channel_pair();
+        let h1 =
+            thread::spawn(move || agree_on_seed(&t1, true, &mut StdRng::seed_from_u64(1)).unwrap());
+        let seed0 = agree_on_seed(&t0, false, &mut StdRng::seed_from_u64(2)).unwrap();
+        assert_eq!(seed0, h1.join().unwrap());
+
+        // a different contribution gives a different seed
+        let (t0, t1) = channel_pair();
+        let h1 =
+            thread::spawn(move || agree_on_seed(&t1, true, &mut StdRng::seed_from_u64(3)).unwrap());
+        assert_ne!(
+            agree_on_seed(&t0, false, &mut StdRng::seed_from_u64(2)).unwrap(),
+            seed0
+        );
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_tampered_opening_is_rejected() {
+        let (t0, t1) = channel_pair();
+        // the other party opens a different contribution than it committed to
+        let h1 = thread::spawn(move || {
+            let commitment = Messages::SeedCommitment {
+                hash: Sha256::digest([1; 32]).into(),
+            };
+            t1.send(Envelope::new(0, commitment)).unwrap();
+            t1.recv().unwrap();
+            let opening = Messages::SeedOpening {
+                contribution: [2; 32],
+            };
+            t1.send(Envelope::new(1, opening)).unwrap();
+            t1.recv().unwrap();
+        });
+        let result = agree_on_seed(&t0, false, &mut StdRng::seed_from_u64(1));
+        assert!(matches!(result, Err(PartyError::InvalidSeedOpening)));
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_parties_with_agreed_seed() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        let (t0, t1) = channel_pair();
+        let c1 = c.clone();
+        let h1 = thread::spawn(move || {
+            let mut p1 = Party::with_agreed_seed(c1, t1, true).unwrap();
+            p1.execute(&to_bits(20, 64).unwrap()).unwrap()
+        });
+        let mut p0 = Party::with_agreed_seed(c, t0, false).unwrap();
+        assert_eq!(
+            p0.execute(&to_bits(22, 64).unwrap()).unwrap(),
+            to_bits(42, 64).unwrap()
+        );
+        assert_eq!(h1.join().unwrap(), to_bits(42, 64).unwrap());
+    }
+}
@@ -0,0 +1,121 @@
+//! A transport which simulates the latency of a network between two parties, to measure how the
+//! number of communication rounds affects the running time.
+
+use crate::party::errors::PartyError;
+use crate::party::transport::{Envelope, Transport};
+use std::thread;
+use std::time::Duration;
+
+/// Wraps the transport of a party and holds back every message for a fixed delay before passing
+/// it on. In GMW, both parties send their message of a round at the same time and then wait for
+/// the other's, so every communication round takes at least the delay, just like a round trip
+/// over a network with that latency.
+pub struct DelayedTransport<C: Transport> {
+    inner: C,
+    delay: Duration,
+}
+
+impl<C: Transport> DelayedTransport<C> {
+    pub fn new(inner: C, delay: Duration) -> Self {
+        DelayedTransport { inner, delay }
+    }
+
+    /// Wraps `inner` with a delay of `delay_ms` milliseconds per message.
+    pub fn from_millis(inner: C, delay_ms: u64) -> Self {
+        Self::new(inner, Duration::from_millis(delay_ms))
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+}
+
+impl<C: Transport> Transport for DelayedTransport<C> {
+    fn send(&self, m: Envelope) -> Result<(), PartyError> {
+        thread::sleep(self.delay);
+        self.inner.send(m)
+    }
+
+    fn recv(&self) -> Result<Envelope, PartyError> {
+        self.inner.recv()
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), PartyError> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn serialization_time(&self) -> Duration {
+        self.inner.serialization_time()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelayedTransport;
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::mul_triple::SeededMTP;
+    use crate::party::party_gmw::Party;
+    use crate::party::transport::channel_pair;
+    use rand::rngs::StdRng;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const DELAY_MS: u64 = 20;
+
+    /// Two chains of `depth` AND gates each, one over each input, whose ends are XORed. The
+    /// circuit has AND depth `depth`, but twice as many AND gates.
+    fn two_chains(depth: usize) -> Circuit {
+        let mut gates = Vec::new();
+        let (mut a, mut b) = (0, 1);
+        for k in 0..depth {
+            let (next_a, next_b) = (2 + 2 * k, 3 + 2 * k);
+            gates.push(format!("2 1 {} 1 {} AND", a, next_a));
+            gates.push(format!("2 1 {} 0 {} AND", b, next_b));
+            (a, b) = (next_a, next_b);
+        }
+        gates.push(format!("2 1 {} {} {} XOR", a, b, 2 + 2 * depth));
+        let header = format!("{} {}\n2 1 1\n1 1\n", gates.len(), 3 + 2 * depth);
+        Circuit::parse(&format!("{}\n{}\n", header, gates.join("\n"))).unwrap()
+    }
+
+    /// Runs the circuit with delayed transports and returns the output, the rounds and the time
+    /// party 0 took.
+    fn run_delayed(c: &Circuit, batched: bool) -> (Vec<bool>, usize, Duration) {
+        let (t0, t1) = channel_pair();
+        let party = |channel, is_p1| {
+            let channel = DelayedTransport::from_millis(channel, DELAY_MS);
+            Party::new(c.clone(), channel, is_p1, SeededMTP::<StdRng>::new([0; 32])).unwrap()
+        };
+        let (mut p0, mut p1) = (party(t0, false), party(t1, true));
+        let run = move |p: &mut Party<_, _>, input: &[bool]| match batched {
+            false => p.execute(input).unwrap(),
+            true => p.execute_batched(input).unwrap(),
+        };
+        let h1 = thread::spawn(move || run(&mut p1, &[true]));
+        let start = Instant::now();
+        let output = run(&mut p0, &[true]);
+        let elapsed = start.elapsed();
+        assert_eq!(output, h1.join().unwrap());
+        (output, p0.rounds(), elapsed)
+    }
+
+    #[test]
+    fn test_depth_costs_delay() {
+        let depth = 5;
+        let c = two_chains(depth);
+        let delay = Duration::from_millis(DELAY_MS);
+
+        let (output, rounds, elapsed) = run_delayed(&c, false);
+        // the two rounds of the handshake, the input sharing, every AND gate and the output
+        // exchange
+        assert_eq!(rounds, 2 * depth + 4);
+        assert!(elapsed >= delay * rounds as u32, "took {:?}", elapsed);
+
+        // batched, the AND gates of both chains share their rounds
+        let (batched_output, batched_rounds, batched_elapsed) = run_delayed(&c, true);
+        assert_eq!(batched_output, output);
+        assert_eq!(batched_rounds, depth + 4);
+        assert!(batched_elapsed >= delay * depth as u32);
+        assert!(batched_elapsed < elapsed, "took {:?}", batched_elapsed);
+    }
+}
@@ -1,17 +1,165 @@
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_parser::GateType;
+use crate::party::party_gmw::OutputMode;
+use std::any::Any;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::io;
 use std::sync::mpsc::{RecvError, SendError};
 
+/// The input of a gate which an error refers to. INV gates only have a left input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Left,
+    Right,
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Left => write!(f, "left"),
+            Operand::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// The step of the protocol a party was in when an error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Checking that both parties speak the same protocol version.
+    Handshake,
+    /// Generating multiplication triples together with the other party.
+    Preprocessing,
+    InputSharing,
+    /// The communication round of an AND gate, or of a layer of AND gates when evaluating
+    /// batched. `gate_index` is the (first) gate evaluated in the round.
+    And {
+        round: usize,
+        gate_index: usize,
+    },
+    /// The communication round of a MUL gate of an arithmetic circuit.
+    Mul {
+        round: usize,
+        gate_index: usize,
+    },
+    OutputExchange,
+    Resume,
+    /// Saying goodbye to the other party at the end of a connection.
+    Shutdown,
+    /// Evaluating a gate which needs no communication.
+    LocalGate {
+        gate_index: usize,
+    },
+    /// Switching to the circuit of the given run of a session.
+    NextCircuit {
+        run: usize,
+    },
+    /// Opening the consumed multiplication triples, see `Party::set_check_triples`.
+    TripleCheck,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Handshake => write!(f, "the handshake"),
+            Phase::Preprocessing => write!(f, "preprocessing"),
+            Phase::InputSharing => write!(f, "input sharing"),
+            Phase::And { round, gate_index } => {
+                write!(f, "AND round {} (gate {})", round, gate_index)
+            }
+            Phase::Mul { round, gate_index } => {
+                write!(f, "MUL round {} (gate {})", round, gate_index)
+            }
+            Phase::OutputExchange => write!(f, "output exchange"),
+            Phase::Resume => write!(f, "resuming"),
+            Phase::Shutdown => write!(f, "the shutdown"),
+            Phase::LocalGate { gate_index } => write!(f, "evaluating gate {}", gate_index),
+            Phase::NextCircuit { run } => write!(f, "switching to the circuit of run {}", run),
+            Phase::TripleCheck => write!(f, "the triple check"),
+        }
+    }
+}
+
 #[derive(Debug)]
-pub enum PartyError<'a> {
+pub enum PartyError {
     ThreadTransmissionError,
     ThreadSendingError,
     ThreadReceivingError,
-    WireNotSetError(usize),
-    PError(Box<dyn Error + 'a>),
+    WireNotSet {
+        wire: usize,
+        gate_index: usize,
+        gate: GateType,
+        operand: Operand,
+    },
+    WrongInputLength(usize, usize),
+    InvalidCircuit(CircuitError),
+    PeerDisconnected {
+        phase: Phase,
+    },
+    TransportClosed,
+    Timeout {
+        phase: Phase,
+    },
+    TransportTimeout,
+    TimeoutUnsupported,
+    MissingSetting(&'static str),
+    FrameTooLarge {
+        len: u64,
+        max: usize,
+    },
+    InflatedTooLarge {
+        max: usize,
+    },
+    ReplayMismatch(usize),
+    UnexpectedMessage {
+        phase: Phase,
+    },
+    OutOfSyncMessage {
+        expected: usize,
+        got: usize,
+        phase: Phase,
+    },
+    OutputWidthMismatch(usize, usize),
+    InvalidOutputIndex(usize, usize),
+    WireIndexOutOfRange {
+        wire: usize,
+        wires_amount: usize,
+    },
+    TripleUnavailable(io::Error),
+    Halted(usize),
+    InvalidSnapshot,
+    NotStarted,
+    ResumeMismatch {
+        ours: usize,
+        theirs: usize,
+    },
+    Panicked {
+        message: String,
+        phase: Phase,
+    },
+    InvalidSeedOpening,
+    InvalidTriple(usize),
+    InvalidPublicInput(usize),
+    RunMismatch {
+        ours: usize,
+        theirs: usize,
+    },
+    CircuitMismatch {
+        run: usize,
+    },
+    ProtocolVersionMismatch {
+        ours: u32,
+        theirs: u32,
+    },
+    OutputChunkSizeMismatch {
+        ours: usize,
+        theirs: usize,
+    },
+    UnsupportedOutputMode(OutputMode),
+    PError(Box<dyn Error + Send + Sync>),
 }
 
-impl<'a> Display for PartyError<'a> {
+impl Display for PartyError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             PartyError::ThreadTransmissionError => {
@@ -23,8 +171,183 @@ impl<'a> Display for PartyError<'a> {
             PartyError::ThreadReceivingError => {
                 write!(f, "Error, whilst Transmissioning Data between Threads")
             }
-            PartyError::WireNotSetError(wire) => {
-                write!(f, "Wire {} has not been set yet", wire)
+            PartyError::WireNotSet {
+                wire,
+                gate_index,
+                gate,
+                operand,
+            } => {
+                write!(
+                    f,
+                    "Wire {} has not been set yet, it is the {} input of gate {} ({})",
+                    wire, operand, gate_index, gate
+                )
+            }
+            PartyError::WrongInputLength(expected, actual) => {
+                write!(
+                    f,
+                    "Wrong amount of input bits. Expected: {}, actually: {}",
+                    expected, actual
+                )
+            }
+            PartyError::InvalidCircuit(e) => {
+                write!(f, "The circuit cannot be evaluated: {}", e)
+            }
+            PartyError::PeerDisconnected { phase } => {
+                write!(f, "The other party disconnected during {}", phase)
+            }
+            PartyError::TransportClosed => {
+                write!(f, "The connection to the other party is closed")
+            }
+            PartyError::Timeout { phase } => {
+                write!(
+                    f,
+                    "The other party did not respond in time during {}",
+                    phase
+                )
+            }
+            PartyError::TransportTimeout => {
+                write!(f, "The other party did not respond in time")
+            }
+            PartyError::TimeoutUnsupported => {
+                write!(f, "The connection to the other party cannot time out")
+            }
+            PartyError::MissingSetting(setting) => {
+                write!(f, "The party cannot be built without a {}", setting)
+            }
+            PartyError::FrameTooLarge { len, max } => {
+                write!(
+                    f,
+                    "The other party sent a message of {} bytes, but at most {} are allowed",
+                    len, max
+                )
+            }
+            PartyError::InflatedTooLarge { max } => {
+                write!(
+                    f,
+                    "The other party sent a message which decompresses to more than {} bytes",
+                    max
+                )
+            }
+            PartyError::ReplayMismatch(position) => {
+                write!(
+                    f,
+                    "The party deviated from the recorded run at message {}",
+                    position
+                )
+            }
+            PartyError::UnexpectedMessage { phase } => {
+                write!(f, "Received an unexpected message during {}", phase)
+            }
+            PartyError::OutOfSyncMessage {
+                expected,
+                got,
+                phase,
+            } => {
+                write!(
+                    f,
+                    "Received message {} during {}, but expected message {}",
+                    got, phase, expected
+                )
+            }
+            PartyError::OutputWidthMismatch(declared, actual) => {
+                write!(
+                    f,
+                    "The declared output values have {} bits, but the circuit has {} output wires",
+                    declared, actual
+                )
+            }
+            PartyError::InvalidOutputIndex(index, outputs) => {
+                write!(
+                    f,
+                    "Output bit {} was selected, but the circuit only has {} output bits",
+                    index, outputs
+                )
+            }
+            PartyError::WireIndexOutOfRange { wire, wires_amount } => {
+                write!(
+                    f,
+                    "Wire {} does not exist, the circuit has {} wire(s)",
+                    wire, wires_amount
+                )
+            }
+            PartyError::TripleUnavailable(e) => {
+                write!(f, "No multiplication triple is available: {}", e)
+            }
+            PartyError::Halted(gate) => {
+                write!(f, "Execution halted before gate {}", gate)
+            }
+            PartyError::InvalidSnapshot => {
+                write!(f, "The snapshot does not fit the circuit")
+            }
+            PartyError::NotStarted => {
+                write!(
+                    f,
+                    "The execution has not been started or is already finished"
+                )
+            }
+            PartyError::ResumeMismatch { ours, theirs } => {
+                write!(
+                    f,
+                    "Cannot resume: this party is at gate {}, but the other party is at gate {}",
+                    ours, theirs
+                )
+            }
+            PartyError::Panicked { message, phase } => {
+                write!(f, "The party panicked during {}: {}", phase, message)
+            }
+            PartyError::InvalidPublicInput(value) => {
+                write!(f, "The circuit has no input value {} to make public", value)
+            }
+            PartyError::RunMismatch { ours, theirs } => {
+                write!(
+                    f,
+                    "The parties are out of step: this party starts run {}, the other party run {}",
+                    ours, theirs
+                )
+            }
+            PartyError::CircuitMismatch { run } => {
+                write!(
+                    f,
+                    "The parties switched to different circuits in run {}",
+                    run
+                )
+            }
+            PartyError::ProtocolVersionMismatch { ours, theirs } => {
+                write!(
+                    f,
+                    "This party speaks protocol version {}, but the other party version {}",
+                    ours, theirs
+                )
+            }
+            PartyError::OutputChunkSizeMismatch { ours, theirs } => {
+                write!(
+                    f,
+                    "This party sends the output in chunks of {} bit(s), but the other party in \
+                     chunks of {}",
+                    ours, theirs
+                )
+            }
+            PartyError::UnsupportedOutputMode(mode) => {
+                write!(
+                    f,
+                    "This execution reveals the output to both parties and cannot use the output \
+                     mode {:?}",
+                    mode
+                )
+            }
+            PartyError::InvalidSeedOpening => {
+                write!(
+                    f,
+                    "The seed contribution of the other party does not match its commitment"
+                )
+            }
+            PartyError::InvalidTriple(index) => {
+                write!(
+                    f,
+                    "Multiplication triple {} of the run does not satisfy c = a & b",
+                    index
+                )
             }
 
             PartyError::PError(e) => write!(f, "ProtocolError! {}", *e),
@@ -32,15 +355,27 @@ impl<'a> Display for PartyError<'a> {
     }
 }
 
-impl<'a, T: 'a> From<SendError<T>> for PartyError<'a> {
+impl<T: Send + Sync + 'static> From<SendError<T>> for PartyError {
     fn from(value: SendError<T>) -> Self {
         Self::PError(Box::new(value))
     }
 }
 
-impl<'a> From<RecvError> for PartyError<'a> {
+impl From<RecvError> for PartyError {
     fn from(value: RecvError) -> Self {
         Self::PError(Box::new(value))
     }
 }
-impl<'a> Error for PartyError<'a> {}
+impl Error for PartyError {}
+
+/// Returns the message of a caught panic, which is a `&str` or a `String` for panics started by
+/// `panic!`, `unwrap` and the like.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "a panic without a message".to_string()
+    }
+}
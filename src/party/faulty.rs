@@ -0,0 +1,240 @@
+//! A transport which makes an otherwise honest party misbehave, to test how the other party
+//! handles a peer that breaks the protocol.
+
+use crate::party::errors::PartyError;
+use crate::party::party_gmw::Messages;
+use crate::party::transport::{Envelope, Transport};
+use std::cell::{Cell, RefCell};
+
+/// The misbehaviour of a [`FaultyTransport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Fault {
+    /// Sends one input share too many.
+    SharesTooLong,
+    /// Sends a `Result` instead of the message of the first AND round.
+    ResultForAnd,
+    /// Flips the first opened mask of every AND gate.
+    FlipAndMasks,
+    /// Drops the connection instead of sending the message of the AND round after the first k.
+    DisconnectAfterAnd(usize),
+    /// Stays connected, but sends nothing from the first AND round on.
+    Stall,
+    /// Sends one output share too few.
+    ResultTooShort,
+    /// Drops the message of the AND round after the first k, as if it got lost.
+    DropAnd(usize),
+    /// Sends the message of the AND round after the first k twice, as a retried send would.
+    RepeatAnd(usize),
+}
+
+/// Wraps the transport of a party and tampers with the messages it sends, see [`Fault`].
+pub(crate) struct FaultyTransport<C: Transport> {
+    // dropped to disconnect
+    inner: RefCell<Option<C>>,
+    fault: Fault,
+    and_rounds: Cell<usize>,
+}
+
+impl<C: Transport> FaultyTransport<C> {
+    pub(crate) fn new(inner: C, fault: Fault) -> Self {
+        FaultyTransport {
+            inner: RefCell::new(Some(inner)),
+            fault,
+            and_rounds: Cell::new(0),
+        }
+    }
+}
+
+impl<C: Transport> Transport for FaultyTransport<C> {
+    fn send(&self, envelope: Envelope) -> Result<(), PartyError> {
+        let Envelope {
+            sequence,
+            message: m,
+        } = envelope;
+        let is_and = matches!(m, Messages::And { .. } | Messages::AndBatch { .. });
+        let and_round = self.and_rounds.get();
+        if is_and {
+            self.and_rounds.set(and_round + 1);
+        }
+
+        let m = match (self.fault, m) {
+            (Fault::SharesTooLong, Messages::Shares { mut shares }) => {
+                shares.push(false);
+                Messages::Shares { shares }
+            }
+            (Fault::ResultForAnd, _) if is_and && and_round == 0 => Messages::Result(Vec::new()),
+            (Fault::FlipAndMasks, Messages::And { s_i, s_j }) => Messages::And { s_i: !s_i, s_j },
+            (Fault::FlipAndMasks, Messages::AndBatch { mut s_i, s_j }) => {
+                s_i.iter_mut().for_each(|bit| *bit = !*bit);
+                Messages::AndBatch { s_i, s_j }
+            }
+            (Fault::DisconnectAfterAnd(k), _) if is_and && and_round == k => {
+                self.inner.borrow_mut().take();
+                return Err(PartyError::TransportClosed);
+            }
+            (Fault::Stall, _) if is_and || and_round > 0 => return Ok(()),
+            (Fault::DropAnd(k), _) if is_and && and_round == k => return Ok(()),
+            (Fault::ResultTooShort, Messages::Result(mut shares)) => {
+                shares.pop();
+                Messages::Result(shares)
+            }
+            (_, m) => m,
+        };
+        let repeat = matches!(self.fault, Fault::RepeatAnd(k) if is_and && and_round == k);
+        match self.inner.borrow().as_ref() {
+            Some(inner) if repeat => {
+                inner.send(Envelope::new(sequence, m.clone()))?;
+                inner.send(Envelope::new(sequence, m))
+            }
+            Some(inner) => inner.send(Envelope::new(sequence, m)),
+            None => Err(PartyError::TransportClosed),
+        }
+    }
+
+    fn recv(&self) -> Result<Envelope, PartyError> {
+        match self.inner.borrow().as_ref() {
+            Some(inner) => inner.recv(),
+            None => Err(PartyError::TransportClosed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fault, FaultyTransport};
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::mul_triple::{MTProvider, SeededMTP};
+    use crate::party::errors::{PartyError, Phase};
+    use crate::party::party_gmw::Party;
+    use crate::party::transport::{channel_pair, Transport};
+    use rand::rngs::StdRng;
+    use std::thread;
+    use std::time::Duration;
+
+    const X: u64 = 5;
+    const Y: u64 = 7;
+
+    fn run<T: MTProvider, C: Transport>(
+        p: &mut Party<T, C>,
+        input: &[bool],
+        batched: bool,
+    ) -> Result<Vec<bool>, PartyError> {
+        if batched {
+            p.execute_batched(input)
+        } else {
+            p.execute(input)
+        }
+    }
+
+    /// Runs the 64 bit adder with an honest party 0 entering `X` and a party 1 entering `Y`,
+    /// which misbehaves with `fault`, and returns what the honest party got.
+    fn run_against(fault: Fault, batched: bool) -> Result<Vec<bool>, PartyError> {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let circuit = Circuit::parse(&contents).unwrap();
+        let (t0, t1) = channel_pair();
+        // the timeouts end the run of a stalled party, for both sides. The faulty party waits
+        // longer, so that the honest one times out before the faulty one gives up and disconnects
+        let t0 = t0.with_timeout(Duration::from_millis(200));
+        let t1 = FaultyTransport::new(t1.with_timeout(Duration::from_millis(1000)), fault);
+        let mut p0 = Party::new(
+            circuit.clone(),
+            t0,
+            false,
+            SeededMTP::<StdRng>::new([9; 32]),
+        )
+        .unwrap();
+        let mut p1 = Party::new(circuit, t1, true, SeededMTP::<StdRng>::new([9; 32])).unwrap();
+
+        let h1 = thread::spawn(move || {
+            // the faulty party fails in all sorts of ways, but must not panic either
+            let _ = run(&mut p1, &to_bits(Y, 64).unwrap(), batched);
+        });
+        let result = run(&mut p0, &to_bits(X, 64).unwrap(), batched);
+        drop(p0);
+        h1.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn test_faults_are_reported() {
+        type Check = fn(&Result<Vec<bool>, PartyError>) -> bool;
+        let table: [(Fault, Check); 8] = [
+            (Fault::SharesTooLong, |r| {
+                matches!(r, Err(PartyError::WrongInputLength(64, 65)))
+            }),
+            (Fault::ResultForAnd, |r| {
+                matches!(
+                    r,
+                    Err(PartyError::UnexpectedMessage {
+                        phase: Phase::And { round: 0, .. }
+                    })
+                )
+            }),
+            (Fault::DisconnectAfterAnd(3), |r| {
+                matches!(
+                    r,
+                    Err(PartyError::PeerDisconnected {
+                        phase: Phase::And { round: 3, .. }
+                    })
+                )
+            }),
+            (Fault::Stall, |r| {
+                matches!(
+                    r,
+                    Err(PartyError::Timeout {
+                        phase: Phase::And { round: 0, .. }
+                    })
+                )
+            }),
+            (Fault::ResultTooShort, |r| {
+                matches!(
+                    r,
+                    Err(PartyError::UnexpectedMessage {
+                        phase: Phase::OutputExchange
+                    })
+                )
+            }),
+            // a lost or repeated message is the answer to another round than the one the honest
+            // party waits for, which the sequence numbers tell
+            (Fault::DropAnd(3), |r| {
+                matches!(
+                    r,
+                    Err(PartyError::OutOfSyncMessage {
+                        expected,
+                        got,
+                        phase: Phase::And { round: 3, .. }
+                    }) if *got == expected + 1
+                )
+            }),
+            (Fault::RepeatAnd(3), |r| {
+                matches!(
+                    r,
+                    Err(PartyError::OutOfSyncMessage {
+                        expected,
+                        got,
+                        phase: Phase::And { round: 4, .. }
+                    }) if *got + 1 == *expected
+                )
+            }),
+            // GMW is only secure against semi-honest parties, so shares carry no MACs and a
+            // party tampering with its masks goes unnoticed. It changes the result instead.
+            (
+                Fault::FlipAndMasks,
+                |r| matches!(r, Ok(output) if *output != to_bits(X + Y, 64).unwrap()),
+            ),
+        ];
+        for (fault, check) in table {
+            for batched in [false, true] {
+                let result = run_against(fault, batched);
+                assert!(
+                    check(&result),
+                    "{:?} (batched: {}) gave {:?}",
+                    fault,
+                    batched,
+                    result
+                );
+            }
+        }
+    }
+}
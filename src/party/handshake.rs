@@ -0,0 +1,128 @@
+//! The handshake opening a connection between two parties, in which they check that they speak
+//! the same version of the protocol before exchanging any other message.
+
+use crate::party::errors::{PartyError, Phase};
+use crate::party::party_gmw::{in_phase, Messages};
+use crate::party::transport::{Envelope, Transport};
+
+/// Version of the messages the parties exchange. Increase it whenever [`Messages`] or the order
+/// of the messages changes.
+pub const PROTOCOL_VERSION: u32 = 5;
+
+/// Marks the preamble of a handshake, so that something which is no party at all is told apart
+/// from a party of another version.
+const MAGIC: [u8; 4] = *b"GMW\0";
+
+/// Encodes the preamble of the handshake: the magic bytes, followed by the version as little
+/// endian. Unlike the rest of the messages, this layout never changes, so that parties of any
+/// two versions can read each other's version.
+pub fn preamble(version: u32) -> [u8; 8] {
+    let mut preamble = [0; 8];
+    preamble[..4].copy_from_slice(&MAGIC);
+    preamble[4..].copy_from_slice(&version.to_le_bytes());
+    preamble
+}
+
+/// Decodes the version from a preamble, or returns `None` if it does not start with the magic
+/// bytes.
+pub fn parse_preamble(preamble: &[u8; 8]) -> Option<u32> {
+    if preamble[..4] != MAGIC {
+        return None;
+    }
+    Some(u32::from_le_bytes(preamble[4..].try_into().unwrap()))
+}
+
+/// Whether a party of version `ours` can talk to a party of version `theirs`. Only the same
+/// version is supported for now.
+pub fn is_compatible(ours: u32, theirs: u32) -> bool {
+    ours == theirs
+}
+
+/// Sends the handshake message announcing `version` to the other party, as the first message of
+/// the connection.
+pub fn send_hello<C: Transport>(transport: &C, version: u32) -> Result<(), PartyError> {
+    let hello = Messages::Hello {
+        preamble: preamble(version),
+    };
+    transport
+        .send(Envelope::new(0, hello))
+        .map_err(|e| in_phase(e, Phase::Handshake))
+}
+
+/// Checks the handshake message of the other party. Fails with
+/// [`PartyError::ProtocolVersionMismatch`] if its version is not compatible with `version`.
+pub fn check_hello(message: Messages, version: u32) -> Result<(), PartyError> {
+    let phase = Phase::Handshake;
+    let Messages::Hello { preamble } = message else {
+        return Err(PartyError::UnexpectedMessage { phase });
+    };
+    let theirs = parse_preamble(&preamble).ok_or(PartyError::UnexpectedMessage { phase })?;
+    if !is_compatible(version, theirs) {
+        return Err(PartyError::ProtocolVersionMismatch {
+            ours: version,
+            theirs,
+        });
+    }
+    Ok(())
+}
+
+/// Exchanges the handshake messages with the other party, which has to call this at the same
+/// time, and checks that both speak compatible versions.
+pub fn exchange_versions<C: Transport>(transport: &C, version: u32) -> Result<(), PartyError> {
+    let phase = Phase::Handshake;
+    send_hello(transport, version)?;
+    let envelope = transport.recv().map_err(|e| in_phase(e, phase))?;
+    check_hello(envelope.open(0, phase)?, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exchange_versions, parse_preamble, preamble, PROTOCOL_VERSION};
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::mul_triple::SeededMTP;
+    use crate::party::errors::PartyError;
+    use crate::party::party_gmw::{Messages, Party};
+    use crate::party::transport::{channel_pair, Envelope, Transport};
+    use rand::rngs::StdRng;
+    use std::thread;
+
+    #[test]
+    fn test_preamble_layout() {
+        assert_eq!(preamble(1), [b'G', b'M', b'W', 0, 1, 0, 0, 0]);
+        assert_eq!(parse_preamble(&preamble(0x0102_0304)), Some(0x0102_0304));
+        assert_eq!(parse_preamble(&[0; 8]), None);
+    }
+
+    #[test]
+    fn test_version_mismatch_fails_on_both_ends() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let circuit = Circuit::parse(&contents).unwrap();
+        let (t0, t1) = channel_pair();
+        let mut p0 = Party::new(circuit, t0, false, SeededMTP::<StdRng>::new([0; 32])).unwrap();
+
+        // the other end is a party of a newer version
+        let newer = PROTOCOL_VERSION + 1;
+        let h1 = thread::spawn(move || exchange_versions(&t1, newer));
+        assert!(matches!(
+            p0.execute(&[false; 64]),
+            Err(PartyError::ProtocolVersionMismatch { ours, theirs })
+                if ours == PROTOCOL_VERSION && theirs == newer
+        ));
+        assert!(matches!(
+            h1.join().unwrap(),
+            Err(PartyError::ProtocolVersionMismatch { ours, theirs })
+                if ours == newer && theirs == PROTOCOL_VERSION
+        ));
+    }
+
+    #[test]
+    fn test_not_a_party() {
+        let (t0, t1) = channel_pair();
+        let hello = Messages::Hello { preamble: [0; 8] };
+        t1.send(Envelope::new(0, hello)).unwrap();
+        assert!(matches!(
+            exchange_versions(&t0, PROTOCOL_VERSION),
+            Err(PartyError::UnexpectedMessage { .. })
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::circuit::circuit_parser::Circuit;
+use crate::mul_triple::SeededMTP;
+use crate::party::errors::PartyError;
+use crate::party::party_gmw::Party;
+use crate::party::transport::{Envelope, Transport};
+use rand::rngs::StdRng;
+use rand::RngCore;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Transport between two parties which take turns on the same thread. Sent messages are queued
+/// until the other party takes them, so `recv` never blocks: it fails with
+/// [`PartyError::TransportClosed`] if no message is queued.
+pub struct LocalTransport {
+    outgoing: Rc<RefCell<VecDeque<Envelope>>>,
+    incoming: Rc<RefCell<VecDeque<Envelope>>>,
+}
+
+/// Creates two local transports which are connected to each other.
+pub fn local_pair() -> (LocalTransport, LocalTransport) {
+    let queue0 = Rc::new(RefCell::new(VecDeque::new()));
+    let queue1 = Rc::new(RefCell::new(VecDeque::new()));
+    (
+        LocalTransport {
+            outgoing: Rc::clone(&queue0),
+            incoming: Rc::clone(&queue1),
+        },
+        LocalTransport {
+            outgoing: queue1,
+            incoming: queue0,
+        },
+    )
+}
+
+impl Transport for LocalTransport {
+    fn send(&self, m: Envelope) -> Result<(), PartyError> {
+        self.outgoing.borrow_mut().push_back(m);
+        Ok(())
+    }
+
+    fn recv(&self) -> Result<Envelope, PartyError> {
+        self.incoming
+            .borrow_mut()
+            .pop_front()
+            .ok_or(PartyError::TransportClosed)
+    }
+}
+
+/// Executes the GMW protocol for both parties on the current thread, with `in0` as the input of
+/// party 0 and `in1` as the input of party 1. Needs no threads, so it also runs where those are
+/// not available, e.g. on wasm32-unknown-unknown, and both parties can be stepped through in a
+/// debugger. The randomness is taken from the thread local RNG, see [`run_local_with_rng`] to
+/// pass a different source and make the run deterministic.
+pub fn run_local(circuit: &Circuit, in0: &[bool], in1: &[bool]) -> Result<Vec<bool>, PartyError> {
+    run_local_with_rng(circuit, in0, in1, &mut rand::thread_rng())
+}
+
+/// Executes the GMW protocol like [`run_local`], drawing all randomness (the seed of the
+/// multiplication triples and the masks of the input shares) from `rng`.
+pub fn run_local_with_rng(
+    circuit: &Circuit,
+    in0: &[bool],
+    in1: &[bool],
+    rng: &mut impl RngCore,
+) -> Result<Vec<bool>, PartyError> {
+    let mut seeds = [[0; 32]; 3];
+    for seed in &mut seeds {
+        rng.fill_bytes(seed);
+    }
+    let [triple_seed, share_seed0, share_seed1] = seeds;
+
+    let (transport0, transport1) = local_pair();
+    let circuit = Arc::new(circuit.clone());
+    let mut p0: Party<SeededMTP<StdRng>, LocalTransport> = Party::with_share_seed(
+        Arc::clone(&circuit),
+        transport0,
+        false,
+        SeededMTP::new(triple_seed),
+        share_seed0,
+    )?;
+    let mut p1: Party<SeededMTP<StdRng>, LocalTransport> = Party::with_share_seed(
+        circuit,
+        transport1,
+        true,
+        SeededMTP::new(triple_seed),
+        share_seed1,
+    )?;
+
+    // Both parties send their first message before either receives one. Afterwards, every step
+    // of a party takes the message the other party sent in its last step and sends the next one.
+    p0.start(in0)?;
+    p1.start(in1)?;
+    let (mut out0, mut out1) = (None, None);
+    while out0.is_none() || out1.is_none() {
+        if out0.is_none() {
+            out0 = p0.step()?;
+        }
+        if out1.is_none() {
+            out1 = p1.step()?;
+        }
+    }
+
+    // both parties reconstruct the same output
+    Ok(out0.unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_local, run_local_with_rng};
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::party::party_gmw::new_party_pair;
+    use crate::testing::{generate_random_circuit, RandomCircuitParams};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::thread;
+
+    #[test]
+    fn test_run_local_matches_threads() {
+        for circuit in ["64_Adder", "64_Sub"] {
+            let contents =
+                std::fs::read_to_string(format!("test_circuits/{}.txt", circuit)).unwrap();
+            let c = Circuit::parse(&contents).unwrap();
+
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let h0 = thread::spawn(move || p0.execute(&to_bits(1234, 64).unwrap()).unwrap());
+            let h1 = thread::spawn(move || p1.execute(&to_bits(4321, 64).unwrap()).unwrap());
+            let threaded = h0.join().unwrap();
+            assert_eq!(threaded, h1.join().unwrap());
+
+            let local =
+                run_local(&c, &to_bits(1234, 64).unwrap(), &to_bits(4321, 64).unwrap()).unwrap();
+            assert_eq!(local, threaded);
+
+            let mut rng = StdRng::seed_from_u64(7);
+            let seeded = run_local_with_rng(
+                &c,
+                &to_bits(1234, 64).unwrap(),
+                &to_bits(4321, 64).unwrap(),
+                &mut rng,
+            );
+            assert_eq!(seeded.unwrap(), threaded);
+        }
+    }
+
+    #[test]
+    fn test_run_local_matches_threads_on_random_circuits() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..20 {
+            let params = RandomCircuitParams {
+                gates: rng.gen_range(8..200),
+                input_widths: vec![rng.gen_range(1..=8), rng.gen_range(1..=8)],
+                ..Default::default()
+            };
+            let c = generate_random_circuit(&mut rng, &params);
+            let in0: Vec<bool> = (0..params.input_widths[0]).map(|_| rng.gen()).collect();
+            let in1: Vec<bool> = (0..params.input_widths[1]).map(|_| rng.gen()).collect();
+
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let input = in1.clone();
+            let h1 = thread::spawn(move || p1.execute(&input).unwrap());
+            let threaded = p0.execute(&in0).unwrap();
+            assert_eq!(threaded, h1.join().unwrap());
+
+            assert_eq!(run_local(&c, &in0, &in1).unwrap(), threaded);
+        }
+    }
+
+    #[test]
+    fn test_run_local_wrong_input_length() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        assert!(run_local(&c, &to_bits(1, 64).unwrap(), &[true]).is_err());
+    }
+}
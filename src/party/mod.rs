@@ -1,2 +1,20 @@
+pub mod allocations;
+pub mod builder;
+pub mod checkpoint;
+pub mod coin_toss;
+pub mod delayed;
 pub mod errors;
+#[cfg(test)]
+mod faulty;
+pub mod handshake;
+pub mod local;
+pub mod observer;
 pub mod party_gmw;
+pub mod profile;
+pub mod session;
+pub mod tcp;
+pub mod threads;
+#[cfg(feature = "timing")]
+pub mod timing;
+pub mod trace;
+pub mod transport;
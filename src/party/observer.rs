@@ -0,0 +1,257 @@
+//! Hooks for watching a party run the protocol, e.g. to visualize or measure a run, see
+//! [`ExecutionObserver`].
+
+use crate::circuit::circuit_parser::GateType;
+use crate::party::errors::Phase;
+use crate::party::party_gmw::Messages;
+use std::sync::{Arc, Mutex};
+
+/// Whether a message is sent to or received from the other party.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// Watches a party evaluate a circuit. Install it with [`crate::party::builder::PartyBuilder`]
+/// or [`crate::party::party_gmw::Party::set_observer`]. Every hook does nothing by default, so an
+/// observer only implements those it needs.
+pub trait ExecutionObserver: Send {
+    /// Called before the gate with the given index is evaluated. For an AND gate, its
+    /// communication round follows.
+    fn on_gate_start(&mut self, index: usize, gate: &GateType) {
+        let _ = (index, gate);
+    }
+
+    /// Called once the gate with the given index has its output share.
+    fn on_gate_done(&mut self, index: usize) {
+        let _ = index;
+    }
+
+    /// Called when a communication round starts, before its message is sent. The rounds are
+    /// numbered from 0 like [`crate::party::party_gmw::Party::rounds`] counts them.
+    fn on_round(&mut self, phase: Phase, round: usize) {
+        let _ = (phase, round);
+    }
+
+    /// Called for every message, before it is sent or after it was received.
+    fn on_message(&mut self, direction: Direction, message: &Messages) {
+        let _ = (direction, message);
+    }
+}
+
+/// An event seen by a [`RecordingObserver`], one per call of a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    GateStart(usize, GateType),
+    GateDone(usize),
+    Round(Phase, usize),
+    Message(Direction, Messages),
+}
+
+/// Records every event. Clones share the recorded events, so a clone kept outside of the party
+/// sees what the installed observer recorded.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingObserver {
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl RecordingObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The events recorded so far, in order.
+    pub fn events(&self) -> Vec<Event> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn record(&self, event: Event) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl ExecutionObserver for RecordingObserver {
+    fn on_gate_start(&mut self, index: usize, gate: &GateType) {
+        self.record(Event::GateStart(index, gate.clone()));
+    }
+
+    fn on_gate_done(&mut self, index: usize) {
+        self.record(Event::GateDone(index));
+    }
+
+    fn on_round(&mut self, phase: Phase, round: usize) {
+        self.record(Event::Round(phase, round));
+    }
+
+    fn on_message(&mut self, direction: Direction, message: &Messages) {
+        self.record(Event::Message(direction, message.clone()));
+    }
+}
+
+/// The traffic of a party, counted by a [`MetricsObserver`]. Bytes are counted as the JSON the
+/// [`crate::party::tcp::TcpTransport`] sends, without its frame headers. The wire bytes are those
+/// it actually sends, which are fewer if it compresses the messages. They are only counted if
+/// the transport records into the observer, see [`crate::party::tcp::TcpTransport::set_metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub rounds: usize,
+    pub messages_sent: usize,
+    pub messages_received: usize,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    pub wire_bytes_sent: usize,
+    pub wire_bytes_received: usize,
+}
+
+/// Counts the rounds, messages and bytes of a party. Clones share the counters, like those of a
+/// [`RecordingObserver`].
+#[derive(Debug, Clone, Default)]
+pub struct MetricsObserver {
+    metrics: Arc<Mutex<Metrics>>,
+}
+
+impl MetricsObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The counters so far.
+    pub fn metrics(&self) -> Metrics {
+        *self.metrics.lock().unwrap()
+    }
+
+    /// Counts the bytes of a message as they went over the connection.
+    pub fn add_wire_bytes(&self, direction: Direction, bytes: usize) {
+        let mut metrics = self.metrics.lock().unwrap();
+        match direction {
+            Direction::Sent => metrics.wire_bytes_sent += bytes,
+            Direction::Received => metrics.wire_bytes_received += bytes,
+        }
+    }
+}
+
+impl ExecutionObserver for MetricsObserver {
+    fn on_round(&mut self, _: Phase, _: usize) {
+        self.metrics.lock().unwrap().rounds += 1;
+    }
+
+    fn on_message(&mut self, direction: Direction, message: &Messages) {
+        // the messages only consist of numbers and bools, which always encode
+        let bytes = serde_json::to_vec(message).map_or(0, |json| json.len());
+        let mut metrics = self.metrics.lock().unwrap();
+        match direction {
+            Direction::Sent => {
+                metrics.messages_sent += 1;
+                metrics.bytes_sent += bytes;
+            }
+            Direction::Received => {
+                metrics.messages_received += 1;
+                metrics.bytes_received += bytes;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Event, MetricsObserver, RecordingObserver};
+    use crate::circuit::circuit_layout::PartyId;
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::mul_triple::SeededMTP;
+    use crate::party::builder::PartyBuilder;
+    use crate::party::party_gmw::Messages;
+    use crate::party::transport::channel_pair;
+    use rand::rngs::StdRng;
+    use std::thread;
+
+    /// The event with messages reduced to their variant, which is all the test cares about.
+    fn describe(event: &Event) -> String {
+        match event {
+            Event::GateStart(index, gate) => format!("start {} {}", index, gate),
+            Event::GateDone(index) => format!("done {}", index),
+            Event::Round(phase, round) => format!("round {} {}", round, phase),
+            Event::Message(direction, m) => {
+                let debug = format!("{:?}", m);
+                let variant = debug.split([' ', '(', '{']).next().unwrap().to_string();
+                format!("{:?} {}", direction, variant)
+            }
+        }
+    }
+
+    #[test]
+    fn test_recorded_events() {
+        // an AND gate and an INV gate reading it
+        let c = Circuit::parse("2 4\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n1 1 2 3 INV\n").unwrap();
+        for batched in [false, true] {
+            let (t0, t1) = channel_pair();
+            let recording = RecordingObserver::new();
+            let metrics = MetricsObserver::new();
+            let party = |channel, role| {
+                PartyBuilder::new()
+                    .circuit(c.clone())
+                    .channel(channel)
+                    .role(role)
+                    .mt_provider(SeededMTP::<StdRng>::new([0; 32]))
+            };
+            let mut p0 = party(t0, PartyId::P0)
+                .observer(recording.clone())
+                .build()
+                .unwrap();
+            let mut p1 = party(t1, PartyId::P1)
+                .observer(metrics.clone())
+                .build()
+                .unwrap();
+            let h1 = thread::spawn(move || {
+                let output = match batched {
+                    false => p1.execute(&[true]),
+                    true => p1.execute_batched(&[true]),
+                };
+                (output.unwrap(), p1.rounds())
+            });
+            let output = match batched {
+                false => p0.execute(&[true]),
+                true => p0.execute_batched(&[true]),
+            };
+            assert_eq!(output.unwrap(), vec![false]);
+
+            let events: Vec<String> = recording.events().iter().map(describe).collect();
+            let and = if batched { "AndBatch" } else { "And" };
+            let expected = [
+                "round 0 the handshake".to_string(),
+                "Sent Hello".to_string(),
+                "Received Hello".to_string(),
+                "round 1 the handshake".to_string(),
+                "Sent Settings".to_string(),
+                "Received Settings".to_string(),
+                "round 2 input sharing".to_string(),
+                "Sent Shares".to_string(),
+                "Received Shares".to_string(),
+                "start 0 AND 0 1".to_string(),
+                "round 3 AND round 0 (gate 0)".to_string(),
+                format!("Sent {}", and),
+                format!("Received {}", and),
+                "done 0".to_string(),
+                "start 1 INV 2".to_string(),
+                "done 1".to_string(),
+                "round 4 output exchange".to_string(),
+                "Sent Result".to_string(),
+                "Received Result".to_string(),
+            ];
+            assert_eq!(events, expected, "batched: {}", batched);
+
+            // party 1 counted the same traffic, from its side
+            let (_, rounds) = h1.join().unwrap();
+            let counted = metrics.metrics();
+            assert_eq!(counted.rounds, rounds);
+            assert_eq!(counted.messages_sent, 5);
+            assert_eq!(counted.messages_received, 5);
+            let hello = serde_json::to_vec(&Messages::Hello { preamble: [0; 8] }).unwrap();
+            assert!(counted.bytes_sent > hello.len());
+            assert!(matches!(
+                recording.events()[1],
+                Event::Message(Direction::Sent, Messages::Hello { .. })
+            ));
+        }
+    }
+}
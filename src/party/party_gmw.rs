@@ -1,53 +1,282 @@
-use crate::circuit::circuit_parser::{Circuit, Gate, GateType};
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_layout::PartyId;
+use crate::circuit::circuit_parser::{Circuit, GateType};
 use crate::mul_triple::{MTProvider, MulTriple, SeededMTP};
-use crate::party::errors::PartyError;
+use crate::party::allocations::allocations;
+use crate::party::builder::PartyBuilder;
+use crate::party::checkpoint::ExecutionSnapshot;
+use crate::party::errors::{panic_message, Operand, PartyError, Phase};
+use crate::party::handshake::{self, PROTOCOL_VERSION};
+use crate::party::observer::{Direction, ExecutionObserver};
+use crate::party::profile::Profile;
+#[cfg(feature = "timing")]
+use crate::party::timing::TimingReport;
+use crate::party::transport::{
+    channel_pair_with_capacity, ChannelTransport, Envelope, Transport, DEFAULT_CHANNEL_CAPACITY,
+};
 use rand::rngs::StdRng;
-use rand::{thread_rng, Rng, RngCore};
+use rand::{thread_rng, Rng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::usize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
 
-pub struct Party<T: MTProvider> {
-    circuit: Circuit,
-    sender: Sender<Messages>,
-    receiver: Receiver<Messages>,
+pub struct Party<T: MTProvider, C: Transport = ChannelTransport> {
+    circuit: Arc<Circuit>,
+    transport: C,
     pub is_p1: bool,
-    mtp: RefCell<T>,
+    mtp: T,
+    // state of the current execution, kept on the party so that it can be checkpointed
+    wires: Vec<Option<bool>>,
+    gate_index: usize,
+    triples_consumed: usize,
+    sequence: usize,
+    halt_at: Option<usize>,
+    checkpointing: Option<(usize, PathBuf)>,
+    // file every consumed triple is written to
+    triple_dump: Option<BufWriter<File>>,
+    // the triples consumed in the current run, kept to be opened at its end, see
+    // `Party::set_check_triples`
+    checked_triples: Option<Vec<MulTriple>>,
+    // randomness for masking the input shares
+    share_rng: StdRng,
+    // the communication round this party waits for the other party's message of
+    round: Option<Round>,
+    inv_convention: InvConvention,
+    // most output shares sent in one message
+    output_chunk_size: usize,
+    // whether both parties send shares of all input bits, see `Party::set_hide_input_ownership`
+    hide_input_ownership: bool,
+    // who learns the output of `Party::execute`
+    output_mode: OutputMode,
+    // input values known to both parties, by their index in the header
+    public_inputs: BTreeMap<usize, Vec<bool>>,
+    // number of circuits this party switched to with `next_circuit`
+    runs: usize,
+    // whether the protocol versions were checked with the other party, and whether the
+    // settings were agreed on as well, which completes the handshake
+    versions_checked: bool,
+    greeted: bool,
+    // notified of the gates, rounds and messages, see `Party::set_observer`
+    observer: Option<RefCell<Box<dyn ExecutionObserver>>>,
+    // where the time of the executions goes, see `Party::set_profiling`
+    profile: Option<RefCell<Profile>>,
+    // the buffers of the AND layers of `Party::execute_batched`, reused across layers and runs
+    batch: BatchBuffers,
+}
+
+/// A point in a profiled execution, from which the time of a block is measured, see
+/// [`Party::set_profiling`].
+struct Mark {
+    at: Instant,
+    // the waiting time of the profile and the serialization time of the transport at that point
+    waiting: Duration,
+    serialized: Duration,
+}
+
+/// Buffers for the AND gates of a layer in [`Party::execute_batched`]. They are zeroized after
+/// every layer, like the shares and the triple of a single AND gate. The buffers of the shares
+/// keep their capacity, so they are only allocated once.
+#[derive(Default, Zeroize)]
+struct BatchBuffers {
+    // this party's shares of the inputs of every gate
+    inputs: Vec<(bool, bool)>,
+    // the triples of all AND gates of the circuit, taken before the first layer. Those of a
+    // layer are zeroized once it is evaluated.
+    triples: Vec<MulTriple>,
+    // this party's shares of the outputs of every gate
+    outputs: Vec<bool>,
+}
+
+/// Which party negates its share of the input of an INV gate. Negating one of the two shares
+/// negates the shared bit. Both parties have to use the same convention.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvConvention {
+    Party0Negates,
+    #[default]
+    Party1Negates,
+}
+
+/// What [`Party::execute`], [`Party::execute_batched`], [`Party::execute_structured`] and
+/// [`Party::execute_resumed`] do with the output. Both parties have to use the same mode. The
+/// executions which always reveal the output to both parties, [`Party::execute_streaming`] and
+/// the step-wise [`Party::start`], fail with [`PartyError::UnsupportedOutputMode`] for any mode
+/// but [`OutputMode::Reveal`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Both parties learn the output.
+    #[default]
+    Reveal,
+    /// Only the given party learns the output, see [`Party::execute_reveal_to`].
+    RevealTo(PartyId),
+    /// The output stays secret-shared, see [`Party::execute_to_shares`].
+    Shares,
+}
+
+/// A communication round for which this party has sent its message and waits for the message of
+/// the other party.
+enum Round {
+    // the handshake of a step-wise execution, the input is shared once it is done
+    Hello {
+        input: Zeroizing<Vec<bool>>,
+    },
+    Settings {
+        input: Zeroizing<Vec<bool>>,
+    },
+    InputSharing {
+        private_share: Zeroizing<Vec<bool>>,
+    },
+    And {
+        phase: Phase,
+        // this party's shares of x ^ a and y ^ b
+        opened: (bool, bool),
+        triple: MulTriple,
+    },
+    Output {
+        shares: Vec<bool>,
+        // the other party's shares, if they were received before sending this party's ones
+        received: Option<Vec<bool>>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The messages the parties exchange. Changing them requires a new
+/// [`crate::party::handshake::PROTOCOL_VERSION`]. `Hello` opens every connection and has to stay
+/// the first variant, so that parties of all versions decode it the same way.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Messages {
+    Hello {
+        preamble: [u8; 8],
+    },
     Result(Vec<bool>),
-    And { s_i: bool, s_j: bool },
-    Shares { shares: Vec<bool> },
+    And {
+        s_i: bool,
+        s_j: bool,
+    },
+    AndBatch {
+        s_i: Vec<bool>,
+        s_j: Vec<bool>,
+    },
+    Shares {
+        shares: Vec<bool>,
+    },
+    Resume {
+        gate_index: usize,
+    },
+    OtChoices {
+        choices: Vec<bool>,
+    },
+    OtAnswers {
+        answers: Vec<bool>,
+    },
+    SeedCommitment {
+        hash: [u8; 32],
+    },
+    SeedOpening {
+        contribution: [u8; 32],
+    },
+    NextCircuit {
+        run: usize,
+        gates: usize,
+        wires: usize,
+    },
+    OutputBit {
+        index: usize,
+        share: bool,
+    },
+    TripleShares {
+        a: Vec<bool>,
+        b: Vec<bool>,
+        c: Vec<bool>,
+    },
+    // messages of the arithmetic protocol, see `crate::arithmetic`
+    ArithmeticShares {
+        shares: Vec<u64>,
+    },
+    ArithmeticMul {
+        d: u64,
+        e: u64,
+    },
+    ArithmeticResult(Vec<u64>),
+    // follows `Hello` in the handshake of a party, see `Party::greet`
+    Settings {
+        output_chunk_size: usize,
+    },
 }
 
-/// Creates a new pair of parties for the provided circuit that can communicate with each other
-/// to execute the provided circuit.
-pub fn new_party_pair(circuit: Circuit) -> (Party<SeededMTP<StdRng>>, Party<SeededMTP<StdRng>>) {
-    let (sender0, receiver1) = channel();
-    let (sender1, receiver0) = channel();
+/// An output bit revealed by [`Party::execute_streaming`]. `index` counts the output bits from 0
+/// over all output values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputBit {
+    pub index: usize,
+    pub value: bool,
+}
+
+/// Two parties created for each other by [`new_party_pair`] and its variants.
+pub type PartyPair = (Party<SeededMTP<StdRng>>, Party<SeededMTP<StdRng>>);
 
+/// Creates a new pair of parties for the provided circuit that can communicate with each other
+/// to execute the provided circuit. Both parties derive their triples from the same locally
+/// generated seed, which only works within one process. Parties in different processes agree on
+/// a seed with [`Party::with_agreed_seed`] instead. Fails like [`Party::new`] if the circuit is
+/// not valid.
+pub fn new_party_pair(circuit: Circuit) -> Result<PartyPair, PartyError> {
     let mut seed: [u8; 32] = Default::default();
     thread_rng().fill_bytes(&mut seed);
 
-    let party0: Party<SeededMTP<StdRng>> = Party::new(
-        circuit.clone(),
-        sender0,
-        receiver0,
-        false,
-        SeededMTP::new(seed),
-    );
+    new_party_pair_seeded(circuit, seed)
+}
+
+/// Creates a new pair of parties like [`new_party_pair`], but derives the multiplication triples
+/// from the given seed instead of a random one. Pairs created with the same seed use the same
+/// triples, which makes runs reproducible.
+pub fn new_party_pair_seeded(circuit: Circuit, seed: [u8; 32]) -> Result<PartyPair, PartyError> {
+    new_party_pair_with_capacity(circuit, seed, DEFAULT_CHANNEL_CAPACITY)
+}
 
-    let party1: Party<SeededMTP<StdRng>> =
-        Party::new(circuit, sender1, receiver1, true, SeededMTP::new(seed));
+/// Creates a new pair of parties like [`new_party_pair_seeded`], whose channels buffer
+/// `capacity` messages in each direction. Panics if the capacity is 0.
+pub fn new_party_pair_with_capacity(
+    circuit: Circuit,
+    seed: [u8; 32],
+    capacity: usize,
+) -> Result<PartyPair, PartyError> {
+    let (transport0, transport1) = channel_pair_with_capacity(capacity);
+    // both parties share the circuit
+    let circuit = Arc::new(circuit);
+    let party = |transport, role| {
+        PartyBuilder::new()
+            .circuit(Arc::clone(&circuit))
+            .channel(transport)
+            .role(role)
+            .mt_provider(SeededMTP::new(seed))
+            .build()
+    };
+    Ok((
+        party(transport0, PartyId::P0)?,
+        party(transport1, PartyId::P1)?,
+    ))
+}
 
-    (party0, party1)
+/// Combines the output shares both parties got from [`Party::execute_to_shares`] into the output.
+///
+/// Panics if the parties have a different number of shares.
+pub fn reconstruct(shares0: &[bool], shares1: &[bool]) -> Vec<bool> {
+    assert_eq!(
+        shares0.len(),
+        shares1.len(),
+        "the parties have shares of different outputs"
+    );
+    shares0.iter().zip(shares1).map(|(x, y)| x ^ y).collect()
 }
 
 // Function to generate shares of inputs between parties
-fn generate_shares(input: &[bool]) -> (Vec<bool>, Vec<bool>) {
-    let mut rng = thread_rng();
+fn generate_shares(rng: &mut impl Rng, input: &[bool]) -> (Vec<bool>, Vec<bool>) {
     let public: Vec<bool> = (0..input.len()).map(|_| rng.gen::<bool>()).collect();
     let private: Vec<bool> = input
         .iter()
@@ -57,160 +286,2622 @@ fn generate_shares(input: &[bool]) -> (Vec<bool>, Vec<bool>) {
     (private, public)
 }
 
-impl<T: MTProvider> Party<T> {
-    /// Create a new party.
+impl<T: MTProvider, C: Transport> Party<T, C> {
+    /// Create a new party, which communicates with the other party over the given transport. The
+    /// circuit can be shared with other parties, e.g. as a
+    /// [`crate::circuit::circuit_prepared::PreparedCircuit`].
+    ///
+    /// Fails with [`PartyError::InvalidCircuit`] if the circuit cannot be evaluated gate by gate,
+    /// see [`Circuit::validate`]. Rejecting it here, before anything is sent, keeps the other
+    /// party from waiting for messages of a run which fails halfway. Circuits which already
+    /// passed a validation, like prepared ones, are not validated again.
     pub fn new(
-        circuit: Circuit,
-        sender: Sender<Messages>,
-        receiver: Receiver<Messages>,
+        circuit: impl Into<Arc<Circuit>>,
+        transport: C,
+        is_p1: bool,
+        mtp: T,
+    ) -> Result<Self, PartyError> {
+        let mut share_seed = [0; 32];
+        thread_rng().fill_bytes(&mut share_seed);
+        Self::with_share_seed(circuit, transport, is_p1, mtp, share_seed)
+    }
+
+    /// Creates a new party like [`Party::new`], but derives the random masks of the input shares
+    /// from `share_seed` instead of the thread local RNG.
+    pub(crate) fn with_share_seed(
+        circuit: impl Into<Arc<Circuit>>,
+        transport: C,
         is_p1: bool,
         mtp: T,
-    ) -> Self {
-        Party {
+        share_seed: [u8; 32],
+    ) -> Result<Self, PartyError> {
+        let circuit = circuit.into();
+        circuit.ensure_valid().map_err(PartyError::InvalidCircuit)?;
+        Ok(Party {
             circuit,
-            sender,
-            receiver,
+            transport,
             is_p1,
-            mtp: RefCell::new(mtp),
+            mtp,
+            wires: Vec::new(),
+            gate_index: 0,
+            triples_consumed: 0,
+            sequence: 0,
+            halt_at: None,
+            checkpointing: None,
+            triple_dump: None,
+            checked_triples: None,
+            share_rng: StdRng::from_seed(share_seed),
+            round: None,
+            inv_convention: InvConvention::default(),
+            output_chunk_size: usize::MAX,
+            hide_input_ownership: false,
+            output_mode: OutputMode::default(),
+            public_inputs: BTreeMap::new(),
+            runs: 0,
+            versions_checked: false,
+            greeted: false,
+            observer: None,
+            profile: None,
+            batch: BatchBuffers::default(),
+        })
+    }
+
+    /// Rebuilds a party from a snapshot taken by [`Party::checkpoint`]. The passed provider must
+    /// produce the same triples as the one of the checkpointed party, the triples which were
+    /// already consumed before the snapshot are skipped. Continue the evaluation with
+    /// [`Party::execute_resumed`].
+    pub fn resume(
+        circuit: Circuit,
+        snapshot: ExecutionSnapshot,
+        transport: C,
+        mut mtp: T,
+    ) -> Result<Self, PartyError> {
+        if snapshot.wires.len() != circuit.header().wires_amount
+            || snapshot.gate_index > circuit.gates().len()
+        {
+            return Err(PartyError::InvalidSnapshot);
+        }
+
+        for _ in 0..snapshot.triples_consumed {
+            mtp.get_triple();
         }
+
+        let mut party = Party::new(circuit, transport, snapshot.is_p1, mtp)?;
+        party.wires = snapshot.wires;
+        party.gate_index = snapshot.gate_index;
+        party.triples_consumed = snapshot.triples_consumed;
+        party.sequence = snapshot.sequence;
+        party.inv_convention = snapshot.inv_convention;
+        Ok(party)
+    }
+
+    /// Takes a snapshot of the current evaluation state.
+    pub fn checkpoint(&self) -> ExecutionSnapshot {
+        ExecutionSnapshot {
+            is_p1: self.is_p1,
+            gate_index: self.gate_index,
+            wires: self.wires.clone(),
+            triples_consumed: self.triples_consumed,
+            sequence: self.sequence,
+            inv_convention: self.inv_convention,
+        }
+    }
+
+    /// Writes a snapshot to `path` every `interval` gates during [`Party::execute`].
+    pub fn checkpoint_every(&mut self, interval: usize, path: impl Into<PathBuf>) {
+        self.checkpointing = Some((interval, path.into()));
+    }
+
+    /// Writes every multiplication triple this party consumes to a file at `path`, in the format
+    /// [`crate::mul_triple::FileMTP`] reads. The triples of both parties together are the true
+    /// triples of a run. The file is created (or truncated) right away.
+    pub fn dump_triples(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.triple_dump = Some(BufWriter::new(File::create(path)?));
+        Ok(())
     }
 
-    fn evaluate_and(&self, x: bool, y: bool) -> Result<bool, PartyError> {
-        let MulTriple { a, b, c } = self.mtp.borrow_mut().get_triple();
+    /// Opens every multiplication triple consumed in a run at its end and checks that
+    /// c = a & b holds for the reconstructed triples, failing with [`PartyError::InvalidTriple`]
+    /// otherwise. This takes an extra message exchange after the output and reveals the triples,
+    /// which destroys the security of the run, so it is only meant for testing triple providers.
+    /// Both parties have to use the same setting. Off by default.
+    pub fn set_check_triples(&mut self, check: bool) {
+        let required = self.circuit.required_triples();
+        self.checked_triples = check.then(|| Vec::with_capacity(required));
+    }
+
+    /// Stops [`Party::execute`] with [`PartyError::Halted`] before the gate with the given index
+    /// is evaluated. The state at that point can then be taken with [`Party::checkpoint`].
+    pub fn halt_at(&mut self, gate_index: Option<usize>) {
+        self.halt_at = gate_index;
+    }
+
+    /// Derives the random masks of the input shares from the given seed. Together with a seeded
+    /// triple provider, this makes all messages the party sends reproducible, e.g. for replaying a
+    /// recorded run. Never reuse a seed outside of testing and debugging.
+    pub fn seed_shares(&mut self, seed: [u8; 32]) {
+        self.share_rng = StdRng::from_seed(seed);
+    }
+
+    /// Sets which party negates its share at INV gates, by default party 1. Other GMW
+    /// implementations may let party 0 negate, which this party has to match to work with them.
+    pub fn set_inv_convention(&mut self, convention: InvConvention) {
+        self.inv_convention = convention;
+    }
 
-        let (s_i1, s_j1) = (x ^ a, y ^ b);
+    /// Sends the output shares in messages of at most `bits` shares each, instead of all at once.
+    /// This keeps the messages of wide outputs below the size limits of a transport. The chunks
+    /// are still exchanged in a single round: party 0 sends all of its chunks before receiving,
+    /// party 1 receives all of them before sending its own, so neither party waits for a full
+    /// transport while the other one does as well.
+    ///
+    /// Both parties have to use the same chunk size, they compare it in the handshake of their
+    /// first execution and fail with [`PartyError::OutputChunkSizeMismatch`] otherwise. It is
+    /// therefore set before the first execution of a connection.
+    ///
+    /// Panics if `bits` is 0.
+    pub fn set_output_chunk_size(&mut self, bits: usize) {
+        assert!(bits > 0, "an output chunk needs at least one bit");
+        self.output_chunk_size = bits;
+    }
+
+    /// Lets both parties send shares of all input bits instead of only their own ones. A party
+    /// shares 0 for each bit of the other party, so the shares of a wire still add up to its input,
+    /// but the messages no longer show which bits belong to whom. Both parties have to use the
+    /// same setting.
+    pub fn set_hide_input_ownership(&mut self, hide: bool) {
+        self.hide_input_ownership = hide;
+    }
+
+    /// Sets what [`Party::execute`], [`Party::execute_batched`] and [`Party::execute_structured`]
+    /// do with the output, by default both parties learn it.
+    pub fn set_output_mode(&mut self, mode: OutputMode) {
+        self.output_mode = mode;
+    }
+
+    /// Lets `observer` watch the gates, rounds and messages of every following execution, in
+    /// place of a previously set observer.
+    pub fn set_observer(&mut self, observer: Box<dyn ExecutionObserver>) {
+        self.observer = Some(RefCell::new(observer));
+    }
+
+    /// Passes the observer, if any, to `f`.
+    fn observe(&self, f: impl FnOnce(&mut dyn ExecutionObserver)) {
+        if let Some(observer) = &self.observer {
+            f(observer.borrow_mut().as_mut());
+        }
+    }
+
+    /// Measures where the time of the following executions goes, see [`Profile`]. The profile
+    /// sums up all executions from here on, the party only looks at the clock while it is
+    /// enabled. Off by default.
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.profile = profiling.then(|| RefCell::new(Profile::default()));
+    }
+
+    /// Returns the profile of the executions since profiling was enabled, if it is.
+    pub fn profile(&self) -> Option<Profile> {
+        self.profile
+            .as_ref()
+            .map(|profile| profile.borrow().clone())
+    }
+
+    /// Marks the start of a block to measure for the profile, if profiling is enabled.
+    fn mark(&self) -> Option<Mark> {
+        self.profile.as_ref().map(|profile| Mark {
+            at: Instant::now(),
+            waiting: profile.borrow().waiting(),
+            serialized: self.transport.serialization_time(),
+        })
+    }
 
-        self.sender.send(Messages::And {
-            s_i: s_i1,
-            s_j: s_j1,
-        })?;
-        let Messages::And {
+    /// Adds the time since `mark` to the profile, as returned by `f` from the profile and the
+    /// elapsed time.
+    fn profile_since(&self, mark: Option<Mark>, f: impl FnOnce(&mut Profile, Mark, Duration)) {
+        if let (Some(profile), Some(mark)) = (&self.profile, mark) {
+            let elapsed = mark.at.elapsed();
+            f(&mut profile.borrow_mut(), mark, elapsed);
+        }
+    }
+
+    /// Adds the time since `mark` to the profile as time spent on a gate of the given type. The
+    /// part of it in which the party did not wait for anything counts as local computation.
+    fn profile_gate(&self, mark: Option<Mark>, gate: &GateType) {
+        self.profile_since(mark, |profile, mark, elapsed| {
+            let waited = profile.waiting() - mark.waiting;
+            profile.local += elapsed.saturating_sub(waited);
+            match gate {
+                GateType::XOR(_, _) => profile.xor_gates += elapsed,
+                GateType::AND(_, _) => profile.and_gates += elapsed,
+                GateType::INV(_) => profile.inv_gates += elapsed,
+            }
+        });
+    }
+
+    /// Adds the time since `mark` to the profile as time spent sending or receiving a message of
+    /// the given phase.
+    fn profile_message(&self, mark: Option<Mark>, phase: Phase) {
+        let serialized = self.transport.serialization_time();
+        self.profile_since(mark, |profile, mark, elapsed| {
+            let serialization = serialized.saturating_sub(mark.serialized);
+            profile.serialization += serialization;
+            let communication = elapsed.saturating_sub(serialization);
+            match phase {
+                Phase::And { .. } => profile.and_communication += communication,
+                _ => profile.other_communication += communication,
+            }
+        });
+    }
+
+    /// Runs `f` as an execution which counts towards the total of the profile.
+    fn profiled<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mark = self.mark();
+        let before = allocations();
+        let result = f(self);
+        let allocated = allocations().saturating_sub(before);
+        self.profile_since(mark, |profile, _, elapsed| {
+            profile.total += elapsed;
+            profile.allocations += allocated;
+        });
+        result
+    }
+
+    /// Counts a new communication round of the given phase.
+    fn next_round(&mut self, phase: Phase) {
+        self.observe(|o| o.on_round(phase, self.sequence));
+        self.sequence += 1;
+    }
+
+    /// Declares the input value with the given index in the header as public, with the given bits.
+    /// It is not secret-shared, instead party 0 takes the bits as its shares and party 1 zeros, so
+    /// the input of the party which would supply the value no longer contains it. Both parties
+    /// have to declare the same public values.
+    pub fn set_public_input(
+        &mut self,
+        value_index: usize,
+        bits: Vec<bool>,
+    ) -> Result<(), PartyError> {
+        let Some(&width) = self.circuit.header().niv.get(value_index) else {
+            return Err(PartyError::InvalidPublicInput(value_index));
+        };
+        if bits.len() != width {
+            return Err(PartyError::WrongInputLength(width, bits.len()));
+        }
+        self.public_inputs.insert(value_index, bits);
+        Ok(())
+    }
+
+    /// Switches to the next circuit of a session, keeping the connection and the triple provider,
+    /// so that several circuits are evaluated without setting up the parties again. The state of
+    /// the previous run and the public inputs are discarded. Both parties announce the number of
+    /// the run and the size of their circuit to each other, and fail with
+    /// [`PartyError::RunMismatch`] or [`PartyError::CircuitMismatch`] if they got out of step.
+    pub fn next_circuit(&mut self, circuit: impl Into<Arc<Circuit>>) -> Result<(), PartyError> {
+        let circuit = circuit.into();
+        circuit.ensure_valid().map_err(PartyError::InvalidCircuit)?;
+        self.greet()?;
+        self.circuit = circuit;
+        self.wires.zeroize();
+        self.gate_index = 0;
+        self.round = None;
+        self.public_inputs.clear();
+        self.runs += 1;
+
+        let phase = Phase::NextCircuit { run: self.runs };
+        let (gates, wires) = (
+            self.circuit.gates().len(),
+            self.circuit.header().wires_amount,
+        );
+        self.next_round(phase);
+        self.send(
+            Messages::NextCircuit {
+                run: self.runs,
+                gates,
+                wires,
+            },
+            phase,
+        )?;
+        let Messages::NextCircuit {
+            run,
+            gates: their_gates,
+            wires: their_wires,
+        } = self.recv(phase)?
+        else {
+            return Err(PartyError::UnexpectedMessage { phase });
+        };
+        if run != self.runs {
+            return Err(PartyError::RunMismatch {
+                ours: self.runs,
+                theirs: run,
+            });
+        }
+        if (their_gates, their_wires) != (gates, wires) {
+            return Err(PartyError::CircuitMismatch { run });
+        }
+        Ok(())
+    }
+
+    /// Evaluates the next circuit of a session: switches to it with [`Party::next_circuit`] and
+    /// executes it like [`Party::execute`].
+    pub fn execute_next(
+        &mut self,
+        circuit: impl Into<Arc<Circuit>>,
+        input: &[bool],
+    ) -> Result<Vec<bool>, PartyError> {
+        self.next_circuit(circuit)?;
+        self.execute(input)
+    }
+
+    /// The sequence number of the messages of the given phase in the current round, see
+    /// [`Envelope`]: the number of the round. The handshake and the resume exchange are numbered
+    /// 0 instead, they open a connection, and the latter is where the parties compare their
+    /// positions in the first place.
+    fn message_number(&self, phase: Phase) -> usize {
+        match phase {
+            Phase::Handshake | Phase::Resume => 0,
+            _ => self
+                .sequence
+                .checked_sub(1)
+                .expect("messages are only exchanged within a round"),
+        }
+    }
+
+    /// Sends a message of the current round to the other party. A closed transport means that the
+    /// other party is gone, which is reported together with the protocol phase this party was in,
+    /// as are timeouts.
+    fn send(&self, message: Messages, phase: Phase) -> Result<(), PartyError> {
+        self.observe(|o| o.on_message(Direction::Sent, &message));
+        let mark = self.mark();
+        let envelope = Envelope::new(self.message_number(phase), message);
+        let result = self
+            .transport
+            .send(envelope)
+            .map_err(|e| in_phase(e, phase));
+        self.profile_message(mark, phase);
+        result
+    }
+
+    /// Receives the next message from the other party, see [`Party::send`]. Fails with
+    /// [`PartyError::OutOfSyncMessage`] if it is not the other party's message of the current
+    /// round.
+    fn recv(&self, phase: Phase) -> Result<Messages, PartyError> {
+        let mark = self.mark();
+        let envelope = self.transport.recv().map_err(|e| in_phase(e, phase));
+        self.profile_message(mark, phase);
+        let message = envelope?.open(self.message_number(phase), phase)?;
+        self.observe(|o| o.on_message(Direction::Received, &message));
+        Ok(message)
+    }
+
+    /// Evaluates a whole layer of independent AND gates in a single communication round.
+    /// `inputs` and `triples` are matched up by position, the output shares are written to
+    /// `outputs` in the same order. Failures are reported for `phase`, which names the first gate
+    /// of the layer.
+    fn evaluate_and_batch(
+        &self,
+        phase: Phase,
+        inputs: &[(bool, bool)],
+        triples: &[MulTriple],
+        outputs: &mut Vec<bool>,
+    ) -> Result<(), PartyError> {
+        // the opened values are handed to the transport, and recomputed from the inputs and
+        // triples below instead of being kept as a copy
+        let mut s_i1 = Vec::with_capacity(inputs.len());
+        let mut s_j1 = Vec::with_capacity(inputs.len());
+        for (&(x, y), t) in inputs.iter().zip(triples) {
+            s_i1.push(x ^ t.a);
+            s_j1.push(y ^ t.b);
+        }
+
+        self.send(
+            Messages::AndBatch {
+                s_i: s_i1,
+                s_j: s_j1,
+            },
+            phase,
+        )?;
+        let Messages::AndBatch {
             s_i: s_i2,
             s_j: s_j2,
-        } = self.receiver.recv()?
+        } = self.recv(phase)?
         else {
-            return Err(PartyError::ThreadReceivingError);
+            return Err(PartyError::UnexpectedMessage { phase });
         };
 
-        let (s_i, s_j) = (s_i1 ^ s_i2, s_j1 ^ s_j2);
+        if s_i2.len() != triples.len() || s_j2.len() != triples.len() {
+            return Err(PartyError::UnexpectedMessage { phase });
+        }
+
+        outputs.extend(
+            inputs
+                .iter()
+                .zip(triples)
+                .enumerate()
+                .map(|(k, (&(x, y), t))| {
+                    self.combine_and(x ^ t.a ^ s_i2[k], y ^ t.b ^ s_j2[k], t.a, t.b, t.c)
+                }),
+        );
+        Ok(())
+    }
 
+    /// Computes this party's share of the AND output from the opened values s_i = x ^ a and
+    /// s_j = y ^ b. Only one party adds the public term s_i & s_j.
+    fn combine_and(&self, s_i: bool, s_j: bool, a: bool, b: bool, c: bool) -> bool {
         if !self.is_p1 {
-            Ok(s_i & b ^ s_j & a ^ c ^ s_i & s_j)
+            s_i & b ^ s_j & a ^ c ^ s_i & s_j
         } else {
-            Ok(s_i & b ^ s_j & a ^ c)
+            s_i & b ^ s_j & a ^ c
+        }
+    }
+
+    /// Returns this party's share of the given input wire of a gate.
+    fn get_operand(&self, gate_index: usize, operand: Operand) -> Result<bool, PartyError> {
+        let gate = &self.circuit.gates()[gate_index].gate_type;
+        let wire = match (gate, operand) {
+            (GateType::XOR(a, _) | GateType::AND(a, _) | GateType::INV(a), Operand::Left) => a,
+            (GateType::XOR(_, b) | GateType::AND(_, b), Operand::Right) => b,
+            (GateType::INV(_), Operand::Right) => unreachable!("INV gates have a single input"),
         }
+        .index();
+        self.get_wire_value(wire)?
+            .ok_or_else(|| PartyError::WireNotSet {
+                wire,
+                gate_index,
+                gate: gate.clone(),
+                operand,
+            })
+    }
+
+    /// Returns this party's share of a wire, `None` if it is not set yet. All wire reads of an
+    /// execution go through here: [`Party::new`] rejects circuits with wires beyond the header,
+    /// but should a wire slip through anyway, the party fails with
+    /// [`PartyError::WireIndexOutOfRange`] instead of panicking.
+    fn get_wire_value(&self, wire: usize) -> Result<Option<bool>, PartyError> {
+        self.wires
+            .get(wire)
+            .copied()
+            .ok_or(PartyError::WireIndexOutOfRange {
+                wire,
+                wires_amount: self.wires.len(),
+            })
+    }
+
+    /// Sets this party's share of a wire, failing like [`Party::get_wire_value`].
+    fn set_wire_value(&mut self, wire: usize, share: Option<bool>) -> Result<(), PartyError> {
+        let wires_amount = self.wires.len();
+        let slot = self
+            .wires
+            .get_mut(wire)
+            .ok_or(PartyError::WireIndexOutOfRange { wire, wires_amount })?;
+        *slot = share;
+        Ok(())
+    }
+
+    /// Sets this party's share of the output wire of a gate.
+    fn set_output(&mut self, gate_index: usize, share: bool) -> Result<(), PartyError> {
+        let wire = self.circuit.gates()[gate_index].output.index();
+        self.set_wire_value(wire, Some(share))
     }
 
-    fn get_wire_value(&self, wires: &[Option<bool>], w: usize) -> Result<bool, PartyError<'_>> {
-        match wires[w] {
-            Some(value) => Ok(value),
-            None => {
-                return Err(PartyError::WireNotSetError(w));
+    /// Returns this party's share of an output bit, counting the output bits from 0. Fails if no
+    /// gate assigned the output wire.
+    fn get_output_share(&self, index: usize) -> Result<bool, PartyError> {
+        let wire = self.circuit.get_output_wires() + index;
+        self.get_wire_value(wire)?
+            .ok_or(PartyError::InvalidCircuit(CircuitError::WireNotSetError(
+                wire,
+            )))
+    }
+
+    fn next_triple(&mut self) -> Result<MulTriple, PartyError> {
+        let mark = self.mark();
+        let triple = self.mtp.try_get_triple();
+        self.profile_since(mark, |profile, _, elapsed| {
+            profile.triple_fetching += elapsed
+        });
+        let triple = triple.map_err(PartyError::TripleUnavailable)?;
+        self.consume_triples(&[triple])?;
+        Ok(triple)
+    }
+
+    /// Takes `count` triples at once, which lets the provider generate them in bulk, see
+    /// [`MTProvider::try_get_triples`].
+    fn next_triples(&mut self, count: usize) -> Result<Vec<MulTriple>, PartyError> {
+        let mark = self.mark();
+        let triples = self.mtp.try_get_triples(count);
+        self.profile_since(mark, |profile, _, elapsed| {
+            profile.triple_fetching += elapsed
+        });
+        let triples = triples.map_err(PartyError::TripleUnavailable)?;
+        self.consume_triples(&triples)?;
+        Ok(triples)
+    }
+
+    /// Counts the taken triples, and keeps them for checking or dumping them if requested.
+    fn consume_triples(&mut self, triples: &[MulTriple]) -> Result<(), PartyError> {
+        self.triples_consumed += triples.len();
+        if let Some(checked) = &mut self.checked_triples {
+            checked.extend_from_slice(triples);
+        }
+        if let Some(dump) = &mut self.triple_dump {
+            // flushed right away, so the dump is complete even if the run fails later on
+            triples
+                .iter()
+                .try_for_each(|triple| triple.write_line(dump))
+                .and_then(|_| dump.flush())
+                .map_err(|e| PartyError::PError(Box::new(e)))?;
+        }
+        Ok(())
+    }
+
+    /// Evaluates an XOR or INV gate, which need no interaction with the other party.
+    fn evaluate_local(&mut self, gate_index: usize) -> Result<(), PartyError> {
+        let mark = self.mark();
+        let gate_type = self.circuit.gates()[gate_index].gate_type.clone();
+        self.observe(|o| o.on_gate_start(gate_index, &gate_type));
+        match gate_type {
+            GateType::INV(_) => {
+                let input = self.get_operand(gate_index, Operand::Left)?;
+                let negates = match self.inv_convention {
+                    InvConvention::Party0Negates => !self.is_p1,
+                    InvConvention::Party1Negates => self.is_p1,
+                };
+                self.set_output(gate_index, input ^ negates)?;
             }
+            GateType::XOR(_, _) => {
+                let input1 = self.get_operand(gate_index, Operand::Left)?;
+                let input2 = self.get_operand(gate_index, Operand::Right)?;
+                self.set_output(gate_index, input1 ^ input2)?;
+            }
+            GateType::AND(_, _) => unreachable!("AND gates require interaction"),
         }
+        self.profile_gate(mark, &gate_type);
+        self.observe(|o| o.on_gate_done(gate_index));
+        Ok(())
     }
 
-    /// Executes the GMW protocol with the linked party for the stored circuit.
-    pub fn execute(&mut self, input: &[bool; 64]) -> Result<Vec<bool>, PartyError> {
-        // TODO change error type
-        // Iterate over the stored circuit in topological order. `match` on the gate type and
-        // evaluate it, potentially using a multiplication triple for and And Gate and communication
-        // over the shared channel.
+    /// Secret-shares this party's input with the other party and resets the wire buffer to
+    /// contain the shares of all input wires.
+    fn share_inputs(&mut self, input: &[bool]) -> Result<(), PartyError> {
+        self.check_input_width(input)?;
+        self.greet()?;
+        self.start_input_sharing(input)?;
+        self.finish_round()?;
+        Ok(())
+    }
 
-        let circuit = &self.circuit;
+    /// Checks that the input has the width of the input values this party supplies.
+    fn check_input_width(&self, input: &[bool]) -> Result<(), PartyError> {
+        let (width_p0, width_p1) = self.input_widths();
+        let own_width = if self.is_p1 { width_p1 } else { width_p0 };
+        if input.len() != own_width {
+            return Err(PartyError::WrongInputLength(own_width, input.len()));
+        }
+        Ok(())
+    }
+
+    /// Checks the protocol versions and the settings both parties have to agree on with the
+    /// other party in a handshake, unless that was done before on this connection. The settings
+    /// take a round of their own, so that no party sends two messages before receiving one.
+    fn greet(&mut self) -> Result<(), PartyError> {
+        if !self.versions_checked {
+            self.send_hello()?;
+            self.check_hello()?;
+        }
+        if !self.greeted {
+            self.send_settings()?;
+            self.check_settings()?;
+        }
+        Ok(())
+    }
 
-        let mut wires: Vec<Option<bool>> = vec![None; circuit.header.wires_amount];
+    /// Sends this party's protocol version in the first round of the handshake.
+    fn send_hello(&mut self) -> Result<(), PartyError> {
+        self.next_round(Phase::Handshake);
+        let hello = handshake::preamble(PROTOCOL_VERSION);
+        self.send(Messages::Hello { preamble: hello }, Phase::Handshake)
+    }
 
-        let (mut private_share, public_share): (Vec<bool>, Vec<bool>) = generate_shares(input);
+    /// Checks the protocol version of the other party, see [`Party::send_hello`].
+    fn check_hello(&mut self) -> Result<(), PartyError> {
+        handshake::check_hello(self.recv(Phase::Handshake)?, PROTOCOL_VERSION)?;
+        self.versions_checked = true;
+        Ok(())
+    }
 
-        self.sender.send(Messages::Shares {
-            shares: public_share,
-        })?;
+    /// Sends the settings both parties have to agree on in the second round of the handshake.
+    fn send_settings(&mut self) -> Result<(), PartyError> {
+        self.next_round(Phase::Handshake);
+        let output_chunk_size = self.output_chunk_size;
+        self.send(Messages::Settings { output_chunk_size }, Phase::Handshake)
+    }
 
-        let Messages::Shares {
-            shares: mut others_shares,
-        } = self.receiver.recv()?
+    /// Checks the settings of the other party against this party's ones, see
+    /// [`Party::send_settings`].
+    fn check_settings(&mut self) -> Result<(), PartyError> {
+        let phase = Phase::Handshake;
+        let Messages::Settings {
+            output_chunk_size: theirs,
+        } = self.recv(phase)?
         else {
-            return Err(PartyError::ThreadReceivingError);
+            return Err(PartyError::UnexpectedMessage { phase });
         };
+        if theirs != self.output_chunk_size {
+            return Err(PartyError::OutputChunkSizeMismatch {
+                ours: self.output_chunk_size,
+                theirs,
+            });
+        }
+        self.greeted = true;
+        Ok(())
+    }
+
+    /// Marks the protocol versions as checked, e.g. by a handshake before creating the party.
+    /// The settings are still compared in the handshake of the first execution.
+    pub(crate) fn set_versions_checked(&mut self) {
+        self.versions_checked = true;
+    }
+
+    /// Resets the wire buffer and sends the shares of this party's input to the other party. The
+    /// input wires are set once the other party's shares are received by [`Party::finish_round`].
+    fn start_input_sharing(&mut self, input: &[bool]) -> Result<(), PartyError> {
+        self.check_input_width(input)?;
 
-        let share = if self.is_p1 {
-            private_share.extend_from_slice(&others_shares);
-            private_share
+        // reuse the wire buffer, so that the shares of a previous run do not linger in memory
+        self.wires.zeroize();
+        self.wires.resize(self.circuit.header().wires_amount, None);
+        self.gate_index = 0;
+
+        let (private_share, public_share): (Vec<bool>, Vec<bool>) = if self.hide_input_ownership {
+            // the input in its place among the bits of both parties, with 0 for the other's bits
+            let (width_p0, _) = self.input_widths();
+            let mut padded = Zeroizing::new(vec![false; self.secret_input_width()]);
+            let offset = if self.is_p1 { width_p0 } else { 0 };
+            padded[offset..offset + input.len()].copy_from_slice(input);
+            generate_shares(&mut self.share_rng, &padded)
         } else {
-            others_shares.extend_from_slice(&private_share);
-            others_shares
-        };
-
-        for (i, &wire) in share.iter().enumerate() {
-            wires[i] = Some(wire);
-        }
-
-        for Gate { gate_type, output } in &circuit.gates {
-            let output_index: usize = *output;
-            match *gate_type {
-                GateType::INV(a) => {
-                    let input = match self.get_wire_value(&wires, a) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    };
-                    if self.is_p1 {
-                        wires[output_index] = Some(!input);
-                    } else {
-                        wires[output_index] = Some(input);
+            generate_shares(&mut self.share_rng, input)
+        };
+
+        self.next_round(Phase::InputSharing);
+        self.send(
+            Messages::Shares {
+                shares: public_share,
+            },
+            Phase::InputSharing,
+        )?;
+        self.round = Some(Round::InputSharing {
+            private_share: Zeroizing::new(private_share),
+        });
+        Ok(())
+    }
+
+    /// Receives the other party's message of the round this party waits for and finishes it.
+    /// Returns the reconstructed output once the output exchange is finished.
+    fn finish_round(&mut self) -> Result<Option<Vec<bool>>, PartyError> {
+        let Some(round) = self.round.take() else {
+            return Err(PartyError::NotStarted);
+        };
+
+        match round {
+            Round::Hello { input } => {
+                self.check_hello()?;
+                self.send_settings()?;
+                self.round = Some(Round::Settings { input });
+                Ok(None)
+            }
+            Round::Settings { input } => {
+                self.check_settings()?;
+                self.start_input_sharing(&input)?;
+                Ok(None)
+            }
+            Round::InputSharing { private_share } => {
+                let Messages::Shares {
+                    shares: others_shares,
+                } = self.recv(Phase::InputSharing)?
+                else {
+                    return Err(PartyError::UnexpectedMessage {
+                        phase: Phase::InputSharing,
+                    });
+                };
+                let (width_p0, width_p1) = self.input_widths();
+                let others_width = match (self.hide_input_ownership, self.is_p1) {
+                    (true, _) => self.secret_input_width(),
+                    (false, true) => width_p0,
+                    (false, false) => width_p1,
+                };
+                if others_shares.len() != others_width {
+                    return Err(PartyError::WrongInputLength(
+                        others_width,
+                        others_shares.len(),
+                    ));
+                }
+
+                let others_shares = Zeroizing::new(others_shares);
+
+                // the shares of the input values in the order of the input layout, except for the
+                // public values
+                let combined: Zeroizing<Vec<bool>>;
+                let (first, second): (&[bool], &[bool]) = if self.hide_input_ownership {
+                    // both parties shared all bits, the shares of the other party's bits add up
+                    // to its input and those of this party's bits to this party's input
+                    combined = Zeroizing::new(
+                        private_share
+                            .iter()
+                            .zip(others_shares.iter())
+                            .map(|(x, y)| x ^ y)
+                            .collect(),
+                    );
+                    combined.split_at(width_p0)
+                } else if self.is_p1 {
+                    (&others_shares, &private_share)
+                } else {
+                    (&private_share, &others_shares)
+                };
+                let mut shares = [first.iter(), second.iter()];
+                for (value, (party, wires)) in self.circuit.input_layout().into_iter().enumerate() {
+                    for (bit, wire) in wires.enumerate() {
+                        let share = match self.public_inputs.get(&value) {
+                            Some(bits) => Some(bits[bit] && !self.is_p1),
+                            None => shares[party.index()].next().copied(),
+                        };
+                        self.set_wire_value(wire, share)?;
                     }
                 }
-                GateType::XOR(a, b) => {
-                    let input1 = match self.get_wire_value(&wires, a) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    };
-
-                    let input2 = match self.get_wire_value(&wires, b) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    };
-
-                    wires[output_index] = Some(input1 ^ input2);
+                Ok(None)
+            }
+            Round::And {
+                phase,
+                opened: (s_i1, s_j1),
+                mut triple,
+            } => {
+                let mark = self.mark();
+                let MulTriple { a, b, c } = triple;
+                triple.zeroize();
+
+                let Messages::And {
+                    s_i: s_i2,
+                    s_j: s_j2,
+                } = self.recv(phase)?
+                else {
+                    return Err(PartyError::UnexpectedMessage { phase });
+                };
+
+                let output = self.combine_and(s_i1 ^ s_i2, s_j1 ^ s_j2, a, b, c);
+                self.set_output(self.gate_index, output)?;
+                self.profile_gate(mark, &self.circuit.gates()[self.gate_index].gate_type);
+                self.observe(|o| o.on_gate_done(self.gate_index));
+                self.gate_index += 1;
+                Ok(None)
+            }
+            Round::Output { shares, received } => {
+                let mut others_shares = match received {
+                    Some(received) => received,
+                    None => self.recv_output_chunks(shares.len())?,
+                };
+                self.check_triples()?;
+                for (output, share) in others_shares.iter_mut().zip(&shares) {
+                    *output ^= share;
                 }
-                GateType::AND(a, b) => {
-                    let input1 = match self.get_wire_value(&wires, a) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    };
-
-                    let input2 = match self.get_wire_value(&wires, b) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    };
-
-                    wires[output_index] = Some(self.evaluate_and(input1, input2)?);
+                Ok(Some(others_shares))
+            }
+        }
+    }
+
+    /// Opens the triples consumed in the run if [`Party::set_check_triples`] is enabled, and
+    /// checks that every reconstructed triple satisfies c = a & b.
+    fn check_triples(&mut self) -> Result<(), PartyError> {
+        let Some(checked) = &mut self.checked_triples else {
+            return Ok(());
+        };
+        let ours = Zeroizing::new(std::mem::replace(
+            checked,
+            Vec::with_capacity(self.circuit.required_triples()),
+        ));
+
+        let phase = Phase::TripleCheck;
+        self.next_round(phase);
+        self.send(
+            Messages::TripleShares {
+                a: ours.iter().map(|t| t.a).collect(),
+                b: ours.iter().map(|t| t.b).collect(),
+                c: ours.iter().map(|t| t.c).collect(),
+            },
+            phase,
+        )?;
+        let Messages::TripleShares { a, b, c } = self.recv(phase)? else {
+            return Err(PartyError::UnexpectedMessage { phase });
+        };
+        if [&a, &b, &c].iter().any(|bits| bits.len() != ours.len()) {
+            return Err(PartyError::UnexpectedMessage { phase });
+        }
+
+        for (i, t) in ours.iter().enumerate() {
+            if (t.a ^ a[i]) & (t.b ^ b[i]) != t.c ^ c[i] {
+                return Err(PartyError::InvalidTriple(i));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of input bits supplied by both parties together, without the public values.
+    fn secret_input_width(&self) -> usize {
+        let (width_p0, width_p1) = self.input_widths();
+        width_p0 + width_p1
+    }
+
+    /// Number of input bits supplied by party 0 and party 1. Party 0 supplies the first input
+    /// value declared in the header, party 1 all remaining ones, except for the public values.
+    fn input_widths(&self) -> (usize, usize) {
+        let mut widths = [0, 0];
+        for (value, (party, wires)) in self.circuit.input_layout().into_iter().enumerate() {
+            if !self.public_inputs.contains_key(&value) {
+                widths[party.index()] += wires.len();
+            }
+        }
+        (widths[0], widths[1])
+    }
+
+    /// Evaluates the gates from the current gate index on in topological order.
+    fn evaluate_gates(&mut self) -> Result<(), PartyError> {
+        loop {
+            self.evaluate_until_and()?;
+            if self.round.is_none() {
+                return Ok(());
+            }
+            self.finish_round()?;
+        }
+    }
+
+    /// Evaluates the gates from the current gate index on, up to the next AND gate. For that gate,
+    /// this party's opened values are sent to the other party, the gate is then finished by
+    /// [`Party::finish_round`]. Stops without starting a round after the last gate.
+    fn evaluate_until_and(&mut self) -> Result<(), PartyError> {
+        while self.gate_index < self.circuit.gates().len() {
+            if self.halt_at == Some(self.gate_index) {
+                return Err(PartyError::Halted(self.gate_index));
+            }
+            if let Some((interval, path)) = &self.checkpointing {
+                if self.gate_index.is_multiple_of(*interval) {
+                    self.checkpoint()
+                        .save(path)
+                        .map_err(|e| PartyError::PError(Box::new(e)))?;
                 }
             }
+
+            if let GateType::AND(_, _) = self.circuit.gates()[self.gate_index].gate_type {
+                return self.start_and();
+            }
+
+            self.evaluate_local(self.gate_index)?;
+            self.gate_index += 1;
         }
+        Ok(())
+    }
+
+    /// Sends this party's opened values for the AND gate at the current gate index, the gate is
+    /// then finished by [`Party::finish_round`].
+    fn start_and(&mut self) -> Result<(), PartyError> {
+        let mark = self.mark();
+        let gate_index = self.gate_index;
+        self.observe(|o| o.on_gate_start(gate_index, &self.circuit.gates()[gate_index].gate_type));
+        let x = self.get_operand(self.gate_index, Operand::Left)?;
+        let y = self.get_operand(self.gate_index, Operand::Right)?;
+
+        // every AND gate is a communication round of its own
+        let phase = Phase::And {
+            round: self.triples_consumed,
+            gate_index: self.gate_index,
+        };
+        let triple = self.next_triple()?;
+        let opened = (x ^ triple.a, y ^ triple.b);
+        self.next_round(phase);
+        self.send(
+            Messages::And {
+                s_i: opened.0,
+                s_j: opened.1,
+            },
+            phase,
+        )?;
+        self.round = Some(Round::And {
+            phase,
+            opened,
+            triple,
+        });
+        self.profile_gate(mark, &self.circuit.gates()[gate_index].gate_type);
+        Ok(())
+    }
+
+    /// Exchanges the shares of the output wires and reconstructs the output.
+    fn reconstruct_outputs(&mut self) -> Result<Vec<bool>, PartyError> {
+        let all: Vec<usize> = (0..self.circuit.get_nov_sum()).collect();
+        self.reconstruct_selected(&all)
+    }
+
+    /// Exchanges the shares of the given output bits only and reconstructs them. The indices count
+    /// the output bits from 0 and must have been checked to be in range.
+    fn reconstruct_selected(&mut self, output_indices: &[usize]) -> Result<Vec<bool>, PartyError> {
+        self.start_output_exchange(output_indices)?;
+        Ok(self
+            .finish_round()?
+            .expect("finishing the output exchange returns the output"))
+    }
+
+    /// Sends this party's shares of the given output bits to the other party, see
+    /// [`Party::reconstruct_selected`].
+    fn start_output_exchange(&mut self, output_indices: &[usize]) -> Result<(), PartyError> {
+        let shares: Vec<bool> = output_indices
+            .iter()
+            .map(|&i| self.get_output_share(i))
+            .collect::<Result<_, _>>()?;
+        // the evaluation is done, only the output shares are needed from here on
+        self.wires.zeroize();
+
+        self.next_round(Phase::OutputExchange);
+        // with several chunks, party 1 takes all chunks of party 0 before sending its own, see
+        // Party::set_output_chunk_size
+        let received = match self.is_p1 && shares.len() > self.output_chunk_size {
+            true => Some(self.recv_output_chunks(shares.len())?),
+            false => None,
+        };
+        self.send_output_chunks(&shares)?;
+        self.round = Some(Round::Output { shares, received });
+        Ok(())
+    }
+
+    /// Sends the output shares in chunks of the output chunk size, see
+    /// [`Party::set_output_chunk_size`]. Without any shares, a single empty chunk is sent.
+    fn send_output_chunks(&mut self, shares: &[bool]) -> Result<(), PartyError> {
+        let phase = Phase::OutputExchange;
+        if shares.is_empty() {
+            return self.send(Messages::Result(Vec::new()), phase);
+        }
+        for chunk in shares.chunks(self.output_chunk_size) {
+            self.send(Messages::Result(chunk.to_vec()), phase)?;
+        }
+        Ok(())
+    }
+
+    /// Receives the other party's chunks of `total` output shares, see
+    /// [`Party::send_output_chunks`].
+    fn recv_output_chunks(&mut self, total: usize) -> Result<Vec<bool>, PartyError> {
+        let phase = Phase::OutputExchange;
+        let mut others_shares = Vec::new();
+        loop {
+            let Messages::Result(chunk) = self.recv(phase)? else {
+                return Err(PartyError::UnexpectedMessage { phase });
+            };
+            // a short message would silently cut off output bits
+            let expected = (total - others_shares.len()).min(self.output_chunk_size);
+            if chunk.len() != expected {
+                return Err(PartyError::UnexpectedMessage { phase });
+            }
+            if others_shares.is_empty() {
+                // the first chunk becomes the buffer the output is reconstructed in
+                others_shares = chunk;
+                others_shares.reserve_exact(total - others_shares.len());
+            } else {
+                others_shares.extend(chunk);
+            }
+            if others_shares.len() == total {
+                return Ok(others_shares);
+            }
+        }
+    }
+
+    /// Starts a step-wise execution of the GMW protocol by sending the shares of this party's
+    /// input. Continue it with [`Party::step`].
+    pub fn start(&mut self, input: &[bool]) -> Result<(), PartyError> {
+        self.check_revealed_to_both()?;
+        if self.greeted {
+            return self.start_input_sharing(input);
+        }
+        self.check_input_width(input)?;
+        // the handshake takes rounds of its own, the input is shared after it
+        let input = Zeroizing::new(input.to_vec());
+        self.round = Some(if self.versions_checked {
+            self.send_settings()?;
+            Round::Settings { input }
+        } else {
+            self.send_hello()?;
+            Round::Hello { input }
+        });
+        Ok(())
+    }
+
+    /// Continues an execution started by [`Party::start`]: receives the other party's message of
+    /// the current round, evaluates the gates up to the next AND gate and sends the message of the
+    /// next round. Every step receives exactly one message and sends at most one, so both parties
+    /// can take turns on a single thread, see [`crate::party::local::run_local`]. Returns the
+    /// output bits once they are reconstructed, like [`Party::execute`].
+    pub fn step(&mut self) -> Result<Option<Vec<bool>>, PartyError> {
+        if let Some(output) = self.finish_round()? {
+            return Ok(Some(output));
+        }
+        if self.round.is_some() {
+            // the handshake is done and the input sharing started
+            return Ok(None);
+        }
+        self.evaluate_until_and()?;
+        if self.round.is_none() {
+            let all: Vec<usize> = (0..self.circuit.get_nov_sum()).collect();
+            self.start_output_exchange(&all)?;
+        }
+        Ok(None)
+    }
+
+    /// Executes the GMW protocol with the linked party for the stored circuit. The input must have
+    /// the width of the input value(s) this party supplies. The output bits of all output values
+    /// are returned in one vector, see [`Party::execute_structured`] for the grouped form. With
+    /// another [`OutputMode`], only one party or none of them learns the output.
+    pub fn execute(&mut self, input: &[bool]) -> Result<Vec<bool>, PartyError> {
+        self.profiled(|party| Ok(party.evaluate_structured(input)?.concat()))
+    }
+
+    /// Executes the GMW protocol like [`Party::execute`], but returns the output grouped into the
+    /// output values declared in the header, in header order and each of its declared width. A
+    /// party which learns nothing by the [`OutputMode`] gets no values at all.
+    pub fn execute_structured(&mut self, input: &[bool]) -> Result<Vec<Vec<bool>>, PartyError> {
+        self.profiled(|party| party.evaluate_structured(input))
+    }
+
+    /// Executes the GMW protocol like [`Party::execute_structured`], without counting towards
+    /// the total of the profile, e.g. for [`Party::execute`] which counts itself.
+    fn evaluate_structured(&mut self, input: &[bool]) -> Result<Vec<Vec<bool>>, PartyError> {
+        // Iterate over the stored circuit in topological order. `match` on the gate type and
+        // evaluate it, potentially using a multiplication triple for and And Gate and communication
+        // over the shared channel.
+        self.share_inputs(input)?;
+        self.evaluate_gates()?;
+        match self.finish_outputs()? {
+            Some(output) => self.split_outputs(output),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Reveals the output shares once all gates are evaluated, or keeps them, as the output mode
+    /// says, see [`Party::set_output_mode`]. Returns `None` to the party which learns nothing.
+    fn finish_outputs(&mut self) -> Result<Option<Vec<bool>>, PartyError> {
+        match self.output_mode {
+            OutputMode::Reveal => self.reconstruct_outputs().map(Some),
+            OutputMode::RevealTo(to) => self.reveal_outputs_to(to),
+            OutputMode::Shares => self.output_shares().map(Some),
+        }
+    }
+
+    /// Executes the GMW protocol like [`Party::execute_structured`], but turns a panic during the
+    /// execution into [`PartyError::Panicked`] with the panic message and the phase this party was
+    /// in. The party must not be used afterwards. Dropping it closes the transport, so the other
+    /// party fails with [`PartyError::PeerDisconnected`] instead of waiting for a message.
+    pub fn execute_structured_catching(
+        &mut self,
+        input: &[bool],
+    ) -> Result<Vec<Vec<bool>>, PartyError> {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.execute_structured(input))) {
+            Ok(result) => result,
+            Err(payload) => Err(PartyError::Panicked {
+                message: panic_message(&*payload),
+                phase: self.current_phase(),
+            }),
+        }
+    }
+
+    /// The protocol phase this party is in, derived from the round it waits for or the gate it
+    /// evaluates next.
+    fn current_phase(&self) -> Phase {
+        match &self.round {
+            Some(Round::Hello { .. } | Round::Settings { .. }) => Phase::Handshake,
+            Some(Round::InputSharing { .. }) => Phase::InputSharing,
+            Some(Round::And { phase, .. }) => *phase,
+            Some(Round::Output { .. }) => Phase::OutputExchange,
+            None if self.gate_index >= self.circuit.gates().len() => Phase::OutputExchange,
+            None if self.wires.is_empty() => Phase::InputSharing,
+            None => match self.circuit.gates()[self.gate_index].gate_type {
+                GateType::AND(_, _) => Phase::And {
+                    round: self.triples_consumed,
+                    gate_index: self.gate_index,
+                },
+                _ => Phase::LocalGate {
+                    gate_index: self.gate_index,
+                },
+            },
+        }
+    }
+
+    /// Executes the GMW protocol like [`Party::execute`] and reports the time this party spent on
+    /// each gate type, taken from a [`Profile`] of this execution alone. The execution counts
+    /// towards the profile of the party as well, if profiling is enabled.
+    #[cfg(feature = "timing")]
+    pub fn execute_timed(
+        &mut self,
+        input: &[bool],
+    ) -> Result<(Vec<bool>, TimingReport), PartyError> {
+        let outer = self.profile.replace(RefCell::new(Profile::default()));
+        let output = self.execute(input);
+        let run = std::mem::replace(&mut self.profile, outer)
+            .map(RefCell::into_inner)
+            .unwrap_or_default();
+        if let Some(outer) = &self.profile {
+            *outer.borrow_mut() += &run;
+        }
+        Ok((output?, TimingReport::from(&run)))
+    }
+
+    /// Executes the GMW protocol like [`Party::execute`], but reveals every output bit as soon as
+    /// the gate assigning it is evaluated, and passes it to `sink` right away. Outputs computed by
+    /// early gates are thereby seen long before the evaluation is finished. Every output bit is
+    /// revealed in a message of its own, only output wires are ever revealed. Returns all output
+    /// bits at the end, like [`Party::execute`]. Both parties learn every bit, so any output mode
+    /// but [`OutputMode::Reveal`] fails with [`PartyError::UnsupportedOutputMode`].
+    pub fn execute_streaming(
+        &mut self,
+        input: &[bool],
+        mut sink: impl FnMut(OutputBit),
+    ) -> Result<Vec<bool>, PartyError> {
+        self.check_revealed_to_both()?;
+        self.share_inputs(input)?;
+
+        let output_offset = self.circuit.get_output_wires();
+        let mut output = vec![false; self.circuit.get_nov_sum()];
+        // output wires which are input wires as well are not assigned by any gate, so they are
+        // revealed right away
+        let passed_through = self.circuit.get_niv_sum().saturating_sub(output_offset);
+        for (index, bit) in output.iter_mut().enumerate().take(passed_through) {
+            *bit = self.reveal_output_bit(index)?;
+            sink(OutputBit { index, value: *bit });
+        }
+        while self.gate_index < self.circuit.gates().len() {
+            let wire = self.circuit.gates()[self.gate_index].output.index();
+            if let GateType::AND(_, _) = self.circuit.gates()[self.gate_index].gate_type {
+                self.start_and()?;
+                self.finish_round()?;
+            } else {
+                self.evaluate_local(self.gate_index)?;
+                self.gate_index += 1;
+            }
+
+            if wire >= output_offset {
+                let index = wire - output_offset;
+                output[index] = self.reveal_output_bit(index)?;
+                sink(OutputBit {
+                    index,
+                    value: output[index],
+                });
+            }
+        }
+        // the evaluation is done, the output bits are revealed
+        self.wires.zeroize();
+        self.check_triples()?;
+        Ok(output)
+    }
+
+    /// Fails before anything is sent if the output mode keeps the output from a party, for the
+    /// executions which reveal it to both.
+    fn check_revealed_to_both(&self) -> Result<(), PartyError> {
+        match self.output_mode {
+            OutputMode::Reveal => Ok(()),
+            mode => Err(PartyError::UnsupportedOutputMode(mode)),
+        }
+    }
+
+    /// Exchanges the shares of a single output bit with the other party and reconstructs it.
+    fn reveal_output_bit(&mut self, index: usize) -> Result<bool, PartyError> {
+        let phase = Phase::OutputExchange;
+        let share = self.get_output_share(index)?;
+        self.next_round(phase);
+        self.send(Messages::OutputBit { index, share }, phase)?;
+        match self.recv(phase)? {
+            Messages::OutputBit {
+                index: their_index,
+                share: their_share,
+            } if their_index == index => Ok(share ^ their_share),
+            _ => Err(PartyError::UnexpectedMessage { phase }),
+        }
+    }
 
-        let output_offset = circuit.get_output_wires();
-        let sol1: Vec<bool> = wires
-            .into_iter()
-            .skip(output_offset)
-            .map(Option::unwrap)
+    /// Executes the GMW protocol like [`Party::execute`], but skips the output exchange and returns
+    /// this party's shares of the output bits instead. The outputs stay secret-shared, e.g. for
+    /// another protocol to continue with. Both parties have to use this mode, their shares are
+    /// combined with [`reconstruct`].
+    pub fn execute_to_shares(&mut self, input: &[bool]) -> Result<Vec<bool>, PartyError> {
+        self.share_inputs(input)?;
+        self.evaluate_gates()?;
+        self.output_shares()
+    }
+
+    /// Collects this party's shares of all output bits once the gates are evaluated, see
+    /// [`Party::execute_to_shares`].
+    fn output_shares(&mut self) -> Result<Vec<bool>, PartyError> {
+        let shares = (0..self.circuit.get_nov_sum())
+            .map(|index| self.get_output_share(index))
+            .collect::<Result<_, _>>()?;
+        self.wires.zeroize();
+        self.check_triples()?;
+        Ok(shares)
+    }
+
+    /// Executes the GMW protocol like [`Party::execute`], but only reveals the output to `party`.
+    /// The other party sends its output shares without getting any back, so it learns nothing
+    /// about the output and returns an empty vector. Both parties have to name the same party. The
+    /// shares are sent in chunks of the output chunk size, see [`Party::set_output_chunk_size`].
+    pub fn execute_reveal_to(
+        &mut self,
+        input: &[bool],
+        party: PartyId,
+    ) -> Result<Vec<bool>, PartyError> {
+        self.share_inputs(input)?;
+        self.evaluate_gates()?;
+        Ok(self.reveal_outputs_to(party)?.unwrap_or_default())
+    }
+
+    /// Reveals the output to `party` once the gates are evaluated, see
+    /// [`Party::execute_reveal_to`]. Returns `None` to the other party.
+    fn reveal_outputs_to(&mut self, party: PartyId) -> Result<Option<Vec<bool>>, PartyError> {
+        let shares = Zeroizing::new(self.output_shares()?);
+        self.next_round(Phase::OutputExchange);
+        if (party == PartyId::P1) != self.is_p1 {
+            self.send_output_chunks(&shares)?;
+            return Ok(None);
+        }
+        let theirs = self.recv_output_chunks(shares.len())?;
+        Ok(Some(reconstruct(&shares, &theirs)))
+    }
+
+    /// Executes the GMW protocol like [`Party::execute`], but only reveals the output bits with
+    /// the given indices (counted from 0 over all output bits), in the requested order. The other
+    /// output bits stay secret-shared. Both parties must request the same indices.
+    pub fn execute_select(
+        &mut self,
+        input: &[bool],
+        output_indices: &[usize],
+    ) -> Result<Vec<bool>, PartyError> {
+        let outputs = self.circuit.get_nov_sum();
+        if let Some(&index) = output_indices.iter().find(|&&i| i >= outputs) {
+            return Err(PartyError::InvalidOutputIndex(index, outputs));
+        }
+
+        self.share_inputs(input)?;
+        self.evaluate_gates()?;
+        self.reconstruct_selected(output_indices)
+    }
+
+    /// Splits the output bits into the output values declared by the nov line of the header.
+    fn split_outputs(&self, output: Vec<bool>) -> Result<Vec<Vec<bool>>, PartyError> {
+        let nov = &self.circuit.header().nov;
+        let declared: usize = nov.iter().sum();
+        if declared != output.len() {
+            return Err(PartyError::OutputWidthMismatch(declared, output.len()));
+        }
+
+        let mut rest = &output[..];
+        let mut values = Vec::with_capacity(nov.len());
+        for &width in nov {
+            let (value, tail) = rest.split_at(width);
+            values.push(value.to_vec());
+            rest = tail;
+        }
+        Ok(values)
+    }
+
+    /// Continues the evaluation of a party rebuilt by [`Party::resume`]. Both parties first agree
+    /// on the gate to continue from and fail with [`PartyError::ResumeMismatch`] if their
+    /// snapshots were taken at different gates. The output is handled by the [`OutputMode`], like
+    /// by [`Party::execute`].
+    pub fn execute_resumed(&mut self) -> Result<Vec<bool>, PartyError> {
+        self.greet()?;
+        self.next_round(Phase::Resume);
+        self.send(
+            Messages::Resume {
+                gate_index: self.gate_index,
+            },
+            Phase::Resume,
+        )?;
+        let Messages::Resume { gate_index } = self.recv(Phase::Resume)? else {
+            return Err(PartyError::UnexpectedMessage {
+                phase: Phase::Resume,
+            });
+        };
+        if gate_index != self.gate_index {
+            return Err(PartyError::ResumeMismatch {
+                ours: self.gate_index,
+                theirs: gate_index,
+            });
+        }
+
+        self.evaluate_gates()?;
+        Ok(self.finish_outputs()?.unwrap_or_default())
+    }
+
+    /// Executes the GMW protocol like [`Party::execute`], but evaluates all AND gates of the same
+    /// multiplicative depth in a single communication round. This reduces the number of rounds
+    /// from the number of AND gates to the AND depth of the circuit.
+    pub fn execute_batched(&mut self, input: &[bool]) -> Result<Vec<bool>, PartyError> {
+        self.profiled(|party| party.evaluate_batched(input))
+    }
+
+    /// Executes the GMW protocol like [`Party::execute_batched`], without counting towards the
+    /// total of the profile.
+    fn evaluate_batched(&mut self, input: &[bool]) -> Result<Vec<bool>, PartyError> {
+        self.share_inputs(input)?;
+
+        // the buffers are taken out of the party while it evaluates the layers, and put back
+        // even if that fails
+        let mut batch = std::mem::take(&mut self.batch);
+        let result = self.evaluate_layers(&mut batch);
+        batch.zeroize();
+        self.batch = batch;
+        result?;
+        self.gate_index = self.circuit.gates().len();
+
+        Ok(self.finish_outputs()?.unwrap_or_default())
+    }
+
+    /// Evaluates the gates layer by layer for [`Party::execute_batched`], with the AND gates of a
+    /// layer in a single round.
+    fn evaluate_layers(&mut self, batch: &mut BatchBuffers) -> Result<(), PartyError> {
+        // the circuit is shared, so that the layers can be borrowed while evaluating
+        let circuit = Arc::clone(&self.circuit);
+        let layers = circuit.layers();
+        let widest = layers
+            .iter()
+            .map(|layer| layer.and.len())
+            .max()
+            .unwrap_or(0);
+        // enough for every layer, which does nothing once the buffers were used for the circuit
+        batch.inputs.reserve(widest);
+        batch.outputs.reserve(widest);
+        // the triples of all layers are preprocessed at once
+        batch.triples = self.next_triples(circuit.required_triples())?;
+
+        let mut round = 0;
+        let mut used = 0;
+        for layer in layers {
+            for &g in &layer.local {
+                self.evaluate_local(g)?;
+            }
+
+            let and_gates = &layer.and;
+            if and_gates.is_empty() {
+                continue;
+            }
+
+            let mark = self.mark();
+            batch.inputs.zeroize();
+            batch.outputs.zeroize();
+            for &g in and_gates {
+                batch.inputs.push((
+                    self.get_operand(g, Operand::Left)?,
+                    self.get_operand(g, Operand::Right)?,
+                ));
+            }
+            let triples = used..used + and_gates.len();
+            used = triples.end;
+
+            let phase = Phase::And {
+                round,
+                gate_index: and_gates[0],
+            };
+            for &g in and_gates {
+                self.observe(|o| o.on_gate_start(g, &circuit.gates()[g].gate_type));
+            }
+            self.next_round(phase);
+            let result = self.evaluate_and_batch(
+                phase,
+                &batch.inputs,
+                &batch.triples[triples.clone()],
+                &mut batch.outputs,
+            );
+            batch.triples[triples].iter_mut().for_each(Zeroize::zeroize);
+            result?;
+            round += 1;
+            for (&g, &value) in and_gates.iter().zip(&batch.outputs) {
+                self.set_output(g, value)?;
+                self.observe(|o| o.on_gate_done(g));
+            }
+            self.profile_gate(mark, &circuit.gates()[and_gates[0]].gate_type);
+        }
+        Ok(())
+    }
+
+    /// Estimates the memory in bytes needed for evaluating the circuit: the wire buffer holding one
+    /// `Option<bool>` per wire, plus one multiplication triple per AND gate.
+    pub fn estimated_memory(&self) -> usize {
+        self.circuit.header().wires_amount * size_of::<Option<bool>>()
+            + self.circuit.required_triples() * size_of::<MulTriple>()
+    }
+
+    /// Returns the connection of this party to the other party.
+    pub fn transport(&self) -> &C {
+        &self.transport
+    }
+
+    /// Returns the multiplication triple provider of this party.
+    pub fn mtp(&self) -> &T {
+        &self.mtp
+    }
+
+    /// Number of multiplication triples this party has taken from its provider, over all runs of
+    /// a session.
+    pub fn triples_consumed(&self) -> usize {
+        self.triples_consumed
+    }
+
+    /// Number of communication rounds this party went through, over all runs of a session.
+    pub fn rounds(&self) -> usize {
+        self.sequence
+    }
+}
+
+impl<T: MTProvider, C: Transport> Drop for Party<T, C> {
+    fn drop(&mut self) {
+        self.wires.zeroize();
+        self.batch.zeroize();
+    }
+}
+
+/// Turns a closed transport into [`PartyError::PeerDisconnected`] and a timed out transport
+/// into [`PartyError::Timeout`] for the given phase.
+pub(crate) fn in_phase(e: PartyError, phase: Phase) -> PartyError {
+    match e {
+        PartyError::TransportClosed => PartyError::PeerDisconnected { phase },
+        PartyError::TransportTimeout => PartyError::Timeout { phase },
+        e => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        new_party_pair, new_party_pair_seeded, new_party_pair_with_capacity, reconstruct,
+        InvConvention, Messages, OutputMode, Party,
+    };
+    use crate::circuit::circuit_error::CircuitError;
+    use crate::circuit::circuit_generators::Builtin;
+    use crate::circuit::circuit_layout::PartyId;
+    use crate::circuit::circuit_parser::{Circuit, GateType, WireId};
+    use crate::input::to_bits;
+    use crate::mul_triple::{FileMTP, MTProvider, MulTriple, RecordingMTP, SeededMTP};
+    use crate::party::checkpoint::ExecutionSnapshot;
+    use crate::party::errors::{PartyError, Phase};
+    use crate::party::handshake::{preamble, PROTOCOL_VERSION};
+    use crate::party::transport::{
+        channel_pair, channel_pair_with_capacity, ChannelTransport, Envelope, Transport,
+    };
+    use rand::rngs::StdRng;
+    use std::sync::mpsc::sync_channel;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    type RecordingParty = Party<RecordingMTP<SeededMTP<StdRng>>>;
+
+    fn recording_party_pair(circuit: Circuit, seed: [u8; 32]) -> (RecordingParty, RecordingParty) {
+        let (transport0, transport1) = channel_pair();
+        let mtp = || RecordingMTP::new(SeededMTP::new(seed));
+        (
+            Party::new(circuit.clone(), transport0, false, mtp()).unwrap(),
+            Party::new(circuit, transport1, true, mtp()).unwrap(),
+        )
+    }
+
+    /// Runs both parties of the pair, either with the naive or the batched evaluation, and
+    /// returns the result together with the triples recorded by both parties.
+    fn run_recorded(
+        circuit: &Circuit,
+        batched: bool,
+        x: u64,
+        y: u64,
+    ) -> (Vec<bool>, Vec<MulTriple>, Vec<MulTriple>) {
+        let (mut p0, mut p1) = recording_party_pair(circuit.clone(), [3; 32]);
+        let run = move |p: &mut RecordingParty, input: Vec<bool>| {
+            if batched {
+                p.execute_batched(&input).unwrap()
+            } else {
+                p.execute(&input).unwrap()
+            }
+        };
+
+        let h0 = thread::spawn(move || (run(&mut p0, to_bits(x, 64).unwrap()), p0));
+        let h1 = thread::spawn(move || (run(&mut p1, to_bits(y, 64).unwrap()), p1));
+        let (res0, p0) = h0.join().unwrap();
+        let (res1, p1) = h1.join().unwrap();
+        assert_eq!(res0, res1);
+
+        (
+            res0,
+            p0.mtp().recorded().to_vec(),
+            p1.mtp().recorded().to_vec(),
+        )
+    }
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_party_is_send() {
+        assert_send::<Party<SeededMTP<StdRng>>>();
+    }
+
+    #[test]
+    fn test_and_in_threads() {
+        let circuit = "\
+            1 129\n\
+            2 64 64\n\
+            1 1\n\
+            \n\
+            2 1 0 64 128 AND\n";
+        let c = Circuit::parse(circuit).unwrap();
+
+        for (x, y) in [(false, false), (false, true), (true, false), (true, true)] {
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let mut input_p0 = [false; 64];
+            let mut input_p1 = [false; 64];
+            input_p0[0] = x;
+            input_p1[0] = y;
+
+            let h0 = thread::spawn(move || p0.execute(&input_p0).unwrap());
+            let h1 = thread::spawn(move || p1.execute(&input_p1).unwrap());
+
+            assert_eq!(h0.join().unwrap(), vec![x & y]);
+            assert_eq!(h1.join().unwrap(), vec![x & y]);
+        }
+    }
+
+    #[test]
+    fn test_widths_not_multiple_of_64() {
+        let contents = std::fs::read_to_string("test_circuits/1_And.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        for (x, y) in [(false, false), (false, true), (true, false), (true, true)] {
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let h1 = thread::spawn(move || p1.execute(&[y]).unwrap());
+            assert_eq!(p0.execute(&[x]).unwrap(), vec![x & y]);
+            assert_eq!(h1.join().unwrap(), vec![x & y]);
+        }
+
+        // the 65th bit is the one which would be lost by packing the inputs into a u64
+        let contents = std::fs::read_to_string("test_circuits/65_Xor.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let mut input_p0 = vec![false; 65];
+        let mut input_p1 = vec![false; 65];
+        input_p0[0] = true;
+        input_p0[64] = true;
+        input_p1[1] = true;
+        let mut expected = vec![false; 65];
+        expected[0] = true;
+        expected[1] = true;
+        expected[64] = true;
+
+        let (mut p0, mut p1) = new_party_pair(c).unwrap();
+        let h1 = thread::spawn(move || p1.execute(&input_p1).unwrap());
+        assert_eq!(p0.execute(&input_p0).unwrap(), expected);
+        assert_eq!(h1.join().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_batched_preprocesses_triples_in_bulk() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let and_gates = c
+            .gates()
+            .iter()
+            .filter(|g| matches!(g.gate_type, GateType::AND(_, _)))
+            .count();
+
+        let (naive, naive0, naive1) = run_recorded(&c, false, 1234, 4321);
+        let (batched, batched0, batched1) = run_recorded(&c, true, 1234, 4321);
+
+        assert_eq!(naive, to_bits(1234 + 4321, 64).unwrap());
+        assert_eq!(naive, batched);
+        assert_eq!(naive0.len(), and_gates);
+        assert_eq!(naive0, naive1);
+        // the batched run takes all triples at once, which the seeded provider generates from
+        // streams of their own
+        let bulk = SeededMTP::<StdRng>::new([3; 32]).get_triples(and_gates);
+        assert_eq!(batched0, bulk);
+        assert_eq!(batched1, bulk);
+    }
+
+    fn seeded_party_pair(circuit: Circuit) -> (Party<SeededMTP<StdRng>>, Party<SeededMTP<StdRng>>) {
+        let (transport0, transport1) = channel_pair();
+        (
+            Party::new(circuit.clone(), transport0, false, SeededMTP::new([5; 32])).unwrap(),
+            Party::new(circuit, transport1, true, SeededMTP::new([5; 32])).unwrap(),
+        )
+    }
+
+    fn resume_pair(
+        circuit: &Circuit,
+        s0: ExecutionSnapshot,
+        s1: ExecutionSnapshot,
+    ) -> (Party<SeededMTP<StdRng>>, Party<SeededMTP<StdRng>>) {
+        let (transport0, transport1) = channel_pair();
+        let p0 = Party::resume(circuit.clone(), s0, transport0, SeededMTP::new([5; 32]));
+        let p1 = Party::resume(circuit.clone(), s1, transport1, SeededMTP::new([5; 32]));
+        (p0.unwrap(), p1.unwrap())
+    }
+
+    /// Runs both parties up to the given gate and returns their snapshots.
+    fn halted_snapshots(circuit: &Circuit, gate: usize) -> (ExecutionSnapshot, ExecutionSnapshot) {
+        let (mut p0, mut p1) = seeded_party_pair(circuit.clone());
+        p0.halt_at(Some(gate));
+        p1.halt_at(Some(gate));
+
+        let h0 = thread::spawn(move || (p0.execute(&to_bits(1234, 64).unwrap()), p0.checkpoint()));
+        let h1 = thread::spawn(move || (p1.execute(&to_bits(4321, 64).unwrap()), p1.checkpoint()));
+        let (r0, s0) = h0.join().unwrap();
+        let (r1, s1) = h1.join().unwrap();
+        assert!(matches!(r0, Err(PartyError::Halted(g)) if g == gate));
+        assert!(matches!(r1, Err(PartyError::Halted(g)) if g == gate));
+        (s0, s1)
+    }
+
+    #[test]
+    fn test_checkpoint_and_resume() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        let (mut p0, mut p1) = seeded_party_pair(c.clone());
+        let h0 = thread::spawn(move || p0.execute(&to_bits(1234, 64).unwrap()).unwrap());
+        let h1 = thread::spawn(move || p1.execute(&to_bits(4321, 64).unwrap()).unwrap());
+        let expected = h0.join().unwrap();
+        assert_eq!(h1.join().unwrap(), expected);
+
+        let (s0, s1) = halted_snapshots(&c, 200);
+        assert_eq!(s0.gate_index, 200);
+
+        let dir = std::env::temp_dir();
+        let path0 = dir.join(format!("mpc_checkpoint_p0_{}.json", std::process::id()));
+        let path1 = dir.join(format!("mpc_checkpoint_p1_{}.json", std::process::id()));
+        s0.save(&path0).unwrap();
+        s1.save(&path1).unwrap();
+        let s0 = ExecutionSnapshot::load(&path0).unwrap();
+        let s1 = ExecutionSnapshot::load(&path1).unwrap();
+        std::fs::remove_file(path0).unwrap();
+        std::fs::remove_file(path1).unwrap();
+
+        let (mut p0, mut p1) = resume_pair(&c, s0, s1);
+        let h0 = thread::spawn(move || p0.execute_resumed().unwrap());
+        let h1 = thread::spawn(move || p1.execute_resumed().unwrap());
+        assert_eq!(h0.join().unwrap(), expected);
+        assert_eq!(h1.join().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_resume_mismatch() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        let (s0, _) = halted_snapshots(&c, 100);
+        let (_, s1) = halted_snapshots(&c, 150);
+
+        let (mut p0, mut p1) = resume_pair(&c, s0, s1);
+        let h0 = thread::spawn(move || p0.execute_resumed());
+        let h1 = thread::spawn(move || p1.execute_resumed());
+        assert!(matches!(
+            h0.join().unwrap(),
+            Err(PartyError::ResumeMismatch {
+                ours: 100,
+                theirs: 150
+            })
+        ));
+        assert!(matches!(
+            h1.join().unwrap(),
+            Err(PartyError::ResumeMismatch {
+                ours: 150,
+                theirs: 100
+            })
+        ));
+    }
+
+    #[test]
+    fn test_execute_structured() {
+        // party 0 supplies a (wires 0, 1), party 1 supplies b (wires 2, 3). The first output
+        // value is a ^ b, the second a_0 & b_0.
+        let circuit = "\
+            3 7\n\
+            2 2 2\n\
+            2 2 1\n\
+            \n\
+            2 1 0 2 4 XOR\n\
+            2 1 1 3 5 XOR\n\
+            2 1 0 2 6 AND\n";
+        let c = Circuit::parse(circuit).unwrap();
+
+        let (mut p0, mut p1) = new_party_pair(c).unwrap();
+        let h0 = thread::spawn(move || p0.execute_structured(&[true, true]).unwrap());
+        let h1 = thread::spawn(move || p1.execute_structured(&[true, false]).unwrap());
+        let expected = vec![vec![false, true], vec![true]];
+        assert_eq!(h0.join().unwrap(), expected);
+        assert_eq!(h1.join().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_seeded_pairs_use_same_triples() {
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let (mut a0, mut a1) = new_party_pair_seeded(c.clone(), [7; 32]).unwrap();
+        let (mut b0, mut b1) = new_party_pair_seeded(c, [7; 32]).unwrap();
+
+        for _ in 0..100 {
+            let triple = a0.mtp.get_triple();
+            assert_eq!(a1.mtp.get_triple(), triple);
+            assert_eq!(b0.mtp.get_triple(), triple);
+            assert_eq!(b1.mtp.get_triple(), triple);
+        }
+    }
+
+    #[test]
+    fn test_dump_triples() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let dir = std::env::temp_dir();
+        let path0 = dir.join(format!("mpc_triples_p0_{}.txt", std::process::id()));
+        let path1 = dir.join(format!("mpc_triples_p1_{}.txt", std::process::id()));
+
+        let (mut p0, mut p1) = recording_party_pair(c.clone(), [4; 32]);
+        p0.dump_triples(&path0).unwrap();
+        p1.dump_triples(&path1).unwrap();
+        let h0 = thread::spawn(move || (p0.execute(&to_bits(1234, 64).unwrap()).unwrap(), p0));
+        let h1 = thread::spawn(move || (p1.execute(&to_bits(4321, 64).unwrap()).unwrap(), p1));
+        let (_, p0) = h0.join().unwrap();
+        let (_, p1) = h1.join().unwrap();
+
+        let mut file0 = FileMTP::open(&path0).unwrap();
+        let mut file1 = FileMTP::open(&path1).unwrap();
+        assert_eq!(file0.remaining(), c.required_triples());
+        let dumped0: Vec<MulTriple> = (0..c.required_triples())
+            .map(|_| file0.get_triple())
             .collect();
+        let dumped1: Vec<MulTriple> = (0..c.required_triples())
+            .map(|_| file1.get_triple())
+            .collect();
+        assert_eq!(dumped0, p0.mtp().recorded());
+        assert_eq!(dumped1, p1.mtp().recorded());
+
+        // the dumped triples can be used for another run
+        let (t0, t1) = channel_pair();
+        let mut p0 = Party::new(c.clone(), t0, false, FileMTP::open(&path0).unwrap()).unwrap();
+        let mut p1 = Party::new(c, t1, true, FileMTP::open(&path1).unwrap()).unwrap();
+        let h0 = thread::spawn(move || p0.execute(&to_bits(10, 64).unwrap()).unwrap());
+        let h1 = thread::spawn(move || p1.execute(&to_bits(20, 64).unwrap()).unwrap());
+        assert_eq!(h0.join().unwrap(), to_bits(30, 64).unwrap());
+        assert_eq!(h1.join().unwrap(), to_bits(30, 64).unwrap());
+
+        std::fs::remove_file(path0).unwrap();
+        std::fs::remove_file(path1).unwrap();
+    }
+
+    #[test]
+    fn test_capacity_one() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        for batched in [false, true] {
+            let (mut p0, mut p1) = new_party_pair_with_capacity(c.clone(), [2; 32], 1).unwrap();
+            let run = move |p: &mut Party<SeededMTP<StdRng>>, input: Vec<bool>| {
+                if batched {
+                    p.execute_batched(&input).unwrap()
+                } else {
+                    p.execute(&input).unwrap()
+                }
+            };
+            let h0 = thread::spawn(move || run(&mut p0, to_bits(1234, 64).unwrap()));
+            let h1 = thread::spawn(move || run(&mut p1, to_bits(4321, 64).unwrap()));
+            assert_eq!(h0.join().unwrap(), to_bits(1234 + 4321, 64).unwrap());
+            assert_eq!(h1.join().unwrap(), to_bits(1234 + 4321, 64).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_stalled_peer_times_out() {
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let (t0, _t1) = channel_pair_with_capacity(1);
+        let t0 = t0.with_timeout(Duration::from_millis(50));
+
+        // the peer never takes part, so party 0 gives up instead of waiting forever
+        let mut p0 = Party::new(c, t0, false, SeededMTP::<StdRng>::new([1; 32])).unwrap();
+        assert!(matches!(
+            p0.execute(&[true]),
+            Err(PartyError::Timeout {
+                phase: Phase::Handshake
+            })
+        ));
+    }
+
+    #[test]
+    fn test_wires_cleared_after_run() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
 
-        self.sender.send(Messages::Result(sol1.clone()))?;
-        let Messages::Result(sol2) = self.receiver.recv()? else {
-            return Err(PartyError::ThreadReceivingError);
+        let (mut p0, mut p1) = new_party_pair(c).unwrap();
+        let h0 = thread::spawn(move || {
+            (
+                p0.execute(&to_bits(1234, 64).unwrap()).unwrap(),
+                p0.checkpoint(),
+            )
+        });
+        let h1 = thread::spawn(move || {
+            (
+                p1.execute(&to_bits(4321, 64).unwrap()).unwrap(),
+                p1.checkpoint(),
+            )
+        });
+        let (out0, s0) = h0.join().unwrap();
+        let (out1, s1) = h1.join().unwrap();
+
+        assert_eq!(out0, to_bits(1234 + 4321, 64).unwrap());
+        assert_eq!(out0, out1);
+        assert!(s0.wires.is_empty());
+        assert!(s1.wires.is_empty());
+    }
+
+    #[test]
+    fn test_batch_buffers_cleared_after_run() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let (mut p0, mut p1) = new_party_pair(Circuit::parse(&contents).unwrap()).unwrap();
+        let h1 = thread::spawn(move || {
+            p1.execute_batched(&to_bits(4321, 64).unwrap()).unwrap();
+            p1
+        });
+        assert_eq!(
+            p0.execute_batched(&to_bits(1234, 64).unwrap()).unwrap(),
+            to_bits(1234 + 4321, 64).unwrap()
+        );
+        let p1 = h1.join().unwrap();
+
+        // the shares and triples of the last layer are gone, the capacity is kept for the next run
+        for party_batch in [&p0.batch, &p1.batch] {
+            assert!(party_batch.inputs.is_empty());
+            assert!(party_batch.triples.is_empty());
+            assert!(party_batch.outputs.is_empty());
+            assert!(party_batch.inputs.capacity() > 0);
+        }
+    }
+
+    #[test]
+    fn test_estimated_memory() {
+        // 504 wires and 63 AND gates, 1 byte per wire and 3 bytes per triple
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        assert_eq!(c.required_triples(), 63);
+        let (p0, _) = new_party_pair(c).unwrap();
+        assert_eq!(p0.estimated_memory(), 504 + 63 * 3);
+
+        let contents = std::fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let (p0, _) = new_party_pair(c).unwrap();
+        assert_eq!(p0.estimated_memory(), 567 + 63 * 3);
+    }
+
+    #[test]
+    fn test_execute_select() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let expected = to_bits(1234 + 4321, 64).unwrap();
+
+        let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+        let h0 = thread::spawn(move || {
+            p0.execute_select(&to_bits(1234, 64).unwrap(), &[4])
+                .unwrap()
+        });
+        let h1 = thread::spawn(move || {
+            p1.execute_select(&to_bits(4321, 64).unwrap(), &[4])
+                .unwrap()
+        });
+        assert_eq!(h0.join().unwrap(), vec![expected[4]]);
+        assert_eq!(h1.join().unwrap(), vec![expected[4]]);
+
+        let (mut p0, _) = new_party_pair(c).unwrap();
+        assert!(matches!(
+            p0.execute_select(&to_bits(1234, 64).unwrap(), &[3, 64]),
+            Err(PartyError::InvalidOutputIndex(64, 64))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_circuit_is_rejected() {
+        // wire 2 is neither an input nor driven by any gate
+        let circuit = "\
+            2 5\n\
+            2 1 1\n\
+            1 1\n\
+            \n\
+            2 1 0 1 3 XOR\n\
+            2 1 3 2 4 AND\n";
+        let c = Circuit::parse(circuit).unwrap();
+
+        let e = new_party_pair(c.clone()).err().unwrap();
+        assert!(matches!(
+            e,
+            PartyError::InvalidCircuit(CircuitError::WireUsedBeforeSet(1, 2))
+        ));
+        assert_eq!(
+            e.to_string(),
+            "The circuit cannot be evaluated: Gate 1 reads wire 2, which is not set by an earlier gate"
+        );
+
+        // nothing is sent, so the other party is not left waiting for a failed run
+        let (t0, t1) = channel_pair();
+        let result = Party::new(c, t0, false, SeededMTP::<StdRng>::new([1; 32]));
+        assert!(matches!(result, Err(PartyError::InvalidCircuit(_))));
+        assert!(matches!(t1.recv(), Err(PartyError::TransportClosed)));
+    }
+
+    #[test]
+    fn test_peer_disconnected_during_and() {
+        let circuit = "\
+            2 4\n\
+            2 1 1\n\
+            1 1\n\
+            \n\
+            2 1 0 1 2 XOR\n\
+            2 1 0 2 3 AND\n";
+        let c = Circuit::parse(circuit).unwrap();
+
+        let (sender0, receiver1) = sync_channel(4);
+        let (sender1, receiver0) = sync_channel(4);
+        let mut p0 = Party::new(
+            c,
+            ChannelTransport::new(sender0, receiver0),
+            false,
+            SeededMTP::<StdRng>::new([1; 32]),
+        )
+        .unwrap();
+
+        // the peer shares its input and then goes away before the AND gate
+        let hello = Messages::Hello {
+            preamble: preamble(PROTOCOL_VERSION),
+        };
+        sender1.send(Envelope::new(0, hello)).unwrap();
+        let settings = Messages::Settings {
+            output_chunk_size: usize::MAX,
+        };
+        sender1.send(Envelope::new(0, settings)).unwrap();
+        let shares = Messages::Shares { shares: vec![true] };
+        sender1.send(Envelope::new(2, shares)).unwrap();
+        drop(sender1);
+
+        let result = p0.execute(&[false]);
+        assert!(matches!(
+            result,
+            Err(PartyError::PeerDisconnected {
+                phase: Phase::And {
+                    round: 0,
+                    gate_index: 1
+                }
+            })
+        ));
+        drop(receiver1);
+    }
+
+    /// Provides the given number of triples and panics when asked for another one.
+    struct PanickingMTP(SeededMTP<StdRng>, usize);
+
+    impl MTProvider for PanickingMTP {
+        fn get_triple(&mut self) -> MulTriple {
+            if self.1 == 0 {
+                panic!("out of triples");
+            }
+            self.1 -= 1;
+            self.0.get_triple()
+        }
+    }
+
+    /// Provides the triples of the wrapped provider, but with the share of c flipped in the
+    /// triple with the given index.
+    struct BrokenMTP(SeededMTP<StdRng>, usize);
+
+    impl MTProvider for BrokenMTP {
+        fn get_triple(&mut self) -> MulTriple {
+            let mut triple = self.0.get_triple();
+            triple.c ^= self.1 == 0;
+            self.1 = self.1.wrapping_sub(1);
+            triple
+        }
+    }
+
+    #[test]
+    fn test_check_triples() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let run = |check: bool, broken: usize| {
+            let (transport0, transport1) = channel_pair();
+            let mtp = BrokenMTP(SeededMTP::new([1; 32]), broken);
+            let mut p0 = Party::new(c.clone(), transport0, false, mtp).unwrap();
+            let mtp = SeededMTP::<StdRng>::new([1; 32]);
+            let mut p1 = Party::new(c.clone(), transport1, true, mtp).unwrap();
+            p0.set_check_triples(check);
+            p1.set_check_triples(check);
+
+            let h1 = thread::spawn(move || p1.execute(&to_bits(20, 64).unwrap()));
+            let r0 = p0.execute(&to_bits(22, 64).unwrap());
+            (r0, h1.join().unwrap())
+        };
+
+        // with correct triples the check passes
+        let (r0, r1) = run(true, usize::MAX);
+        assert_eq!(r0.unwrap(), to_bits(42, 64).unwrap());
+        assert_eq!(r1.unwrap(), to_bits(42, 64).unwrap());
+
+        // a broken triple goes unnoticed without the check
+        assert!(run(false, 3).0.is_ok());
+
+        let (r0, r1) = run(true, 3);
+        assert!(matches!(r0, Err(PartyError::InvalidTriple(3))));
+        assert!(matches!(r1, Err(PartyError::InvalidTriple(3))));
+    }
+
+    #[test]
+    fn test_panic_is_reported() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let (transport0, transport1) = channel_pair();
+        let mtp = PanickingMTP(SeededMTP::new([1; 32]), 1);
+        let mut p0 = Party::new(c.clone(), transport0, false, mtp).unwrap();
+        let mut p1 = Party::new(c, transport1, true, SeededMTP::<StdRng>::new([1; 32])).unwrap();
+
+        let h1 = thread::spawn(move || p1.execute(&[false; 64]));
+        let e = p0.execute_structured_catching(&[false; 64]).unwrap_err();
+        drop(p0);
+
+        let PartyError::Panicked { message, phase } = e else {
+            panic!("expected a panic, got {}", e);
         };
+        assert_eq!(message, "out of triples");
+        assert!(matches!(phase, Phase::And { round: 1, .. }));
+        // the other party does not wait for the panicked one
+        assert!(matches!(
+            h1.join().unwrap(),
+            Err(PartyError::PeerDisconnected { .. })
+        ));
+    }
+
+    #[test]
+    fn test_inv_conventions() {
+        // out = !(a ^ b)
+        let c = Circuit::parse("2 4\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n1 1 2 3 INV\n").unwrap();
+        for convention in [InvConvention::Party0Negates, InvConvention::Party1Negates] {
+            for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+                let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+                p0.set_inv_convention(convention);
+                p1.set_inv_convention(convention);
+                let h1 = thread::spawn(move || p1.execute(&[b]).unwrap());
+                assert_eq!(p0.execute(&[a]).unwrap(), vec![!(a ^ b)]);
+                assert_eq!(h1.join().unwrap(), vec![!(a ^ b)]);
+            }
+        }
+
+        // if both parties negate, the negations cancel out
+        let (mut p0, mut p1) = new_party_pair(c).unwrap();
+        p0.set_inv_convention(InvConvention::Party0Negates);
+        let h1 = thread::spawn(move || p1.execute(&[true]).unwrap());
+        assert_eq!(p0.execute(&[false]).unwrap(), vec![true]);
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_public_threshold() {
+        let contents = std::fs::read_to_string("test_circuits/8_Less.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+
+        for (a, threshold) in [(5, 9), (9, 9), (200, 9), (0, 1), (254, 255)] {
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            p0.set_public_input(1, to_bits(threshold, 8).unwrap())
+                .unwrap();
+            p1.set_public_input(1, to_bits(threshold, 8).unwrap())
+                .unwrap();
+
+            // party 1 supplies no input, since its only input value is public
+            let h1 = thread::spawn(move || p1.execute(&[]).unwrap());
+            let output = p0.execute(&to_bits(a, 8).unwrap()).unwrap();
+            assert_eq!(output, h1.join().unwrap());
+            let plain = c
+                .evaluate_plain(&[to_bits(a, 8).unwrap(), to_bits(threshold, 8).unwrap()].concat());
+            assert_eq!(output, plain.unwrap());
+            assert_eq!(output, vec![a < threshold]);
+        }
+
+        let (mut p0, _) = new_party_pair(c).unwrap();
+        assert!(matches!(
+            p0.set_public_input(2, to_bits(1, 8).unwrap()),
+            Err(PartyError::InvalidPublicInput(2))
+        ));
+        assert!(matches!(
+            p0.set_public_input(1, vec![true]),
+            Err(PartyError::WrongInputLength(8, 1))
+        ));
+        assert!(matches!(
+            p0.execute(&to_bits(1, 8).unwrap()[..4]),
+            Err(PartyError::WrongInputLength(8, 4))
+        ));
+    }
+
+    #[test]
+    fn test_session_runs_circuits_back_to_back() {
+        let read = |name: &str| {
+            let contents = std::fs::read_to_string(format!("test_circuits/{}", name)).unwrap();
+            Arc::new(Circuit::parse(&contents).unwrap())
+        };
+        let (adder, sub, less) = (read("64_Adder.txt"), read("64_Sub.txt"), read("8_Less.txt"));
+
+        // (circuit, input of party 0, input of party 1, expected output)
+        let runs = vec![
+            (
+                Arc::clone(&adder),
+                to_bits(40, 64).unwrap(),
+                to_bits(2, 64).unwrap(),
+                to_bits(42, 64).unwrap(),
+            ),
+            (
+                Arc::clone(&sub),
+                to_bits(40, 64).unwrap(),
+                to_bits(2, 64).unwrap(),
+                to_bits(38, 64).unwrap(),
+            ),
+            (
+                Arc::clone(&less),
+                to_bits(3, 8).unwrap(),
+                to_bits(7, 8).unwrap(),
+                vec![true],
+            ),
+            (
+                adder,
+                to_bits(1, 64).unwrap(),
+                to_bits(u64::MAX, 64).unwrap(),
+                to_bits(0, 64).unwrap(),
+            ),
+        ];
+
+        let (mut p0, mut p1) = new_party_pair((*runs[0].0).clone()).unwrap();
+        let runs1 = runs.clone();
+        let h1 = thread::spawn(move || {
+            let mut outputs = vec![p1.execute(&runs1[0].2).unwrap()];
+            for (circuit, _, input, _) in &runs1[1..] {
+                outputs.push(p1.execute_next(Arc::clone(circuit), input).unwrap());
+            }
+            outputs
+        });
+        let mut outputs = vec![p0.execute(&runs[0].1).unwrap()];
+        for (circuit, input, _, _) in &runs[1..] {
+            outputs.push(p0.execute_next(Arc::clone(circuit), input).unwrap());
+        }
 
-        Ok(sol1.iter().zip(sol2.iter()).map(|(x, y)| x ^ y).collect())
+        assert_eq!(outputs, h1.join().unwrap());
+        for (output, (_, _, _, expected)) in outputs.iter().zip(&runs) {
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_session_detects_different_circuits() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+        let contents = std::fs::read_to_string("test_circuits/8_Less.txt").unwrap();
+        let less = Circuit::parse(&contents).unwrap();
+
+        let (mut p0, mut p1) = new_party_pair(adder.clone()).unwrap();
+        let h1 = thread::spawn(move || p1.next_circuit(less));
+        assert!(matches!(
+            p0.next_circuit(adder),
+            Err(PartyError::CircuitMismatch { run: 1 })
+        ));
+        assert!(matches!(
+            h1.join().unwrap(),
+            Err(PartyError::CircuitMismatch { run: 1 })
+        ));
+    }
+
+    /// Passes the messages on and keeps a copy of every sent one.
+    struct Tap {
+        inner: ChannelTransport,
+        sent: Arc<Mutex<Vec<Messages>>>,
+    }
+
+    impl Transport for Tap {
+        fn send(&self, m: Envelope) -> Result<(), PartyError> {
+            self.sent.lock().unwrap().push(m.message.clone());
+            self.inner.send(m)
+        }
+
+        fn recv(&self) -> Result<Envelope, PartyError> {
+            self.inner.recv()
+        }
+    }
+
+    #[test]
+    fn test_hide_input_ownership() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        for hide in [false, true] {
+            let (t0, t1) = channel_pair();
+            let sent = [
+                Arc::new(Mutex::new(Vec::new())),
+                Arc::new(Mutex::new(Vec::new())),
+            ];
+            let tap = |inner, i: usize| Tap {
+                inner,
+                sent: Arc::clone(&sent[i]),
+            };
+            let mut p0 = Party::new(
+                c.clone(),
+                tap(t0, 0),
+                false,
+                SeededMTP::<StdRng>::new([4; 32]),
+            )
+            .unwrap();
+            let mut p1 = Party::new(
+                c.clone(),
+                tap(t1, 1),
+                true,
+                SeededMTP::<StdRng>::new([4; 32]),
+            )
+            .unwrap();
+            p0.set_hide_input_ownership(hide);
+            p1.set_hide_input_ownership(hide);
+            let h1 = thread::spawn(move || p1.execute(&to_bits(4321, 64).unwrap()).unwrap());
+            assert_eq!(
+                p0.execute(&to_bits(1234, 64).unwrap()).unwrap(),
+                to_bits(5555, 64).unwrap()
+            );
+            assert_eq!(h1.join().unwrap(), to_bits(5555, 64).unwrap());
+
+            // each party shares its own 64 bits, or all 128 input bits
+            for sent in &sent {
+                let sent = sent.lock().unwrap();
+                let widths: Vec<usize> = sent
+                    .iter()
+                    .filter_map(|m| match m {
+                        Messages::Shares { shares } => Some(shares.len()),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(widths, vec![if hide { 128 } else { 64 }]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_to_shares() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+        let h1 = thread::spawn(move || {
+            let shares = p1.execute_to_shares(&to_bits(4321, 64).unwrap()).unwrap();
+            (shares, p1.rounds())
+        });
+        let shares0 = p0.execute_to_shares(&to_bits(1234, 64).unwrap()).unwrap();
+        let (shares1, rounds1) = h1.join().unwrap();
+
+        let xored: Vec<bool> = shares0.iter().zip(&shares1).map(|(a, b)| a ^ b).collect();
+        let mut input = to_bits(1234, 64).unwrap().to_vec();
+        input.extend(to_bits(4321, 64).unwrap());
+        assert_eq!(xored, c.evaluate_plain(&input).unwrap());
+        assert_eq!(reconstruct(&shares0, &shares1), xored);
+        // the two rounds of the handshake, the input sharing and one round per AND gate, but no
+        // output exchange
+        assert_eq!(p0.rounds(), 3 + 63);
+        assert_eq!(rounds1, p0.rounds());
+    }
+
+    #[test]
+    fn test_reveal_to_one_party() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        for learner in [PartyId::P0, PartyId::P1] {
+            let (t0, t1) = channel_pair();
+            let sent = Arc::new(Mutex::new(Vec::new()));
+            let t0 = Tap {
+                inner: t0,
+                sent: Arc::clone(&sent),
+            };
+            let mut p0 =
+                Party::new(c.clone(), t0, false, SeededMTP::<StdRng>::new([5; 32])).unwrap();
+            let mut p1 =
+                Party::new(c.clone(), t1, true, SeededMTP::<StdRng>::new([5; 32])).unwrap();
+            // the 64 output shares go in chunks of 10 bits
+            p0.set_output_chunk_size(10);
+            p1.set_output_chunk_size(10);
+            let h1 = thread::spawn(move || {
+                p1.execute_reveal_to(&to_bits(4321, 64).unwrap(), learner)
+                    .unwrap()
+            });
+            let output0 = p0
+                .execute_reveal_to(&to_bits(1234, 64).unwrap(), learner)
+                .unwrap();
+            let output1 = h1.join().unwrap();
+
+            let sum = to_bits(1234 + 4321, 64).unwrap().to_vec();
+            let results_sent = sent
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|m| matches!(m, Messages::Result(_)))
+                .count();
+            if learner == PartyId::P0 {
+                assert_eq!(output0, sum);
+                assert!(output1.is_empty());
+                // party 0 only receives the shares of party 1
+                assert_eq!(results_sent, 0);
+            } else {
+                assert!(output0.is_empty());
+                assert_eq!(output1, sum);
+                assert_eq!(results_sent, 7);
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_mode_of_all_executions() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let sum = to_bits(1234 + 4321, 64).unwrap().to_vec();
+        for mode in [OutputMode::RevealTo(PartyId::P1), OutputMode::Shares] {
+            for execution in 0..3 {
+                let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+                p0.set_output_mode(mode);
+                p1.set_output_mode(mode);
+                let run = move |party: &mut Party<_>, input: &[bool]| match execution {
+                    0 => party.execute(input).unwrap(),
+                    1 => party.execute_batched(input).unwrap(),
+                    _ => party.execute_structured(input).unwrap().concat(),
+                };
+                let h1 = thread::spawn(move || run(&mut p1, &to_bits(4321, 64).unwrap()));
+                let output0 = run(&mut p0, &to_bits(1234, 64).unwrap());
+                let output1 = h1.join().unwrap();
+                if mode == OutputMode::Shares {
+                    assert_eq!(reconstruct(&output0, &output1), sum);
+                } else {
+                    assert!(output0.is_empty());
+                    assert_eq!(output1, sum);
+                }
+            }
+
+            let (s0, s1) = halted_snapshots(&c, 200);
+            let (mut p0, mut p1) = resume_pair(&c, s0, s1);
+            p0.set_output_mode(mode);
+            p1.set_output_mode(mode);
+            let h1 = thread::spawn(move || p1.execute_resumed().unwrap());
+            let output0 = p0.execute_resumed().unwrap();
+            let output1 = h1.join().unwrap();
+            if mode == OutputMode::Shares {
+                assert_eq!(reconstruct(&output0, &output1), sum);
+            } else {
+                assert!(output0.is_empty());
+                assert_eq!(output1, sum);
+            }
+
+            // streaming and stepping reveal every bit to both parties, they refuse to run
+            let (t0, t1) = channel_pair();
+            let mut p0 =
+                Party::new(c.clone(), t0, false, SeededMTP::<StdRng>::new([5; 32])).unwrap();
+            p0.set_output_mode(mode);
+            let input = to_bits(1234, 64).unwrap();
+            assert!(matches!(
+                p0.execute_streaming(&input, |_| {}),
+                Err(PartyError::UnsupportedOutputMode(m)) if m == mode
+            ));
+            assert!(matches!(
+                p0.start(&input),
+                Err(PartyError::UnsupportedOutputMode(m)) if m == mode
+            ));
+            // the mode is checked before anything is sent
+            assert!(t1.with_timeout(Duration::from_millis(10)).recv().is_err());
+        }
+    }
+
+    #[test]
+    fn test_output_chunks() {
+        let c = Builtin::Xor.circuit(1000);
+        let x: Vec<bool> = (0..1000).map(|i| i % 3 == 0).collect();
+        let y: Vec<bool> = (0..1000).map(|i| i % 5 == 0).collect();
+        let expected: Vec<bool> = x.iter().zip(&y).map(|(a, b)| a ^ b).collect();
+
+        let mut rounds = Vec::new();
+        for chunk_size in [usize::MAX, 1000, 7] {
+            // even channels holding a single message don't stall the chunks
+            let (mut p0, mut p1) = new_party_pair_with_capacity(c.clone(), [3; 32], 1).unwrap();
+            p0.set_output_chunk_size(chunk_size);
+            p1.set_output_chunk_size(chunk_size);
+            let y = y.clone();
+            let h1 = thread::spawn(move || p1.execute(&y).unwrap());
+            assert_eq!(p0.execute(&x).unwrap(), expected);
+            assert_eq!(h1.join().unwrap(), expected);
+            rounds.push(p0.rounds());
+        }
+        // the 143 chunks of 7 bits, the last one of 6 bits, are sent in a single round
+        assert_eq!(rounds[0], rounds[1]);
+        assert_eq!(rounds[2], rounds[0]);
+    }
+
+    #[test]
+    fn test_output_chunk_size_mismatch() {
+        let c = Builtin::Xor.circuit(8);
+        let (mut p0, mut p1) = new_party_pair(c).unwrap();
+        p0.set_output_chunk_size(7);
+        p1.set_output_chunk_size(8);
+        let h1 = thread::spawn(move || p1.execute(&[false; 8]));
+        assert!(matches!(
+            p0.execute(&[true; 8]),
+            Err(PartyError::OutputChunkSizeMismatch { ours: 7, theirs: 8 })
+        ));
+        assert!(matches!(
+            h1.join().unwrap(),
+            Err(PartyError::OutputChunkSizeMismatch { ours: 8, theirs: 7 })
+        ));
+    }
+
+    #[test]
+    fn test_execute_streaming() {
+        // output bit 1 is assigned by the first gate, output bit 0 after a chain of AND gates
+        let staged = "\
+            6 8\n\
+            2 1 1\n\
+            1 2\n\
+            \n\
+            2 1 0 1 7 XOR\n\
+            2 1 0 1 2 AND\n\
+            2 1 2 0 3 AND\n\
+            2 1 3 1 4 AND\n\
+            2 1 4 0 5 XOR\n\
+            2 1 5 7 6 AND\n";
+        let c = Circuit::parse(staged).unwrap();
+        for (x, y) in [(false, true), (true, true)] {
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let h1 = thread::spawn(move || p1.execute_streaming(&[y], |_| {}).unwrap());
+            let mut streamed = Vec::new();
+            let output = p0
+                .execute_streaming(&[x], |bit| streamed.push(bit))
+                .unwrap();
+            assert_eq!(output, h1.join().unwrap());
+            assert_eq!(output, c.evaluate_plain(&[x, y]).unwrap());
+
+            // the early output bit arrives first
+            let order: Vec<usize> = streamed.iter().map(|bit| bit.index).collect();
+            assert_eq!(order, vec![1, 0]);
+            assert!(streamed.iter().all(|bit| bit.value == output[bit.index]));
+        }
+
+        // every output bit of the adder is streamed once, and the result equals the usual one
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let (mut p0, mut p1) = new_party_pair(Circuit::parse(&contents).unwrap()).unwrap();
+        let h1 = thread::spawn(move || {
+            p1.execute_streaming(&to_bits(4321, 64).unwrap(), |_| {})
+                .unwrap()
+        });
+        let mut streamed = Vec::new();
+        let output = p0
+            .execute_streaming(&to_bits(1234, 64).unwrap(), |bit| streamed.push(bit.index))
+            .unwrap();
+        h1.join().unwrap();
+        assert_eq!(output, to_bits(1234 + 4321, 64).unwrap());
+        streamed.sort();
+        assert_eq!(streamed, (0..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_execute_streaming_input_is_output() {
+        // the input of party 1 is wired straight to output bit 0, output bit 1 is computed
+        let c = Circuit::parse("1 3\n2 1 1\n1 2\n\n2 1 0 1 2 AND\n").unwrap();
+        for (x, y) in [(false, true), (true, false), (true, true)] {
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let h1 = thread::spawn(move || p1.execute_streaming(&[y], |_| {}).unwrap());
+            let mut streamed = Vec::new();
+            let output = p0
+                .execute_streaming(&[x], |bit| streamed.push(bit))
+                .unwrap();
+            assert_eq!(output, vec![y, x & y]);
+            assert_eq!(output, h1.join().unwrap());
+            assert_eq!(streamed.len(), 2);
+            assert!(streamed.iter().all(|bit| bit.value == output[bit.index]));
+        }
+
+        // without gates, every output bit is an input bit
+        let c = Circuit::parse("0 2\n2 1 1\n1 2\n").unwrap();
+        let (mut p0, mut p1) = new_party_pair(c).unwrap();
+        let h1 = thread::spawn(move || p1.execute_streaming(&[false], |_| {}).unwrap());
+        assert_eq!(
+            p0.execute_streaming(&[true], |_| {}).unwrap(),
+            vec![true, false]
+        );
+        assert_eq!(h1.join().unwrap(), vec![true, false]);
+    }
+
+    #[test]
+    fn test_zero_gate_circuit() {
+        // the inputs are passed through as outputs
+        let c = Circuit::parse("0 2\n2 1 1\n1 2\n").unwrap();
+        for (x, y) in [(false, true), (true, false), (true, true)] {
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let h0 = thread::spawn(move || p0.execute(&[x]).unwrap());
+            let h1 = thread::spawn(move || p1.execute(&[y]).unwrap());
+            assert_eq!(h0.join().unwrap(), vec![x, y]);
+            assert_eq!(h1.join().unwrap(), vec![x, y]);
+
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            let h1 = thread::spawn(move || p1.execute_batched(&[y]).unwrap());
+            assert_eq!(p0.execute_batched(&[x]).unwrap(), vec![x, y]);
+            assert_eq!(h1.join().unwrap(), vec![x, y]);
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_wire_is_an_error() {
+        // the parser and `Party::new` reject such a circuit, so it is swapped in afterwards. The
+        // first gate writes an output index beyond the wires, the second reads one
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+        let [zero, one, two, seven, nine] = [0, 1, 2, 7, 9].map(WireId::unchecked);
+        for (gate_type, output, wire) in [
+            (GateType::XOR(zero, one), seven, 7),
+            (GateType::XOR(zero, nine), two, 9),
+        ] {
+            let (header, mut gates) = c.clone().into_parts();
+            gates[0].gate_type = gate_type;
+            gates[0].output = output;
+            let bad = Circuit::new(header, gates);
+            p0.circuit = Arc::new(bad.clone());
+            p1.circuit = Arc::new(bad);
+            let h1 = thread::spawn(move || {
+                let result = p1.execute(&[true]);
+                (p1, result)
+            });
+            let result = p0.execute(&[false]);
+            let (returned, result1) = h1.join().unwrap();
+            p1 = returned;
+            for result in [result, result1] {
+                assert!(matches!(
+                    result,
+                    Err(PartyError::WireIndexOutOfRange {
+                        wire: w,
+                        wires_amount: 3
+                    }) if w == wire
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_input_wire_out_of_range_is_an_error() {
+        // like above, but the header declares a wire less than the two input bits need
+        let c = Circuit::parse("0 2\n2 1 1\n1 1\n\n").unwrap();
+        let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+        let (mut header, gates) = c.into_parts();
+        header.wires_amount = 1;
+        let bad = Circuit::new(header, gates);
+        p0.circuit = Arc::new(bad.clone());
+        p1.circuit = Arc::new(bad);
+        let h1 = thread::spawn(move || p1.execute(&[true]));
+        for result in [p0.execute(&[false]), h1.join().unwrap()] {
+            assert!(matches!(
+                result,
+                Err(PartyError::WireIndexOutOfRange {
+                    wire: 1,
+                    wires_amount: 1
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unset_output_wire_is_an_error() {
+        // the output is wire 3, but the only gate writes wire 2
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        let (mut header, gates) = c.clone().into_parts();
+        header.wires_amount = 4;
+        let bad = Circuit::new(header, gates);
+        for to_shares in [false, true] {
+            let (mut p0, mut p1) = new_party_pair(c.clone()).unwrap();
+            p0.circuit = Arc::new(bad.clone());
+            p1.circuit = Arc::new(bad.clone());
+            let run = move |p: &mut Party<SeededMTP<StdRng>>, input: &[bool]| match to_shares {
+                true => p.execute_to_shares(input),
+                false => p.execute(input),
+            };
+            let h1 = thread::spawn(move || run(&mut p1, &[true]));
+            for result in [run(&mut p0, &[false]), h1.join().unwrap()] {
+                assert!(matches!(
+                    result,
+                    Err(PartyError::InvalidCircuit(CircuitError::WireNotSetError(3)))
+                ));
+            }
+        }
     }
 }
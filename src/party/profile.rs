@@ -0,0 +1,170 @@
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// Where a party spent its time in the executions since profiling was enabled, see
+/// [`crate::party::party_gmw::Party::set_profiling`]. This needs no feature, but is switched on
+/// at runtime. The `TimingReport` of the `timing` feature summarizes the profile of a single
+/// execution.
+///
+/// The categories do not overlap, so together they are at most the total. The rest is spent on
+/// bookkeeping between the measured blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// The whole executions, including the input sharing and the output exchange.
+    pub total: Duration,
+    /// XOR and INV gates, and the local work of AND gates: reading the operands, opening the
+    /// masked values and combining them with the other party's.
+    pub local: Duration,
+    /// Taking the multiplication triples from the provider.
+    pub triple_fetching: Duration,
+    /// Sending the opened values of AND gates and waiting for the other party's.
+    pub and_communication: Duration,
+    /// Sending and waiting for the messages of the other rounds, e.g. the input sharing.
+    pub other_communication: Duration,
+    /// Turning messages into bytes and back, which only some transports do, see
+    /// [`crate::party::transport::Transport::serialization_time`].
+    pub serialization: Duration,
+    /// Time spent on the gates of each type, across the categories above.
+    pub xor_gates: Duration,
+    pub inv_gates: Duration,
+    pub and_gates: Duration,
+    /// Number of allocations the party made, if the program counts them with
+    /// [`crate::party::allocations::CountingAllocator`].
+    pub allocations: usize,
+}
+
+impl Profile {
+    /// The time of each category, from the largest to the smallest.
+    pub fn categories(&self) -> Vec<(&'static str, Duration)> {
+        sorted(vec![
+            ("local computation", self.local),
+            ("triple fetching", self.triple_fetching),
+            ("AND communication", self.and_communication),
+            ("other communication", self.other_communication),
+            ("serialization", self.serialization),
+        ])
+    }
+
+    /// The time spent on each gate type, from the largest to the smallest.
+    pub fn gate_types(&self) -> Vec<(&'static str, Duration)> {
+        sorted(vec![
+            ("AND", self.and_gates),
+            ("XOR", self.xor_gates),
+            ("INV", self.inv_gates),
+        ])
+    }
+
+    /// Time of all categories together.
+    pub fn categorized(&self) -> Duration {
+        self.categories().iter().map(|(_, time)| *time).sum()
+    }
+
+    /// Time of the categories in which the party waits for something else than its own
+    /// computation, used to tell the local work of a gate apart.
+    pub(crate) fn waiting(&self) -> Duration {
+        self.triple_fetching
+            + self.and_communication
+            + self.other_communication
+            + self.serialization
+    }
+}
+
+impl AddAssign<&Profile> for Profile {
+    fn add_assign(&mut self, other: &Profile) {
+        self.total += other.total;
+        self.local += other.local;
+        self.triple_fetching += other.triple_fetching;
+        self.and_communication += other.and_communication;
+        self.other_communication += other.other_communication;
+        self.serialization += other.serialization;
+        self.xor_gates += other.xor_gates;
+        self.inv_gates += other.inv_gates;
+        self.and_gates += other.and_gates;
+        self.allocations += other.allocations;
+    }
+}
+
+fn sorted(mut times: Vec<(&'static str, Duration)>) -> Vec<(&'static str, Duration)> {
+    times.sort_by_key(|&(_, time)| Reverse(time));
+    times
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // the share of the total, guarding against a total of 0 for empty runs
+        let percent = |time: Duration| match self.total.as_secs_f64() {
+            0.0 => 0.0,
+            total => 100.0 * time.as_secs_f64() / total,
+        };
+        writeln!(f, "total: {:?}", self.total)?;
+        writeln!(f, "allocations: {}", self.allocations)?;
+        for (name, time) in self.categories() {
+            writeln!(f, "  {:<20} {:>12?} {:5.1}%", name, time, percent(time))?;
+        }
+        writeln!(f, "by gate type:")?;
+        for (name, time) in self.gate_types() {
+            writeln!(f, "  {:<20} {:>12?} {:5.1}%", name, time, percent(time))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::party::party_gmw::new_party_pair;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_profile() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let (mut p0, mut p1) = new_party_pair(Circuit::parse(&contents).unwrap()).unwrap();
+        assert_eq!(p0.profile(), None);
+        p0.set_profiling(true);
+        let h = thread::spawn(move || p1.execute(&[true; 64]).unwrap());
+        p0.execute(&[false; 64]).unwrap();
+        h.join().unwrap();
+
+        let profile = p0.profile().unwrap();
+        assert!(profile.and_communication > Duration::ZERO);
+        assert!(profile.triple_fetching > Duration::ZERO);
+        assert!(profile.local > Duration::ZERO);
+        assert!(profile.and_gates > Duration::ZERO);
+        assert!(profile.xor_gates > Duration::ZERO);
+        // the adder has no INV gates
+        assert_eq!(profile.inv_gates, Duration::ZERO);
+        // channels pass the messages on as they are
+        assert_eq!(profile.serialization, Duration::ZERO);
+        // only the bookkeeping between the measured blocks is left out
+        assert!(profile.categorized() <= profile.total);
+        assert!(profile.xor_gates + profile.and_gates <= profile.total);
+
+        let printed = profile.to_string();
+        let and = printed.find("AND communication").unwrap();
+        assert!(printed.starts_with("total: "));
+        assert!(and < printed.find("serialization").unwrap());
+    }
+
+    #[test]
+    fn test_profile_without_and_gates() {
+        let xor = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        let (mut p0, mut p1) = new_party_pair(xor).unwrap();
+        p0.set_profiling(true);
+        let h = thread::spawn(move || p1.execute(&[true]).unwrap());
+        assert_eq!(p0.execute(&[false]).unwrap(), vec![true]);
+        h.join().unwrap();
+
+        let profile = p0.profile().unwrap();
+        assert_eq!(profile.and_communication, Duration::ZERO);
+        assert_eq!(profile.triple_fetching, Duration::ZERO);
+        assert_eq!(profile.and_gates, Duration::ZERO);
+        assert!(profile.xor_gates > Duration::ZERO);
+        assert!(profile.other_communication > Duration::ZERO);
+        assert!(profile.categorized() <= profile.total);
+        // the unit tests run with the system allocator, which counts nothing
+        assert_eq!(profile.allocations, 0);
+    }
+}
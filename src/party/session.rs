@@ -0,0 +1,110 @@
+use crate::circuit::circuit_parser::Circuit;
+use crate::mul_triple::SeededMTP;
+use crate::party::errors::PartyError;
+use crate::party::party_gmw::Party;
+use crate::party::threads::{run_in_threads, PartyFailure};
+use crate::party::transport::channel_pair;
+use rand::rngs::StdRng;
+use std::sync::Arc;
+
+type SessionParty = Party<SeededMTP<StdRng>>;
+
+/// Both parties of a long-lived MPC service, which evaluate a sequence of circuits over the same
+/// connection. The triples of all circuits are drawn from one seeded triple source per party,
+/// which is set up once for the whole session instead of being seeded again for every circuit.
+///
+/// A failed run leaves the parties out of step, so the session should be dropped after it.
+pub struct MpcSession {
+    seed: [u8; 32],
+    // created with the first circuit
+    parties: Option<(SessionParty, SessionParty)>,
+}
+
+impl MpcSession {
+    /// Starts a session whose triples are derived from `seed`.
+    pub fn new(seed: [u8; 32]) -> Self {
+        MpcSession {
+            seed,
+            parties: None,
+        }
+    }
+
+    /// Evaluates the next circuit of the session with the inputs of both parties, running each
+    /// party in a thread of its own, and returns the output bits.
+    pub fn run(
+        &mut self,
+        circuit: impl Into<Arc<Circuit>>,
+        first_in: &[bool],
+        second_in: &[bool],
+    ) -> Result<Vec<bool>, PartyFailure> {
+        let circuit = circuit.into();
+        let (outputs, _) = match &mut self.parties {
+            Some((p0, p1)) => {
+                let c1 = Arc::clone(&circuit);
+                run_in_threads(
+                    || p0.execute_next(circuit, first_in),
+                    || p1.execute_next(c1, second_in),
+                )?
+            }
+            None => {
+                let (t0, t1) = channel_pair();
+                // both parties reject an invalid circuit alike, so the failure is put on party 0
+                let failure = |e: PartyError| PartyFailure {
+                    party: 0,
+                    message: e.to_string(),
+                };
+                let p0 = Party::new(Arc::clone(&circuit), t0, false, SeededMTP::new(self.seed))
+                    .map_err(failure)?;
+                let p1 =
+                    Party::new(circuit, t1, true, SeededMTP::new(self.seed)).map_err(failure)?;
+                let (p0, p1) = self.parties.insert((p0, p1));
+                run_in_threads(|| p0.execute(first_in), || p1.execute(second_in))?
+            }
+        };
+        Ok(outputs)
+    }
+
+    /// Number of triples each party has drawn from the triple source so far.
+    pub fn triples_consumed(&self) -> usize {
+        self.parties
+            .as_ref()
+            .map_or(0, |(p0, _)| p0.triples_consumed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MpcSession;
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+
+    #[test]
+    fn test_session_shares_triples() {
+        let read = |path: &str| Circuit::parse(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let circuits = [
+            read("test_circuits/64_Adder.txt"),
+            read("test_circuits/8_Sub.txt"),
+            read("test_circuits/64_Adder.txt"),
+        ];
+        let required: usize = circuits.iter().map(|c| c.required_triples()).sum();
+
+        let mut session = MpcSession::new([3; 32]);
+        let [adder, sub, adder_again] = circuits;
+        let sum = session.run(
+            adder,
+            &to_bits(1234, 64).unwrap(),
+            &to_bits(4321, 64).unwrap(),
+        );
+        assert_eq!(sum.unwrap(), to_bits(1234 + 4321, 64).unwrap());
+        let difference = session.run(sub, &to_bits(200, 8).unwrap(), &to_bits(58, 8).unwrap());
+        assert_eq!(difference.unwrap(), to_bits(142, 8).unwrap());
+        let sum = session.run(
+            adder_again,
+            &to_bits(u64::MAX, 64).unwrap(),
+            &to_bits(2, 64).unwrap(),
+        );
+        assert_eq!(sum.unwrap(), to_bits(1, 64).unwrap());
+
+        assert_eq!(session.triples_consumed(), required);
+    }
+}
@@ -0,0 +1,566 @@
+//! Transport between parties in different processes or on different machines, over TCP.
+//!
+//! Every message is sent as a frame: the length of its payload as 8 bytes in big endian and a
+//! flag byte, followed by the message as JSON, which may be compressed with deflate. A frame of
+//! length 0 is the goodbye of a party which finished normally, so the other party can tell a
+//! completed run from a dropped connection, see [`TcpTransport::close`].
+//!
+//! Bit 0 of the flag byte marks a compressed payload, bit 1 tells that the sender compresses its
+//! messages. The handshake messages are the first frames in both directions, so both parties
+//! know after the handshake whether the other one compresses too. A party only compresses if
+//! both do, so a party which does not compress still understands every message it gets.
+
+use crate::party::errors::{PartyError, Phase};
+use crate::party::observer::{Direction, MetricsObserver};
+use crate::party::party_gmw::in_phase;
+use crate::party::transport::{Envelope, Transport};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::cell::Cell;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Largest message a party accepts by default, in bytes.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 1 << 28;
+
+/// Smallest message which is compressed by default, in bytes. Compressing shorter messages saves
+/// next to nothing.
+pub const DEFAULT_COMPRESS_THRESHOLD: usize = 1024;
+
+/// Marks a frame whose payload is compressed.
+const FLAG_DEFLATE: u8 = 1;
+/// Marks a frame of a party which compresses its messages.
+const FLAG_COMPRESSES: u8 = 2;
+
+/// The wait before the first retry of a connection, which doubles with every further retry.
+const FIRST_BACKOFF: Duration = Duration::from_millis(100);
+/// The longest wait between two retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+/// The settings of a TCP connection between two parties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpConfig {
+    /// How long a single attempt to connect may take.
+    pub connect_timeout: Duration,
+    /// How long sending or receiving a message may block, without a limit if `None`.
+    pub io_timeout: Option<Duration>,
+    /// Largest message accepted from the other party, in bytes. A longer frame is rejected
+    /// before anything is allocated for it.
+    pub max_frame_len: usize,
+    /// How often the connecting party tries again if the other party does not listen yet. The
+    /// waits between the attempts grow from 100ms to at most 2s.
+    pub retries: u32,
+    /// Whether to compress messages, which only happens if the other party compresses as well.
+    pub compress: bool,
+    /// Messages shorter than this many bytes are sent uncompressed.
+    pub compress_threshold: usize,
+}
+
+impl Default for TcpConfig {
+    fn default() -> Self {
+        TcpConfig {
+            connect_timeout: Duration::from_secs(5),
+            io_timeout: None,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            retries: 10,
+            compress: false,
+            compress_threshold: DEFAULT_COMPRESS_THRESHOLD,
+        }
+    }
+}
+
+/// A connection to the other party over TCP. One party listens with [`TcpTransport::accept`],
+/// the other one connects with [`TcpTransport::connect`].
+pub struct TcpTransport {
+    stream: TcpStream,
+    max_frame_len: usize,
+    compress: bool,
+    compress_threshold: usize,
+    // set once a frame of the other party tells that it compresses too
+    peer_compresses: Cell<bool>,
+    // set once the other party said goodbye
+    peer_closed: Cell<bool>,
+    said_goodbye: Cell<bool>,
+    metrics: Option<MetricsObserver>,
+    // time spent encoding and decoding messages, see `Transport::serialization_time`
+    serialization: Cell<Duration>,
+}
+
+impl TcpTransport {
+    /// Connects to the party listening at `addr`. If it does not listen yet, the connection is
+    /// retried up to `config.retries` times with a growing wait in between, so the parties may
+    /// be started in any order.
+    pub fn connect(addr: impl ToSocketAddrs, config: &TcpConfig) -> io::Result<Self> {
+        let addrs: Vec<_> = addr.to_socket_addrs()?.collect();
+        let mut backoff = FIRST_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let mut last_error =
+                io::Error::new(ErrorKind::InvalidInput, "no address to connect to");
+            for addr in &addrs {
+                match TcpStream::connect_timeout(addr, config.connect_timeout) {
+                    Ok(stream) => return Self::new(stream, config),
+                    Err(e) => last_error = e,
+                }
+            }
+            if attempt == config.retries {
+                return Err(last_error);
+            }
+            attempt += 1;
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Waits for the other party to connect to the listener.
+    pub fn accept(listener: &TcpListener, config: &TcpConfig) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        Self::new(stream, config)
+    }
+
+    fn new(stream: TcpStream, config: &TcpConfig) -> io::Result<Self> {
+        // every round sends a small message and waits for the answer, which Nagle's algorithm
+        // would hold back
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(config.io_timeout)?;
+        stream.set_write_timeout(config.io_timeout)?;
+        Ok(TcpTransport {
+            stream,
+            max_frame_len: config.max_frame_len,
+            compress: config.compress,
+            compress_threshold: config.compress_threshold,
+            peer_compresses: Cell::new(false),
+            peer_closed: Cell::new(false),
+            said_goodbye: Cell::new(false),
+            metrics: None,
+            serialization: Cell::new(Duration::ZERO),
+        })
+    }
+
+    /// Counts the bytes of every message as it goes over the connection into `metrics`, to
+    /// compare them with the bytes the observer counts before compression.
+    pub fn set_metrics(&mut self, metrics: MetricsObserver) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Whether the messages sent from now on are compressed if they are long enough, because
+    /// both parties compress.
+    pub fn compresses(&self) -> bool {
+        self.compress && self.peer_compresses.get()
+    }
+
+    /// Sends a frame with the given payload, compressing it if both parties compress and it is
+    /// long enough.
+    fn write_frame(&self, payload: &[u8]) -> Result<(), PartyError> {
+        let mut flags = if self.compress { FLAG_COMPRESSES } else { 0 };
+        let deflated = match self.compresses() && payload.len() >= self.compress_threshold {
+            true => deflate(payload).filter(|deflated| deflated.len() < payload.len()),
+            false => None,
+        };
+        let payload = match &deflated {
+            Some(deflated) => {
+                flags |= FLAG_DEFLATE;
+                deflated
+            }
+            None => payload,
+        };
+        let mut frame = Vec::with_capacity(9 + payload.len());
+        frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+        frame.push(flags);
+        frame.extend_from_slice(payload);
+        (&self.stream).write_all(&frame).map_err(io_error)?;
+        if let Some(metrics) = &self.metrics {
+            metrics.add_wire_bytes(Direction::Sent, payload.len());
+        }
+        Ok(())
+    }
+
+    /// Reads the next frame and decompresses its payload, which is `None` for the goodbye of the
+    /// other party.
+    fn read_frame(&self) -> Result<Option<Vec<u8>>, PartyError> {
+        if self.peer_closed.get() {
+            return Err(PartyError::TransportClosed);
+        }
+        let mut header = [0; 9];
+        (&self.stream).read_exact(&mut header).map_err(io_error)?;
+        let len = u64::from_be_bytes(header[..8].try_into().unwrap());
+        let flags = header[8];
+        if len == 0 {
+            self.peer_closed.set(true);
+            return Ok(None);
+        }
+        if len > self.max_frame_len as u64 {
+            return Err(PartyError::FrameTooLarge {
+                len,
+                max: self.max_frame_len,
+            });
+        }
+        let mut payload = vec![0; len as usize];
+        (&self.stream).read_exact(&mut payload).map_err(io_error)?;
+        if let Some(metrics) = &self.metrics {
+            metrics.add_wire_bytes(Direction::Received, payload.len());
+        }
+        self.peer_compresses.set(flags & FLAG_COMPRESSES != 0);
+        if flags & FLAG_DEFLATE == 0 {
+            return Ok(Some(payload));
+        }
+        // the limit also holds for the decompressed message, which may be far larger than the
+        // frame
+        let mut inflated = Vec::new();
+        DeflateDecoder::new(payload.as_slice())
+            .take(self.max_frame_len as u64 + 1)
+            .read_to_end(&mut inflated)
+            .map_err(|e| PartyError::PError(Box::new(e)))?;
+        if inflated.len() > self.max_frame_len {
+            return Err(PartyError::InflatedTooLarge {
+                max: self.max_frame_len,
+            });
+        }
+        Ok(Some(inflated))
+    }
+
+    /// Says goodbye to the other party and waits for its goodbye, after the last message. Fails
+    /// with [`PartyError::PeerDisconnected`] if the other party dropped the connection without
+    /// saying goodbye, e.g. because it crashed, and with [`PartyError::UnexpectedMessage`] if it
+    /// still sends messages.
+    pub fn close(&self) -> Result<(), PartyError> {
+        let phase = Phase::Shutdown;
+        if !self.said_goodbye.replace(true) {
+            self.write_frame(&[]).map_err(|e| in_phase(e, phase))?;
+            // nothing follows the goodbye
+            let _ = self.stream.shutdown(Shutdown::Write);
+        }
+        if self.peer_closed.get() {
+            return Ok(());
+        }
+        match self.read_frame().map_err(|e| in_phase(e, phase))? {
+            None => Ok(()),
+            Some(_) => Err(PartyError::UnexpectedMessage { phase }),
+        }
+    }
+}
+
+/// Compresses a payload with deflate. Writing into a vector cannot fail, but `None` just sends
+/// the payload uncompressed anyway.
+fn deflate(payload: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(payload).ok()?;
+    encoder.finish().ok()
+}
+
+/// A timed out read or write is a [`PartyError::TransportTimeout`], every other failure means
+/// the connection is gone.
+fn io_error(e: io::Error) -> PartyError {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => PartyError::TransportTimeout,
+        _ => PartyError::TransportClosed,
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&self, m: Envelope) -> Result<(), PartyError> {
+        if self.said_goodbye.get() {
+            return Err(PartyError::TransportClosed);
+        }
+        let start = Instant::now();
+        let payload = serde_json::to_vec(&m).map_err(|e| PartyError::PError(Box::new(e)))?;
+        self.serialization
+            .set(self.serialization.get() + start.elapsed());
+        self.write_frame(&payload)
+    }
+
+    fn recv(&self) -> Result<Envelope, PartyError> {
+        // a goodbye in the middle of the protocol is a disconnect
+        let payload = self.read_frame()?.ok_or(PartyError::TransportClosed)?;
+        let start = Instant::now();
+        let m = serde_json::from_slice(&payload).map_err(|e| PartyError::PError(Box::new(e)));
+        self.serialization
+            .set(self.serialization.get() + start.elapsed());
+        m
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), PartyError> {
+        self.stream
+            .set_read_timeout(Some(timeout))
+            .and_then(|_| self.stream.set_write_timeout(Some(timeout)))
+            .map_err(|e| PartyError::PError(Box::new(e)))
+    }
+
+    fn serialization_time(&self) -> Duration {
+        self.serialization.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deflate, TcpConfig, TcpTransport, FLAG_DEFLATE};
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::party::errors::{PartyError, Phase};
+    use crate::party::handshake::{preamble, PROTOCOL_VERSION};
+    use crate::party::observer::MetricsObserver;
+    use crate::party::party_gmw::{Messages, Party};
+    use crate::party::transport::{Envelope, Transport};
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    /// A listener on a free port of the loopback interface.
+    fn listener() -> TcpListener {
+        TcpListener::bind("127.0.0.1:0").unwrap()
+    }
+
+    #[test]
+    fn test_connect_retries_until_listening() {
+        // find a free port, which nobody listens on for a while
+        let addr = listener().local_addr().unwrap();
+        let h0 = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            let listener = TcpListener::bind(addr).unwrap();
+            let t0 = TcpTransport::accept(&listener, &TcpConfig::default()).unwrap();
+            t0.send(Envelope::new(0, Messages::Result(vec![true])))
+                .unwrap();
+            t0.close().unwrap();
+        });
+        let t1 = TcpTransport::connect(addr, &TcpConfig::default()).unwrap();
+        assert_eq!(
+            t1.recv().unwrap(),
+            Envelope::new(0, Messages::Result(vec![true]))
+        );
+        t1.close().unwrap();
+        h0.join().unwrap();
+
+        // without retries, nobody listening is an error right away
+        let addr = listener().local_addr().unwrap();
+        let config = TcpConfig {
+            retries: 0,
+            ..TcpConfig::default()
+        };
+        assert!(TcpTransport::connect(addr, &config).is_err());
+    }
+
+    #[test]
+    fn test_oversized_frame_and_timeout() {
+        let listener = listener();
+        let addr = listener.local_addr().unwrap();
+        let h1 = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            // a frame announcing far more bytes than are allowed, which never arrive
+            stream.write_all(&1_000_000u64.to_be_bytes()).unwrap();
+            stream.write_all(&[0]).unwrap();
+            stream
+        });
+        let config = TcpConfig {
+            io_timeout: Some(Duration::from_millis(100)),
+            max_frame_len: 1000,
+            ..TcpConfig::default()
+        };
+        let t0 = TcpTransport::accept(&listener, &config).unwrap();
+        let _stream = h1.join().unwrap();
+        assert!(matches!(
+            t0.recv(),
+            Err(PartyError::FrameTooLarge {
+                len: 1_000_000,
+                max: 1000
+            })
+        ));
+        assert_eq!(
+            PartyError::FrameTooLarge {
+                len: 1_000_000,
+                max: 1000
+            }
+            .to_string(),
+            "The other party sent a message of 1000000 bytes, but at most 1000 are allowed"
+        );
+        // the other party stays connected, but sends nothing more
+        assert!(matches!(t0.recv(), Err(PartyError::TransportTimeout)));
+    }
+
+    #[test]
+    fn test_run_and_shutdown() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let listener = listener();
+        let addr = listener.local_addr().unwrap();
+
+        let c1 = c.clone();
+        let h1 = thread::spawn(move || {
+            let t1 = TcpTransport::connect(addr, &TcpConfig::default()).unwrap();
+            let mut p1 = Party::with_agreed_seed(c1, t1, true).unwrap();
+            let output = p1.execute(&to_bits(20, 64).unwrap()).unwrap();
+            p1.transport().close().unwrap();
+            output
+        });
+        let t0 = TcpTransport::accept(&listener, &TcpConfig::default()).unwrap();
+        let mut p0 = Party::with_agreed_seed(c, t0, false).unwrap();
+        assert_eq!(
+            p0.execute(&to_bits(22, 64).unwrap()).unwrap(),
+            to_bits(42, 64).unwrap()
+        );
+        p0.transport().close().unwrap();
+        assert_eq!(h1.join().unwrap(), to_bits(42, 64).unwrap());
+
+        // a party which goes away without a goodbye is noticed at the shutdown
+        let h1 = thread::spawn(move || {
+            let t1 = TcpTransport::connect(addr, &TcpConfig::default()).unwrap();
+            t1.send(Envelope::new(0, Messages::Result(Vec::new())))
+                .unwrap();
+        });
+        let t0 = TcpTransport::accept(&listener, &TcpConfig::default()).unwrap();
+        h1.join().unwrap();
+        assert!(matches!(
+            t0.close(),
+            Err(PartyError::UnexpectedMessage {
+                phase: Phase::Shutdown
+            })
+        ));
+        let h1 = thread::spawn(move || drop(TcpTransport::connect(addr, &TcpConfig::default())));
+        let t0 = TcpTransport::accept(&listener, &TcpConfig::default()).unwrap();
+        h1.join().unwrap();
+        assert!(matches!(
+            t0.close(),
+            Err(PartyError::PeerDisconnected {
+                phase: Phase::Shutdown
+            })
+        ));
+    }
+
+    /// A connected pair of transports with the given settings, which record their wire bytes.
+    fn transport_pair(
+        config0: TcpConfig,
+        config1: TcpConfig,
+    ) -> (
+        (TcpTransport, MetricsObserver),
+        (TcpTransport, MetricsObserver),
+    ) {
+        let listener = listener();
+        let addr = listener.local_addr().unwrap();
+        let h1 = thread::spawn(move || TcpTransport::connect(addr, &config1).unwrap());
+        let mut t0 = TcpTransport::accept(&listener, &config0).unwrap();
+        let mut t1 = h1.join().unwrap();
+        let (m0, m1) = (MetricsObserver::new(), MetricsObserver::new());
+        t0.set_metrics(m0.clone());
+        t1.set_metrics(m1.clone());
+        ((t0, m0), (t1, m1))
+    }
+
+    #[test]
+    fn test_compressed_frames() {
+        let compressing = TcpConfig {
+            compress: true,
+            compress_threshold: 100,
+            ..TcpConfig::default()
+        };
+        let hello = Envelope::new(
+            0,
+            Messages::Hello {
+                preamble: preamble(PROTOCOL_VERSION),
+            },
+        );
+        let small = Envelope::new(1, Messages::Result(vec![true; 5]));
+        let large = Envelope::new(
+            2,
+            Messages::Shares {
+                shares: vec![true; 1000],
+            },
+        );
+        let json_len = |m: &Envelope| serde_json::to_vec(m).unwrap().len();
+
+        let ((t0, m0), (t1, m1)) = transport_pair(compressing, compressing);
+        // nothing is compressed before the handshake
+        assert!(!t0.compresses());
+        t0.send(hello.clone()).unwrap();
+        t1.send(hello.clone()).unwrap();
+        assert_eq!(t0.recv().unwrap(), hello);
+        assert_eq!(t1.recv().unwrap(), hello);
+        assert!(t0.compresses() && t1.compresses());
+        let sent_before = m0.metrics().wire_bytes_sent;
+
+        // a message below the threshold goes out as it is, one above it compressed
+        t0.send(small.clone()).unwrap();
+        assert_eq!(t1.recv().unwrap(), small);
+        assert_eq!(m0.metrics().wire_bytes_sent - sent_before, json_len(&small));
+        t0.send(large.clone()).unwrap();
+        assert_eq!(t1.recv().unwrap(), large);
+        let large_wire = m0.metrics().wire_bytes_sent - sent_before - json_len(&small);
+        assert!(
+            large_wire < json_len(&large) / 10,
+            "sent {} bytes",
+            large_wire
+        );
+        assert_eq!(
+            m1.metrics().wire_bytes_received,
+            m0.metrics().wire_bytes_sent
+        );
+
+        // a compressed frame which decompresses beyond the limit is rejected
+        let json = serde_json::to_vec(&large).unwrap();
+        let bomb = deflate(&json).unwrap();
+        let limited = TcpConfig {
+            max_frame_len: 1000,
+            ..compressing
+        };
+        let listener = listener();
+        let addr = listener.local_addr().unwrap();
+        let h1 = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream
+                .write_all(&(bomb.len() as u64).to_be_bytes())
+                .unwrap();
+            stream.write_all(&[FLAG_DEFLATE]).unwrap();
+            stream.write_all(&bomb).unwrap();
+            stream
+        });
+        let t0 = TcpTransport::accept(&listener, &limited).unwrap();
+        let _stream = h1.join().unwrap();
+        assert!(matches!(
+            t0.recv(),
+            Err(PartyError::InflatedTooLarge { max: 1000 })
+        ));
+    }
+
+    #[test]
+    fn test_compressing_and_plain_party() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let compressing = TcpConfig {
+            compress: true,
+            compress_threshold: 64,
+            ..TcpConfig::default()
+        };
+        let mut plain_wire_bytes = 0;
+        for peer_compresses in [false, true] {
+            let config1 = TcpConfig {
+                compress: peer_compresses,
+                ..compressing
+            };
+            let ((t0, m0), (t1, _)) = transport_pair(compressing, config1);
+            let c1 = c.clone();
+            let h1 = thread::spawn(move || {
+                let mut p1 = Party::with_agreed_seed(c1, t1, true).unwrap();
+                p1.execute(&to_bits(20, 64).unwrap()).unwrap()
+            });
+            let mut p0 = Party::with_agreed_seed(c.clone(), t0, false).unwrap();
+            p0.set_observer(Box::new(m0.clone()));
+            assert_eq!(
+                p0.execute(&to_bits(22, 64).unwrap()).unwrap(),
+                to_bits(42, 64).unwrap()
+            );
+            assert_eq!(h1.join().unwrap(), to_bits(42, 64).unwrap());
+
+            // the wire bytes also count the seed agreement, which the observer did not see
+            let metrics = m0.metrics();
+            if peer_compresses {
+                assert!(metrics.wire_bytes_sent < plain_wire_bytes);
+            } else {
+                assert!(metrics.wire_bytes_sent > metrics.bytes_sent);
+                assert!(!p0.transport().compresses());
+                plain_wire_bytes = metrics.wire_bytes_sent;
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::party::errors::{panic_message, PartyError};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::thread;
+
+/// The failure of a run of both parties in threads, see [`run_in_threads`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartyFailure {
+    /// The party which caused the failure, 0 or 1.
+    pub party: usize,
+    /// The message of its error, or of its panic.
+    pub message: String,
+}
+
+impl Display for PartyFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Party {} failed first: {}", self.party, self.message)
+    }
+}
+
+impl Error for PartyFailure {}
+
+/// Runs `p0` and `p1`, usually the executions of the two parties, in threads of their own and
+/// returns both results. If a party fails or its thread panics, the error of the party which
+/// caused the failure is returned with its readable message, rather than a panic payload. The
+/// threads are scoped, so `p0` and `p1` may borrow the parties instead of taking them.
+pub fn run_in_threads<R: Send>(
+    p0: impl FnOnce() -> Result<R, PartyError> + Send,
+    p1: impl FnOnce() -> Result<R, PartyError> + Send,
+) -> Result<(R, R), PartyFailure> {
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    thread::scope(|scope| {
+        let handles = [scope.spawn(p0), scope.spawn(p1)];
+        for (party, handle) in handles.into_iter().enumerate() {
+            match handle.join() {
+                Ok(Ok(result)) => results.push(result),
+                Ok(Err(e)) => {
+                    let disconnected = matches!(e, PartyError::PeerDisconnected { .. });
+                    failures.push((party, e.to_string(), disconnected));
+                }
+                Err(payload) => {
+                    let message = format!("The thread panicked: {}", panic_message(&*payload));
+                    failures.push((party, message, false))
+                }
+            }
+        }
+    });
+
+    // A party which only noticed that the other one went away did not cause the failure, so the
+    // other party's error is reported if there is one.
+    if let Some((party, message, _)) = failures
+        .into_iter()
+        .min_by_key(|(_, _, disconnected)| *disconnected)
+    {
+        return Err(PartyFailure { party, message });
+    }
+
+    let r1 = results.pop().unwrap();
+    let r0 = results.pop().unwrap();
+    Ok((r0, r1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_in_threads, PartyFailure};
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::party::errors::{PartyError, Phase};
+    use crate::party::party_gmw::new_party_pair;
+
+    #[test]
+    fn test_failing_execution_is_readable() {
+        // party 0 enters one bit too few, party 1 then finds it disconnected
+        let c = Circuit::parse("1 5\n2 2 2\n1 1\n\n2 1 0 2 4 AND\n").unwrap();
+        let (mut p0, mut p1) = new_party_pair(c).unwrap();
+        let result = run_in_threads(
+            move || p0.execute(&[true]),
+            move || p1.execute(&[false, true]),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Party 0 failed first: Wrong amount of input bits. Expected: 2, actually: 1"
+        );
+    }
+
+    #[test]
+    fn test_blames_the_party_which_did_not_disconnect() {
+        let result: Result<((), ()), _> = run_in_threads(
+            || {
+                Err(PartyError::PeerDisconnected {
+                    phase: Phase::InputSharing,
+                })
+            },
+            || panic!("out of triples"),
+        );
+        assert_eq!(
+            result,
+            Err(PartyFailure {
+                party: 1,
+                message: "The thread panicked: out of triples".to_string()
+            })
+        );
+
+        assert_eq!(run_in_threads(|| Ok(1), || Ok(2)), Ok((1, 2)));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::party::profile::Profile;
+use std::time::Duration;
+
+/// Time spent by a party in one execution of the GMW protocol, split by gate type. Recorded by
+/// [`crate::party::party_gmw::Party::execute_timed`], which needs the `timing` feature, as a
+/// summary of the [`Profile`] of the execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimingReport {
+    /// The whole execution, including the input sharing and the output exchange.
+    pub total: Duration,
+    pub xor: Duration,
+    pub inv: Duration,
+    /// Local work of the AND gates: reading the operands, taking a triple, opening the masked
+    /// values and combining them with the other party's.
+    pub and_compute: Duration,
+    /// Sending the opened values of the AND gates and waiting for the other party's.
+    pub and_communication: Duration,
+}
+
+impl From<&Profile> for TimingReport {
+    fn from(profile: &Profile) -> Self {
+        TimingReport {
+            total: profile.total,
+            xor: profile.xor_gates,
+            inv: profile.inv_gates,
+            and_compute: profile.and_gates.saturating_sub(profile.and_communication),
+            and_communication: profile.and_communication,
+        }
+    }
+}
+
+impl TimingReport {
+    /// Total time spent on AND gates.
+    pub fn and(&self) -> Duration {
+        self.and_compute + self.and_communication
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::party::party_gmw::new_party_pair;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timing_report() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let (mut p0, mut p1) = new_party_pair(Circuit::parse(&contents).unwrap()).unwrap();
+        let h = thread::spawn(move || p1.execute_timed(&[true; 64]).unwrap());
+        let (_, report) = p0.execute_timed(&[false; 64]).unwrap();
+        h.join().unwrap();
+
+        assert!(report.and_compute > Duration::ZERO);
+        assert!(report.and_communication > Duration::ZERO);
+        assert!(report.xor > Duration::ZERO);
+        assert!(report.total >= report.xor + report.inv + report.and());
+    }
+
+    #[test]
+    fn test_timing_report_counts_towards_profile() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let (mut p0, mut p1) = new_party_pair(Circuit::parse(&contents).unwrap()).unwrap();
+        p0.set_profiling(true);
+        let h = thread::spawn(move || {
+            p1.execute(&[true; 64]).unwrap();
+            p1.execute(&[true; 64]).unwrap();
+        });
+        p0.execute(&[false; 64]).unwrap();
+        let before = p0.profile().unwrap();
+        let (_, report) = p0.execute_timed(&[false; 64]).unwrap();
+        h.join().unwrap();
+
+        let after = p0.profile().unwrap();
+        assert_eq!(after.total, before.total + report.total);
+        assert_eq!(after.xor_gates, before.xor_gates + report.xor);
+        assert_eq!(
+            after.and_communication,
+            before.and_communication + report.and_communication
+        );
+    }
+
+    #[test]
+    fn test_timing_report_without_and_gates() {
+        let xor = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+        let (mut p0, mut p1) = new_party_pair(xor).unwrap();
+        let h = thread::spawn(move || p1.execute_timed(&[true]).unwrap());
+        let (output, report) = p0.execute_timed(&[false]).unwrap();
+        assert_eq!(output, vec![true]);
+        h.join().unwrap();
+
+        assert_eq!(report.and(), Duration::ZERO);
+        assert!(report.xor > Duration::ZERO);
+    }
+}
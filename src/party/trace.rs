@@ -0,0 +1,284 @@
+use crate::party::errors::PartyError;
+use crate::party::transport::{Envelope, Transport};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// One message of a recorded protocol run, seen from the recording party.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraceEntry {
+    Sent(Envelope),
+    Received(Envelope),
+}
+
+impl TraceEntry {
+    /// The same message, seen from the other party.
+    fn flipped(self) -> TraceEntry {
+        match self {
+            TraceEntry::Sent(m) => TraceEntry::Received(m),
+            TraceEntry::Received(m) => TraceEntry::Sent(m),
+        }
+    }
+}
+
+/// A line of a trace file. The sequence number counts the messages of the recording party, sent
+/// and received ones, from 0. The message variant tells the protocol phase.
+#[derive(Serialize, Deserialize)]
+struct TraceLine {
+    sequence: usize,
+    #[serde(flatten)]
+    entry: TraceEntry,
+}
+
+/// A transport which passes all messages on to the wrapped transport and records them to a trace
+/// file, one JSON encoded [`TraceEntry`] with its sequence number per line. Every entry is written
+/// right away, so the trace of a run which crashed is complete up to the crash.
+pub struct RecordingTransport<C: Transport> {
+    inner: C,
+    trace: RefCell<BufWriter<File>>,
+    sequence: RefCell<usize>,
+}
+
+impl<C: Transport> RecordingTransport<C> {
+    /// Wraps `inner` and creates (or truncates) the trace file at `path`.
+    pub fn create(inner: C, path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(RecordingTransport {
+            inner,
+            trace: RefCell::new(BufWriter::new(File::create(path)?)),
+            sequence: RefCell::new(0),
+        })
+    }
+
+    fn record(&self, entry: TraceEntry) -> Result<(), PartyError> {
+        let mut sequence = self.sequence.borrow_mut();
+        let line = TraceLine {
+            sequence: *sequence,
+            entry,
+        };
+        *sequence += 1;
+        let mut trace = self.trace.borrow_mut();
+        serde_json::to_writer(&mut *trace, &line).map_err(|e| PartyError::PError(Box::new(e)))?;
+        writeln!(trace)
+            .and_then(|_| trace.flush())
+            .map_err(|e| PartyError::PError(Box::new(e)))
+    }
+}
+
+impl<C: Transport> Transport for RecordingTransport<C> {
+    fn send(&self, m: Envelope) -> Result<(), PartyError> {
+        self.record(TraceEntry::Sent(m.clone()))?;
+        self.inner.send(m)
+    }
+
+    fn recv(&self) -> Result<Envelope, PartyError> {
+        let m = self.inner.recv()?;
+        self.record(TraceEntry::Received(m.clone()))?;
+        Ok(m)
+    }
+
+    fn serialization_time(&self) -> Duration {
+        self.inner.serialization_time()
+    }
+}
+
+/// A transport which plays the other party of a recorded run. Received messages are taken from
+/// the trace, and every sent message is checked against the recorded one, so a party which
+/// behaves differently than in the recorded run fails with [`PartyError::ReplayMismatch`] and the
+/// sequence number of the message. Once the trace is exhausted, the transport behaves as if the
+/// other party disconnected.
+pub struct ReplayTransport {
+    // the messages the party has to send and the ones it receives, each with its position in the
+    // trace
+    sent: RefCell<VecDeque<(usize, Envelope)>>,
+    received: RefCell<VecDeque<(usize, Envelope)>>,
+}
+
+impl ReplayTransport {
+    /// Creates a replay of the given trace entries.
+    pub fn new(entries: Vec<TraceEntry>) -> Self {
+        let mut sent = VecDeque::new();
+        let mut received = VecDeque::new();
+        for (position, entry) in entries.into_iter().enumerate() {
+            match entry {
+                TraceEntry::Sent(m) => sent.push_back((position, m)),
+                TraceEntry::Received(m) => received.push_back((position, m)),
+            }
+        }
+        ReplayTransport {
+            sent: RefCell::new(sent),
+            received: RefCell::new(received),
+        }
+    }
+
+    /// Loads a trace written by a [`RecordingTransport`], to replay the run against the party
+    /// which recorded it.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(read_trace(path)?))
+    }
+
+    /// Loads a trace written by a [`RecordingTransport`] of the other party, to replay the run
+    /// against a party in the role of the one which did not record it. The messages the
+    /// recording party sent are received, and the ones it received must be sent.
+    pub fn open_peer(path: impl AsRef<Path>) -> io::Result<Self> {
+        let entries = read_trace(path)?;
+        Ok(Self::new(
+            entries.into_iter().map(TraceEntry::flipped).collect(),
+        ))
+    }
+}
+
+fn read_trace(path: impl AsRef<Path>) -> io::Result<Vec<TraceEntry>> {
+    fs::read_to_string(path)?
+        .lines()
+        .map(|line| Ok(serde_json::from_str::<TraceLine>(line)?.entry))
+        .collect()
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, m: Envelope) -> Result<(), PartyError> {
+        // Sends and receives are checked separately, since both parties send their message of a
+        // round before receiving the other one's, so the order in the trace of the other party
+        // differs.
+        match self.sent.borrow_mut().pop_front() {
+            Some((_, recorded)) if recorded == m => Ok(()),
+            Some((position, _)) => Err(PartyError::ReplayMismatch(position)),
+            None => Err(PartyError::TransportClosed),
+        }
+    }
+
+    fn recv(&self) -> Result<Envelope, PartyError> {
+        self.received
+            .borrow_mut()
+            .pop_front()
+            .map(|(_, m)| m)
+            .ok_or(PartyError::TransportClosed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecordingTransport, ReplayTransport};
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::input::to_bits;
+    use crate::mul_triple::SeededMTP;
+    use crate::party::errors::PartyError;
+    use crate::party::party_gmw::Party;
+    use crate::party::transport::{channel_pair, Transport};
+    use rand::rngs::StdRng;
+    use std::thread;
+
+    /// Creates a party whose messages only depend on its input.
+    fn party<C: Transport>(
+        c: &Circuit,
+        transport: C,
+        is_p1: bool,
+        seed: [u8; 32],
+    ) -> Party<SeededMTP<StdRng>, C> {
+        let mut p = Party::new(c.clone(), transport, is_p1, SeededMTP::new([9; 32])).unwrap();
+        p.seed_shares(seed);
+        p
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let dir = std::env::temp_dir();
+        let path0 = dir.join(format!("mpc_trace_p0_{}.jsonl", std::process::id()));
+        let path1 = dir.join(format!("mpc_trace_p1_{}.jsonl", std::process::id()));
+
+        let (t0, t1) = channel_pair();
+        let mut p0 = party(
+            &c,
+            RecordingTransport::create(t0, &path0).unwrap(),
+            false,
+            [1; 32],
+        );
+        let mut p1 = party(
+            &c,
+            RecordingTransport::create(t1, &path1).unwrap(),
+            true,
+            [2; 32],
+        );
+        let h0 = thread::spawn(move || p0.execute(&[true]).unwrap());
+        let h1 = thread::spawn(move || p1.execute(&[true]).unwrap());
+        assert_eq!(h0.join().unwrap(), vec![true]);
+        assert_eq!(h1.join().unwrap(), vec![true]);
+
+        // both parties replay their side of the run, without the other party
+        let mut p0 = party(&c, ReplayTransport::open(&path0).unwrap(), false, [1; 32]);
+        let mut p1 = party(&c, ReplayTransport::open(&path1).unwrap(), true, [2; 32]);
+        assert_eq!(p0.execute(&[true]).unwrap(), vec![true]);
+        assert_eq!(p1.execute(&[true]).unwrap(), vec![true]);
+
+        // the sent input share is just a random mask, but a different input changes the opened
+        // values of the AND gate, the seventh recorded message after the two rounds of the handshake
+        // and the input shares
+        let mut p0 = party(&c, ReplayTransport::open(&path0).unwrap(), false, [1; 32]);
+        assert!(matches!(
+            p0.execute(&[false]),
+            Err(PartyError::ReplayMismatch(6))
+        ));
+
+        std::fs::remove_file(path0).unwrap();
+        std::fs::remove_file(path1).unwrap();
+    }
+
+    #[test]
+    fn test_replay_peer() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let c = Circuit::parse(&contents).unwrap();
+        let path1 =
+            std::env::temp_dir().join(format!("mpc_trace_peer_{}.jsonl", std::process::id()));
+        let (a, b) = (to_bits(1234, 64).unwrap(), to_bits(4321, 64).unwrap());
+
+        let (t0, t1) = channel_pair();
+        let mut p0 = party(&c, t0, false, [1; 32]);
+        let mut p1 = party(
+            &c,
+            RecordingTransport::create(t1, &path1).unwrap(),
+            true,
+            [2; 32],
+        );
+        let input = a.clone();
+        let h0 = thread::spawn(move || p0.execute(&input).unwrap());
+        let output = p1.execute(&b).unwrap();
+        assert_eq!(h0.join().unwrap(), output);
+        drop(p1);
+
+        let trace = std::fs::read_to_string(&path1).unwrap();
+        assert!(trace
+            .lines()
+            .next()
+            .unwrap()
+            .starts_with(r#"{"sequence":0,"#));
+
+        // party 1's side of the run is played against a fresh party 0
+        let mut p0 = party(
+            &c,
+            ReplayTransport::open_peer(&path1).unwrap(),
+            false,
+            [1; 32],
+        );
+        assert_eq!(p0.execute(&a).unwrap(), output);
+
+        // party 0 with a different input deviates when opening the first AND gate
+        let mut flipped = a.clone();
+        flipped[0] = !flipped[0];
+        let mut p0 = party(
+            &c,
+            ReplayTransport::open_peer(&path1).unwrap(),
+            false,
+            [1; 32],
+        );
+        assert!(matches!(
+            p0.execute(&flipped),
+            Err(PartyError::ReplayMismatch(_))
+        ));
+
+        std::fs::remove_file(path1).unwrap();
+    }
+}
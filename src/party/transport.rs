@@ -0,0 +1,275 @@
+use crate::party::errors::{PartyError, Phase};
+use crate::party::party_gmw::Messages;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A message together with its sequence number, which is what the transports carry. A party
+/// numbers its messages by the communication round they belong to, so that a message of another
+/// round, e.g. a dropped or repeated one, is noticed instead of being taken as the answer to the
+/// wrong gate. The layout of the envelope never changes, see [`Messages`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub sequence: usize,
+    pub message: Messages,
+}
+
+impl Envelope {
+    pub fn new(sequence: usize, message: Messages) -> Self {
+        Envelope { sequence, message }
+    }
+
+    /// Returns the message if it has the `expected` sequence number. Fails with
+    /// [`PartyError::OutOfSyncMessage`] for `phase` otherwise.
+    pub fn open(self, expected: usize, phase: Phase) -> Result<Messages, PartyError> {
+        if self.sequence != expected {
+            return Err(PartyError::OutOfSyncMessage {
+                expected,
+                got: self.sequence,
+                phase,
+            });
+        }
+        Ok(self.message)
+    }
+}
+
+/// The connection of a party to the other party. A `Party` only talks to the other party through
+/// this trait, so the protocol can run over channels, a network connection or a mock.
+pub trait Transport {
+    /// Sends a message to the other party. Fails with [`PartyError::TransportClosed`] if the other
+    /// party is gone, and with [`PartyError::TransportTimeout`] if the transport has a timeout
+    /// and the other party does not take the message in time.
+    fn send(&self, m: Envelope) -> Result<(), PartyError>;
+    /// Blocks until the next message of the other party arrives. Fails with
+    /// [`PartyError::TransportClosed`] if the other party is gone, and with
+    /// [`PartyError::TransportTimeout`] if the transport has a timeout and no message arrives in
+    /// time.
+    fn recv(&self) -> Result<Envelope, PartyError>;
+    /// Lets sending and receiving fail with [`PartyError::TransportTimeout`] if they block for
+    /// longer than `timeout`. Fails with [`PartyError::TimeoutUnsupported`] if the transport
+    /// cannot time out.
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), PartyError> {
+        let _ = timeout;
+        Err(PartyError::TimeoutUnsupported)
+    }
+    /// Total time spent turning messages into bytes and back so far. Transports which pass the
+    /// messages on as they are report zero.
+    fn serialization_time(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Number of messages a channel between two parties buffers by default.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 16;
+
+/// Transport between two parties running in the same process, e.g. in two threads. The channels
+/// are bounded, so a sender blocks once the other party has `capacity` messages it did not take
+/// yet, just like on a real connection. In GMW, every party sends one message per round and then
+/// waits for the message of the other party, so a capacity of 1 suffices. A capacity of 0 would
+/// deadlock, as both parties send at the same time.
+///
+/// Without a timeout, a party waits forever for a stalled party. With a timeout, both blocking
+/// receives and sends blocked on a full channel fail with [`PartyError::TransportTimeout`].
+pub struct ChannelTransport {
+    sender: SyncSender<Envelope>,
+    receiver: Receiver<Envelope>,
+    timeout: Option<Duration>,
+}
+
+impl ChannelTransport {
+    pub fn new(sender: SyncSender<Envelope>, receiver: Receiver<Envelope>) -> Self {
+        ChannelTransport {
+            sender,
+            receiver,
+            timeout: None,
+        }
+    }
+
+    /// Lets sending and receiving fail if they block for longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Creates two channel transports which are connected to each other, buffering
+/// [`DEFAULT_CHANNEL_CAPACITY`] messages in each direction.
+pub fn channel_pair() -> (ChannelTransport, ChannelTransport) {
+    channel_pair_with_capacity(DEFAULT_CHANNEL_CAPACITY)
+}
+
+/// Creates two connected channel transports buffering `capacity` messages in each direction.
+/// Panics if the capacity is 0, see [`ChannelTransport`].
+pub fn channel_pair_with_capacity(capacity: usize) -> (ChannelTransport, ChannelTransport) {
+    assert!(
+        capacity > 0,
+        "parties deadlock on channels without capacity"
+    );
+    let (sender0, receiver1) = sync_channel(capacity);
+    let (sender1, receiver0) = sync_channel(capacity);
+    (
+        ChannelTransport::new(sender0, receiver0),
+        ChannelTransport::new(sender1, receiver1),
+    )
+}
+
+/// How long a send waits before checking again whether the channel has room.
+const SEND_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+impl Transport for ChannelTransport {
+    fn send(&self, m: Envelope) -> Result<(), PartyError> {
+        let Some(timeout) = self.timeout else {
+            // sending only fails if the receiver was dropped
+            return self.sender.send(m).map_err(|_| PartyError::TransportClosed);
+        };
+
+        // a bounded sender has no send with a timeout, so retry until the deadline
+        let deadline = Instant::now() + timeout;
+        let mut m = m;
+        loop {
+            match self.sender.try_send(m) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(_)) => return Err(PartyError::TransportClosed),
+                Err(TrySendError::Full(returned)) => {
+                    if Instant::now() >= deadline {
+                        return Err(PartyError::TransportTimeout);
+                    }
+                    m = returned;
+                    thread::sleep(SEND_POLL_INTERVAL);
+                }
+            }
+        }
+    }
+
+    fn recv(&self) -> Result<Envelope, PartyError> {
+        match self.timeout {
+            None => self
+                .receiver
+                .recv()
+                .map_err(|_| PartyError::TransportClosed),
+            Some(timeout) => self.receiver.recv_timeout(timeout).map_err(|e| match e {
+                RecvTimeoutError::Timeout => PartyError::TransportTimeout,
+                RecvTimeoutError::Disconnected => PartyError::TransportClosed,
+            }),
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), PartyError> {
+        self.timeout = Some(timeout);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel_pair_with_capacity, Envelope, Transport};
+    use crate::circuit::circuit_parser::Circuit;
+    use crate::mul_triple::SeededMTP;
+    use crate::party::errors::PartyError;
+    use crate::party::party_gmw::{Messages, Party};
+    use rand::rngs::StdRng;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    /// Plays an honest party 1 with the input `y` for a circuit computing x ^ y, answering every
+    /// message of party 0 right away.
+    struct MockTransport {
+        y: bool,
+        // the share of its own input which party 1 sends to party 0
+        share: bool,
+        // the share of party 0's input which party 1 received
+        received: RefCell<Option<bool>>,
+        incoming: RefCell<VecDeque<Envelope>>,
+    }
+
+    impl Transport for MockTransport {
+        fn send(&self, m: Envelope) -> Result<(), PartyError> {
+            let reply = match m.message {
+                Messages::Hello { preamble } => Messages::Hello { preamble },
+                Messages::Settings { output_chunk_size } => {
+                    Messages::Settings { output_chunk_size }
+                }
+                Messages::Shares { shares } => {
+                    *self.received.borrow_mut() = Some(shares[0]);
+                    Messages::Shares {
+                        shares: vec![self.share],
+                    }
+                }
+                Messages::Result(_) => {
+                    let x_share = self.received.borrow().unwrap();
+                    Messages::Result(vec![x_share ^ self.y ^ self.share])
+                }
+                _ => return Err(PartyError::ThreadSendingError),
+            };
+            // the answer belongs to the same round
+            self.incoming
+                .borrow_mut()
+                .push_back(Envelope::new(m.sequence, reply));
+            Ok(())
+        }
+
+        fn recv(&self) -> Result<Envelope, PartyError> {
+            self.incoming
+                .borrow_mut()
+                .pop_front()
+                .ok_or(PartyError::TransportClosed)
+        }
+    }
+
+    #[test]
+    fn test_mock_transport() {
+        let c = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 XOR\n").unwrap();
+
+        for (x, y, share) in [
+            (false, true, false),
+            (true, true, true),
+            (true, false, true),
+        ] {
+            let transport = MockTransport {
+                y,
+                share,
+                received: RefCell::new(None),
+                incoming: RefCell::new(VecDeque::new()),
+            };
+            let mut p0 = Party::new(
+                c.clone(),
+                transport,
+                false,
+                SeededMTP::<StdRng>::new([0; 32]),
+            )
+            .unwrap();
+            assert_eq!(p0.execute(&[x]).unwrap(), vec![x ^ y]);
+        }
+    }
+
+    #[test]
+    fn test_stalled_receiver_times_out() {
+        let (t0, t1) = channel_pair_with_capacity(1);
+        let t0 = t0.with_timeout(Duration::from_millis(50));
+
+        // the first message fits into the channel, the second waits for room which never comes
+        let message = || {
+            Envelope::new(
+                0,
+                Messages::Shares {
+                    shares: vec![true; 8],
+                },
+            )
+        };
+        assert!(t0.send(message()).is_ok());
+        assert!(matches!(
+            t0.send(message()),
+            Err(PartyError::TransportTimeout)
+        ));
+        assert!(matches!(t0.recv(), Err(PartyError::TransportTimeout)));
+
+        // once the other party is gone, the channel reports that instead
+        drop(t1);
+        assert!(matches!(
+            t0.send(message()),
+            Err(PartyError::TransportClosed)
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// The output of a protocol run, as reconstructed by the parties.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunReport {
+    /// The bits of every output value, in header order.
+    pub outputs: Vec<Vec<bool>>,
+    /// The names of the output values, if the circuit has metadata naming them.
+    pub names: Option<Vec<String>>,
+    /// What the run cost, if it is known.
+    pub metadata: Option<RunMetadata>,
+}
+
+/// Facts about a protocol run besides its outputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RunMetadata {
+    /// Number of AND gates of the circuit.
+    pub and_gates: usize,
+    /// Number of communication rounds of the run. The first run of a connection includes the
+    /// handshake.
+    pub rounds: usize,
+    /// Number of multiplication triples taken from the provider.
+    pub triples_consumed: usize,
+}
+
+/// How the result of a run is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// The decoded output values, separated by commas and labelled by their names if they have
+    /// any. Single bit values are printed as `true` or `false`, values wider than 64 bits in hex.
+    Text,
+    /// A JSON object containing the decoded output values, their bits in hex and binary, their
+    /// names if they have any, and the metadata of the run. Values wider than 64 bits are given
+    /// in hex among the decoded values as well.
+    Json,
+}
+
+/// Decodes the bits of an output value, least significant bit first, into an unsigned number.
+/// Values wider than 64 bits are truncated to their lowest 64 bits.
+pub fn decode_output(value: &[bool]) -> u64 {
+    let mut solution: u64 = 0;
+    for (i, v) in value.iter().enumerate().take(64) {
+        solution |= u64::from(*v) << i;
+    }
+    solution
+}
+
+/// Decodes the bits of an output value like [`decode_output`], but as a two's complement number
+/// of the value's width, e.g. the 8 bit value 0xFF is -1. Values wider than 64 bits are truncated
+/// to their lowest 64 bits first.
+pub fn decode_signed(value: &[bool]) -> i64 {
+    let width = value.len().min(64);
+    if width == 0 {
+        return 0;
+    }
+    // move the sign bit of the value to bit 63, the arithmetic shift back copies it into all
+    // bits above the value
+    let unused = 64 - width as u32;
+    ((decode_output(value) as i64) << unused) >> unused
+}
+
+/// Formats the bits of a value, least significant bit first, as a hex number of any width, e.g.
+/// `0x1ffffffffffffffff` for 65 bits set.
+pub fn to_hex(value: &[bool]) -> String {
+    let digits: String = value
+        .chunks(4)
+        .rev()
+        .map(|nibble| {
+            let digit = nibble
+                .iter()
+                .enumerate()
+                .fold(0, |digit, (i, &bit)| digit | u32::from(bit) << i);
+            char::from_digit(digit, 16).unwrap()
+        })
+        .collect();
+    // without leading zeros, like the `{:#x}` format of a number
+    match digits.trim_start_matches('0') {
+        "" => "0x0".to_string(),
+        digits => format!("0x{}", digits),
+    }
+}
+
+/// A decoded output value in the JSON report, which is a number unless it is too wide for one.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum JsonValue {
+    Number(i128),
+    Hex(String),
+}
+
+impl RunReport {
+    /// Decodes every output value, as two's complement numbers if `signed` is set.
+    pub fn decoded(&self, signed: bool) -> Vec<i128> {
+        let decode = |v: &[bool]| match signed {
+            true => i128::from(decode_signed(v)),
+            false => i128::from(decode_output(v)),
+        };
+        self.outputs.iter().map(|v| decode(v)).collect()
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    values: Vec<JsonValue>,
+    hex: Vec<String>,
+    bits: &'a [Vec<bool>],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    names: Option<&'a [String]>,
+    #[serde(flatten)]
+    metadata: Option<&'a RunMetadata>,
+}
+
+/// Formats the result of a run in the given format. If `signed` is set, the output values are
+/// decoded as two's complement numbers.
+pub fn format_result(report: &RunReport, format: Format, signed: bool) -> String {
+    match format {
+        Format::Text => {
+            let values: Vec<String> = report
+                .decoded(signed)
+                .iter()
+                .zip(&report.outputs)
+                .map(|(v, bits)| match bits[..] {
+                    [bit] => bit.to_string(),
+                    _ if bits.len() > 64 => to_hex(bits),
+                    _ => v.to_string(),
+                })
+                .collect();
+            if let Some(names) = &report.names {
+                let labelled: Vec<String> = names
+                    .iter()
+                    .zip(&values)
+                    .map(|(name, value)| format!("{} = {}", name, value))
+                    .collect();
+                return labelled.join(", ");
+            }
+            values.join(", ")
+        }
+        Format::Json => serde_json::to_string(&JsonReport {
+            values: report
+                .decoded(signed)
+                .into_iter()
+                .zip(&report.outputs)
+                .map(|(v, bits)| match bits.len() > 64 {
+                    true => JsonValue::Hex(to_hex(bits)),
+                    false => JsonValue::Number(v),
+                })
+                .collect(),
+            // the hex form shows the bits, so it is never signed
+            hex: report.outputs.iter().map(|v| to_hex(v)).collect(),
+            bits: &report.outputs,
+            names: report.names.as_deref(),
+            metadata: report.metadata.as_ref(),
+        })
+        .expect("serializing a report cannot fail"),
+    }
+}
+
+/// Writes the formatted results of the runs to `writer`, one per line, as the `--output-file` of
+/// the CLI gets them.
+pub fn write_results(writer: &mut impl Write, results: &[String]) -> io::Result<()> {
+    for result in results {
+        writeln!(writer, "{}", result)?;
+    }
+    Ok(())
+}
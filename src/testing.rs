@@ -0,0 +1,312 @@
+//! Generators for synthetic circuits, shared by the tests and the benchmarks. Enable the `testing`
+//! feature to use them outside of the crate's own tests.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::circuit::circuit_builder::CircuitBuilder;
+use crate::circuit::circuit_parser::{Circuit, Gate, GateType, Header, WireId};
+
+/// Generates a random circuit with two input values of `input_width` bits and `gates` gates.
+/// Every gate reads two (or for INV one) uniformly chosen earlier wires, a fraction of
+/// `and_ratio` of the gates are ANDs and the rest are split between XOR and INV. The last
+/// `min(input_width, gates)` gates drive the output wires. The same seed yields the same circuit.
+pub fn synthetic_circuit(input_width: usize, gates: usize, and_ratio: f64, seed: u64) -> Circuit {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let inputs = 2 * input_width;
+
+    let circuit_gates: Vec<Gate> = (0..gates)
+        .map(|i| {
+            let available = inputs + i;
+            let a = WireId::unchecked(rng.gen_range(0..available));
+            let b = WireId::unchecked(rng.gen_range(0..available));
+            let gate_type = if rng.gen_bool(and_ratio) {
+                GateType::AND(a, b)
+            } else if rng.gen_bool(0.8) {
+                GateType::XOR(a, b)
+            } else {
+                GateType::INV(a)
+            };
+            Gate {
+                gate_type,
+                output: WireId::unchecked(available),
+            }
+        })
+        .collect();
+
+    Circuit::new(
+        Header {
+            gates_amount: gates,
+            wires_amount: inputs + gates,
+            niv: vec![input_width, input_width],
+            nov: vec![input_width.min(gates)],
+        },
+        circuit_gates,
+    )
+}
+
+/// Generates a circuit with two input values of `width` bits and an AND depth of exactly
+/// `and_depth`, e.g. to measure how the number of communication rounds affects the latency. Each
+/// of the `and_depth` layers computes `width` wires, each of them as `(u & v) ^ w` of three
+/// wires of the layer before, chosen with an RNG seeded with `seed`. The last layer is the
+/// output. Panics if `width` or `and_depth` is 0.
+pub fn layered_circuit(width: usize, and_depth: usize, seed: u64) -> Circuit {
+    assert!(
+        width > 0 && and_depth > 0,
+        "the circuit needs at least one gate"
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let inputs = 2 * width;
+
+    let wires_amount = inputs + 2 * width * and_depth;
+    let outputs_start = wires_amount - width;
+
+    let mut gates = Vec::with_capacity(2 * width * and_depth);
+    let mut previous: Vec<WireId> = (0..inputs).map(WireId::unchecked).collect();
+    let mut next_wire = inputs;
+    for layer in 0..and_depth {
+        let mut current = Vec::with_capacity(width);
+        for i in 0..width {
+            let u = previous[i];
+            let v = previous[rng.gen_range(0..previous.len())];
+            let w = previous[rng.gen_range(0..previous.len())];
+            let and_wire = WireId::unchecked(next_wire);
+            next_wire += 1;
+            // the last layer assigns the trailing wires, which are the outputs
+            let xor_wire = WireId::unchecked(if layer + 1 == and_depth {
+                outputs_start + i
+            } else {
+                next_wire += 1;
+                next_wire - 1
+            });
+            gates.push(Gate {
+                gate_type: GateType::AND(u, v),
+                output: and_wire,
+            });
+            gates.push(Gate {
+                gate_type: GateType::XOR(and_wire, w),
+                output: xor_wire,
+            });
+            current.push(xor_wire);
+        }
+        previous = current;
+    }
+
+    Circuit::new(
+        Header {
+            gates_amount: gates.len(),
+            wires_amount,
+            niv: vec![width, width],
+            nov: vec![width],
+        },
+        gates,
+    )
+}
+
+/// Parameters of [`generate_random_circuit`].
+#[derive(Debug, Clone)]
+pub struct RandomCircuitParams {
+    pub gates: usize,
+    /// Fraction of the gates which are ANDs, the rest are split between XOR and INV.
+    pub and_ratio: f64,
+    /// Widths of the input values. Party 0 supplies the first one, party 1 the rest.
+    pub input_widths: Vec<usize>,
+    /// Widths of the output values, together at most `gates` bits unless `outputs_anywhere` is
+    /// set.
+    pub output_widths: Vec<usize>,
+    /// Between 0 and 1. At 0, gates read uniformly chosen earlier wires, towards 1 they prefer
+    /// the most recently assigned wires, which makes the circuit deeper.
+    pub depth_bias: f64,
+    /// Picks the output bits among all wires, inputs and repeated wires included, instead of
+    /// taking the last gates. [`CircuitBuilder`] copies such bits to wires of their own with
+    /// extra gates.
+    pub outputs_anywhere: bool,
+}
+
+impl Default for RandomCircuitParams {
+    fn default() -> Self {
+        RandomCircuitParams {
+            gates: 100,
+            and_ratio: 0.3,
+            input_widths: vec![8, 8],
+            output_widths: vec![8],
+            depth_bias: 0.5,
+            outputs_anywhere: false,
+        }
+    }
+}
+
+/// Generates a random circuit which passes [`Circuit::validate`], for fuzzing the parser and the
+/// protocol. It is built with [`CircuitBuilder`]: each gate only reads wires created before it,
+/// so every wire is assigned once and the gates are in topological order. Unless
+/// `outputs_anywhere` is set, the last gates assign the trailing wires, which are the outputs.
+pub fn generate_random_circuit<R: Rng>(rng: &mut R, params: &RandomCircuitParams) -> Circuit {
+    let outputs: usize = params.output_widths.iter().sum();
+    assert!(
+        params.input_widths.iter().sum::<usize>() > 0,
+        "the circuit needs at least one input bit"
+    );
+    assert!(
+        params.outputs_anywhere || outputs <= params.gates,
+        "every output bit needs a gate"
+    );
+
+    // the exponent skews the uniform samples towards 0, i.e. towards the most recent wires
+    let skew = 1.0 + 8.0 * params.depth_bias;
+    let operand = |rng: &mut R, available: usize| {
+        let back = (rng.gen::<f64>().powf(skew) * available as f64) as usize;
+        available - 1 - back.min(available - 1)
+    };
+
+    let mut builder = CircuitBuilder::new();
+    let mut wires: Vec<WireId> = params
+        .input_widths
+        .iter()
+        .flat_map(|&width| builder.input(width))
+        .collect();
+    for _ in 0..params.gates {
+        let a = wires[operand(rng, wires.len())];
+        let b = wires[operand(rng, wires.len())];
+        let gate_type = if rng.gen_bool(params.and_ratio) {
+            GateType::AND(a, b)
+        } else if rng.gen_bool(0.8) {
+            GateType::XOR(a, b)
+        } else {
+            GateType::INV(a)
+        };
+        wires.push(builder.gate(gate_type));
+    }
+
+    let mut output_wires = if params.outputs_anywhere {
+        (0..outputs)
+            .map(|_| wires[rng.gen_range(0..wires.len())])
+            .collect()
+    } else {
+        wires.split_off(wires.len() - outputs)
+    }
+    .into_iter();
+    for &width in &params.output_widths {
+        builder.output(output_wires.by_ref().take(width).collect());
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_random_circuit, layered_circuit, synthetic_circuit, RandomCircuitParams};
+    use crate::circuit::circuit_parser::{Circuit, GateType};
+    use crate::party::local::run_local_with_rng;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_synthetic_circuit() {
+        let c = synthetic_circuit(8, 1000, 0.5, 1);
+        assert_eq!(c.gates().len(), 1000);
+        assert_eq!(c.header().nov, vec![8]);
+
+        let ands = c
+            .gates()
+            .iter()
+            .filter(|g| matches!(g.gate_type, GateType::AND(_, _)))
+            .count();
+        assert!(ands > 400 && ands < 600);
+
+        let parsed = Circuit::parse(&c.to_bristol()).unwrap();
+        assert_eq!(parsed.gates(), c.gates());
+        assert_eq!(c.evaluate_plain(&[false; 16]).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_layered_circuit() {
+        for (width, and_depth) in [(1, 1), (8, 1), (8, 16), (64, 5)] {
+            let c = layered_circuit(width, and_depth, 3);
+            c.validate().unwrap();
+            assert_eq!(c.and_depth(), and_depth);
+            assert_eq!(c.stats().and, width * and_depth);
+            assert_eq!(c.header().niv, vec![width, width]);
+            assert_eq!(c.header().nov, vec![width]);
+
+            let mut rng = StdRng::seed_from_u64(4);
+            let input: Vec<bool> = (0..2 * width).map(|_| rng.gen()).collect();
+            let plain = c.evaluate_plain(&input).unwrap();
+            let gmw = run_local_with_rng(&c, &input[..width], &input[width..], &mut rng);
+            assert_eq!(gmw.unwrap(), plain);
+        }
+        assert_eq!(
+            layered_circuit(8, 4, 1).gates(),
+            layered_circuit(8, 4, 1).gates()
+        );
+    }
+
+    #[test]
+    fn test_synthetic_circuit_is_deterministic() {
+        assert_eq!(
+            synthetic_circuit(4, 100, 0.3, 7).gates(),
+            synthetic_circuit(4, 100, 0.3, 7).gates()
+        );
+    }
+
+    /// Random parameters for the property tests, with small widths to keep them fast.
+    fn random_params(rng: &mut impl Rng) -> RandomCircuitParams {
+        let output_width = rng.gen_range(1..=8);
+        let outputs_anywhere = rng.gen();
+        // outputs picked among all wires need no gates of their own
+        let fewest_gates = if outputs_anywhere { 0 } else { output_width };
+        RandomCircuitParams {
+            gates: rng.gen_range(fewest_gates..=200),
+            and_ratio: rng.gen(),
+            input_widths: (0..rng.gen_range(1..=3))
+                .map(|_| rng.gen_range(1..=8))
+                .collect(),
+            output_widths: vec![output_width],
+            depth_bias: rng.gen(),
+            outputs_anywhere,
+        }
+    }
+
+    #[test]
+    fn test_random_circuits_are_valid() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..200 {
+            let params = random_params(&mut rng);
+            let c = generate_random_circuit(&mut rng, &params);
+            c.validate().unwrap();
+            assert_eq!(c.header().niv, params.input_widths);
+            assert_eq!(c.header().nov, params.output_widths);
+            assert_eq!(c.header().gates_amount, c.gates().len());
+            // only copied output bits need extra gates
+            match params.outputs_anywhere {
+                true => assert!(c.gates().len() >= params.gates),
+                false => assert_eq!(c.gates().len(), params.gates),
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_circuits_round_trip() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..200 {
+            let params = random_params(&mut rng);
+            let c = generate_random_circuit(&mut rng, &params);
+            let parsed = Circuit::parse(&c.to_bristol()).unwrap();
+            assert_eq!(parsed.header(), c.header());
+            assert_eq!(parsed.gates(), c.gates());
+        }
+    }
+
+    #[test]
+    fn test_random_circuits_plain_matches_gmw() {
+        let mut rng = StdRng::seed_from_u64(13);
+        for _ in 0..100 {
+            let params = random_params(&mut rng);
+            let c = generate_random_circuit(&mut rng, &params);
+
+            let width0 = params.input_widths[0];
+            let input: Vec<bool> = (0..c.get_niv_sum()).map(|_| rng.gen()).collect();
+            let (in0, in1) = input.split_at(width0);
+            let gmw = run_local_with_rng(&c, in0, in1, &mut rng).unwrap();
+            assert_eq!(gmw, c.evaluate_plain(&input).unwrap());
+        }
+    }
+}
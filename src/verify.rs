@@ -0,0 +1,253 @@
+//! Checks that a circuit computes an expected function of its two input values, by comparing its
+//! plain evaluation with a native Rust operation or with a reference circuit.
+
+use clap::ValueEnum;
+use rand::Rng;
+
+use crate::circuit::circuit_error::CircuitError;
+use crate::circuit::circuit_eval::{find_input, EquivCheck, EXHAUSTIVE_EQUIV_BITS};
+use crate::circuit::circuit_parser::Circuit;
+
+/// A native operation on two values, with wrapping semantics at the output width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Function {
+    Add,
+    Sub,
+    Mul,
+    And,
+    Xor,
+}
+
+impl Function {
+    /// Applies the operation and truncates the result to `width` bits.
+    pub fn apply(self, a: u64, b: u64, width: usize) -> u64 {
+        let result = match self {
+            Function::Add => a.wrapping_add(b),
+            Function::Sub => a.wrapping_sub(b),
+            Function::Mul => a.wrapping_mul(b),
+            Function::And => a & b,
+            Function::Xor => a ^ b,
+        };
+        result & mask(width)
+    }
+}
+
+/// What a circuit is checked against.
+pub enum Reference<'a> {
+    Function(Function),
+    /// Another circuit with the same input and output widths, e.g. the unoptimized original.
+    Circuit(&'a Circuit),
+}
+
+/// An input pair on which the circuit does not compute the expected value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counterexample {
+    pub a: u64,
+    pub b: u64,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+/// The outcome of [`verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// Number of input pairs evaluated, including the counterexample.
+    pub checked: u64,
+    /// Whether all input pairs were checked, instead of random samples.
+    pub exhaustive: bool,
+    /// The first input pair on which the outputs differ.
+    pub counterexample: Option<Counterexample>,
+}
+
+impl Verification {
+    pub fn passed(&self) -> bool {
+        self.counterexample.is_none()
+    }
+}
+
+fn mask(width: usize) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+fn from_bits(bits: &[bool]) -> u64 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |acc, (i, &b)| acc | (u64::from(b) << i))
+}
+
+/// Returns the widths of the two input values and of the output of a circuit, which must compute a
+/// function of two values of at most 64 bits each into at most 64 bits.
+fn shape(circuit: &Circuit) -> Result<(usize, usize, usize), CircuitError> {
+    let unsupported = |reason: &str| Err(CircuitError::UnsupportedShape(reason.to_string()));
+    let (w0, w1) = match circuit.header().niv[..] {
+        [w0, w1] => (w0, w1),
+        _ => return unsupported("it does not have exactly two input values"),
+    };
+    if w0 > 64 || w1 > 64 {
+        return unsupported("an input value is wider than 64 bits");
+    }
+    let output_width = circuit.get_nov_sum();
+    if output_width > 64 {
+        return unsupported("the output is wider than 64 bits");
+    }
+    Ok((w0, w1, output_width))
+}
+
+/// Compares `circuit` with `reference` on every input pair if the circuit has at most
+/// [`EXHAUSTIVE_EQUIV_BITS`] input bits, otherwise on `samples` random input pairs, like
+/// [`Circuit::equivalent_to`]. All output values together are read as one number. Stops at the
+/// first input pair on which they differ.
+pub fn verify(
+    circuit: &Circuit,
+    reference: &Reference,
+    samples: u64,
+    rng: &mut impl Rng,
+) -> Result<Verification, CircuitError> {
+    let (w0, w1, output_width) = shape(circuit)?;
+    if let Reference::Circuit(other) = reference {
+        if other.header().niv != circuit.header().niv {
+            return Err(CircuitError::InputWidthMismatch(
+                circuit.get_niv_sum(),
+                other.get_niv_sum(),
+            ));
+        }
+        if other.get_nov_sum() != output_width {
+            return Err(CircuitError::OutputWidthMismatch(
+                output_width,
+                other.get_nov_sum(),
+            ));
+        }
+    }
+
+    // the values of an input pair and the outputs of the circuit and the reference on it
+    let evaluate = |input: &[bool]| -> Result<(u64, u64, u64, u64), CircuitError> {
+        let (a, b) = (from_bits(&input[..w0]), from_bits(&input[w0..]));
+        let expected = match reference {
+            Reference::Function(f) => f.apply(a, b, output_width),
+            Reference::Circuit(other) => from_bits(&other.evaluate_plain(input)?),
+        };
+        Ok((a, b, expected, from_bits(&circuit.evaluate_plain(input)?)))
+    };
+
+    let exhaustive = w0 + w1 <= EXHAUSTIVE_EQUIV_BITS;
+    let strategy = match exhaustive {
+        true => EquivCheck::Exhaustive,
+        false => EquivCheck::Random {
+            samples: samples as usize,
+            seed: rng.gen(),
+        },
+    };
+    let mut last = None;
+    let differs = |input: &[bool]| {
+        let values = evaluate(input)?;
+        last = Some(values);
+        Ok(values.2 != values.3)
+    };
+    let (input, checked) = find_input(w0 + w1, strategy, differs)?;
+    // the search stops at the counterexample, so the last evaluated input pair is the one
+    let counterexample = input
+        .and(last)
+        .map(|(a, b, expected, actual)| Counterexample {
+            a,
+            b,
+            expected,
+            actual,
+        });
+
+    Ok(Verification {
+        checked,
+        exhaustive,
+        counterexample,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, Function, Reference};
+    use crate::circuit::circuit_generators::Builtin;
+    use crate::circuit::circuit_parser::{Circuit, GateType};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_verify_adder() {
+        let contents = std::fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+        let adder = Circuit::parse(&contents).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let result = verify(&adder, &Reference::Function(Function::Add), 100, &mut rng).unwrap();
+        assert!(result.passed());
+        assert!(!result.exhaustive);
+        assert_eq!(result.checked, 100);
+
+        let result = verify(&adder, &Reference::Function(Function::Sub), 100, &mut rng).unwrap();
+        assert!(!result.passed());
+
+        // turning an XOR gate into an AND gate changes the computed function
+        let (header, mut gates) = adder.clone().into_parts();
+        let gate = gates
+            .iter_mut()
+            .find(|g| matches!(g.gate_type, GateType::XOR(_, _)))
+            .unwrap();
+        let GateType::XOR(a, b) = gate.gate_type else {
+            unreachable!()
+        };
+        gate.gate_type = GateType::AND(a, b);
+        let corrupted = Circuit::new(header, gates);
+
+        let function = Reference::Function(Function::Add);
+        let result = verify(&corrupted, &function, 100, &mut rng).unwrap();
+        let counterexample = result.counterexample.unwrap();
+        assert_eq!(
+            counterexample.expected,
+            counterexample.a.wrapping_add(counterexample.b)
+        );
+        assert_ne!(counterexample.actual, counterexample.expected);
+
+        let result = verify(&corrupted, &Reference::Circuit(&adder), 100, &mut rng).unwrap();
+        assert!(!result.passed());
+    }
+
+    #[test]
+    fn test_verify_exhaustive() {
+        let and = Circuit::parse("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let result = verify(&and, &Reference::Function(Function::And), 0, &mut rng).unwrap();
+        assert!(result.passed());
+        assert!(result.exhaustive);
+        assert_eq!(result.checked, 4);
+
+        // AND and XOR first differ on 1 and 0, the inputs are counted up with a in the low bits
+        let result = verify(&and, &Reference::Function(Function::Xor), 0, &mut rng).unwrap();
+        let counterexample = result.counterexample.unwrap();
+        assert_eq!((counterexample.a, counterexample.b), (1, 0));
+        assert_eq!(result.checked, 2);
+    }
+
+    #[test]
+    fn test_verify_exhaustive_up_to_equivalence_bound() {
+        // 18 input bits are checked exhaustively, like by `Circuit::equivalent_to`
+        let and = Builtin::And.circuit(9);
+        let mut rng = StdRng::seed_from_u64(1);
+        let result = verify(&and, &Reference::Function(Function::And), 10, &mut rng).unwrap();
+        assert!(result.passed());
+        assert!(result.exhaustive);
+        assert_eq!(result.checked, 1 << 18);
+
+        let result = verify(&and, &Reference::Circuit(&and), 10, &mut rng).unwrap();
+        assert!(result.passed());
+        assert_eq!(result.checked, 1 << 18);
+    }
+
+    #[test]
+    fn test_verify_unsupported_shape() {
+        let c = Circuit::parse("1 4\n3 1 1 1\n1 1\n\n2 1 0 1 3 AND\n").unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(verify(&c, &Reference::Function(Function::And), 10, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use mpc_in_rust::circuit::circuit_parser::Circuit;
+use mpc_in_rust::input::{encode_input, parse_number};
+use mpc_in_rust::party::party_gmw::new_party_pair;
+use mpc_in_rust::report::to_hex;
+
+// The AES-128 circuit from https://nigelsmart.github.io/MPC-Circuits/ is not part of the
+// repository because of its size. Download it to this path to run the test, without it the test
+// is skipped.
+const AES_PATH: &str = "test_circuits/aes_128.txt";
+
+/// The bits of a 128-bit block given in hex, least significant bit first (the same order in which
+/// the other circuits expect their inputs).
+fn block(hex: &str) -> Vec<bool> {
+    encode_input(&parse_number(hex).unwrap(), 128, false).unwrap()
+}
+
+#[test]
+fn test_aes_128() {
+    if !Path::new(AES_PATH).is_file() {
+        eprintln!(
+            "skipping the AES-128 test, there is no circuit at {}",
+            AES_PATH
+        );
+        return;
+    }
+    let contents = fs::read_to_string(AES_PATH)
+        .unwrap_or_else(|e| panic!("cannot read the AES-128 circuit at {}: {}", AES_PATH, e));
+    let circuit = Circuit::parse(&contents).unwrap();
+    assert_eq!(circuit.header().niv, vec![128, 128]);
+    assert_eq!(circuit.header().nov, vec![128]);
+
+    // Test vector from FIPS-197, Appendix C.1. Party 0 holds the key, party 1 the plaintext.
+    let key = block("0x000102030405060708090a0b0c0d0e0f");
+    let plaintext = block("0x00112233445566778899aabbccddeeff");
+    let ciphertext = "0x69c4e0d86a7b0430d8cdb78070b4c55a";
+
+    let (mut p0, mut p1) = new_party_pair(circuit).unwrap();
+    let h0 = thread::spawn(move || p0.execute(&key).unwrap());
+    let h1 = thread::spawn(move || p1.execute(&plaintext).unwrap());
+    let out0 = h0.join().unwrap();
+    let out1 = h1.join().unwrap();
+
+    assert_eq!(out0, out1);
+    assert_eq!(to_hex(&out0), ciphertext);
+}
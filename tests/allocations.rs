@@ -0,0 +1,60 @@
+use std::fs;
+use std::thread;
+
+use mpc_in_rust::circuit::circuit_parser::Circuit;
+use mpc_in_rust::party::allocations::CountingAllocator;
+use mpc_in_rust::party::party_gmw::new_party_pair;
+
+// counts the allocations of this test binary only, the other tests keep the system allocator
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs the 64 bit adder and returns how often party 0 allocated and the number of AND gates.
+fn allocations_of_adder(batched: bool) -> (usize, usize) {
+    let contents = fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+    let circuit = Circuit::parse(&contents).unwrap();
+    let and_gates = circuit.stats().and;
+    // the layers are cached in the circuit both parties share, whichever party gets to them first
+    // would count the allocations of computing them
+    circuit.layers();
+    let (mut p0, mut p1) = new_party_pair(circuit).unwrap();
+    p0.set_profiling(true);
+
+    let h1 = thread::spawn(move || match batched {
+        true => p1.execute_batched(&[true; 64]).unwrap(),
+        false => p1.execute(&[true; 64]).unwrap(),
+    });
+    match batched {
+        true => p0.execute_batched(&[false; 64]).unwrap(),
+        false => p0.execute(&[false; 64]).unwrap(),
+    };
+    h1.join().unwrap();
+    (p0.profile().unwrap().allocations, and_gates)
+}
+
+#[test]
+fn test_and_gates_do_not_allocate() {
+    // all allocations are made while setting up the run and exchanging inputs and outputs, the
+    // AND gates themselves only pass single bits around
+    let (allocations, and_gates) = allocations_of_adder(false);
+    assert!(allocations > 0, "the counting allocator is not installed");
+    assert!(
+        allocations * 3 <= and_gates,
+        "{} allocations for {} AND gates",
+        allocations,
+        and_gates
+    );
+}
+
+#[test]
+fn test_batched_allocations_per_and_gate() {
+    // every layer of the adder holds a single AND gate, whose opened values are sent in two
+    // vectors of their own. The buffers of the layer are reused.
+    let (allocations, and_gates) = allocations_of_adder(true);
+    assert!(
+        allocations <= 3 * and_gates,
+        "{} allocations for {} AND gates",
+        allocations,
+        and_gates
+    );
+}
@@ -0,0 +1,937 @@
+use std::fs;
+use std::process::Command;
+
+use mpc_in_rust::circuit::circuit_parser::Circuit;
+use mpc_in_rust::input::to_bits;
+use mpc_in_rust::report::{decode_signed, format_result, write_results, Format, RunReport};
+
+fn bin() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_mpc-in-rust"))
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("mpc_cli_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn test_format_result() {
+    let report = RunReport {
+        outputs: vec![vec![true, false, true], vec![false, true]],
+        names: None,
+        metadata: None,
+    };
+    assert_eq!(format_result(&report, Format::Text, false), "5, 2");
+    assert_eq!(format_result(&report, Format::Text, true), "-3, -2");
+    assert_eq!(
+        format_result(&report, Format::Json, false),
+        r#"{"values":[5,2],"hex":["0x5","0x2"],"bits":[[true,false,true],[false,true]]}"#
+    );
+
+    let report = RunReport {
+        outputs: vec![vec![true], vec![false], vec![true, true]],
+        names: None,
+        metadata: None,
+    };
+    assert_eq!(
+        format_result(&report, Format::Text, false),
+        "true, false, 3"
+    );
+    assert_eq!(
+        format_result(&report, Format::Text, true),
+        "true, false, -1"
+    );
+
+    let report = RunReport {
+        names: Some(vec!["x".to_string(), "y".to_string(), "z".to_string()]),
+        ..report
+    };
+    assert_eq!(
+        format_result(&report, Format::Text, false),
+        "x = true, y = false, z = 3"
+    );
+    assert!(format_result(&report, Format::Json, false).contains(r#""names":["x","y","z"]"#));
+}
+
+#[test]
+fn test_single_bit_output() {
+    for (a, b, expected) in [("1", "1", "true"), ("1", "0", "false")] {
+        let output = bin()
+            .args(["-p", "test_circuits/1_And.txt", "-f", a, "-s", b])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("The result of the calculation is {}\n", expected)
+        );
+    }
+
+    let output = mpc_in_rust::run("test_circuits/1_And.txt", &[true], &[true]).unwrap();
+    assert_eq!(output, vec![true]);
+}
+
+#[test]
+fn test_several_circuits() {
+    let output = bin()
+        .args(["-p", "test_circuits/64_Adder.txt", "-f", "40", "-s", "2"])
+        .args(["-p", "test_circuits/8_Less.txt", "-f", "3", "-s", "7"])
+        .args(["-p", "test_circuits/64_Sub.txt", "-f", "40", "-s", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is 42\n\
+         The result of the calculation is true\n\
+         The result of the calculation is 38\n"
+    );
+
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/64_Adder.txt",
+            "-f",
+            "1",
+            "-f",
+            "2",
+            "-s",
+            "3",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("more inputs than circuits"));
+}
+
+#[test]
+fn test_input_too_wide() {
+    let output = bin()
+        .args(["-p", "test_circuits/1_And.txt", "-f", "2", "-s", "1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "2 does not fit into 1 bit(s)\n"
+    );
+
+    let output = bin()
+        .args(["-p", "test_circuits/8_Less.txt", "-f", "255", "-s", "256"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "256 does not fit into 8 bit(s)\n"
+    );
+
+    let output = bin()
+        .args(["-p", "test_circuits/8_Less.txt", "-f", "255", "-s", "0"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_public_input() {
+    // the second party's threshold is public, so only the first party supplies an input
+    for (a, expected) in [("5", "true"), ("9", "false")] {
+        let output = bin()
+            .args(["-p", "test_circuits/8_Less.txt", "-f", a, "--public", "1=9"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("The result of the calculation is {}\n", expected)
+        );
+    }
+
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/8_Less.txt",
+            "-f",
+            "5",
+            "--public",
+            "2=9",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no input value 2"));
+}
+
+#[test]
+fn test_decode_signed() {
+    assert_eq!(decode_signed(&to_bits(0xff, 8).unwrap()), -1);
+    assert_eq!(decode_signed(&to_bits(0x7f, 8).unwrap()), 127);
+    assert_eq!(decode_signed(&to_bits(0x80, 8).unwrap()), -128);
+    assert_eq!(decode_signed(&to_bits(1, 1).unwrap()), -1);
+    assert_eq!(decode_signed(&to_bits(0xfffe, 16).unwrap()), -2);
+    assert_eq!(
+        decode_signed(&to_bits(0x8000_0000, 32).unwrap()),
+        i32::MIN as i64
+    );
+    assert_eq!(decode_signed(&to_bits(u64::MAX - 6, 64).unwrap()), -7);
+    assert_eq!(decode_signed(&to_bits(5, 64).unwrap()), 5);
+    assert_eq!(decode_signed(&[]), 0);
+}
+
+#[test]
+fn test_signed_flag() {
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/64_Sub.txt",
+            "-f",
+            "3",
+            "-s",
+            "10",
+            "--signed",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is -7\n"
+    );
+}
+
+#[test]
+fn test_signed_inputs() {
+    let run = |circuit: &str, first: &str, second: &str, signed: bool| {
+        let mut command = bin();
+        command.args(["-p", circuit, "-f", first, "-s", second]);
+        if signed {
+            command.arg("--signed");
+        }
+        let output = command.output().unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.success(), stdout + &stderr)
+    };
+    let result = |value: &str| {
+        (
+            true,
+            format!("The result of the calculation is {}\n", value),
+        )
+    };
+
+    // 64 bit subtraction going negative, and a negative operand
+    let sub64 = "test_circuits/64_Sub.txt";
+    assert_eq!(run(sub64, "-5", "10", true), result("-15"));
+    assert_eq!(run(sub64, "5", "-10", true), result("15"));
+    assert_eq!(
+        run(sub64, "-9223372036854775808", "1", true),
+        result("9223372036854775807")
+    );
+    // the unsigned result of 3 - 10 has the top bit set and must not print as negative
+    assert_eq!(run(sub64, "3", "10", false), result("18446744073709551609"));
+
+    // 8 bit subtraction, the sign is taken from the output width and not from bit 63
+    let sub8 = "test_circuits/8_Sub.txt";
+    assert_eq!(run(sub8, "3", "5", true), result("-2"));
+    assert_eq!(run(sub8, "-128", "1", true), result("127"));
+    assert_eq!(run(sub8, "-100", "-28", true), result("-72"));
+    assert_eq!(run(sub8, "3", "5", false), result("254"));
+
+    let (success, stderr) = run(sub8, "128", "0", true);
+    assert!(!success);
+    assert_eq!(
+        stderr,
+        "128 does not fit into 8 bit(s) as a two's complement number\n"
+    );
+    let (success, stderr) = run(sub8, "-1", "0", false);
+    assert!(!success);
+    assert_eq!(stderr, "-1 is negative, but the inputs are unsigned\n");
+}
+
+#[test]
+fn test_run_prints_result() {
+    let output = bin()
+        .args(["-p", "test_circuits/64_Sub.txt", "-f", "10", "-s", "3"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is 7\n"
+    );
+}
+
+#[test]
+fn test_output_file_and_quiet() {
+    let path = temp_path("quiet.txt");
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/64_Adder.txt",
+            "-f",
+            "10",
+            "-s",
+            "3",
+            "--quiet",
+        ])
+        .arg("--output-file")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "13\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_profile() {
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/64_Adder.txt",
+            "-f",
+            "3",
+            "-s",
+            "4",
+            "--profile",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is 7\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Profile of party 0:\ntotal: "));
+    assert!(stderr.contains("Profile of party 1:\ntotal: "));
+    assert!(stderr.contains("AND communication"));
+    // the binary only counts allocations with the count-allocations feature
+    assert!(stderr.contains("allocations: "));
+    assert_eq!(
+        !stderr.contains("allocations: 0\n"),
+        cfg!(feature = "count-allocations")
+    );
+}
+
+#[test]
+fn test_write_results() {
+    let report = RunReport {
+        outputs: vec![vec![true, false, true]],
+        names: None,
+        metadata: None,
+    };
+    let results = vec![
+        format_result(&report, Format::Text, false),
+        format_result(&report, Format::Json, false),
+    ];
+    let mut buffer = Vec::new();
+    write_results(&mut buffer, &results).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "5\n{\"values\":[5],\"hex\":[\"0x5\"],\"bits\":[[true,false,true]]}\n"
+    );
+
+    // --out is short for --output-file, and truncates the file
+    let path = temp_path("out.txt");
+    fs::write(&path, "an older and longer result\n").unwrap();
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/64_Adder.txt",
+            "-f",
+            "2",
+            "-s",
+            "3",
+            "-q",
+            "--out",
+        ])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "5\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_output_file_json() {
+    let path = temp_path("result.json");
+    let output = bin()
+        .args(["-p", "test_circuits/64_Adder.txt", "-f", "1", "-s", "2"])
+        .args(["--output-format", "json", "--output-file"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with(r#"{"values":[3],"hex":["0x3"],"bits":[[true,true,false"#));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), contents);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_wide_output_in_hex() {
+    let report = RunReport {
+        outputs: vec![vec![true; 65], vec![true, false, true]],
+        names: None,
+        metadata: None,
+    };
+    assert_eq!(
+        format_result(&report, Format::Text, false),
+        "0x1ffffffffffffffff, 5"
+    );
+    assert_eq!(
+        format_result(&report, Format::Json, false),
+        r#"{"values":["0x1ffffffffffffffff",5],"hex":["0x1ffffffffffffffff","0x5"],"bits":[[true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true,true],[true,false,true]]}"#
+    );
+
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/65_Xor.txt",
+            "-f",
+            "18446744073709551615",
+            "-s",
+            "1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        "The result of the calculation is 0xfffffffffffffffe"
+    );
+
+    // the top bit of a 65 bit input is passed through, in hex, binary and decimal
+    for first in [
+        "0x1_0000_0000_0000_0001",
+        "0b1_0000000000000000000000000000000000000000000000000000000000000001",
+        "18446744073709551617",
+    ] {
+        let output = bin()
+            .args(["-p", "test_circuits/65_Xor.txt", "-f", first, "-s", "2"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap().trim(),
+            "The result of the calculation is 0x10000000000000003"
+        );
+    }
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/65_Xor.txt",
+            "-f",
+            "0x20000000000000000",
+        ])
+        .args(["-s", "0"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("does not fit into 65 bit(s)"));
+
+    let output = bin()
+        .args(["-p", "test_circuits/1_And.txt", "-f", "1", "-s", "1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        "The result of the calculation is true"
+    );
+}
+
+#[test]
+fn test_json_metadata() {
+    let output = bin()
+        .args(["-p", "test_circuits/64_Adder.txt", "-f", "200", "-s", "55"])
+        .args(["--output-format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let contents = fs::read_to_string("test_circuits/64_Adder.txt").unwrap();
+    let adder = Circuit::parse(&contents).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["values"], serde_json::json!([255]));
+    assert_eq!(json["hex"], serde_json::json!(["0xff"]));
+    assert_eq!(json["bits"][0].as_array().unwrap().len(), 64);
+    assert_eq!(json["and_gates"], adder.stats().and);
+    assert_eq!(json["triples_consumed"], adder.required_triples());
+    // the two rounds of the handshake, the input sharing, one round per AND layer and the output
+    // exchange
+    assert_eq!(json["rounds"], adder.and_depth() + 4);
+}
+
+#[test]
+fn test_missing_file_fails() {
+    let output = bin()
+        .args([
+            "-p",
+            "test_circuits/missing.txt",
+            "-f",
+            "1",
+            "-s",
+            "2",
+            "--quiet",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn test_circuit_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = bin()
+        .args(["-p", "-", "-f", "10", "-s", "3"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let circuit = fs::read_to_string("test_circuits/64_Sub.txt").unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(circuit.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is 7\n"
+    );
+}
+
+#[test]
+fn test_info() {
+    let output = bin()
+        .args(["info", "test_circuits/64_Adder.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Gates: 376 (AND: 63, XOR: 313, INV: 0)\n\
+         Wires: 504\n\
+         Input bits: 64, 64\n\
+         Output bits: 64\n\
+         Depth: 188\n\
+         AND depth: 63\n\
+         Required triples: 63\n"
+    );
+}
+
+#[test]
+fn test_named_inputs() {
+    let circuit = temp_path("named.txt");
+    let sidecar = temp_path("named.meta.json");
+    fs::copy("test_circuits/64_Adder.txt", &circuit).unwrap();
+    fs::write(
+        &sidecar,
+        r#"{
+            "inputs": [{"name": "salary_a", "width": 64}, {"name": "salary_b", "width": 64}],
+            "outputs": [{"name": "total", "width": 64}]
+        }"#,
+    )
+    .unwrap();
+    let path = circuit.to_str().unwrap();
+
+    let output = bin()
+        .args([
+            "run",
+            "-p",
+            path,
+            "--in",
+            "salary_b=7",
+            "--in",
+            "salary_a=50000",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is total = 50007\n"
+    );
+
+    let output = bin().args(["info", path]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Input bits: 64 (salary_a), 64 (salary_b)\n"));
+    assert!(stdout.contains("Output bits: 64 (total)\n"));
+
+    for (args, message) in [
+        (
+            vec!["--in", "salary_a=1"],
+            "No value was given for the input salary_b",
+        ),
+        (
+            vec!["--in", "salary_a=1", "--in", "bonus=2"],
+            "The circuit has no input named bonus",
+        ),
+    ] {
+        let output = bin().args(["run", "-p", path]).args(args).output().unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr).unwrap().contains(message));
+    }
+
+    // a sidecar which does not match the header is rejected
+    fs::write(
+        &sidecar,
+        r#"{"inputs": [{"name": "a", "width": 64}, {"name": "b", "width": 32}], "outputs": [{"name": "sum", "width": 64}]}"#,
+    )
+    .unwrap();
+    let output = bin()
+        .args(["run", "-p", path, "-f", "1", "-s", "2"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "The metadata gives b 32 bit(s), but the circuit declares 64\n"
+    );
+
+    // without a sidecar, there are no names to give inputs by
+    fs::remove_file(&sidecar).unwrap();
+    let output = bin()
+        .args(["run", "-p", path, "--in", "a=1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    fs::remove_file(&circuit).unwrap();
+}
+
+#[test]
+fn test_run_subcommand() {
+    let output = bin()
+        .args([
+            "run",
+            "-p",
+            "test_circuits/64_Sub.txt",
+            "-f",
+            "10",
+            "-s",
+            "3",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is 7\n"
+    );
+}
+
+#[test]
+fn test_verify() {
+    let output = bin()
+        .args(["verify", "-p", "test_circuits/64_Adder.txt", "-f", "add"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("PASS"));
+
+    let output = bin()
+        .args(["verify", "-p", "test_circuits/64_Adder.txt", "-f", "sub"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("FAIL"));
+}
+
+#[test]
+fn test_gen_triples() {
+    let (path0, path1) = (temp_path("triples0.mtp"), temp_path("triples1.mtp"));
+    let output = bin()
+        .args(["gen-triples", "-c", "100", "--seed", "7", "--first-out"])
+        .arg(&path0)
+        .arg("--second-out")
+        .arg(&path1)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // a header of 16 bytes and 3 bytes for every 8 triples
+    assert_eq!(fs::metadata(&path0).unwrap().len(), 16 + 13 * 3);
+    assert_eq!(fs::metadata(&path1).unwrap().len(), 16 + 13 * 3);
+    assert_ne!(fs::read(&path0).unwrap(), fs::read(&path1).unwrap());
+
+    fs::remove_file(path0).unwrap();
+    fs::remove_file(path1).unwrap();
+}
+
+#[test]
+fn test_diff() {
+    let output = bin()
+        .args([
+            "diff",
+            "test_circuits/64_Adder.txt",
+            "test_circuits/64_Adder.txt",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = bin()
+        .args([
+            "diff",
+            "test_circuits/64_Adder.txt",
+            "test_circuits/64_Sub.txt",
+            "--limit",
+            "2",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().filter(|l| l.starts_with('-')).count(), 2);
+    assert!(stdout.starts_with("AND gates: "));
+    assert!(stdout.contains("equivalent: no\n"));
+    assert!(stdout.lines().last().unwrap().starts_with("... and "));
+}
+
+#[test]
+fn test_dot() {
+    let output = bin()
+        .args(["dot", "test_circuits/1_And.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("digraph circuit {"));
+    assert!(stdout.contains("\"g0\" [label=\"AND #0\"];"));
+
+    let path = temp_path("adder.dot");
+    let output = bin()
+        .args([
+            "dot",
+            "test_circuits/64_Adder.txt",
+            "--wire",
+            "440",
+            "--output-file",
+        ])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(fs::read_to_string(&path).unwrap().ends_with("}\n"));
+    fs::remove_file(path).unwrap();
+
+    // input wires are not assigned by a gate
+    let output = bin()
+        .args(["dot", "test_circuits/64_Adder.txt", "--wire", "3"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Wire 3 is not assigned by a gate\n"
+    );
+}
+
+#[test]
+fn test_expr() {
+    let output = bin()
+        .args([
+            "--expr",
+            "sum = a ^ b; carry = a & b",
+            "--expr-input",
+            "a:4",
+            "--expr-input",
+            "b:4",
+            "-f",
+            "12",
+            "-s",
+            "10",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // 12 ^ 10 and 12 & 10
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with(r#"{"values":[6,8],"#));
+
+    let output = bin()
+        .args([
+            "--expr",
+            "out = a &",
+            "--expr-input",
+            "a:1",
+            "--expr-input",
+            "b:1",
+        ])
+        .args(["-f", "1", "-s", "0"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Parsing failed at line 1, column 10: expected a value\n"
+    );
+}
+
+#[test]
+fn test_builtin() {
+    let output = bin()
+        .args(["--builtin", "adder:64", "-f", "40", "-s", "2"])
+        .args(["--builtin", "lt:8", "-f", "3", "-s", "200"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "The result of the calculation is 42\nThe result of the calculation is true\n"
+    );
+
+    let output = bin()
+        .args(["verify", "--builtin", "sub:65", "--function", "sub"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("wider than 64 bits"));
+
+    let output = bin()
+        .args(["--builtin", "adder:0", "-f", "1", "-s", "1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_builtin_info_dot_and_diff() {
+    let output = bin().args(["info", "--builtin", "and:1"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Gates: 1 (AND: 1, XOR: 0, INV: 0)"));
+
+    let output = bin().args(["dot", "--builtin", "and:1"]).output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("digraph circuit {"));
+
+    let output = bin()
+        .args(["diff", "test_circuits/1_And.txt", "--builtin", "and:1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // a builtin circuit replaces the path, it can not be given in addition to it
+    let output = bin()
+        .args(["info", "test_circuits/1_And.txt", "--builtin", "and:1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let output = bin().args(["dot"]).output().unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_max_wires() {
+    let path = temp_path("huge_header.txt");
+    fs::write(&path, "1 1000000000000\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").unwrap();
+    let output = bin()
+        .arg("-p")
+        .arg(&path)
+        .args(["-f", "1", "-s", "1"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("declares 1000000000000 wires"));
+    fs::remove_file(path).unwrap();
+
+    let output = bin()
+        .args(["-p", "test_circuits/1_And.txt", "-f", "1", "-s", "1"])
+        .args(["--max-wires", "2"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let output = bin()
+        .args(["info", "test_circuits/1_And.txt", "--max-wires", "3"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_networked_parties() {
+    // a free port, which party 1 already tries to connect to before party 0 listens on it
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("127.0.0.1:{}", port);
+    let p1 = bin()
+        .args([
+            "-p",
+            "test_circuits/64_Sub.txt",
+            "-s",
+            "3",
+            "--connect",
+            &addr,
+        ])
+        .args(["--io-timeout-ms", "10000"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let p0 = bin()
+        .args([
+            "-p",
+            "test_circuits/64_Sub.txt",
+            "-f",
+            "10",
+            "--listen",
+            &addr,
+        ])
+        // party 1 does not compress, so neither party does
+        .args(["--compress", "--compress-threshold", "16"])
+        .output()
+        .unwrap();
+    let p1 = p1.wait_with_output().unwrap();
+    for output in [p0, p1] {
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "The result of the calculation is 7\n"
+        );
+    }
+
+    // the other input belongs to the other process
+    let output = bin()
+        .args(["-p", "test_circuits/64_Sub.txt", "-f", "10", "-s", "3"])
+        .args(["--listen", &addr])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}